@@ -1,6 +1,10 @@
-use std::{path::Path, time::Duration};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use tokio_postgres_rustls::MakeRustlsConnect;
 use deadpool_postgres::{
     Config as PoolConfig, ManagerConfig, Pool, RecyclingMethod, Runtime, Timeouts,
@@ -55,64 +59,98 @@ pub async fn create_pool(config: &Config) -> Result<DbPool> {
     Ok(pool)
 }
 
+const MIGRATION_PATHS: [&str; 3] = ["../migrations", "./migrations", "migrations"];
+const DOWN_SUFFIX: &str = ".down.sql";
+
+/// Applies pending migrations. A pending migration numbered below one already
+/// applied is logged as out of order, or refused when `MIGRATION_STRICT=true`.
 pub async fn run_migrations(pool: &DbPool) -> Result<()> {
-    const MIGRATION_PATHS: [&str; 3] = ["../migrations", "./migrations", "migrations"];
+    let strict = std::env::var("MIGRATION_STRICT").is_ok_and(|v| v.eq_ignore_ascii_case("true"));
+    run_migrations_from(pool, &find_migration_dir()?, strict).await
+}
 
-    let client = pool
+async fn run_migrations_from(pool: &DbPool, migration_dir: &Path, strict: bool) -> Result<()> {
+    let mut client = pool
         .get()
         .await
         .map_err(|e| ApiError::Database(format!("Failed to get client: {e}")))?;
 
-    client
-        .execute(
-            "CREATE TABLE IF NOT EXISTS __migrations (
-            filename TEXT PRIMARY KEY,
-            applied_at TIMESTAMPTZ DEFAULT NOW()
-        )",
-            &[],
-        )
-        .await
-        .map_err(|e| ApiError::Database(format!("Failed to create migrations table: {e}")))?;
+    ensure_migrations_table(&client).await?;
 
-    let migration_dir = MIGRATION_PATHS
-        .iter()
-        .map(Path::new)
-        .find(|path| path.exists())
-        .ok_or_else(|| ApiError::Database("No migrations directory found".to_owned()))?;
     let mut migrations = std::fs::read_dir(migration_dir)
         .map_err(|e| ApiError::Database(format!("Failed to read migrations directory: {e}")))?
         .filter_map(|entry| {
             let entry = entry.ok()?;
             let path = entry.path();
-            if path.extension()? == "sql" {
-                Some(path)
-            } else {
-                None
+            if path.extension()? != "sql" {
+                return None;
+            }
+
+            let file_name = path.file_name()?.to_str()?;
+            if file_name.ends_with(DOWN_SUFFIX) {
+                return None;
+            }
+
+            match parse_migration_version(file_name) {
+                Some(version) => Some((version, path)),
+                None => {
+                    warn!(
+                        "Skipping {}: migration files must be named NNN_description.up.sql",
+                        path.display()
+                    );
+                    None
+                }
             }
         })
         .collect::<Vec<_>>();
 
     migrations.sort();
 
-    for migration_path in migrations {
+    if let Some(pair) = migrations.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+        return Err(ApiError::Database(format!(
+            "Duplicate migration version {:03}: {} and {}",
+            pair[0].0,
+            pair[0].1.display(),
+            pair[1].1.display()
+        )));
+    }
+
+    let applied_names: Vec<String> = client
+        .query("SELECT filename FROM __migrations", &[])
+        .await
+        .map_err(|e| ApiError::Database(format!("Failed to check migration status: {e}")))?
+        .iter()
+        .map(|row| row.get("filename"))
+        .collect();
+    let latest_applied = applied_names
+        .iter()
+        .filter_map(|name| parse_migration_version(name))
+        .max();
+    // Compared by stem so migrations recorded before the `.up.sql` rename
+    // still count as applied.
+    let applied: HashSet<&str> = applied_names.iter().map(|name| migration_stem(name)).collect();
+
+    for (version, migration_path) in migrations {
         let migration_name = migration_path
             .file_name()
             .and_then(|n| n.to_str())
             .ok_or_else(|| ApiError::Database("Invalid migration filename".to_owned()))?;
 
-        let already_applied = client
-            .query_opt(
-                "SELECT 1 FROM __migrations WHERE filename = $1",
-                &[&migration_name],
-            )
-            .await
-            .map_err(|e| ApiError::Database(format!("Failed to check migration status: {e}")))?;
-
-        if already_applied.is_some() {
+        if applied.contains(migration_stem(migration_name)) {
             info!("Skipping already applied migration: {}", migration_name);
             continue;
         }
 
+        if let Some(latest) = latest_applied.filter(|&latest| version < latest) {
+            let message = format!(
+                "Migration {migration_name} is older than already applied migration {latest:03} and will run out of order"
+            );
+            if strict {
+                return Err(ApiError::Database(message));
+            }
+            warn!("{}", message);
+        }
+
         let migration_sql = std::fs::read_to_string(&migration_path).map_err(|e| {
             ApiError::Database(format!(
                 "Failed to read migration {migration_name}: {e}"
@@ -121,29 +159,548 @@ pub async fn run_migrations(pool: &DbPool) -> Result<()> {
 
         info!("Running migration: {}", migration_name);
 
-        match client.batch_execute(&migration_sql).await {
-            Ok(()) => {
-                client
-                    .execute(
-                        "INSERT INTO __migrations (filename) VALUES ($1)",
-                        &[&migration_name],
-                    )
-                    .await
-                    .map_err(|e| {
-                        ApiError::Database(format!("Failed to mark migration as applied: {e}"))
-                    })?;
-
-                info!("Successfully applied migration: {}", migration_name);
+        if let Err(e) = apply_migration_sql(
+            &mut client,
+            &migration_sql,
+            "INSERT INTO __migrations (filename) VALUES ($1)",
+            migration_name,
+        )
+        .await
+        {
+            error!("Migration {} failed, rolling back: {}", migration_name, e);
+            return Err(ApiError::Database(format!(
+                "Migration {migration_name} failed: {e}"
+            )));
+        }
+
+        info!("Successfully applied migration: {}", migration_name);
+    }
+
+    info!("All migrations completed successfully");
+    Ok(())
+}
+
+/// Reverts the `count` most recent applied migrations, newest first, by running
+/// their `.down.sql` counterparts. Returns the names of the reverted migrations.
+pub async fn rollback_migrations(pool: &DbPool, count: usize) -> Result<Vec<String>> {
+    rollback_migrations_from(pool, &find_migration_dir()?, count).await
+}
+
+async fn rollback_migrations_from(
+    pool: &DbPool,
+    migration_dir: &Path,
+    count: usize,
+) -> Result<Vec<String>> {
+    let mut client = pool
+        .get()
+        .await
+        .map_err(|e| ApiError::Database(format!("Failed to get client: {e}")))?;
+
+    ensure_migrations_table(&client).await?;
+
+    let mut applied: Vec<(u32, String)> = client
+        .query("SELECT filename FROM __migrations", &[])
+        .await
+        .map_err(|e| ApiError::Database(format!("Failed to check migration status: {e}")))?
+        .iter()
+        .filter_map(|row| {
+            let name: String = row.get("filename");
+            parse_migration_version(&name).map(|version| (version, name))
+        })
+        .collect();
+    applied.sort_by(|a, b| b.cmp(a));
+
+    if applied.is_empty() {
+        info!("No applied migrations to roll back");
+        return Ok(Vec::new());
+    }
+
+    let mut rolled_back = Vec::with_capacity(count.min(applied.len()));
+    for (_, migration_name) in applied.into_iter().take(count) {
+        let down_name = down_migration_name(&migration_name);
+        let down_path = migration_dir.join(&down_name);
+        if !down_path.is_file() {
+            return Err(ApiError::Database(format!(
+                "Cannot roll back {migration_name}: {} does not exist",
+                down_path.display()
+            )));
+        }
+
+        let down_sql = std::fs::read_to_string(&down_path).map_err(|e| {
+            ApiError::Database(format!("Failed to read migration {down_name}: {e}"))
+        })?;
+
+        info!("Rolling back migration: {}", migration_name);
+
+        if let Err(e) = apply_migration_sql(
+            &mut client,
+            &down_sql,
+            "DELETE FROM __migrations WHERE filename = $1",
+            &migration_name,
+        )
+        .await
+        {
+            error!("Rollback {} failed: {}", down_name, e);
+            return Err(ApiError::Database(format!("Rollback {down_name} failed: {e}")));
+        }
+
+        info!("Rolled back migration: {}", migration_name);
+        rolled_back.push(migration_name);
+    }
+
+    Ok(rolled_back)
+}
+
+/// First line of a migration that must run outside a transaction, e.g. one
+/// using `CREATE INDEX CONCURRENTLY`.
+const NO_TRANSACTION_DIRECTIVE: &str = "-- migrate:no-transaction";
+
+fn runs_outside_transaction(sql: &str) -> bool {
+    sql.trim_start().starts_with(NO_TRANSACTION_DIRECTIVE)
+}
+
+/// Splits a script on the `;`s that end statements, skipping those inside
+/// string literals, quoted identifiers, comments and `$tag$` function bodies.
+fn sql_statements(sql: &str) -> Vec<&str> {
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"') => {
+                // A doubled quote is an escaped one and simply reopens the literal.
+                i = sql[i + 1..].find(quote as char).map_or(bytes.len(), |end| i + 1 + end);
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = sql[i..].find('\n').map_or(bytes.len(), |end| i + end);
             }
-            Err(e) => {
-                error!("Migration {} failed: {}", migration_name, e);
-                return Err(ApiError::Database(format!(
-                    "Migration {migration_name} failed: {e}"
-                )));
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = sql[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 1);
             }
+            b'$' => {
+                if let Some(tag) = dollar_quote_tag(&sql[i..]) {
+                    let body = i + tag.len();
+                    i = sql[body..].find(tag).map_or(bytes.len(), |end| body + end + tag.len() - 1);
+                }
+            }
+            b';' => {
+                statements.push(&sql[start..i]);
+                start = i + 1;
+            }
+            _ => {}
         }
+        i += 1;
     }
+    statements.push(&sql[start.min(sql.len())..]);
 
-    info!("All migrations completed successfully");
+    statements
+        .into_iter()
+        .map(str::trim)
+        .filter(|statement| !statement.is_empty())
+        .collect()
+}
+
+/// The `$tag$` opening a dollar-quoted string at the start of `sql`, if any.
+/// `$1`-style parameters are not tags because a tag cannot start with a digit.
+fn dollar_quote_tag(sql: &str) -> Option<&str> {
+    let rest = sql.strip_prefix('$')?;
+    let tag_len = rest.find('$')?;
+    let tag = &rest[..tag_len];
+    let valid = tag.chars().next().is_none_or(|c| c.is_ascii_alphabetic() || c == '_')
+        && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    valid.then(|| &sql[..tag_len + 2])
+}
+
+/// Runs a migration's SQL and its `__migrations` bookkeeping statement in one
+/// transaction. A no-transaction migration runs statement by statement instead,
+/// so it is not atomic and every statement in it has to be safe to re-run.
+async fn apply_migration_sql(
+    client: &mut deadpool_postgres::Client,
+    sql: &str,
+    bookkeeping: &str,
+    migration_name: &str,
+) -> std::result::Result<(), tokio_postgres::Error> {
+    if runs_outside_transaction(sql) {
+        for statement in sql_statements(sql) {
+            client.batch_execute(statement).await?;
+        }
+        client.execute(bookkeeping, &[&migration_name]).await?;
+        return Ok(());
+    }
+
+    let transaction = client.transaction().await?;
+    transaction.batch_execute(sql).await?;
+    transaction.execute(bookkeeping, &[&migration_name]).await?;
+    transaction.commit().await
+}
+
+async fn ensure_migrations_table(client: &deadpool_postgres::Client) -> Result<()> {
+    client
+        .execute(
+            "CREATE TABLE IF NOT EXISTS __migrations (
+            filename TEXT PRIMARY KEY,
+            applied_at TIMESTAMPTZ DEFAULT NOW()
+        )",
+            &[],
+        )
+        .await
+        .map_err(|e| ApiError::Database(format!("Failed to create migrations table: {e}")))?;
     Ok(())
 }
+
+fn find_migration_dir() -> Result<PathBuf> {
+    let migration_dir = resolve_migration_dir(std::env::var("MIGRATIONS_DIR").ok())?;
+    info!("Using migrations from {}", migration_dir.display());
+    Ok(migration_dir)
+}
+
+/// An explicit `MIGRATIONS_DIR` must exist: silently falling back to a default
+/// directory could apply a different set of migrations than the one asked for.
+fn resolve_migration_dir(configured: Option<String>) -> Result<PathBuf> {
+    if let Some(dir) = configured.filter(|dir| !dir.is_empty()) {
+        let dir = PathBuf::from(dir);
+        if !dir.is_dir() {
+            return Err(ApiError::Database(format!(
+                "MIGRATIONS_DIR {} is not a directory",
+                dir.display()
+            )));
+        }
+        return Ok(dir);
+    }
+
+    MIGRATION_PATHS
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_dir())
+        .ok_or_else(|| {
+            ApiError::Database(format!(
+                "No migrations directory found (tried: {})",
+                MIGRATION_PATHS.join(", ")
+            ))
+        })
+}
+
+fn migration_stem(file_name: &str) -> &str {
+    file_name
+        .strip_suffix(".up.sql")
+        .or_else(|| file_name.strip_suffix(".sql"))
+        .unwrap_or(file_name)
+}
+
+fn down_migration_name(up_name: &str) -> String {
+    format!("{}{DOWN_SUFFIX}", migration_stem(up_name))
+}
+
+fn parse_migration_version(file_name: &str) -> Option<u32> {
+    let stem = file_name.strip_suffix(".sql")?;
+    let stem = stem.strip_suffix(".up").unwrap_or(stem);
+    let (prefix, description) = stem.split_once('_')?;
+
+    if prefix.len() < 3 || !prefix.bytes().all(|b| b.is_ascii_digit()) || description.is_empty() {
+        return None;
+    }
+
+    prefix.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_postgres::NoTls;
+
+    #[test]
+    fn down_name_matches_up_name() {
+        assert_eq!(down_migration_name("002_job_runs.up.sql"), "002_job_runs.down.sql");
+        assert_eq!(down_migration_name("002_job_runs.sql"), "002_job_runs.down.sql");
+    }
+
+    #[test]
+    fn parses_versions_of_up_and_legacy_names() {
+        assert_eq!(parse_migration_version("002_job_runs.up.sql"), Some(2));
+        assert_eq!(parse_migration_version("002_job_runs.sql"), Some(2));
+        assert_eq!(parse_migration_version("2_job_runs.sql"), None);
+        assert_eq!(parse_migration_version("002_.sql"), None);
+    }
+
+    #[test]
+    fn splits_no_transaction_migrations_into_statements() {
+        let sql = "-- migrate:no-transaction\nCREATE INDEX CONCURRENTLY a ON t (x);\n\nDROP INDEX CONCURRENTLY b;\n";
+        assert!(runs_outside_transaction(sql));
+        assert!(!runs_outside_transaction("CREATE TABLE t (x INT);"));
+        assert_eq!(
+            sql_statements(sql),
+            [
+                "-- migrate:no-transaction\nCREATE INDEX CONCURRENTLY a ON t (x)",
+                "DROP INDEX CONCURRENTLY b",
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_function_bodies_strings_and_comments_whole() {
+        let sql = r#"-- migrate:no-transaction
+CREATE FUNCTION touch() RETURNS trigger AS $$
+BEGIN
+    NEW.updated_at = now();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+CREATE FUNCTION tagged() RETURNS text AS $body$ SELECT 'a;b' $body$ LANGUAGE sql;
+INSERT INTO notes (text) VALUES ('semi; colon'), ('it''s; fine');
+/* a; block */ COMMENT ON TABLE "odd;name" IS 'x';
+SELECT $1::int; -- trailing; comment
+"#;
+        assert_eq!(
+            sql_statements(sql),
+            [
+                "-- migrate:no-transaction\nCREATE FUNCTION touch() RETURNS trigger AS $$\nBEGIN\n    NEW.updated_at = now();\n    RETURN NEW;\nEND;\n$$ LANGUAGE plpgsql",
+                "CREATE FUNCTION tagged() RETURNS text AS $body$ SELECT 'a;b' $body$ LANGUAGE sql",
+                "INSERT INTO notes (text) VALUES ('semi; colon'), ('it''s; fine')",
+                "/* a; block */ COMMENT ON TABLE \"odd;name\" IS 'x'",
+                "SELECT $1::int",
+                "-- trailing; comment",
+            ]
+        );
+    }
+
+    #[test]
+    fn configured_migration_dir_must_exist() {
+        let dir = std::env::temp_dir().join(format!("migrations-dir-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let configured = resolve_migration_dir(Some(dir.display().to_string()));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(configured.unwrap(), dir);
+
+        let missing = dir.join("missing");
+        match resolve_migration_dir(Some(missing.display().to_string())) {
+            Err(ApiError::Database(message)) => assert!(message.contains("missing"), "{message}"),
+            other => panic!("expected a missing MIGRATIONS_DIR to fail, got {other:?}"),
+        }
+
+        // Unset or empty falls back to the default locations; tests run from
+        // the crate directory, where `../migrations` exists.
+        assert_eq!(resolve_migration_dir(None).unwrap(), PathBuf::from("../migrations"));
+        assert_eq!(resolve_migration_dir(Some(String::new())).unwrap(), PathBuf::from("../migrations"));
+    }
+
+    #[test]
+    fn every_migration_has_a_down_migration() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../migrations");
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let name = entry.unwrap().file_name().into_string().unwrap();
+            if name.ends_with(".up.sql") {
+                assert!(
+                    dir.join(down_migration_name(&name)).is_file(),
+                    "{name} has no down migration"
+                );
+            }
+        }
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn applies_and_rolls_back_a_migration() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let config = Config {
+            database_url,
+            max_db_connections: 2,
+            ..Config::default()
+        };
+        let pool = create_pool(&config).await.unwrap();
+
+        let dir = std::env::temp_dir().join(format!("migrations-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("901_rollback_probe.up.sql"), "CREATE TABLE rollback_probe (id INT);").unwrap();
+        std::fs::write(dir.join("901_rollback_probe.down.sql"), "DROP TABLE rollback_probe;").unwrap();
+        std::fs::write(
+            dir.join("902_rollback_probe_index.up.sql"),
+            "-- migrate:no-transaction\nCREATE INDEX CONCURRENTLY rollback_probe_id ON rollback_probe (id);",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("902_rollback_probe_index.down.sql"),
+            "-- migrate:no-transaction\nDROP INDEX CONCURRENTLY rollback_probe_id;",
+        )
+        .unwrap();
+
+        let table_exists = || async {
+            pool.get()
+                .await
+                .unwrap()
+                .query_one("SELECT to_regclass('rollback_probe') IS NOT NULL", &[])
+                .await
+                .unwrap()
+                .get::<_, bool>(0)
+        };
+
+        run_migrations_from(&pool, &dir, false).await.unwrap();
+        assert!(table_exists().await);
+
+        let rolled_back = rollback_migrations_from(&pool, &dir, 2).await.unwrap();
+        assert_eq!(rolled_back, ["902_rollback_probe_index.up.sql", "901_rollback_probe.up.sql"]);
+        assert!(!table_exists().await);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn rollback_without_down_migration_fails_and_keeps_it_applied() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let config = Config {
+            database_url,
+            max_db_connections: 2,
+            ..Config::default()
+        };
+        let pool = create_pool(&config).await.unwrap();
+
+        let dir = std::env::temp_dir().join(format!("migrations-no-down-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("904_no_down_probe.up.sql"), "CREATE TABLE no_down_probe (id INT);").unwrap();
+
+        run_migrations_from(&pool, &dir, false).await.unwrap();
+        let result = rollback_migrations_from(&pool, &dir, 1).await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let client = pool.get().await.unwrap();
+        let table_exists: bool = client
+            .query_one("SELECT to_regclass('no_down_probe') IS NOT NULL", &[])
+            .await
+            .unwrap()
+            .get(0);
+        let recorded: i64 = client
+            .query_one("SELECT COUNT(*) FROM __migrations WHERE filename = '904_no_down_probe.up.sql'", &[])
+            .await
+            .unwrap()
+            .get(0);
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS no_down_probe; \
+                 DELETE FROM __migrations WHERE filename = '904_no_down_probe.up.sql';",
+            )
+            .await
+            .unwrap();
+
+        let Err(ApiError::Database(message)) = result else {
+            panic!("expected a database error, got {result:?}");
+        };
+        assert!(message.contains("904_no_down_probe.down.sql does not exist"), "{message}");
+        assert!(table_exists);
+        assert_eq!(recorded, 1);
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn failing_statement_rolls_back_the_whole_migration() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let config = Config {
+            database_url,
+            max_db_connections: 2,
+            ..Config::default()
+        };
+        let pool = create_pool(&config).await.unwrap();
+
+        let dir = std::env::temp_dir().join(format!("migrations-atomic-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("903_atomic_probe.up.sql"),
+            "CREATE TABLE atomic_probe (id INT);\nINSERT INTO atomic_probe VALUES ('not a number');",
+        )
+        .unwrap();
+
+        let result = run_migrations_from(&pool, &dir, false).await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let client = pool.get().await.unwrap();
+        let table_exists: bool = client
+            .query_one("SELECT to_regclass('atomic_probe') IS NOT NULL", &[])
+            .await
+            .unwrap()
+            .get(0);
+        let recorded: i64 = client
+            .query_one("SELECT COUNT(*) FROM __migrations WHERE filename = '903_atomic_probe.up.sql'", &[])
+            .await
+            .unwrap()
+            .get(0);
+
+        assert!(result.is_err());
+        assert!(!table_exists, "the first statement was not rolled back");
+        assert_eq!(recorded, 0);
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn late_lower_numbered_migration_is_flagged_out_of_order() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let schema = format!("migration_order_test_{}", std::process::id());
+        let (setup, connection) = tokio_postgres::connect(&database_url, NoTls).await.unwrap();
+        tokio::spawn(connection);
+        setup
+            .batch_execute(&format!("DROP SCHEMA IF EXISTS {schema} CASCADE; CREATE SCHEMA {schema};"))
+            .await
+            .unwrap();
+
+        // A fresh `__migrations` table, so the probes are the only ones applied.
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let separator = if database_url.contains('?') { '&' } else { '?' };
+        let config = Config {
+            database_url: format!("{database_url}{separator}options=-csearch_path%3D{schema}"),
+            max_db_connections: 2,
+            ..Config::default()
+        };
+        let pool = create_pool(&config).await.unwrap();
+
+        let dir = std::env::temp_dir().join(format!("migrations-order-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("920_order_probe.up.sql"), "CREATE TABLE order_probe (id INT);").unwrap();
+        run_migrations_from(&pool, &dir, true).await.unwrap();
+
+        std::fs::write(dir.join("910_late_probe.up.sql"), "CREATE TABLE late_probe (id INT);").unwrap();
+        let strict = run_migrations_from(&pool, &dir, true).await;
+        let applied_when_strict: bool = pool
+            .get()
+            .await
+            .unwrap()
+            .query_one("SELECT to_regclass('late_probe') IS NOT NULL", &[])
+            .await
+            .unwrap()
+            .get(0);
+        let lenient = run_migrations_from(&pool, &dir, false).await;
+        let applied_when_lenient: bool = pool
+            .get()
+            .await
+            .unwrap()
+            .query_one("SELECT to_regclass('late_probe') IS NOT NULL", &[])
+            .await
+            .unwrap()
+            .get(0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        setup.batch_execute(&format!("DROP SCHEMA {schema} CASCADE")).await.unwrap();
+
+        let Err(ApiError::Database(message)) = strict else {
+            panic!("expected the strict run to fail, got {strict:?}");
+        };
+        assert!(message.contains("910_late_probe.up.sql is older than already applied migration 920"), "{message}");
+        assert!(!applied_when_strict);
+        assert!(lenient.is_ok());
+        assert!(applied_when_lenient);
+    }
+}
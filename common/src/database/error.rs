@@ -0,0 +1,47 @@
+use tokio_postgres::error::SqlState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DbErrorKind {
+    UniqueViolation,
+    ForeignKeyViolation,
+    SerializationFailure,
+    Other,
+}
+
+impl DbErrorKind {
+    pub fn of(error: &tokio_postgres::Error) -> Self {
+        error.code().map_or(Self::Other, Self::from_sqlstate)
+    }
+
+    pub fn from_sqlstate(code: &SqlState) -> Self {
+        if *code == SqlState::UNIQUE_VIOLATION {
+            Self::UniqueViolation
+        } else if *code == SqlState::FOREIGN_KEY_VIOLATION {
+            Self::ForeignKeyViolation
+        } else if *code == SqlState::T_R_SERIALIZATION_FAILURE {
+            Self::SerializationFailure
+        } else {
+            Self::Other
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_sqlstates_to_kinds() {
+        assert_eq!(DbErrorKind::from_sqlstate(&SqlState::UNIQUE_VIOLATION), DbErrorKind::UniqueViolation);
+        assert_eq!(
+            DbErrorKind::from_sqlstate(&SqlState::FOREIGN_KEY_VIOLATION),
+            DbErrorKind::ForeignKeyViolation
+        );
+        assert_eq!(
+            DbErrorKind::from_sqlstate(&SqlState::T_R_SERIALIZATION_FAILURE),
+            DbErrorKind::SerializationFailure
+        );
+        assert_eq!(DbErrorKind::from_sqlstate(&SqlState::NOT_NULL_VIOLATION), DbErrorKind::Other);
+        assert_eq!(DbErrorKind::from_sqlstate(&SqlState::from_code("23505")), DbErrorKind::UniqueViolation);
+    }
+}
@@ -1,4 +1,6 @@
+use deadpool_postgres::{Object, PoolError};
 use std::sync::OnceLock;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
 use super::connection::{DbPool, create_pool};
@@ -8,6 +10,67 @@ static SHARED_POOL: OnceLock<Mutex<Option<DbPool>>> = OnceLock::new();
 
 pub struct ConnectionManager;
 
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+pub async fn get_client_with_retry(
+    pool: &DbPool,
+    policy: RetryPolicy,
+) -> std::result::Result<Object, PoolError> {
+    let mut backoff = policy.initial_backoff;
+
+    for attempt in 1..=policy.max_attempts.max(1) {
+        match pool.get().await {
+            Err(PoolError::Timeout(timeout)) if attempt < policy.max_attempts => {
+                tracing::warn!(
+                    "Timed out waiting for a pooled connection ({:?}), retrying in {:?} (attempt {}/{})",
+                    timeout,
+                    backoff,
+                    attempt,
+                    policy.max_attempts
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(policy.max_backoff);
+            }
+            result => return result,
+        }
+    }
+
+    unreachable!()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStatus {
+    pub size: usize,
+    pub available: usize,
+    pub waiting: usize,
+    pub max_size: usize,
+}
+
+impl PoolStatus {
+    pub fn headroom(&self) -> usize {
+        self.available + self.max_size.saturating_sub(self.size)
+    }
+
+    pub fn is_near_exhaustion(&self) -> bool {
+        self.waiting > 0 || self.headroom() < (self.max_size / 10).max(1)
+    }
+}
+
 impl ConnectionManager {
     pub async fn get_shared_pool(config: &Config) -> Result<DbPool> {
         let pool_mutex = SHARED_POOL.get_or_init(|| Mutex::new(None));
@@ -31,4 +94,112 @@ impl ConnectionManager {
             *pool_mutex.lock().await = None;
         }
     }
+
+    pub fn pool_status(pool: &DbPool) -> PoolStatus {
+        let status = pool.status();
+        PoolStatus {
+            size: status.size,
+            available: status.available,
+            waiting: status.waiting,
+            max_size: status.max_size,
+        }
+    }
+
+    pub fn spawn_pool_monitor(pool: DbPool, interval: Duration) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            while !pool.is_closed() {
+                ticker.tick().await;
+                let status = Self::pool_status(&pool);
+                if status.is_near_exhaustion() {
+                    tracing::warn!(
+                        "Database pool near exhaustion: {} available, {}/{} in use, {} waiting",
+                        status.available,
+                        status.size - status.available,
+                        status.max_size,
+                        status.waiting
+                    );
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use deadpool_postgres::{Config as PoolConfig, Runtime, Timeouts};
+    use tokio_postgres::NoTls;
+
+    use super::*;
+
+    fn test_pool(database_url: &str, max_size: usize, wait: Duration) -> DbPool {
+        let mut cfg = PoolConfig::new();
+        cfg.url = Some(database_url.to_string());
+        cfg.pool = Some(deadpool_postgres::PoolConfig {
+            max_size,
+            timeouts: Timeouts {
+                wait: Some(wait),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        cfg.create_pool(Some(Runtime::Tokio1), NoTls).unwrap()
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn available_drops_as_connections_are_acquired() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let pool = test_pool(&database_url, 3, Duration::from_secs(5));
+
+        let first = pool.get().await.unwrap();
+        let second = pool.get().await.unwrap();
+        let third = pool.get().await.unwrap();
+        let all_in_use = ConnectionManager::pool_status(&pool);
+
+        drop(third);
+        let one_idle = ConnectionManager::pool_status(&pool);
+        drop(second);
+        let two_idle = ConnectionManager::pool_status(&pool);
+        let reused = pool.get().await.unwrap();
+        let one_reused = ConnectionManager::pool_status(&pool);
+        drop((first, reused));
+
+        assert_eq!(all_in_use, PoolStatus { size: 3, available: 0, waiting: 0, max_size: 3 });
+        assert!(all_in_use.is_near_exhaustion());
+        assert_eq!(one_idle.available, 1);
+        assert_eq!(two_idle.available, 2);
+        assert!(!two_idle.is_near_exhaustion());
+        assert_eq!(one_reused, PoolStatus { size: 3, available: 1, waiting: 0, max_size: 3 });
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn momentarily_exhausted_pool_succeeds_on_retry() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let pool = test_pool(&database_url, 1, Duration::from_millis(50));
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_millis(200),
+        };
+
+        let held = pool.get().await.unwrap();
+        let no_retry = get_client_with_retry(&pool, RetryPolicy { max_attempts: 1, ..policy }).await;
+        assert!(matches!(no_retry, Err(PoolError::Timeout(_))), "{no_retry:?}");
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            drop(held);
+        });
+        let retried = get_client_with_retry(&pool, policy).await.unwrap();
+        let row = retried.query_one("SELECT 1::INT", &[]).await.unwrap();
+        assert_eq!(row.get::<_, i32>(0), 1);
+    }
 }
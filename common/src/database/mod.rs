@@ -1,5 +1,7 @@
 pub mod manager;
 pub mod connection;
+pub mod error;
 
-pub use manager::ConnectionManager;
-pub use connection::{DbPool, create_pool, run_migrations};
+pub use manager::{ConnectionManager, PoolStatus, RetryPolicy, get_client_with_retry};
+pub use connection::{DbPool, create_pool, rollback_migrations, run_migrations};
+pub use error::DbErrorKind;
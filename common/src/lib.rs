@@ -2,6 +2,6 @@ pub mod utils;
 pub mod services;
 pub mod database;
 
-pub use utils::{Config, Result, ApiError};
+pub use utils::{Config, Result, ApiError, SlackId, SyncKey};
 pub use services::{EmbeddingService, ExternalApiService};
-pub use database::{DbPool, ConnectionManager, create_pool, run_migrations};
+pub use database::{DbPool, DbErrorKind, ConnectionManager, PoolStatus, create_pool, rollback_migrations, run_migrations};
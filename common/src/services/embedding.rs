@@ -1,5 +1,5 @@
 use std::{
-    sync::Arc,
+    sync::{Arc, OnceLock},
     collections::HashMap,
     time::{Duration, Instant},
 };
@@ -13,7 +13,7 @@ use futures::stream::{FuturesUnordered, StreamExt};
 use parking_lot::Mutex;// just faster!
 use tokenizers::Tokenizer;
 use tokio::sync::Semaphore;
-use tracing::{info, instrument};
+use tracing::{info, instrument, warn};
 
 use crate::utils::error::{ApiError, Result};
 
@@ -23,16 +23,299 @@ static MODEL_BYTES: &[u8] = include_bytes!("../../../minilm-build/model.onnx");
 static TOKENIZER_JSON: &str = include_str!("../../../minilm-build/tokenizer.json");
 
 const MAX_MODEL_INPUT_LENGTH: usize = 512;
-const EMBEDDING_DIM: usize = 384;
+pub const EMBEDDING_DIM: usize = 384;
 const OVERLAP: usize = 64;
 
+// Long enough to clear the short-input shortcut in `embed_text` and reach the model.
+const WARMUP_TEXT: &str = "Warming up the embedding model so that the first search request does not pay for session optimization.";
+
+const DEFAULT_MEMORY_PER_SLOT_MB: u64 = 256;
+// Leave the other half of available memory to the model weights, caches and
+// everything else running on the box.
+const INFERENCE_MEMORY_FRACTION: u64 = 2;
+
+fn available_memory_bytes() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let kib: u64 = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemAvailable:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+/// Caps `concurrency` so that `concurrency * per_slot_bytes` fits in the share
+/// of `available_bytes` set aside for inference. Never returns less than 1.
+fn clamp_to_memory(concurrency: usize, available_bytes: u64, per_slot_bytes: u64) -> usize {
+    let budget = available_bytes / INFERENCE_MEMORY_FRACTION;
+    let slots = budget / per_slot_bytes.max(1);
+    concurrency.min(usize::try_from(slots).unwrap_or(usize::MAX)).max(1)
+}
+
+/// Applies the memory cap to `concurrency` using the host's available memory and
+/// `EMBED_MEMORY_PER_SLOT_MB` (default 256). Hosts without /proc/meminfo are
+/// left uncapped.
+pub fn memory_aware_concurrency(concurrency: usize) -> usize {
+    let Some(available) = available_memory_bytes() else {
+        return concurrency;
+    };
+    let per_slot_mb = std::env::var("EMBED_MEMORY_PER_SLOT_MB")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|&v| v > 0)
+        .unwrap_or(DEFAULT_MEMORY_PER_SLOT_MB);
+
+    let capped = clamp_to_memory(concurrency, available, per_slot_mb * 1024 * 1024);
+    if capped < concurrency {
+        info!(
+            "Capping embedding concurrency at {} (wanted {}) to fit {} MB available memory",
+            capped,
+            concurrency,
+            available / (1024 * 1024)
+        );
+    }
+    capped
+}
+
+/// `EMBED_CONCURRENCY` when set, which is always honored as given.
+pub fn embed_concurrency_override() -> Option<usize> {
+    std::env::var("EMBED_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|&v| v > 0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProjectEmbedField {
+    Title,
+    Description,
+    Category,
+}
+
+const DEFAULT_PROJECT_EMBED_FIELDS: [ProjectEmbedField; 2] =
+    [ProjectEmbedField::Title, ProjectEmbedField::Description];
+
+static PROJECT_EMBED_FIELDS: OnceLock<Vec<ProjectEmbedField>> = OnceLock::new();
+
+fn project_embed_fields() -> &'static [ProjectEmbedField] {
+    PROJECT_EMBED_FIELDS.get_or_init(|| {
+        let fields: Vec<ProjectEmbedField> = std::env::var("PROJECT_EMBED_FIELDS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .filter_map(|field| match field.to_lowercase().as_str() {
+                "title" => Some(ProjectEmbedField::Title),
+                "description" => Some(ProjectEmbedField::Description),
+                "category" => Some(ProjectEmbedField::Category),
+                other => {
+                    warn!(
+                        "Ignoring unknown field '{}' in PROJECT_EMBED_FIELDS. Valid options: title, description, category",
+                        other
+                    );
+                    None
+                }
+            })
+            .collect();
+
+        if fields.is_empty() {
+            DEFAULT_PROJECT_EMBED_FIELDS.to_vec()
+        } else {
+            fields
+        }
+    })
+}
+
+/// Builds the text a project is embedded from, using the fields listed in
+/// `PROJECT_EMBED_FIELDS` (default `title,description`). Every path that embeds
+/// a project goes through here so stored and regenerated vectors agree.
+pub fn project_embedding_text_from_parts(
+    title: &str,
+    description: Option<&str>,
+    category: Option<&str>,
+) -> String {
+    project_embed_fields()
+        .iter()
+        .filter_map(|field| match field {
+            ProjectEmbedField::Title => Some(title),
+            ProjectEmbedField::Description => description,
+            ProjectEmbedField::Category => category,
+        })
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Embedding(Vec<f32>);
+
+impl Embedding {
+    pub fn try_from_vec(values: Vec<f32>) -> Result<Self> {
+        if values.len() != EMBEDDING_DIM {
+            return Err(ApiError::Embedding(format!(
+                "Expected embedding of dimension {EMBEDDING_DIM}, got {}",
+                values.len()
+            )));
+        }
+        Ok(Self(values))
+    }
+
+    pub fn zeros() -> Self {
+        Self(vec![0.0; EMBEDDING_DIM])
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.iter().all(|&v| v == 0.0)
+    }
+
+    pub fn as_slice(&self) -> &[f32] {
+        &self.0
+    }
+
+    pub fn into_vec(self) -> Vec<f32> {
+        self.0
+    }
+}
+
+impl TryFrom<Vec<f32>> for Embedding {
+    type Error = ApiError;
+
+    fn try_from(values: Vec<f32>) -> Result<Self> {
+        Self::try_from_vec(values)
+    }
+}
+
+impl From<Embedding> for pgvector::Vector {
+    fn from(embedding: Embedding) -> Self {
+        Self::from(embedding.0)
+    }
+}
+
+/// How token states from `last_hidden_state` are reduced to one vector.
+/// `MeanMax` averages the mean- and max-pooled vectors rather than
+/// concatenating them, so every strategy keeps `EMBEDDING_DIM` dimensions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PoolingStrategy {
+    #[default]
+    Mean,
+    Max,
+    MeanMax,
+    Cls,
+}
+
+impl PoolingStrategy {
+    /// Reads `EMBED_POOLING` (`mean`, `max`, `mean_max`, `cls`; default `mean`).
+    /// The explorer and oculus must agree on it, or query and stored vectors
+    /// will not be comparable.
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("EMBED_POOLING")
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "" | "mean" => Ok(Self::Mean),
+            "max" => Ok(Self::Max),
+            "mean_max" => Ok(Self::MeanMax),
+            "cls" => Ok(Self::Cls),
+            other => Err(ApiError::Config(format!(
+                "Invalid EMBED_POOLING '{other}'. Valid options: mean, max, mean_max, cls"
+            ))),
+        }
+    }
+
+    fn pool(self, hidden: &ArrayViewD<f32>, attention_mask: &[u32]) -> Array1<f32> {
+        let shape = hidden.shape();
+        let (seq_len, hidden_size) = (shape[1], shape[2]);
+        let attended = || {
+            attention_mask
+                .iter()
+                .take(seq_len)
+                .enumerate()
+                .filter(|(_, mask)| **mask > 0)
+                .map(|(i, _)| i)
+        };
+
+        let mean = || {
+            let mut pooled = Array1::zeros(hidden_size);
+            let mut total_mask = 0.0;
+            for i in attended() {
+                total_mask += 1.0;
+                for j in 0..hidden_size {
+                    pooled[j] += hidden[[0, i, j]];
+                }
+            }
+            if total_mask > 0.0 {
+                pooled /= total_mask;
+            }
+            pooled
+        };
+
+        let max = || {
+            let mut pooled = Array1::from_elem(hidden_size, f32::NEG_INFINITY);
+            let mut any = false;
+            for i in attended() {
+                any = true;
+                for j in 0..hidden_size {
+                    pooled[j] = pooled[j].max(hidden[[0, i, j]]);
+                }
+            }
+            if any { pooled } else { Array1::zeros(hidden_size) }
+        };
+
+        match self {
+            Self::Mean => mean(),
+            Self::Max => max(),
+            Self::MeanMax => (mean() + max()) / 2.0,
+            Self::Cls => Array1::from_iter((0..hidden_size).map(|j| hidden[[0, 0, j]])),
+        }
+    }
+}
+
+/// How window embeddings are combined for inputs longer than one model window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WindowWeighting {
+    #[default]
+    Equal,
+    TokenCount,
+}
+
+impl WindowWeighting {
+    /// Reads `WINDOW_WEIGHTING` (`equal` or `token_count`; default `equal`).
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("WINDOW_WEIGHTING")
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "" | "equal" => Ok(Self::Equal),
+            "token_count" => Ok(Self::TokenCount),
+            other => Err(ApiError::Config(format!(
+                "Invalid WINDOW_WEIGHTING '{other}'. Valid options: equal, token_count"
+            ))),
+        }
+    }
+
+    fn weight(self, window_attention_mask: &[u32]) -> f32 {
+        match self {
+            Self::Equal => 1.0,
+            #[allow(clippy::cast_precision_loss)] // window lengths are far below f32's exact range
+            Self::TokenCount => window_attention_mask.iter().filter(|&&m| m > 0).count() as f32,
+        }
+    }
+}
+
 pub struct EmbeddingModel {
     session: Mutex<Session>,
     tokenizer: Tokenizer,
+    pooling: PoolingStrategy,
 }
 
 impl EmbeddingModel {
-    pub fn new() -> Result<Self> {
+    pub fn new(pooling: PoolingStrategy) -> Result<Self> {
         let session_builder = {
             let builder = Session::builder()?
                 .with_optimization_level(GraphOptimizationLevel::Level3)?
@@ -61,7 +344,7 @@ impl EmbeddingModel {
         let tokenizer = Tokenizer::from_bytes(TOKENIZER_JSON.as_bytes())
             .map_err(|e| ApiError::Embedding(format!("Failed to load tokenizer: {e}")))?;
 
-        Ok(Self { session, tokenizer })
+        Ok(Self { session, tokenizer, pooling })
     }
 
     #[allow(clippy::significant_drop_tightening)]
@@ -91,35 +374,65 @@ impl EmbeddingModel {
         let attention_mask_u32: Vec<u32> = attention_mask.into_iter().map(|x| {
             u32::try_from(x).expect("Attention mask value should fit in u32")
         }).collect();
-        let shape = output.shape();
-        let (_batch_size, seq_len, hidden_size) = (shape[0], shape[1], shape[2]);
+        Ok(normalize(self.pooling.pool(&output, &attention_mask_u32)))
+    }
 
-        let mut pooled = Array1::zeros(hidden_size);
-        let mut total_mask = 0.0;
+}
+
+/// Scales `pooled` to unit length, leaving near-zero vectors as they are.
+fn normalize(pooled: Array1<f32>) -> Vec<f32> {
+    let norm = pooled.dot(&pooled).sqrt();
+    if norm > 1e-6 {
+        (pooled / norm).to_vec()
+    } else {
+        pooled.to_vec()
+    }
+}
 
-        for (i, &mask_value) in attention_mask_u32.iter().take(seq_len).enumerate() {
-            #[allow(clippy::cast_precision_loss)] // it's ok lol
-            let mask_f32 = mask_value as f32;
-            total_mask += mask_f32;
+/// Splits `len` tokens into `(start, end)` windows of at most `max` tokens, each
+/// starting `max - overlap` after the previous one. The last window ends at `len`.
+fn windows(len: usize, max: usize, overlap: usize) -> Vec<(usize, usize)> {
+    if len <= max {
+        return vec![(0, len)];
+    }
 
-            for j in 0..hidden_size {
-                pooled[j] += output[[0, i, j]] * mask_f32;
-            }
+    let step = max.saturating_sub(overlap).max(1);
+    let count = (len - max).div_ceil(step) + 1;
+    (0..count)
+        .map(|i| {
+            let start = i * step;
+            (start, (start + max).min(len))
+        })
+        .collect()
+}
+
+/// Weighted average of per-window embeddings, scaled back to unit length.
+fn combine_windows(embeddings: &[Vec<f32>], weights: &[f32]) -> Vec<f32> {
+    let embedding_len = embeddings.first().map_or(0, Vec::len);
+    let mut averaged = vec![0.0; embedding_len];
+    let mut total_weight = 0.0;
+
+    for (embedding, &weight) in embeddings.iter().zip(weights) {
+        total_weight += weight;
+        for (i, &val) in embedding.iter().enumerate() {
+            averaged[i] += val * weight;
         }
+    }
 
-        if total_mask > 0.0 {
-            pooled /= total_mask;
+    if total_weight > 0.0 {
+        for val in &mut averaged {
+            *val /= total_weight;
         }
+    }
 
-        let norm = pooled.dot(&pooled).sqrt();
-        let result = if norm > 1e-6 {
-            (pooled / norm).to_vec()
-        } else {
-            pooled.to_vec()
-        };
-        Ok(result)
+    let norm = averaged.iter().map(|&x| x * x).sum::<f32>().sqrt();
+    if norm > 1e-6 {
+        for val in &mut averaged {
+            *val /= norm;
+        }
     }
 
+    averaged
 }
 
 #[derive(Clone, Hash, PartialEq, Eq)]
@@ -127,7 +440,7 @@ struct CacheKey(String);
 
 #[derive(Clone)]
 struct CacheEntry {
-    embedding: Vec<f32>,
+    embedding: Embedding,
     created_at: Instant,
 }
 
@@ -136,19 +449,18 @@ pub struct EmbeddingService {
     semaphore: Arc<Semaphore>,
     cache: Arc<Mutex<HashMap<CacheKey, CacheEntry>>>,
     cache_ttl: Duration,
+    window_weighting: WindowWeighting,
 }
 
 impl EmbeddingService {
     pub fn new(force_regenerate: bool) -> Result<Self> {
         let cpu_count = std::thread::available_parallelism().map_or(1, std::num::NonZero::get);
-        let env_override = std::env::var("EMBED_CONCURRENCY")
-            .ok()
-            .and_then(|v| v.parse::<usize>().ok())
-            .filter(|&v| v > 0);
+        let max_concurrent =
+            embed_concurrency_override().unwrap_or_else(|| memory_aware_concurrency(cpu_count));
 
-        let max_concurrent = env_override.unwrap_or(cpu_count);
-
-        let model = EmbeddingModel::new()?;
+        let pooling = PoolingStrategy::from_env()?;
+        let window_weighting = WindowWeighting::from_env()?;
+        let model = EmbeddingModel::new(pooling)?;
 
         let cache_ttl = if force_regenerate {
             Duration::from_secs(0)
@@ -157,8 +469,8 @@ impl EmbeddingService {
         };
 
         info!(
-            "Embedding service initialized with {} concurrent slots (CPU count: {}). Cache enabled: {}.",
-            max_concurrent, cpu_count, !force_regenerate
+            "Embedding service initialized with {} concurrent slots (CPU count: {}), {:?} pooling. Cache enabled: {}.",
+            max_concurrent, cpu_count, pooling, !force_regenerate
         );
 
         Ok(Self {
@@ -166,11 +478,12 @@ impl EmbeddingService {
             semaphore: Arc::new(Semaphore::new(max_concurrent)),
             cache: Arc::new(Mutex::new(HashMap::with_capacity(1000))),
             cache_ttl,
+            window_weighting,
         })
     }
 
     #[instrument(skip(self, sentences))]
-    pub async fn embed_batch(&self, sentences: Vec<String>) -> Result<Vec<Vec<f32>>> {        
+    pub async fn embed_batch(&self, sentences: Vec<String>) -> Result<Vec<Embedding>> {        
         if sentences.is_empty() {
             return Ok(Vec::new());
         }
@@ -194,9 +507,31 @@ impl EmbeddingService {
         Ok(results.into_iter().map(|(_, embedding)| embedding).collect())
     }
 
-    pub async fn embed_text(&self, text: &str) -> Result<Vec<f32>> {
+    pub fn count_tokens(&self, text: &str) -> Result<usize> {
+        let encoding = self
+            .model
+            .tokenizer
+            .encode(text, false)
+            .map_err(|e| ApiError::Embedding(format!("Tokenization failed: {e}")))?;
+
+        Ok(encoding.get_ids().len())
+    }
+
+    /// Runs one inference so ONNX Runtime finishes its lazy session setup before
+    /// real traffic arrives. Failures are logged, not returned.
+    pub async fn warmup(&self) {
+        let start = Instant::now();
+        match self.embed_text(WARMUP_TEXT).await {
+            Ok(_) => info!("Embedding model warmed up in {:?}", start.elapsed()),
+            Err(e) => warn!("Embedding model warmup failed: {}", e),
+        }
+    }
+
+    /// Empty input and input shorter than 8 tokens yield `Embedding::zeros()`
+    /// without running the model.
+    pub async fn embed_text(&self, text: &str) -> Result<Embedding> {
         if text.trim().is_empty() {
-            return Ok(vec![0.0; EMBEDDING_DIM]);
+            return Ok(Embedding::zeros());
         }
 
         let encoding = self
@@ -206,7 +541,7 @@ impl EmbeddingService {
             .map_err(|e| ApiError::Embedding(format!("Tokenization failed: {e}")))?;
 
         if encoding.get_ids().len() < 8 {
-            return Ok(vec![0.0; EMBEDDING_DIM]);
+            return Ok(Embedding::zeros());
         }
 
         let cache_key = CacheKey(text.to_string());
@@ -239,7 +574,7 @@ impl EmbeddingService {
         Ok(embedding)
     }
 
-    async fn embed_single_text(&self, text: &str) -> Result<Vec<f32>> {
+    async fn embed_single_text(&self, text: &str) -> Result<Embedding> {
         let _permit = self
             .semaphore
             .acquire()
@@ -247,9 +582,10 @@ impl EmbeddingService {
             .map_err(|_| ApiError::Embedding("Failed to acquire semaphore".to_owned()))?;
 
         let model = Arc::clone(&self.model);
+        let window_weighting = self.window_weighting;
         let text = text.to_string();
 
-        tokio::task::spawn_blocking(move || -> Result<Vec<f32>> {
+        let embedding = tokio::task::spawn_blocking(move || -> Result<Vec<f32>> {
             let encoding = model
                 .tokenizer
                 .encode(text, true)
@@ -259,18 +595,10 @@ impl EmbeddingService {
             let attention_mask = encoding.get_attention_mask();
 
             if input_ids.len() > MAX_MODEL_INPUT_LENGTH {
-                let step = MAX_MODEL_INPUT_LENGTH - OVERLAP;
-                let num_windows = if input_ids.len() <= step {
-                    1
-                } else {
-                    (input_ids.len() - MAX_MODEL_INPUT_LENGTH).div_ceil(step) + 1
-                };
-
-                let mut embeddings = Vec::with_capacity(num_windows);
-                let mut pos = 0;
-
-                while pos < input_ids.len() {
-                    let end = (pos + MAX_MODEL_INPUT_LENGTH).min(input_ids.len());
+                let windows = windows(input_ids.len(), MAX_MODEL_INPUT_LENGTH, OVERLAP);
+                let mut embeddings = Vec::with_capacity(windows.len());
+
+                for &(pos, end) in &windows {
                     let window_input_ids = &input_ids[pos..end];
                     let window_attention_mask = &attention_mask[pos..end];
 
@@ -285,36 +613,14 @@ impl EmbeddingService {
 
                     let embedding = model.forward(input_ids_i64, attention_mask_i64)?;
                     embeddings.push(embedding);
-
-                    if end >= input_ids.len() {
-                        break;
-                    }
-                    pos += MAX_MODEL_INPUT_LENGTH - OVERLAP;
                 }
 
-                let embedding_len = embeddings[0].len();
-                let mut averaged = vec![0.0; embedding_len];
-
-                for embedding in &embeddings {
-                    for (i, &val) in embedding.iter().enumerate() {
-                        averaged[i] += val;
-                    }
-                }
-
-                #[allow(clippy::cast_precision_loss)] // SAFETY: we ain losing any data
-                let count = embeddings.len() as f32;
-                for val in &mut averaged {
-                    *val /= count;
-                }
-
-                let norm = averaged.iter().map(|&x| x * x).sum::<f32>().sqrt();
-                if norm > 1e-6 {
-                    for val in &mut averaged {
-                        *val /= norm;
-                    }
-                }
+                let weights: Vec<f32> = windows
+                    .iter()
+                    .map(|&(start, end)| window_weighting.weight(&attention_mask[start..end]))
+                    .collect();
 
-                return Ok(averaged);
+                return Ok(combine_windows(&embeddings, &weights));
             }
 
             let mut padded_input_ids = input_ids.to_vec();
@@ -329,8 +635,165 @@ impl EmbeddingService {
             model.forward(input_ids_i64, attention_mask_i64)
         })
         .await
-        .map_err(|e| ApiError::Embedding(format!("Task join error: {e}")))?
+        .map_err(|e| ApiError::Embedding(format!("Task join error: {e}")))??;
+
+        Embedding::try_from_vec(embedding)
     }
 
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_vectors_of_embedding_dim() {
+        let values: Vec<f32> = (0..EMBEDDING_DIM).map(|i| i as f32).collect();
+        let embedding = Embedding::try_from_vec(values.clone()).unwrap();
+
+        assert_eq!(embedding.as_slice(), values.as_slice());
+        assert!(!embedding.is_zero());
+        assert_eq!(pgvector::Vector::from(embedding).to_vec(), values);
+        assert!(Embedding::zeros().is_zero());
+    }
+
+    #[test]
+    fn window_counts_around_the_model_limit() {
+        let max = MAX_MODEL_INPUT_LENGTH;
+        let step = max - OVERLAP;
+
+        assert_eq!(windows(511, max, OVERLAP), [(0, 511)]);
+        assert_eq!(windows(512, max, OVERLAP), [(0, 512)]);
+        assert_eq!(windows(513, max, OVERLAP), [(0, 512), (step, 513)]);
+        assert_eq!(windows(576, max, OVERLAP), [(0, 512), (step, 576)]);
+        assert_eq!(windows(1024, max, OVERLAP), [(0, 512), (step, step + max), (2 * step, 1024)]);
+
+        for len in [513, 576, 960, 961, 1024, 5000] {
+            let windows = windows(len, max, OVERLAP);
+            assert_eq!(windows.first().map(|w| w.0), Some(0));
+            assert_eq!(windows.last().map(|w| w.1), Some(len), "len {len}");
+            assert!(windows.iter().all(|&(start, end)| end - start <= max));
+            // No window is redundant: dropping the last one would leave tokens uncovered.
+            assert!(windows[windows.len() - 2].1 < len, "len {len} has an extra window");
+        }
+    }
+
+    #[test]
+    fn combined_windows_are_unit_norm() {
+        for len in [511, 512, 513, 576, 1024] {
+            let windows = windows(len, MAX_MODEL_INPUT_LENGTH, OVERLAP);
+            let embeddings: Vec<Vec<f32>> = (0..windows.len())
+                .map(|w| (0..EMBEDDING_DIM).map(|i| ((i + w * 7) % 13) as f32 - 6.0).collect())
+                .collect();
+            let combined = combine_windows(&embeddings, &vec![1.0; windows.len()]);
+
+            let norm = combined.iter().map(|x| x * x).sum::<f32>().sqrt();
+            assert_eq!(combined.len(), EMBEDDING_DIM);
+            assert!((norm - 1.0).abs() < 1e-5, "len {len} gave norm {norm}");
+        }
+    }
+
+    #[test]
+    fn token_count_weighting_leans_toward_the_longer_window() {
+        let mut full = vec![0.0; EMBEDDING_DIM];
+        full[0] = 1.0;
+        let mut tail = vec![0.0; EMBEDDING_DIM];
+        tail[1] = 1.0;
+        let embeddings = [full, tail];
+
+        let full_mask = vec![1; MAX_MODEL_INPUT_LENGTH];
+        let mut tail_mask = vec![1; 20];
+        tail_mask.resize(MAX_MODEL_INPUT_LENGTH, 0);
+        let weights = |weighting: WindowWeighting| {
+            [weighting.weight(&full_mask), weighting.weight(&tail_mask)]
+        };
+
+        assert_eq!(weights(WindowWeighting::TokenCount), [512.0, 20.0]);
+        assert_eq!(weights(WindowWeighting::Equal), [1.0, 1.0]);
+
+        let equal = combine_windows(&embeddings, &weights(WindowWeighting::Equal));
+        assert!((equal[0] - equal[1]).abs() < 1e-6);
+
+        let weighted = combine_windows(&embeddings, &weights(WindowWeighting::TokenCount));
+        assert!(weighted[0] > 0.99 && weighted[1] < 0.05, "got {:?}", &weighted[..2]);
+        assert!((weighted[1] / weighted[0] - 20.0 / 512.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rejects_other_lengths() {
+        for len in [0, EMBEDDING_DIM - 1, EMBEDDING_DIM + 1, EMBEDDING_DIM * 2] {
+            let err = Embedding::try_from(vec![0.5; len]).unwrap_err();
+            assert!(matches!(err, ApiError::Embedding(_)), "length {len} gave {err:?}");
+        }
+    }
+
+    #[test]
+    fn pooling_strategies_match_hand_computed_vectors() {
+        // Three tokens of two dimensions; the third is padding and must be ignored.
+        let hidden = ndarray::ArrayD::from_shape_vec(
+            IxDyn(&[1, 3, 2]),
+            vec![3.0, 4.0, 1.0, -2.0, 100.0, 100.0],
+        )
+        .unwrap();
+        let mask = [1, 1, 0];
+        let pooled = |strategy: PoolingStrategy| strategy.pool(&hidden.view(), &mask).to_vec();
+
+        assert_eq!(pooled(PoolingStrategy::Mean), [2.0, 1.0]);
+        assert_eq!(pooled(PoolingStrategy::Max), [3.0, 4.0]);
+        assert_eq!(pooled(PoolingStrategy::MeanMax), [2.5, 2.5]);
+        assert_eq!(pooled(PoolingStrategy::Cls), [3.0, 4.0]);
+
+        let normalized = |strategy: PoolingStrategy| normalize(strategy.pool(&hidden.view(), &mask));
+        let close = |got: Vec<f32>, want: [f32; 2]| {
+            got.iter().zip(want).all(|(g, w)| (g - w).abs() < 1e-6)
+        };
+        assert!(close(normalized(PoolingStrategy::Mean), [2.0 / 5f32.sqrt(), 1.0 / 5f32.sqrt()]));
+        assert!(close(normalized(PoolingStrategy::Max), [0.6, 0.8]));
+        assert!(close(normalized(PoolingStrategy::MeanMax), [0.5f32.sqrt(), 0.5f32.sqrt()]));
+        assert!(close(normalized(PoolingStrategy::Cls), [0.6, 0.8]));
+    }
+
+    #[test]
+    fn fully_masked_input_pools_to_zeros() {
+        let hidden = ndarray::ArrayD::from_shape_vec(IxDyn(&[1, 2, 2]), vec![1.0, 2.0, 3.0, 4.0]).unwrap();
+
+        for strategy in [PoolingStrategy::Mean, PoolingStrategy::Max, PoolingStrategy::MeanMax] {
+            let pooled = strategy.pool(&hidden.view(), &[0, 0]);
+            assert_eq!(normalize(pooled), [0.0, 0.0], "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn concurrency_is_clamped_to_half_of_available_memory() {
+        const MIB: u64 = 1024 * 1024;
+        let per_slot = DEFAULT_MEMORY_PER_SLOT_MB * MIB;
+
+        // 2 GiB available leaves 1 GiB for inference: four 256 MiB slots.
+        assert_eq!(clamp_to_memory(16, 2048 * MIB, per_slot), 4);
+        assert_eq!(clamp_to_memory(16, 64 * 1024 * MIB, per_slot), 16);
+        assert_eq!(clamp_to_memory(16, 100 * MIB, per_slot), 1);
+        assert_eq!(clamp_to_memory(16, 2048 * MIB, 0), 16);
+    }
+
+    /// Needs the ONNX Runtime library and the real model; skipped otherwise.
+    #[tokio::test]
+    async fn warmup_leaves_its_text_cached() {
+        // Loading a missing ONNX Runtime library panics rather than erroring.
+        let Ok(Ok(service)) = std::panic::catch_unwind(|| EmbeddingService::new(false)) else {
+            eprintln!("embedding model not available, skipping");
+            return;
+        };
+
+        service.warmup().await;
+        let key = CacheKey(WARMUP_TEXT.to_owned());
+        let warmed_at = service.cache.lock().get(&key).map(|entry| entry.created_at);
+        assert!(warmed_at.is_some(), "warmup did not reach the model");
+
+        let embedding = service.embed_text(WARMUP_TEXT).await.unwrap();
+        let cache = service.cache.lock();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(&key).map(|entry| entry.created_at), warmed_at);
+        assert_eq!(cache[&key].embedding.as_slice(), embedding.as_slice());
+    }
+}
@@ -1,23 +1,147 @@
+use crate::utils::config::Config;
 use crate::utils::error::{ApiError, Result};
+use crate::utils::pagination::Paginated;
 use crate::utils::modal::{
     CommentsResponse, DevlogsResponse, HackatimeRateLimitError, HackatimeResponse,
     LeaderboardResponse, ProjectsResponse, RawLeaderboardEntry,
 };
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
-use tokio::time::sleep;
+use tokio::sync::Mutex;
+use tokio::time::{Instant, sleep, sleep_until};
 
-use reqwest::{Client, ClientBuilder, cookie::Jar};
+use reqwest::{Client, ClientBuilder, Response, StatusCode, cookie::Jar, header};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheValidators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+static HOST_PACERS: OnceLock<std::sync::Mutex<HashMap<String, Arc<RequestPacer>>>> = OnceLock::new();
+static GLOBAL_RESPONSE_CACHE: OnceLock<Arc<ResponseCache>> = OnceLock::new();
+static PAGE_VALIDATORS: OnceLock<std::sync::Mutex<HashMap<String, CacheValidators>>> = OnceLock::new();
+static PENDING_PAGE_VALIDATORS: OnceLock<std::sync::Mutex<HashMap<String, CacheValidators>>> = OnceLock::new();
+
+/// Request budget for one upstream host, shared by every `ExternalApiService`
+/// in the process, so that host sees at most `rate_per_second` requests (after
+/// an initial `burst`) no matter how many tasks are fetching.
+pub struct RequestPacer {
+    interval: Option<Duration>,
+    burst_allowance: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl RequestPacer {
+    pub fn new(rate_per_second: f64, burst: u32) -> Self {
+        let interval = (rate_per_second > 0.0).then(|| Duration::from_secs_f64(1.0 / rate_per_second));
+        Self {
+            interval,
+            burst_allowance: interval.unwrap_or_default() * burst.saturating_sub(1),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// The pacer for `host` (`name:port`). Hosts are paced independently, so a
+    /// slow Hackatime budget never holds back Summer page fetches.
+    pub fn for_host(host: &str, rate_per_second: f64, burst: u32) -> Arc<Self> {
+        let mut pacers = HOST_PACERS.get_or_init(Default::default).lock().unwrap();
+        Arc::clone(
+            pacers
+                .entry(host.to_owned())
+                .or_insert_with(|| Arc::new(Self::new(rate_per_second, burst))),
+        )
+    }
+
+    pub async fn wait(&self) {
+        let Some(interval) = self.interval else {
+            return;
+        };
+
+        let slot = {
+            let mut next_slot = self.next_slot.lock().await;
+            let now = Instant::now();
+            let slot = next_slot
+                .checked_sub(self.burst_allowance)
+                .map_or(now, |earliest| earliest.max(now));
+            *next_slot = (*next_slot).max(now) + interval;
+            slot
+        };
+
+        sleep_until(slot).await;
+    }
+}
+
+const MAX_CACHED_RESPONSES: usize = 256;
+
+/// Short-lived cache of raw response bodies keyed by URL, so jobs that walk
+/// overlapping pages in quick succession don't fetch them twice.
+pub struct ResponseCache {
+    ttl: Duration,
+    entries: std::sync::Mutex<HashMap<String, (Instant, Arc<str>)>>,
+}
+
+impl ResponseCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn global(ttl: Duration) -> Arc<Self> {
+        Arc::clone(GLOBAL_RESPONSE_CACHE.get_or_init(|| Arc::new(Self::new(ttl))))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    pub fn get(&self, url: &str) -> Option<Arc<str>> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(url)
+            .filter(|(stored_at, _)| stored_at.elapsed() < self.ttl)
+            .map(|(_, body)| Arc::clone(body))
+    }
+
+    pub fn insert(&self, url: &str, body: Arc<str>) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_CACHED_RESPONSES {
+            entries.retain(|_, (stored_at, _)| stored_at.elapsed() < self.ttl);
+            if entries.len() >= MAX_CACHED_RESPONSES {
+                return;
+            }
+        }
+        entries.insert(url.to_owned(), (Instant::now(), body));
+    }
+}
 
 #[derive(Clone)]
 pub struct ExternalApiService {
     client: Client,
     journey_session_cookie: String,
+    rate_per_second: f64,
+    rate_burst: u32,
+    max_response_bytes: usize,
+    response_cache: Arc<ResponseCache>,
+    summer_base_url: String,
+    explorpheus_base_url: String,
+    hackatime_base_url: String,
 }
 
 impl ExternalApiService {
-    pub fn new(journey_session_cookie: String) -> Result<Self> {
+    pub fn new(config: &Config) -> Result<Self> {
         let jar = Arc::new(Jar::default());
 
         let client = ClientBuilder::new()
@@ -29,45 +153,64 @@ impl ExternalApiService {
 
         Ok(Self {
             client,
-            journey_session_cookie,
+            journey_session_cookie: config.journey_session_cookie.clone(),
+            rate_per_second: config.external_rate_per_second,
+            rate_burst: config.external_rate_burst,
+            max_response_bytes: config.external_max_response_bytes,
+            response_cache: ResponseCache::global(Duration::from_secs(
+                config.external_cache_ttl_seconds,
+            )),
+            summer_base_url: config.summer_api_base_url.trim_end_matches('/').to_owned(),
+            explorpheus_base_url: config.explorpheus_base_url.trim_end_matches('/').to_owned(),
+            hackatime_base_url: config.hackatime_api_base_url.trim_end_matches('/').to_owned(),
         })
     }
 
     pub async fn fetch_projects(&self, page: Option<i32>) -> Result<ProjectsResponse> {
-        let mut url = "https://summer.hackclub.com/api/v1/projects".to_string();
-        if let Some(page) = page {
-            url.push_str(&format!("?page={}", page));
-        }
-        self.fetch_with_retry(&url).await
+        self.fetch_with_retry(&self.page_url("projects", page)).await
     }
 
     pub async fn fetch_devlogs(&self, page: Option<i32>) -> Result<DevlogsResponse> {
-        let mut url = "https://summer.hackclub.com/api/v1/devlogs".to_string();
-        if let Some(page) = page {
-            url.push_str(&format!("?page={}", page));
-        }
-        self.fetch_with_retry(&url).await
+        self.fetch_with_retry(&self.page_url("devlogs", page)).await
     }
 
     pub async fn fetch_comments(&self, page: Option<i32>) -> Result<CommentsResponse> {
-        let mut url = "https://summer.hackclub.com/api/v1/comments".to_string();
+        self.fetch_with_retry(&self.page_url("comments", page)).await
+    }
+
+    pub async fn fetch_projects_if_modified(&self, page: i32) -> Result<Option<ProjectsResponse>> {
+        self.fetch_page_if_modified(&self.page_url("projects", Some(page))).await
+    }
+
+    pub async fn fetch_devlogs_if_modified(&self, page: i32) -> Result<Option<DevlogsResponse>> {
+        self.fetch_page_if_modified(&self.page_url("devlogs", Some(page))).await
+    }
+
+    pub async fn fetch_comments_if_modified(&self, page: i32) -> Result<Option<CommentsResponse>> {
+        self.fetch_page_if_modified(&self.page_url("comments", Some(page))).await
+    }
+
+    fn page_url(&self, resource: &str, page: Option<i32>) -> String {
+        let mut url = format!("{}/{}", self.summer_base_url, resource);
         if let Some(page) = page {
             url.push_str(&format!("?page={}", page));
         }
-        self.fetch_with_retry(&url).await
+        url
     }
 
-    pub async fn fetch_leaderboard(&self) -> Result<LeaderboardResponse> {
-        let url = "https://explorpheus.hackclub.com/leaderboard?historicalData=true";
-        let users: Vec<RawLeaderboardEntry> = self.fetch_with_retry(url).await?;
-        Ok(LeaderboardResponse { users })
+    pub async fn fetch_leaderboard(
+        &self,
+        validators: Option<&CacheValidators>,
+    ) -> Result<Option<(LeaderboardResponse, CacheValidators)>> {
+        let url = format!("{}/leaderboard?historicalData=true", self.explorpheus_base_url);
+        let response: Option<(Vec<RawLeaderboardEntry>, CacheValidators)> =
+            self.fetch_conditional_with_retry(&url, validators).await?;
+        Ok(response.map(|(users, validators)| (LeaderboardResponse { users }, validators)))
     }
 
     pub async fn fetch_user_stats(&self, slack_id: &str) -> Result<Option<HackatimeResponse>> {
-        let url = format!(
-            "https://hackatime.hackclub.com/api/v1/users/{}/stats",
-            slack_id
-        );
+        let url = format!("{}/users/{}/stats", self.hackatime_base_url, slack_id);
+        self.pace(&url).await;
         let response = self
             .client
             .get(&url)
@@ -78,9 +221,7 @@ impl ExternalApiService {
             return Ok(None);
         }
         if response.status() == 429 {
-            let response_text = response.text().await.map_err(|e| {
-                ApiError::ExternalApi(format!("Failed to read rate limit response: {}", e))
-            })?;
+            let response_text = self.read_body(response).await?;
             let rate_limit_error: HackatimeRateLimitError = serde_json::from_str(&response_text)
                 .map_err(|e| {
                     ApiError::ExternalApi(format!("Failed to parse rate limit response: {}", e))
@@ -92,8 +233,8 @@ impl ExternalApiService {
         }
         if !response.status().is_success() {
             let status = response.status();
-            let body = response
-                .text()
+            let body = self
+                .read_body(response)
                 .await
                 .unwrap_or_else(|_| "Unable to read response body".to_string());
             return Err(ApiError::ExternalApi(format!(
@@ -101,26 +242,169 @@ impl ExternalApiService {
                 status, body
             )));
         }
-        let stats_response = response.json::<HackatimeResponse>().await.map_err(|e| {
+        let response_text = self.read_body(response).await?;
+        let stats_response: HackatimeResponse = serde_json::from_str(&response_text).map_err(|e| {
             ApiError::ExternalApi(format!("Failed to parse hackatime stats response: {}", e))
         })?;
         Ok(Some(stats_response))
     }
 
+    async fn pace(&self, url: &str) {
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| Some(format!("{}:{}", url.host_str()?, url.port_or_known_default()?)))
+            .unwrap_or_default();
+        RequestPacer::for_host(&host, self.rate_per_second, self.rate_burst)
+            .wait()
+            .await;
+    }
+
+    async fn read_body(&self, mut response: Response) -> Result<String> {
+        let url = response.url().clone();
+        let too_large = || {
+            ApiError::ExternalApi(format!(
+                "Response from {} exceeded the {} byte limit",
+                url, self.max_response_bytes
+            ))
+        };
+
+        if response
+            .content_length()
+            .is_some_and(|len| len > self.max_response_bytes as u64)
+        {
+            return Err(too_large());
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| ApiError::ExternalApi(format!("Failed to read response body: {}", e)))?
+        {
+            if body.len() + chunk.len() > self.max_response_bytes {
+                return Err(too_large());
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        String::from_utf8(body)
+            .map_err(|e| ApiError::ExternalApi(format!("Response body was not valid UTF-8: {}", e)))
+    }
+
+    /// Validators from a 200 are held as pending until [`Self::commit_page_validators`]
+    /// is called, so a run that fails before storing the page refetches it in full.
+    /// Empty pages never get validators: they mark the end of the data, and a
+    /// 304 there would look like an unchanged page to keep paging past.
+    async fn fetch_page_if_modified<T>(&self, url: &str) -> Result<Option<T>>
+    where
+        T: for<'de> serde::Deserialize<'de> + Paginated,
+    {
+        let known = PAGE_VALIDATORS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap()
+            .get(url)
+            .cloned();
+
+        let Some((body, new_validators)) = self.fetch_conditional_with_retry::<T>(url, known.as_ref()).await? else {
+            tracing::debug!("{} not modified since last fetch, skipping", url);
+            return Ok(None);
+        };
+
+        if new_validators != CacheValidators::default() && !body.is_empty() {
+            PENDING_PAGE_VALIDATORS
+                .get_or_init(Default::default)
+                .lock()
+                .unwrap()
+                .insert(url.to_owned(), new_validators);
+        }
+
+        Ok(Some(body))
+    }
+
+    /// Makes validators from pages fetched since the last commit or discard
+    /// count for later conditional requests. Call once the pages' items are stored.
+    pub fn commit_page_validators(&self) {
+        let pending = std::mem::take(&mut *PENDING_PAGE_VALIDATORS.get_or_init(Default::default).lock().unwrap());
+        PAGE_VALIDATORS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap()
+            .extend(pending);
+    }
+
+    pub fn discard_page_validators(&self) {
+        PENDING_PAGE_VALIDATORS.get_or_init(Default::default).lock().unwrap().clear();
+    }
+
     async fn fetch_with_retry<T>(&self, url: &str) -> Result<T>
     where
         T: for<'de> serde::Deserialize<'de>,
     {
+        let body = match self.response_cache.get(url) {
+            Some(body) => {
+                tracing::debug!("Serving {} from response cache", url);
+                body
+            }
+            None => {
+                let (body, _) = self
+                    .fetch_text_with_retry(url, None)
+                    .await?
+                    .ok_or_else(|| ApiError::ExternalApi(format!("Unexpected 304 Not Modified from {}", url)))?;
+                let body: Arc<str> = body.into();
+                self.response_cache.insert(url, Arc::clone(&body));
+                body
+            }
+        };
+
+        Self::parse_body(&body)
+    }
+
+    async fn fetch_conditional_with_retry<T>(
+        &self,
+        url: &str,
+        validators: Option<&CacheValidators>,
+    ) -> Result<Option<(T, CacheValidators)>>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        self.fetch_text_with_retry(url, validators)
+            .await?
+            .map(|(body, validators)| Self::parse_body(&body).map(|body| (body, validators)))
+            .transpose()
+    }
+
+    fn parse_body<T>(body: &str) -> Result<T>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        serde_json::from_str(body)
+            .map_err(|e| ApiError::ExternalApi(format!("Failed to parse API response: {}", e)))
+    }
+
+    async fn fetch_text_with_retry(
+        &self,
+        url: &str,
+        validators: Option<&CacheValidators>,
+    ) -> Result<Option<(String, CacheValidators)>> {
         let mut backoff_ms = 1000;
         
         for attempt in 1..=5 {
-            let response = self
+            let mut request = self
                 .client
                 .get(url)
                 .header("Cookie", format!("_journey_session={}", self.journey_session_cookie))
-                .timeout(Duration::from_secs(30))
-                .send()
-                .await;
+                .timeout(Duration::from_secs(30));
+            if let Some(validators) = validators {
+                if let Some(etag) = &validators.etag {
+                    request = request.header(header::IF_NONE_MATCH, etag);
+                }
+                if let Some(last_modified) = &validators.last_modified {
+                    request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+                }
+            }
+            self.pace(url).await;
+            let response = request.send().await;
                 
             match response {
                 Ok(response) => {
@@ -134,8 +418,11 @@ impl ExternalApiService {
                     }
                     
                     let status = response.status();
+                    if status == StatusCode::NOT_MODIFIED {
+                        return Ok(None);
+                    }
                     if !status.is_success() {
-                        let body = response.text().await
+                        let body = self.read_body(response).await
                             .unwrap_or_else(|_| "Unable to read response body".to_string());
                             
                         return match status.as_u16() {
@@ -156,10 +443,19 @@ impl ExternalApiService {
                         };
                     }
                     
-                    let response_text = response.text().await
-                        .map_err(|e| ApiError::ExternalApi(format!("Failed to read response body: {}", e)))?;
-                    return serde_json::from_str(&response_text)
-                        .map_err(|e| ApiError::ExternalApi(format!("Failed to parse API response: {}", e)));
+                    let header_value = |name: header::HeaderName| {
+                        response.headers()
+                            .get(name)
+                            .and_then(|v| v.to_str().ok())
+                            .map(str::to_owned)
+                    };
+                    let new_validators = CacheValidators {
+                        etag: header_value(header::ETAG),
+                        last_modified: header_value(header::LAST_MODIFIED),
+                    };
+
+                    let response_text = self.read_body(response).await?;
+                    return Ok(Some((response_text, new_validators)));
                 }
                 Err(e) if attempt < 5 && (e.is_timeout() || e.is_connect()) => {
                     let delay = Duration::from_millis(backoff_ms);
@@ -175,3 +471,276 @@ impl ExternalApiService {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::modal::RawProject;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use axum::{
+        Router,
+        http::{HeaderMap, StatusCode as MockStatus},
+        response::IntoResponse,
+        routing::get,
+    };
+
+    /// Serves `app` on an ephemeral local port and returns its base URL.
+    async fn serve(app: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        format!("http://{addr}")
+    }
+
+    /// A service with every upstream pointed at `base_url`.
+    fn service(base_url: &str, configure: impl FnOnce(&mut Config)) -> ExternalApiService {
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let mut config = Config {
+            summer_api_base_url: base_url.to_owned(),
+            explorpheus_base_url: base_url.to_owned(),
+            hackatime_api_base_url: base_url.to_owned(),
+            external_max_response_bytes: 1 << 20,
+            ..Config::default()
+        };
+        configure(&mut config);
+        ExternalApiService::new(&config).unwrap()
+    }
+
+    fn empty_projects(hits: Arc<AtomicUsize>) -> Router {
+        Router::new().route(
+            "/projects",
+            get(move || {
+                hits.fetch_add(1, Ordering::SeqCst);
+                async { r#"{"projects": [], "pagination": null}"# }
+            }),
+        )
+    }
+
+    /// A one-project page served with an ETag, answering `304` to requests
+    /// that already hold it.
+    fn etagged_projects(hits: Arc<AtomicUsize>) -> Router {
+        Router::new().route(
+            "/projects",
+            get(move |headers: HeaderMap| {
+                hits.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if headers.get(header::IF_NONE_MATCH).is_some_and(|etag| etag == "\"v1\"") {
+                        return MockStatus::NOT_MODIFIED.into_response();
+                    }
+                    let page = serde_json::json!({
+                        "projects": [RawProject { id: 1, ..RawProject::default() }],
+                        "pagination": null,
+                    });
+                    ([(header::ETAG, "\"v1\"")], page.to_string()).into_response()
+                }
+            }),
+        )
+    }
+
+    fn rate_limited(config: &mut Config) {
+        config.external_rate_per_second = 20.0;
+        config.external_rate_burst = 1;
+    }
+
+    #[tokio::test]
+    async fn pacer_spaces_requests_after_the_burst() {
+        let pacer = RequestPacer::new(20.0, 3);
+
+        let started = Instant::now();
+        for _ in 0..3 {
+            pacer.wait().await;
+        }
+        let burst_elapsed = started.elapsed();
+        for _ in 0..5 {
+            pacer.wait().await;
+        }
+        let elapsed = started.elapsed();
+
+        // The first 3 go out at once, the other 5 each wait a 50ms interval.
+        assert!(burst_elapsed < Duration::from_millis(40), "burst was paced: {burst_elapsed:?}");
+        assert!(elapsed >= Duration::from_millis(250), "8 requests at 20 per second took {elapsed:?}");
+    }
+
+    #[tokio::test]
+    async fn zero_rate_disables_pacing() {
+        let pacer = RequestPacer::new(0.0, 1);
+
+        let started = Instant::now();
+        for _ in 0..100 {
+            pacer.wait().await;
+        }
+
+        assert!(started.elapsed() < Duration::from_millis(40));
+    }
+
+    #[tokio::test]
+    async fn services_share_one_budget_per_host() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let base = serve(empty_projects(Arc::clone(&hits))).await;
+        let other_base = serve(empty_projects(Arc::new(AtomicUsize::new(0)))).await;
+
+        // Two jobs, each with its own service, fetching from the same upstream.
+        let jobs = [service(&base, rate_limited), service(&base, rate_limited)];
+        let other_host = service(&other_base, rate_limited);
+
+        let started = Instant::now();
+        let fetches = futures::future::join_all(
+            jobs.iter().flat_map(|job| (0..5).map(move |_| job.fetch_projects(None))),
+        );
+        let other_fetch = async {
+            other_host.fetch_projects(None).await.unwrap();
+            started.elapsed()
+        };
+        let (results, other_elapsed) = tokio::join!(fetches, other_fetch);
+        let elapsed = started.elapsed();
+
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(hits.load(Ordering::SeqCst), 10);
+        // 10 requests at 20 per second: the last starts 9 intervals after the first.
+        assert!(elapsed >= Duration::from_millis(450), "combined rate exceeded the limit: {elapsed:?}");
+        assert!(other_elapsed < Duration::from_millis(200), "another host waited on this one: {other_elapsed:?}");
+    }
+
+    #[tokio::test]
+    async fn not_modified_page_is_skipped_once_committed() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let base = serve(etagged_projects(Arc::clone(&hits))).await;
+        let external = service(&base, |_| {});
+
+        let first = external.fetch_projects_if_modified(1).await.unwrap();
+        assert_eq!(first.map(|page| page.projects.len()), Some(1));
+
+        // Not committed yet: the page is fetched in full again.
+        external.discard_page_validators();
+        let refetched = external.fetch_projects_if_modified(1).await.unwrap();
+        assert!(refetched.is_some());
+
+        external.commit_page_validators();
+        let unchanged = external.fetch_projects_if_modified(1).await.unwrap();
+        assert!(unchanged.is_none());
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn not_modified_leaderboard_is_none() {
+        let base = serve(Router::new().route(
+            "/leaderboard",
+            get(|headers: HeaderMap| async move {
+                if headers.get(header::IF_NONE_MATCH).is_some_and(|etag| etag == "\"lb1\"") {
+                    return MockStatus::NOT_MODIFIED.into_response();
+                }
+                ([(header::ETAG, "\"lb1\"")], "[]").into_response()
+            }),
+        ))
+        .await;
+        let external = service(&base, |_| {});
+
+        let (_, validators) = external.fetch_leaderboard(None).await.unwrap().unwrap();
+        assert_eq!(validators.etag.as_deref(), Some("\"lb1\""));
+
+        assert!(external.fetch_leaderboard(Some(&validators)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn oversized_responses_are_rejected() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&hits);
+        let base = serve(
+            Router::new()
+                .route(
+                    "/projects",
+                    get(move || {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        async { "x".repeat(4096) }
+                    }),
+                )
+                // Chunked, so the size is only known once the body is read.
+                .route(
+                    "/devlogs",
+                    get(|| async {
+                        let chunks = (0..8).map(|_| Ok::<_, std::io::Error>(bytes::Bytes::from(vec![b' '; 512])));
+                        axum::body::Body::from_stream(futures::stream::iter(chunks))
+                    }),
+                ),
+        )
+        .await;
+        let external = service(&base, |config| config.external_max_response_bytes = 1024);
+
+        let declared = external.fetch_projects(None).await.unwrap_err().to_string();
+        let streamed = external.fetch_devlogs(None).await.unwrap_err().to_string();
+
+        assert!(declared.contains("exceeded the 1024 byte limit"), "{declared}");
+        assert!(streamed.contains("exceeded the 1024 byte limit"), "{streamed}");
+        assert_eq!(hits.load(Ordering::SeqCst), 1, "an oversized response was retried");
+    }
+
+    #[tokio::test]
+    async fn repeat_fetches_within_the_ttl_hit_the_cache() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let base = serve(empty_projects(Arc::clone(&hits))).await;
+        let cached = |ttl| ExternalApiService {
+            response_cache: Arc::new(ResponseCache::new(ttl)),
+            ..service(&base, |_| {})
+        };
+
+        let external = cached(Duration::from_secs(60));
+        external.fetch_projects(Some(1)).await.unwrap();
+        external.fetch_projects(Some(1)).await.unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        external.fetch_projects(Some(2)).await.unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 2, "a different page was served from the cache");
+
+        let short_lived = cached(Duration::from_millis(50));
+        short_lived.fetch_projects(Some(3)).await.unwrap();
+        sleep(Duration::from_millis(80)).await;
+        short_lived.fetch_projects(Some(3)).await.unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 4, "an expired entry was served");
+    }
+
+    #[tokio::test]
+    async fn fetches_go_to_the_configured_base_urls() {
+        let summer_hits = Arc::new(AtomicUsize::new(0));
+        let explorpheus_hits = Arc::new(AtomicUsize::new(0));
+        let hackatime_hits = Arc::new(AtomicUsize::new(0));
+        let counted = |hits: &Arc<AtomicUsize>, body: &'static str| {
+            let hits = Arc::clone(hits);
+            get(move || {
+                hits.fetch_add(1, Ordering::SeqCst);
+                async move { body }
+            })
+        };
+        let summer = serve(Router::new().route(
+            "/staging/api/v1/comments",
+            counted(&summer_hits, r#"{"comments": [], "pagination": null}"#),
+        ))
+        .await;
+        let explorpheus = serve(Router::new().route("/leaderboard", counted(&explorpheus_hits, "[]"))).await;
+        let hackatime = serve(Router::new().route(
+            "/api/v1/users/{slack_id}/stats",
+            get({
+                let hits = Arc::clone(&hackatime_hits);
+                move || {
+                    hits.fetch_add(1, Ordering::SeqCst);
+                    async { MockStatus::NOT_FOUND }
+                }
+            }),
+        ))
+        .await;
+
+        let external = service("http://unused.invalid", |config| {
+            config.summer_api_base_url = format!("{summer}/staging/api/v1/");
+            config.explorpheus_base_url = explorpheus.clone();
+            config.hackatime_api_base_url = format!("{hackatime}/api/v1");
+        });
+
+        external.fetch_comments(None).await.unwrap();
+        external.fetch_leaderboard(None).await.unwrap();
+        assert!(external.fetch_user_stats("U123").await.unwrap().is_none());
+
+        assert_eq!(summer_hits.load(Ordering::SeqCst), 1);
+        assert_eq!(explorpheus_hits.load(Ordering::SeqCst), 1);
+        assert_eq!(hackatime_hits.load(Ordering::SeqCst), 1);
+    }
+}
@@ -1,5 +1,8 @@
 pub mod external;
 pub mod embedding;
 
-pub use embedding::EmbeddingService;
-pub use external::ExternalApiService;
+pub use embedding::{
+    Embedding, EmbeddingService, PoolingStrategy, WindowWeighting, embed_concurrency_override,
+    memory_aware_concurrency, project_embedding_text_from_parts,
+};
+pub use external::{CacheValidators, ExternalApiService};
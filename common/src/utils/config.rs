@@ -17,9 +17,40 @@ pub struct Config {
     pub skip_comments_sync: bool,
     pub skip_leaderboard_sync: bool,
     pub slack_token: String,
+    pub slack_tokens: Vec<String>,
+    pub slack_concurrency: usize,
+    pub trace_batch_size: i64,
     pub embedding_cache_size: usize,
     pub embedding_cache_ttl_seconds: u64,
     pub embedding_max_concurrent_requests: usize,
+    pub search_max_concurrency: usize,
+    pub search_cache_ttl_seconds: u64,
+    pub search_rate_limit_per_minute: u32,
+    pub trusted_proxies: Vec<std::net::IpAddr>,
+    pub request_timeout_seconds: u64,
+    pub max_concurrent_requests: usize,
+    pub confidence_calibration: String,
+    pub confidence_baseline: f64,
+    pub confidence_sigmoid_steepness: f64,
+    pub confidence_decimals: u32,
+    pub debug_endpoints: bool,
+    pub admin_api_key: Option<String>,
+    pub idempotency_ttl_seconds: u64,
+    pub external_rate_per_second: f64,
+    pub external_rate_burst: u32,
+    pub external_max_response_bytes: usize,
+    pub external_cache_ttl_seconds: u64,
+    pub summer_api_base_url: String,
+    pub explorpheus_base_url: String,
+    pub hackatime_api_base_url: String,
+    pub slack_api_base_url: String,
+    pub maintenance_mode: bool,
+    pub maintenance_file: Option<String>,
+    pub maintenance_retry_after_seconds: u64,
+    pub sync_stale_after_seconds: u64,
+    pub cors_max_age_seconds: u64,
+    pub db_acquire_timeout_ms: u64,
+    pub hide_comment_serial_id: bool,
 }
 
 impl Config {
@@ -39,9 +70,57 @@ impl Config {
             skip_comments_sync: Self::parse_env("SKIP_COMMENTS_SYNC", "false")?,
             skip_leaderboard_sync: Self::parse_env("SKIP_LEADERBOARD_SYNC", "false")?,
             slack_token: env::var("SLACK_TOKEN").unwrap_or_default(),
+            slack_tokens: env::var("SLACK_TOKENS")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|token| !token.is_empty())
+                .map(str::to_owned)
+                .collect(),
+            slack_concurrency: Self::parse_env("SLACK_CONCURRENCY", "0")?,
+            trace_batch_size: Self::parse_positive_env("TRACE_BATCH_SIZE", "100")?,
             embedding_cache_size: Self::parse_env("EMBEDDING_CACHE_SIZE", "1000")?,
             embedding_cache_ttl_seconds: Self::parse_env("EMBEDDING_CACHE_TTL_SECONDS", "3600")?,
             embedding_max_concurrent_requests: Self::parse_env("EMBEDDING_MAX_CONCURRENT_REQUESTS", "16")?,
+            search_max_concurrency: Self::parse_env("SEARCH_MAX_CONCURRENCY", "32")?,
+            search_cache_ttl_seconds: Self::parse_env("SEARCH_CACHE_TTL_SECONDS", "60")?,
+            search_rate_limit_per_minute: Self::parse_env("SEARCH_RATE_LIMIT_PER_MINUTE", "60")?,
+            trusted_proxies: env::var("TRUSTED_PROXIES")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|proxy| !proxy.is_empty())
+                .map(|proxy| {
+                    proxy
+                        .parse()
+                        .map_err(|_| ApiError::Config(format!("Invalid TRUSTED_PROXIES entry '{}'", proxy)))
+                })
+                .collect::<Result<_>>()?,
+            request_timeout_seconds: Self::parse_env("REQUEST_TIMEOUT_SECONDS", "30")?,
+            max_concurrent_requests: Self::parse_env("MAX_CONCURRENT_REQUESTS", "256")?,
+            confidence_calibration: Self::parse_env("CONFIDENCE_CALIBRATION", "none")?,
+            confidence_baseline: Self::parse_env("CONFIDENCE_BASELINE", "0.3")?,
+            confidence_sigmoid_steepness: Self::parse_env("CONFIDENCE_SIGMOID_STEEPNESS", "10")?,
+            confidence_decimals: Self::parse_env("CONFIDENCE_DECIMALS", "4")?,
+            debug_endpoints: env::var("DEBUG_ENDPOINTS")
+                .is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true")),
+            admin_api_key: env::var("ADMIN_API_KEY").ok().filter(|v| !v.is_empty()),
+            idempotency_ttl_seconds: Self::parse_env("IDEMPOTENCY_TTL_SECONDS", "3600")?,
+            external_rate_per_second: Self::parse_env("EXTERNAL_RATE_PER_SECOND", "5")?,
+            external_rate_burst: Self::parse_env("EXTERNAL_RATE_BURST", "1")?,
+            external_max_response_bytes: Self::parse_env("EXTERNAL_MAX_RESPONSE_BYTES", "67108864")?,
+            external_cache_ttl_seconds: Self::parse_env("EXTERNAL_CACHE_TTL_SECONDS", "0")?,
+            summer_api_base_url: Self::parse_env("SUMMER_API_BASE_URL", "https://summer.hackclub.com/api/v1")?,
+            explorpheus_base_url: Self::parse_env("EXPLORPHEUS_BASE_URL", "https://explorpheus.hackclub.com")?,
+            hackatime_api_base_url: Self::parse_env("HACKATIME_API_BASE_URL", "https://hackatime.hackclub.com/api/v1")?,
+            slack_api_base_url: Self::parse_env("SLACK_API_BASE_URL", "https://slack.com/api")?,
+            maintenance_mode: Self::parse_env("MAINTENANCE_MODE", "false")?,
+            maintenance_file: env::var("MAINTENANCE_FILE").ok().filter(|v| !v.is_empty()),
+            maintenance_retry_after_seconds: Self::parse_env("MAINTENANCE_RETRY_AFTER_SECONDS", "300")?,
+            sync_stale_after_seconds: Self::parse_env("SYNC_STALE_AFTER_SECONDS", "0")?,
+            cors_max_age_seconds: Self::parse_env("CORS_MAX_AGE_SECONDS", "3600")?,
+            db_acquire_timeout_ms: Self::parse_env("DB_ACQUIRE_TIMEOUT_MS", "2000")?,
+            hide_comment_serial_id: Self::parse_env("HIDE_COMMENT_SERIAL_ID", "false")?,
         })
     }
 
@@ -60,4 +139,26 @@ impl Config {
             .parse()
             .map_err(|_| ApiError::Config(format!("Invalid {}", key)))
     }
+
+    fn parse_positive_env(key: &str, default: &str) -> Result<i64> {
+        match Self::parse_env(key, default)? {
+            value if value > 0 => Ok(value),
+            _ => Err(ApiError::Config(format!("{} must be at least 1", key))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positive_settings_reject_zero() {
+        assert_eq!(Config::parse_positive_env("CONFIG_TEST_UNSET_POSITIVE", "100").unwrap(), 100);
+        assert!(matches!(
+            Config::parse_positive_env("CONFIG_TEST_UNSET_POSITIVE", "0"),
+            Err(ApiError::Config(message)) if message.contains("CONFIG_TEST_UNSET_POSITIVE")
+        ));
+        assert!(Config::parse_positive_env("CONFIG_TEST_UNSET_POSITIVE", "-5").is_err());
+    }
 }
@@ -28,6 +28,21 @@ pub enum ApiError {
 
     #[error("Rate limit exceeded: {message}")]
     RateLimit { retry_after: u64, message: String },
+
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
+
+    #[error("Request timed out: {0}")]
+    GatewayTimeout(String),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Unprocessable request: {0}")]
+    Unprocessable(String),
 }
 
 impl ApiError {
@@ -38,6 +53,11 @@ impl ApiError {
     const CONFIG_ERROR: &'static str = "CONFIG_ERROR";
     const NOT_FOUND: &'static str = "NOT_FOUND";
     const RATE_LIMITED: &'static str = "RATE_LIMITED";
+    const SERVICE_UNAVAILABLE: &'static str = "SERVICE_UNAVAILABLE";
+    const GATEWAY_TIMEOUT: &'static str = "GATEWAY_TIMEOUT";
+    const UNAUTHORIZED: &'static str = "UNAUTHORIZED";
+    const CONFLICT: &'static str = "CONFLICT";
+    const UNPROCESSABLE: &'static str = "UNPROCESSABLE";
 }
 
 impl IntoResponse for ApiError {
@@ -82,6 +102,31 @@ impl IntoResponse for ApiError {
                 format!("{resource} with id {id} not found"),
                 Self::NOT_FOUND,
             ),
+            Self::ServiceUnavailable(msg) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                msg.clone(),
+                Self::SERVICE_UNAVAILABLE,
+            ),
+            Self::GatewayTimeout(msg) => (
+                StatusCode::GATEWAY_TIMEOUT,
+                msg.clone(),
+                Self::GATEWAY_TIMEOUT,
+            ),
+            Self::Unauthorized(msg) => (
+                StatusCode::UNAUTHORIZED,
+                msg.clone(),
+                Self::UNAUTHORIZED,
+            ),
+            Self::Conflict(msg) => (
+                StatusCode::CONFLICT,
+                msg.clone(),
+                Self::CONFLICT,
+            ),
+            Self::Unprocessable(msg) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                msg.clone(),
+                Self::UNPROCESSABLE,
+            ),
             Self::RateLimit {
                 retry_after,
                 message,
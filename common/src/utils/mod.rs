@@ -1,8 +1,11 @@
 pub mod error;
 pub mod modal;
+pub mod pagination;
 pub mod certs;
 pub mod config;
+pub mod types;
 
 pub use config::Config;
 pub use error::{Result, ApiError};
 pub use modal::PaginatedResponse;
+pub use types::{SlackId, SyncKey};
@@ -1,4 +1,6 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use super::types::SlackId;
 
 #[derive(Debug, Deserialize)]
 pub struct PaginationInfo {
@@ -19,13 +21,16 @@ pub struct ProjectsResponse {
     pub pagination: Option<PaginationInfo>,
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct RawProject {
     pub id: i64,
     pub title: String,
     pub description: Option<String>,
     pub readme_link: Option<String>,
-    pub slack_id: String,
+    pub category: Option<String>,
+    pub demo_link: Option<String>,
+    pub repo_link: Option<String>,
+    pub slack_id: SlackId,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -35,12 +40,13 @@ pub struct DevlogsResponse {
     pub pagination: Option<PaginationInfo>,
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct RawDevlog {
     pub id: i64,
     pub text: String,
+    pub attachment: Option<String>,
     pub project_id: i64,
-    pub slack_id: String,
+    pub slack_id: SlackId,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -50,11 +56,14 @@ pub struct CommentsResponse {
     pub pagination: Option<PaginationInfo>,
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct RawComment {
+    /// Absent from the current API; stored as `comments.upstream_id` when sent.
+    #[serde(default)]
+    pub id: Option<i64>,
     pub text: String,
     pub devlog_id: i64,
-    pub slack_id: String,
+    pub slack_id: SlackId,
     pub created_at: String,
 }
 #[derive(Debug, Deserialize)]
@@ -87,7 +96,7 @@ pub struct LeaderboardResponse {
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct RawLeaderboardEntry {
-    pub slack_id: String,
+    pub slack_id: SlackId,
     pub username: Option<String>,
     pub shells: i32,
     pub payouts: Option<Vec<RawPayout>>,
@@ -0,0 +1,495 @@
+use std::{
+    collections::HashSet,
+    future::{Future, ready},
+    hash::Hash,
+};
+
+use futures::{
+    Stream, StreamExt,
+    future::Either,
+    stream,
+};
+
+use super::modal::{CommentsResponse, DevlogsResponse, ProjectsResponse, RawComment, RawDevlog, RawProject};
+use super::types::SlackId;
+
+pub trait Paginated {
+    type Item;
+
+    fn total_pages(&self) -> Option<i32>;
+    fn is_empty(&self) -> bool;
+    fn into_items(self) -> Vec<Self::Item>;
+
+    /// Whether the upstream reported this page as unchanged since the last
+    /// fetch, in which case its items are not known rather than absent.
+    fn is_unchanged(&self) -> bool {
+        false
+    }
+}
+
+impl Paginated for ProjectsResponse {
+    type Item = RawProject;
+
+    fn total_pages(&self) -> Option<i32> {
+        self.pagination.as_ref().and_then(|p| p.pages)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.projects.is_empty()
+    }
+
+    fn into_items(self) -> Vec<Self::Item> {
+        self.projects
+    }
+}
+
+impl Paginated for DevlogsResponse {
+    type Item = RawDevlog;
+
+    fn total_pages(&self) -> Option<i32> {
+        self.pagination.as_ref().and_then(|p| p.pages)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.devlogs.is_empty()
+    }
+
+    fn into_items(self) -> Vec<Self::Item> {
+        self.devlogs
+    }
+}
+
+impl Paginated for CommentsResponse {
+    type Item = RawComment;
+
+    fn total_pages(&self) -> Option<i32> {
+        self.pagination.as_ref().and_then(|p| p.pages)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.comments.is_empty()
+    }
+
+    fn into_items(self) -> Vec<Self::Item> {
+        self.comments
+    }
+}
+
+// A `None` page is one the upstream reported as unchanged (304).
+impl<R: Paginated> Paginated for Option<R> {
+    type Item = R::Item;
+
+    fn total_pages(&self) -> Option<i32> {
+        self.as_ref().and_then(Paginated::total_pages)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.as_ref().is_none_or(Paginated::is_empty)
+    }
+
+    fn into_items(self) -> Vec<Self::Item> {
+        self.map(Paginated::into_items).unwrap_or_default()
+    }
+
+    fn is_unchanged(&self) -> bool {
+        self.as_ref().is_none_or(Paginated::is_unchanged)
+    }
+}
+
+/// Identity of an upstream item, used to drop repeats when offset pagination
+/// shifts under a long-running sync.
+pub trait PageItem {
+    type Key: Hash + Eq;
+
+    fn key(&self) -> Self::Key;
+}
+
+impl PageItem for RawProject {
+    type Key = i64;
+
+    fn key(&self) -> i64 {
+        self.id
+    }
+}
+
+impl PageItem for RawDevlog {
+    type Key = i64;
+
+    fn key(&self) -> i64 {
+        self.id
+    }
+}
+
+impl PageItem for RawComment {
+    type Key = (i64, SlackId);
+
+    fn key(&self) -> Self::Key {
+        (self.devlog_id, self.slack_id.clone())
+    }
+}
+
+/// Appends `items` to `out`, skipping any whose key was already seen.
+/// Returns the number of duplicates skipped.
+pub fn extend_unique<T: PageItem>(
+    out: &mut Vec<T>,
+    seen: &mut HashSet<T::Key>,
+    items: impl IntoIterator<Item = T>,
+) -> usize {
+    let mut duplicates = 0;
+    for item in items {
+        if seen.insert(item.key()) {
+            out.push(item);
+        } else {
+            duplicates += 1;
+        }
+    }
+    duplicates
+}
+
+#[derive(Debug)]
+pub struct Page<T> {
+    pub number: i32,
+    pub total_pages: Option<i32>,
+    pub items: Vec<T>,
+    pub unchanged: bool,
+}
+
+impl<T> Page<T> {
+    fn new<R: Paginated<Item = T>>(number: i32, response: R) -> Self {
+        Self {
+            number,
+            total_pages: response.total_pages(),
+            unchanged: response.is_unchanged(),
+            items: response.into_items(),
+        }
+    }
+
+    // An unchanged page says nothing about whether later pages exist.
+    fn is_last(&self, last_allowed: i32) -> bool {
+        (self.items.is_empty() && !self.unchanged)
+            || self.number >= last_allowed
+            || self.total_pages.is_some_and(|total| self.number >= total)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PaginateOptions {
+    pub start_page: i32,
+    pub max_pages: Option<i32>,
+    pub concurrency: usize,
+}
+
+impl Default for PaginateOptions {
+    fn default() -> Self {
+        Self {
+            start_page: 1,
+            max_pages: None,
+            concurrency: 1,
+        }
+    }
+}
+
+/// Walks a paginated source starting at `opts.start_page`.
+///
+/// The first page is always fetched on its own. If it reports a total page
+/// count the remaining pages are fetched with up to `opts.concurrency`
+/// requests in flight (in completion order, errors do not stop the stream);
+/// otherwise pages are fetched one at a time until an empty page or an error.
+pub fn paginate<R, E, F, Fut>(
+    fetch_page: F,
+    opts: PaginateOptions,
+) -> impl Stream<Item = Result<Page<R::Item>, E>>
+where
+    R: Paginated,
+    F: Fn(i32) -> Fut + Clone,
+    Fut: Future<Output = Result<R, E>>,
+{
+    let start = opts.start_page;
+    let last_allowed = opts
+        .max_pages
+        .map_or(i32::MAX, |max| start.saturating_add(max.max(1) - 1));
+    let first_fetch = fetch_page.clone();
+
+    stream::once(async move { first_fetch(start).await.map(|r| Page::new(start, r)) }).flat_map(
+        move |first| {
+            let rest = match &first {
+                Ok(page) if page.is_last(last_allowed) => Either::Left(stream::empty()),
+                Ok(page) => match page.total_pages {
+                    Some(total) => Either::Right(Either::Left(fetch_concurrently(
+                        fetch_page.clone(),
+                        start + 1,
+                        total.min(last_allowed),
+                        opts.concurrency,
+                    ))),
+                    None => Either::Right(Either::Right(fetch_sequentially(
+                        fetch_page.clone(),
+                        start + 1,
+                        last_allowed,
+                    ))),
+                },
+                Err(_) => Either::Left(stream::empty()),
+            };
+
+            stream::once(ready(first)).chain(rest)
+        },
+    )
+}
+
+fn fetch_concurrently<R, E, F, Fut>(
+    fetch_page: F,
+    from: i32,
+    to: i32,
+    concurrency: usize,
+) -> impl Stream<Item = Result<Page<R::Item>, E>>
+where
+    R: Paginated,
+    F: Fn(i32) -> Fut + Clone,
+    Fut: Future<Output = Result<R, E>>,
+{
+    stream::iter(from..=to)
+        .map(move |number| {
+            let fetch_page = fetch_page.clone();
+            async move { fetch_page(number).await.map(|r| Page::new(number, r)) }
+        })
+        .buffer_unordered(concurrency.max(1))
+}
+
+fn fetch_sequentially<R, E, F, Fut>(
+    fetch_page: F,
+    from: i32,
+    last_allowed: i32,
+) -> impl Stream<Item = Result<Page<R::Item>, E>>
+where
+    R: Paginated,
+    F: Fn(i32) -> Fut + Clone,
+    Fut: Future<Output = Result<R, E>>,
+{
+    stream::unfold(Some(from), move |next| {
+        let fetch_page = fetch_page.clone();
+        async move {
+            let number = next?;
+            match fetch_page(number).await {
+                Ok(response) => {
+                    let page = Page::new(number, response);
+                    let next = (!page.is_last(last_allowed)).then_some(number + 1);
+                    Some((Ok(page), next))
+                }
+                Err(e) => Some((Err(e), None)),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    };
+    use std::time::Duration;
+
+    /// A page of a fake source: `items` are the ids on the page.
+    struct FakePage {
+        pages: Option<i32>,
+        items: Vec<i32>,
+    }
+
+    impl Paginated for FakePage {
+        type Item = i32;
+
+        fn total_pages(&self) -> Option<i32> {
+            self.pages
+        }
+
+        fn is_empty(&self) -> bool {
+            self.items.is_empty()
+        }
+
+        fn into_items(self) -> Vec<i32> {
+            self.items
+        }
+    }
+
+    /// A source of `total` pages of two items each, recording every page
+    /// requested. `reports_total` controls whether pages carry the count.
+    fn source(
+        total: i32,
+        reports_total: bool,
+        requested: Arc<Mutex<Vec<i32>>>,
+    ) -> impl Fn(i32) -> std::future::Ready<Result<FakePage, String>> + Clone {
+        move |number| {
+            requested.lock().unwrap().push(number);
+            let items = if number <= total { vec![number * 10, number * 10 + 1] } else { Vec::new() };
+            ready(Ok(FakePage {
+                pages: reports_total.then_some(total),
+                items,
+            }))
+        }
+    }
+
+    async fn page_numbers<T, E: std::fmt::Debug>(
+        pages: impl Stream<Item = Result<Page<T>, E>>,
+    ) -> Vec<i32> {
+        let mut numbers: Vec<i32> = pages.map(|page| page.unwrap().number).collect().await;
+        numbers.sort_unstable();
+        numbers
+    }
+
+    #[tokio::test]
+    async fn fetches_every_reported_page_once() {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let opts = PaginateOptions {
+            concurrency: 3,
+            ..PaginateOptions::default()
+        };
+
+        let pages: Vec<_> = paginate(source(5, true, requested.clone()), opts).collect().await;
+        let mut items: Vec<i32> = pages.into_iter().flat_map(|page| page.unwrap().items).collect();
+        items.sort_unstable();
+
+        let mut requested = requested.lock().unwrap().clone();
+        requested.sort_unstable();
+        assert_eq!(requested, [1, 2, 3, 4, 5]);
+        assert_eq!(items, [10, 11, 20, 21, 30, 31, 40, 41, 50, 51]);
+    }
+
+    #[tokio::test]
+    async fn start_page_and_max_pages_bound_the_walk() {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let opts = PaginateOptions {
+            start_page: 3,
+            max_pages: Some(2),
+            concurrency: 2,
+        };
+
+        let numbers = page_numbers(paginate(source(10, true, requested.clone()), opts)).await;
+
+        assert_eq!(numbers, [3, 4]);
+        assert_eq!(requested.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn concurrent_fetches_stay_within_the_limit() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let fetch_page = {
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            move |number: i32| {
+                let in_flight = in_flight.clone();
+                let max_in_flight = max_in_flight.clone();
+                async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    Ok::<_, String>(FakePage {
+                        pages: Some(8),
+                        items: vec![number],
+                    })
+                }
+            }
+        };
+        let opts = PaginateOptions {
+            concurrency: 3,
+            ..PaginateOptions::default()
+        };
+
+        let numbers = page_numbers(paginate(fetch_page, opts)).await;
+
+        assert_eq!(numbers, [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn failed_first_page_ends_the_walk() {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let fetch_page = {
+            let requested = requested.clone();
+            move |number: i32| {
+                requested.lock().unwrap().push(number);
+                ready(Err::<FakePage, _>("upstream down".to_string()))
+            }
+        };
+
+        let pages: Vec<_> = paginate(fetch_page, PaginateOptions::default()).collect().await;
+
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].as_ref().unwrap_err(), "upstream down");
+        assert_eq!(*requested.lock().unwrap(), [1]);
+    }
+
+    #[tokio::test]
+    async fn failed_later_page_does_not_stop_the_others() {
+        let fetch_page = |number: i32| {
+            ready(if number == 2 {
+                Err("page 2 failed".to_string())
+            } else {
+                Ok(FakePage {
+                    pages: Some(4),
+                    items: vec![number],
+                })
+            })
+        };
+        let opts = PaginateOptions {
+            concurrency: 2,
+            ..PaginateOptions::default()
+        };
+
+        let pages: Vec<_> = paginate(fetch_page, opts).collect().await;
+        let mut fetched: Vec<i32> = pages.iter().filter_map(|page| page.as_ref().ok()).map(|page| page.number).collect();
+        fetched.sort_unstable();
+
+        assert_eq!(pages.len(), 4);
+        assert_eq!(fetched, [1, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn missing_page_count_fetches_until_an_empty_page() {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let opts = PaginateOptions {
+            concurrency: 4,
+            ..PaginateOptions::default()
+        };
+
+        let numbers = page_numbers(paginate(source(3, false, requested.clone()), opts)).await;
+
+        assert_eq!(numbers, [1, 2, 3, 4]);
+        assert_eq!(*requested.lock().unwrap(), [1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn missing_page_count_still_honours_max_pages() {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let opts = PaginateOptions {
+            max_pages: Some(2),
+            ..PaginateOptions::default()
+        };
+
+        let numbers = page_numbers(paginate(source(10, false, requested.clone()), opts)).await;
+
+        assert_eq!(numbers, [1, 2]);
+        assert_eq!(*requested.lock().unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn unchanged_page_is_not_the_last_without_a_page_count() {
+        let unchanged = Page::<i32> {
+            number: 2,
+            total_pages: None,
+            items: Vec::new(),
+            unchanged: true,
+        };
+        let empty = Page::<i32> {
+            number: 2,
+            total_pages: None,
+            items: Vec::new(),
+            unchanged: false,
+        };
+
+        assert!(!unchanged.is_last(i32::MAX));
+        assert!(unchanged.is_last(2));
+        assert!(empty.is_last(i32::MAX));
+    }
+}
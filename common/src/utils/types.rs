@@ -0,0 +1,149 @@
+use std::{error::Error, fmt};
+
+use bytes::BytesMut;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::types::{FromSql, IsNull, ToSql, Type, to_sql_checked};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SlackId(String);
+
+impl SlackId {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Display for SlackId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for SlackId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SlackId {
+    fn from(id: String) -> Self {
+        Self(id)
+    }
+}
+
+impl From<&str> for SlackId {
+    fn from(id: &str) -> Self {
+        Self(id.to_owned())
+    }
+}
+
+impl From<SlackId> for String {
+    fn from(id: SlackId) -> Self {
+        id.0
+    }
+}
+
+impl ToSql for SlackId {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        self.0.to_sql(ty, out)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <String as ToSql>::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
+impl<'a> FromSql<'a> for SlackId {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        String::from_sql(ty, raw).map(Self)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <String as FromSql>::accepts(ty)
+    }
+}
+
+/// The `sync_metadata` keys oculus advances as it ingests each resource.
+/// Shared with the explorer so both agree on which keys exist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyncKey {
+    Projects,
+    Comments,
+    Devlogs,
+}
+
+impl SyncKey {
+    pub const ALL: [SyncKey; 3] = [Self::Projects, Self::Comments, Self::Devlogs];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Projects => "projects",
+            Self::Comments => "comments",
+            Self::Devlogs => "devlogs",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == key)
+    }
+}
+
+impl fmt::Display for SyncKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_like_the_raw_string() {
+        let raw = "U012AB3CD";
+        let id = SlackId::new(raw);
+
+        assert_eq!(serde_json::to_string(&id).unwrap(), serde_json::to_string(raw).unwrap());
+        assert_eq!(serde_json::from_str::<SlackId>("\"U012AB3CD\"").unwrap(), id);
+        assert_eq!(serde_json::to_string(&Some(id.clone())).unwrap(), "\"U012AB3CD\"");
+        assert_eq!(serde_json::to_string(&None::<SlackId>).unwrap(), "null");
+    }
+
+    #[test]
+    fn encodes_to_postgres_like_the_raw_string() {
+        let raw = String::from("U012AB3CD");
+        let (mut from_id, mut from_raw) = (BytesMut::new(), BytesMut::new());
+
+        SlackId::new(raw.clone()).to_sql(&Type::TEXT, &mut from_id).unwrap();
+        raw.to_sql(&Type::TEXT, &mut from_raw).unwrap();
+
+        assert_eq!(from_id, from_raw);
+        assert_eq!(SlackId::from_sql(&Type::VARCHAR, &from_raw).unwrap().as_str(), raw);
+        assert!(<SlackId as ToSql>::accepts(&Type::VARCHAR));
+        assert!(!<SlackId as ToSql>::accepts(&Type::INT8));
+    }
+
+    #[test]
+    fn every_sync_key_round_trips_through_its_string() {
+        for key in SyncKey::ALL {
+            assert_eq!(SyncKey::from_key(key.as_str()), Some(key));
+            assert_eq!(key.to_string(), key.as_str());
+        }
+
+        let names: std::collections::HashSet<_> = SyncKey::ALL.iter().map(|key| key.as_str()).collect();
+        assert_eq!(names.len(), SyncKey::ALL.len());
+        assert_eq!(SyncKey::from_key("devlog"), None);
+        assert_eq!(SyncKey::from_key("Projects"), None);
+    }
+}
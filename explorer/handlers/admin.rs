@@ -0,0 +1,312 @@
+use axum::{
+    Extension, Json,
+    extract::{Query, State},
+};
+use serde_json::json;
+use tokio_postgres::Client;
+
+use crate::AppState;
+use crate::models::admin::{
+    AuditEntry, AuditLogFilter, ResetSyncRequest, ResetSyncResponse, SyncStatus, SyncStatusResponse,
+};
+use crate::services::audit::{record_admin_action, AdminActor};
+use crate::utils::database::{decode_username, map_audit_entry_row, try_column, QueryBuilder};
+use crate::utils::error::{ApiError, Result};
+
+#[utoipa::path(
+    post,
+    path = "/v1/admin/users/reset-sync",
+    request_body = ResetSyncRequest,
+    responses(
+        (status = 200, description = "Number of users queued for re-sync", body = ResetSyncResponse),
+        (status = 400, description = "No users selected"),
+        (status = 401, description = "Missing or invalid admin API key")
+    ),
+    tag = "admin"
+)]
+pub async fn reset_user_sync(
+    State(state): State<AppState>,
+    Extension(actor): Extension<AdminActor>,
+    Json(request): Json<ResetSyncRequest>,
+) -> Result<Json<ResetSyncResponse>> {
+    let mut client = state.db().await?;
+    let reset = reset_users(&mut client, &actor, request).await?;
+
+    tracing::info!("Reset sync state for {} users", reset);
+
+    Ok(Json(ResetSyncResponse { reset }))
+}
+
+/// Marks the selected users unresolved so the trace job picks them up again,
+/// recording the reset in the audit log in the same transaction.
+async fn reset_users(client: &mut Client, actor: &AdminActor, request: ResetSyncRequest) -> Result<u64> {
+    let mut query_builder = QueryBuilder::new();
+    let details = json!({ "slackIds": request.slack_ids, "username": request.username });
+
+    if let Some(slack_ids) = request.slack_ids.filter(|ids| !ids.is_empty()) {
+        query_builder.add_condition("slack_id = ANY(${})", slack_ids);
+    }
+
+    if let Some(username) = request.username {
+        query_builder.add_condition("username ILIKE ${}", decode_username(&username));
+    }
+
+    if query_builder.param_count() == 0 {
+        return Err(ApiError::Validation {
+            field: "slackIds".to_string(),
+            message: "Provide slackIds or username to select users to reset".to_string(),
+        });
+    }
+
+    let query = format!(
+        "UPDATE users 
+         SET pfp_url = 'notfound', trust_level = 'unavailable', last_synced = 'epoch' 
+         {}",
+        query_builder.build_where_clause()
+    );
+
+    let transaction = client.transaction().await?;
+    let reset = transaction.execute(&query, &query_builder.params()).await?;
+
+    let mut details = details;
+    details["reset"] = json!(reset);
+    record_admin_action(&transaction, actor, "users.reset_sync", None, details).await?;
+    transaction.commit().await?;
+
+    Ok(reset)
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/admin/audit",
+    params(AuditLogFilter),
+    responses(
+        (status = 200, description = "Most recent admin actions", body = [AuditEntry]),
+        (status = 401, description = "Missing or invalid admin API key")
+    ),
+    tag = "admin"
+)]
+pub async fn get_audit_log(
+    State(state): State<AppState>,
+    Query(filter): Query<AuditLogFilter>,
+) -> Result<Json<Vec<AuditEntry>>> {
+    let client = state.db().await?;
+    let mut query_builder = QueryBuilder::new();
+
+    if let Some(action) = filter.action {
+        query_builder.add_condition("action = ${}", action);
+    }
+
+    let limit = i64::from(filter.limit.unwrap_or(50).min(500));
+    let limit_param = query_builder.add_param(limit);
+
+    let where_clause = query_builder.build_where_clause();
+    let params = query_builder.params();
+
+    let query = format!(
+        "SELECT id, at, actor_key_hash, action, target, details_json 
+         FROM audit_log 
+         {} 
+         ORDER BY at DESC 
+         LIMIT ${}",
+        where_clause,
+        limit_param
+    );
+
+    let rows = client.query(&query, &params).await?;
+    let entries = rows.iter().map(map_audit_entry_row).collect::<Result<_>>()?;
+
+    Ok(Json(entries))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/admin/sync-status",
+    responses(
+        (status = 200, description = "Last sync time, page and status per sync key", body = SyncStatusResponse),
+        (status = 401, description = "Missing or invalid admin API key")
+    ),
+    tag = "admin"
+)]
+pub async fn get_sync_status(State(state): State<AppState>) -> Result<Json<SyncStatusResponse>> {
+    let client = state.db().await?;
+    Ok(Json(read_sync_status(&client).await?))
+}
+
+async fn read_sync_status(client: &Client) -> Result<SyncStatusResponse> {
+    let rows = client
+        .query("SELECT key, last_sync, last_page, status FROM sync_metadata", &[])
+        .await?;
+
+    rows.iter()
+        .map(|row| {
+            Ok((
+                try_column(row, "key")?,
+                SyncStatus {
+                    last_sync: try_column(row, "last_sync")?,
+                    last_page: try_column(row, "last_page")?,
+                    status: try_column(row, "status")?,
+                },
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn sync_status_reports_each_metadata_key() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("sync_status_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE sync_metadata (
+                     key VARCHAR(50) PRIMARY KEY, last_sync TIMESTAMPTZ, last_page INTEGER,
+                     status VARCHAR(20) DEFAULT 'pending', etag TEXT, last_modified TEXT
+                 );
+                 INSERT INTO sync_metadata (key, last_sync, last_page, status) VALUES
+                     ('projects', '2026-03-01T12:00:00Z', 42, 'completed'),
+                     ('devlogs', NULL, NULL, 'running');
+                 INSERT INTO sync_metadata (key) VALUES ('comments');"
+            ))
+            .await
+            .unwrap();
+
+        let statuses = read_sync_status(&client).await;
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(statuses.unwrap()).unwrap(),
+            json!({
+                "comments": { "lastSync": null, "lastPage": null, "status": "pending" },
+                "devlogs": { "lastSync": null, "lastPage": null, "status": "running" },
+                "projects": { "lastSync": "2026-03-01T12:00:00Z", "lastPage": 42, "status": "completed" },
+            })
+        );
+    }
+    /// Creates `schema` with the tables a reset touches and three synced users,
+    /// and points `client` at it.
+    async fn reset_sync_schema(client: &Client, schema: &str) {
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE users (
+                     slack_id VARCHAR(50) PRIMARY KEY, username VARCHAR(255), pfp_url VARCHAR(500) DEFAULT 'notfound',
+                     trust_level VARCHAR(50) DEFAULT 'unavailable', last_synced TIMESTAMPTZ
+                 );
+                 CREATE TABLE audit_log (
+                     id BIGSERIAL PRIMARY KEY, at TIMESTAMPTZ NOT NULL DEFAULT NOW(), actor_key_hash VARCHAR(64) NOT NULL,
+                     action VARCHAR(100) NOT NULL, target TEXT, details_json JSONB NOT NULL DEFAULT '{{}}'::jsonb
+                 );
+                 INSERT INTO users (slack_id, username, pfp_url, trust_level, last_synced) VALUES
+                     ('U1', 'ada', 'https://example.com/ada.png', 'green', now()),
+                     ('U2', 'grace', 'https://example.com/grace.png', 'blue', now()),
+                     ('U3', 'linus', 'https://example.com/linus.png', 'green', now());"
+            ))
+            .await
+            .unwrap();
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn reset_users_are_picked_up_by_the_trace_job_again() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (mut client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("reset_sync_test_{}", std::process::id());
+        reset_sync_schema(&client, &schema).await;
+
+        // The trace job's selection of users whose Slack info needs resolving.
+        let needing_info = "SELECT slack_id FROM users \
+             WHERE username IS NULL OR pfp_url = 'notfound' OR trust_level = 'unavailable' \
+             ORDER BY last_synced ASC, slack_id";
+        let before = client.query(needing_info, &[]).await.unwrap();
+        let request = ResetSyncRequest {
+            slack_ids: Some(vec!["U1".into(), "U3".into()]),
+            username: None,
+        };
+        let reset = reset_users(&mut client, &AdminActor::from_key("secret"), request).await;
+        let after = client.query(needing_info, &[]).await.unwrap();
+        let empty = ResetSyncRequest {
+            slack_ids: Some(Vec::new()),
+            username: None,
+        };
+        let rejected = reset_users(&mut client, &AdminActor::from_key("secret"), empty).await;
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert!(before.is_empty());
+        assert_eq!(reset.unwrap(), 2);
+        let after: Vec<String> = after.iter().map(|row| row.get(0)).collect();
+        assert_eq!(after, ["U1", "U3"]);
+        assert!(matches!(rejected, Err(ApiError::Validation { .. })));
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn reset_is_recorded_in_the_audit_log() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (mut client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("reset_audit_test_{}", std::process::id());
+        reset_sync_schema(&client, &schema).await;
+
+        let actor = AdminActor::from_key("secret");
+        let request = ResetSyncRequest {
+            slack_ids: None,
+            username: Some("grace".to_owned()),
+        };
+        reset_users(&mut client, &actor, request).await.unwrap();
+        let rows = client
+            .query("SELECT id, at, actor_key_hash, action, target, details_json FROM audit_log", &[])
+            .await
+            .unwrap();
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        let entries: Vec<AuditEntry> = rows.iter().map(map_audit_entry_row).collect::<Result<_>>().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, "users.reset_sync");
+        assert_eq!(entries[0].actor_key_hash, actor.key_hash);
+        assert_ne!(entries[0].actor_key_hash, "secret");
+        assert_eq!(entries[0].details, json!({ "slackIds": null, "username": "grace", "reset": 1 }));
+    }
+}
@@ -1,13 +1,39 @@
 use axum::Json;
-use pgvector::Vector;
 use tracing::{info, instrument};
+use std::collections::HashMap;
+use tokio_postgres::Client;
+
 use axum::extract::{Query, State};
+use axum::response::{IntoResponse, Response};
 
 use crate::AppState;
-use crate::utils::error::Result;
-use crate::utils::database::{decode_username, map_comment_row, QueryBuilder};
+use crate::models::debug::DebugParams;
+use crate::utils::error::{ApiError, Result};
+use crate::utils::database::{build_order_by, decode_username, like_pattern, explain_query, map_comment_row, map_log_row, try_column, QueryBuilder};
 use crate::models::comment::{Comment, CommentFilter, CommentSearchRequest};
 
+const COMMENT_SORT_COLUMNS: [&str; 2] = ["created_at", "username"];
+
+pub(crate) const COMMENT_SEARCH_SQL: &str = r#"
+    SELECT 
+        id, upstream_id, text, devlog_id, slack_id, username, created_at, last_synced,
+        (1 - (text_embedding <=> $1)) as confidence
+    FROM comments 
+    WHERE text_embedding IS NOT NULL
+    ORDER BY text_embedding <=> $1, id DESC
+    LIMIT $2
+"#;
+
+const COMMENT_FULL_TEXT_SQL: &str = r#"
+    SELECT 
+        id, upstream_id, text, devlog_id, slack_id, username, created_at, last_synced,
+        ts_rank(to_tsvector('english', text), plainto_tsquery('english', $1))::FLOAT8 as rank
+    FROM comments 
+    WHERE to_tsvector('english', text) @@ plainto_tsquery('english', $1)
+    ORDER BY rank DESC, id DESC
+    LIMIT $2
+"#;
+
 #[utoipa::path(
     post,
     path = "/v1/comments/search",
@@ -20,38 +46,51 @@ use crate::models::comment::{Comment, CommentFilter, CommentSearchRequest};
 #[instrument(skip(state), fields(query = %request.query, limit = request.limit.unwrap_or(20)))]
 pub async fn search_comments(
     State(state): State<AppState>,
+    Query(debug_params): Query<DebugParams>,
     Json(request): Json<CommentSearchRequest>,
-) -> Result<Json<Vec<Comment>>> {
-    let embedding_vec = state.embedding_service.embed_text(&request.query).await?;
-    let embedding = Vector::from(embedding_vec);
+) -> Result<Response> {
     let limit = i64::from(request.limit.unwrap_or(20).min(100));
+    if debug_params.explain(state.debug_endpoints) {
+        let embedding = state.embed_query(&request.query).await?;
+        let client = state.db().await?;
+        return explain_query(&client, COMMENT_SEARCH_SQL, &[&embedding, &limit]).await;
+    }
 
-    let client = state.pool.get().await?;
-
-    let rows = client
-        .query(
-            r#"
-            SELECT 
-                id, text, devlog_id, slack_id, username, created_at, last_synced,
-                (1 - (text_embedding <=> $1)) as confidence
-            FROM comments 
-            WHERE text_embedding IS NOT NULL
-            ORDER BY text_embedding <=> $1
-            LIMIT $2
-            "#,
-            &[&embedding, &limit],
-        )
+    let results = state
+        .search_cache
+        .comments
+        .get_or_search(&request.query, limit, || run_comment_search(&state, &request.query, limit))
         .await?;
 
-    let comments = rows
-        .into_iter()
+    Ok(Json(results).into_response())
+}
+
+async fn run_comment_search(state: &AppState, query: &str, limit: i64) -> Result<Vec<Comment>> {
+    let embedding = state.embed_query(query).await?;
+
+    let client = state.db().await?;
+    let rows = client.query(COMMENT_SEARCH_SQL, &[&embedding, &limit]).await?;
+    let full_text_rows = if rows.is_empty() {
+        tracing::debug!("Vector search returned nothing, falling back to full-text search");
+        Some(client.query(COMMENT_FULL_TEXT_SQL, &[&query, &limit]).await?)
+    } else {
+        None
+    };
+    drop(client);
+
+    if let Some(rows) = full_text_rows {
+        return rows
+            .iter()
+            .map(|row| Ok(map_comment_row(row, state.hide_comment_serial_id)?.with_text_rank(state.text_rank(try_column(row, "rank")?))))
+            .collect();
+    }
+
+    rows.iter()
         .map(|row| {
-            let confidence: f64 = row.get("confidence");
-            map_comment_row(&row).with_confidence(confidence)
+            let confidence = state.confidence(try_column(row, "confidence")?);
+            Ok(map_comment_row(row, state.hide_comment_serial_id)?.with_confidence(confidence))
         })
-        .collect();
-
-    Ok(Json(comments))
+        .collect()
 }
 
 #[utoipa::path(
@@ -67,8 +106,9 @@ pub async fn search_comments(
 pub async fn filter_comments(
     State(state): State<AppState>,
     Query(filter): Query<CommentFilter>,
-) -> Result<Json<Vec<Comment>>> {
-    let client = state.pool.get().await?;
+    Query(debug_params): Query<DebugParams>,
+) -> Result<Response> {
+    let client = state.db().await?;
     let mut query_builder = QueryBuilder::new();
 
     if let Some(devlog_id) = filter.devlog_id {
@@ -85,7 +125,10 @@ pub async fn filter_comments(
     }
 
     if let Some(text) = filter.text {
-        query_builder.add_condition("text ILIKE ${}", text);
+        query_builder.add_condition(
+            "text ILIKE ${}",
+            like_pattern(&text, filter.text_match.unwrap_or_default()),
+        );
     }
 
     query_builder.add_date_range_condition(
@@ -94,31 +137,189 @@ pub async fn filter_comments(
         filter.to_date.as_deref()
     )?;
 
+    let order_by = build_order_by(
+        filter.sort_by.as_deref(),
+        filter.sort_dir.as_deref(),
+        &COMMENT_SORT_COLUMNS,
+        "created_at",
+    )?;
+
     let limit = i64::from(filter.limit.unwrap_or(20).min(100));
-    query_builder.add_condition("1=1", limit);
+    let limit_param = query_builder.add_param(limit);
 
     let where_clause = query_builder.build_where_clause();
     let params = query_builder.params();
-    let param_count = query_builder.param_count();
 
     let query = format!(
-        "SELECT id, text, devlog_id, slack_id, username, created_at, last_synced 
+        "SELECT id, upstream_id, text, devlog_id, slack_id, username, created_at, last_synced 
          FROM comments 
          {} 
-         ORDER BY created_at DESC 
+         {} 
          LIMIT ${}", 
         where_clause,
-        param_count
+        order_by,
+        limit_param
     );
 
+    if debug_params.explain(state.debug_endpoints) {
+        return explain_query(&client, &query, &params).await;
+    }
+
     let rows = client.query(&query, &params).await?;
-    let comments: Vec<_> = rows.iter().map(map_comment_row).collect();
+    let comments: Vec<_> = rows.iter().map(|row| map_comment_row(row, state.hide_comment_serial_id)).collect::<Result<_>>()?;
 
     info!(
         results_count = comments.len(),
         "Filter completed successfully"
     );
 
-    Ok(Json(comments))
+    Ok(Json(comments).into_response())
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/comments/details",
+    params(
+        ("id" = Option<i64>, Query, description = "Comment ID (local serial)"),
+        ("upstreamId" = Option<i64>, Query, description = "Comment ID in the upstream API, used instead of `id`")
+    ),
+    responses(
+        (status = 200, description = "Comment with its parent devlog", body = Comment),
+        (status = 400, description = "Neither a valid id nor upstreamId"),
+        (status = 404, description = "Comment not found")
+    ),
+    tag = "comments"
+)]
+pub async fn get_comment_details(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Comment>> {
+    let (field, column) = if params.contains_key("upstreamId") {
+        ("upstreamId", "upstream_id")
+    } else {
+        ("id", "id")
+    };
+    let comment_id = params
+        .get(field)
+        .ok_or_else(|| ApiError::Validation {
+            field: field.to_string(),
+            message: "Missing comment ID".to_string(),
+        })?
+        .parse::<i64>()
+        .map_err(|_| ApiError::Validation {
+            field: field.to_string(),
+            message: "Invalid comment ID".to_string(),
+        })?;
+
+    let client = state.db().await?;
+    let comment = comment_details(&client, column, comment_id, state.hide_comment_serial_id).await?;
+
+    Ok(Json(comment))
+}
+
+/// Looks a comment up by `column` (`id` or `upstream_id`) and attaches its
+/// devlog when that is stored.
+async fn comment_details(client: &Client, column: &str, comment_id: i64, hide_serial_id: bool) -> Result<Comment> {
+    let comment_row = client
+        .query_opt(
+            &format!(
+                r#"
+        SELECT 
+            id, upstream_id, text, devlog_id, slack_id, username, created_at, last_synced
+        FROM comments 
+        WHERE {column} = $1
+        "#
+            ),
+            &[&comment_id],
+        )
+        .await?
+        .ok_or_else(|| ApiError::NotFound {
+            resource: "Comment".to_string(),
+            id: comment_id.to_string(),
+        })?;
+
+    let comment = map_comment_row(&comment_row, hide_serial_id)?;
+
+    let log_row = client
+        .query_opt(
+            r#"
+        SELECT 
+            id, text, attachment, project_id, slack_id, username, 
+            created_at, updated_at, last_synced
+        FROM logs 
+        WHERE id = $1
+        "#,
+            &[&comment.devlog_id],
+        )
+        .await?;
+
+    Ok(match log_row {
+        Some(log_row) => comment.with_devlog(map_log_row(&log_row)?),
+        None => comment,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn details_look_up_by_serial_or_upstream_id() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("comment_details_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE logs (
+                     id BIGINT PRIMARY KEY, text TEXT NOT NULL, attachment VARCHAR(500), project_id BIGINT NOT NULL,
+                     slack_id VARCHAR(50) NOT NULL, username VARCHAR(255), created_at TIMESTAMPTZ DEFAULT now(),
+                     updated_at TIMESTAMPTZ DEFAULT now(), last_synced TIMESTAMPTZ
+                 );
+                 CREATE TABLE comments (
+                     id BIGSERIAL PRIMARY KEY, upstream_id BIGINT UNIQUE, text TEXT NOT NULL, devlog_id BIGINT NOT NULL,
+                     slack_id VARCHAR(50) NOT NULL, username VARCHAR(255), created_at TIMESTAMPTZ DEFAULT now(),
+                     last_synced TIMESTAMPTZ
+                 );
+                 INSERT INTO logs (id, text, project_id, slack_id) VALUES (3, 'shipped it', 1, 'U1');
+                 INSERT INTO comments (upstream_id, text, devlog_id, slack_id) VALUES
+                     (9001, 'first', 3, 'U2'), (9002, 'second', 3, 'U3');"
+            ))
+            .await
+            .unwrap();
+
+        let by_upstream = comment_details(&client, "upstream_id", 9002, false).await;
+        let by_serial = comment_details(&client, "id", 1, false).await;
+        let hidden = comment_details(&client, "upstream_id", 9001, true).await;
+        let missing = comment_details(&client, "upstream_id", 2, false).await;
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        let by_upstream = by_upstream.unwrap();
+        assert_eq!((by_upstream.id, by_upstream.upstream_id), (Some(2), Some(9002)));
+        assert_eq!(by_upstream.text, "second");
+        assert_eq!(by_upstream.devlog.map(|log| log.text).as_deref(), Some("shipped it"));
+
+        let by_serial = by_serial.unwrap();
+        assert_eq!((by_serial.id, by_serial.upstream_id), (Some(1), Some(9001)));
+
+        let hidden = serde_json::to_value(hidden.unwrap()).unwrap();
+        assert!(hidden.get("id").is_none());
+        assert_eq!(hidden["upstream_id"], 9001);
+
+        assert!(matches!(missing, Err(ApiError::NotFound { .. })));
+    }
+}
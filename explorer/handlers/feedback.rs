@@ -0,0 +1,129 @@
+use axum::{Json, extract::State, http::StatusCode};
+
+use crate::AppState;
+use crate::models::feedback::SearchFeedbackRequest;
+use crate::utils::error::{ApiError, Result};
+
+const MAX_FEEDBACK_QUERY_LEN: usize = 1000;
+
+#[utoipa::path(
+    post,
+    path = "/v1/search/feedback",
+    request_body = SearchFeedbackRequest,
+    responses(
+        (status = 202, description = "Feedback accepted for recording"),
+        (status = 400, description = "Invalid feedback payload")
+    ),
+    tag = "search"
+)]
+pub async fn record_search_feedback(
+    State(state): State<AppState>,
+    Json(feedback): Json<SearchFeedbackRequest>,
+) -> Result<StatusCode> {
+    if feedback.query.trim().is_empty() || feedback.query.len() > MAX_FEEDBACK_QUERY_LEN {
+        return Err(ApiError::Validation {
+            field: "query".to_string(),
+            message: format!("Query must be between 1 and {} bytes", MAX_FEEDBACK_QUERY_LEN),
+        });
+    }
+
+    if feedback.rank < 0 {
+        return Err(ApiError::Validation {
+            field: "rank".to_string(),
+            message: "Rank must not be negative".to_string(),
+        });
+    }
+
+    // The caller does not wait on the insert; a lost feedback row is not worth a slow response.
+    let pool = state.pool.clone();
+    tokio::spawn(async move {
+        let result = async {
+            let client = pool.get().await?;
+            insert_feedback(&client, &feedback).await
+        }
+        .await;
+
+        if let Err(e) = result {
+            tracing::warn!("Failed to record search feedback: {}", e);
+        }
+    });
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn insert_feedback(client: &tokio_postgres::Client, feedback: &SearchFeedbackRequest) -> Result<()> {
+    client
+        .execute(
+            "INSERT INTO search_feedback (query, result_type, result_id, rank, clicked) 
+             VALUES ($1, $2, $3, $4, $5)",
+            &[
+                &feedback.query,
+                &feedback.result_type.as_str(),
+                &feedback.result_id,
+                &feedback.rank,
+                &feedback.clicked,
+            ],
+        )
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::feedback::SearchResultType;
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn feedback_is_persisted() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("search_feedback_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 {}",
+                include_str!("../../migrations/006_search_feedback.up.sql")
+            ))
+            .await
+            .unwrap();
+
+        let feedback = SearchFeedbackRequest {
+            query: "tiny robot".to_string(),
+            result_type: SearchResultType::Devlog,
+            result_id: 42,
+            rank: 3,
+            clicked: true,
+        };
+        let inserted = insert_feedback(&client, &feedback).await;
+        let rows = client
+            .query("SELECT query, result_type, result_id, rank, clicked, at FROM search_feedback", &[])
+            .await
+            .unwrap();
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        inserted.unwrap();
+        assert_eq!(rows.len(), 1);
+        let row = &rows[0];
+        assert_eq!(row.get::<_, String>("query"), "tiny robot");
+        assert_eq!(row.get::<_, String>("result_type"), "devlog");
+        assert_eq!(row.get::<_, i64>("result_id"), 42);
+        assert_eq!(row.get::<_, i32>("rank"), 3);
+        assert!(row.get::<_, bool>("clicked"));
+        let age = chrono::Utc::now() - row.get::<_, chrono::DateTime<chrono::Utc>>("at");
+        assert!(age < chrono::Duration::minutes(1), "{age}");
+    }
+}
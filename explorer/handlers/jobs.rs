@@ -0,0 +1,51 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+
+use crate::AppState;
+use crate::models::job::{JobHistoryFilter, JobRun};
+use crate::utils::database::{map_job_run_row, QueryBuilder};
+use crate::utils::error::Result;
+
+#[utoipa::path(
+    get,
+    path = "/v1/jobs/history",
+    params(JobHistoryFilter),
+    responses(
+        (status = 200, description = "Most recent job runs", body = [JobRun])
+    ),
+    tag = "jobs"
+)]
+pub async fn get_job_history(
+    State(state): State<AppState>,
+    Query(filter): Query<JobHistoryFilter>,
+) -> Result<Json<Vec<JobRun>>> {
+    let client = state.db().await?;
+    let mut query_builder = QueryBuilder::new();
+
+    if let Some(job_name) = filter.job_name {
+        query_builder.add_condition("job_name = ${}", job_name);
+    }
+
+    let limit = i64::from(filter.limit.unwrap_or(20).min(100));
+    let limit_param = query_builder.add_param(limit);
+
+    let where_clause = query_builder.build_where_clause();
+    let params = query_builder.params();
+
+    let query = format!(
+        "SELECT id, job_name, started_at, finished_at, status, error_message, items_processed 
+         FROM job_runs 
+         {} 
+         ORDER BY started_at DESC 
+         LIMIT ${}",
+        where_clause,
+        limit_param
+    );
+
+    let rows = client.query(&query, &params).await?;
+    let runs = rows.iter().map(map_job_run_row).collect::<Result<_>>()?;
+
+    Ok(Json(runs))
+}
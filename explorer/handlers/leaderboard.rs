@@ -1,17 +1,91 @@
 use axum::{
     Json,
+    body::{Body, Bytes},
     extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
 };
+use chrono::{DateTime, Utc};
+use futures::{stream, Stream, StreamExt};
 use std::collections::HashMap;
+use tokio_postgres::{types::Json as PgJson, Client, Row};
 
 use crate::{
     AppState,
     models::user::{
-        LeaderboardEntry, LeaderboardResponse, ShellHistory,
+        LeaderboardEntry, LeaderboardMover, LeaderboardMoversFilter, LeaderboardResponse,
+        ShellHistory,
+    },
+    utils::{
+        database::{parse_date_string, try_column, QueryBuilder},
+        error::{ApiError, Result},
     },
-    utils::error::Result,
 };
 
+const STREAM_CHUNK_SIZE: usize = 500;
+
+const CURRENT_RANKING_SQL: &str = r#"
+        SELECT 
+            slack_id,
+            username,
+            pfp_url,
+            current_shells as shells,
+            RANK() OVER (ORDER BY current_shells DESC) as rank
+        FROM users
+        WHERE current_shells > 0
+        ORDER BY current_shells DESC
+        LIMIT $1 OFFSET $2
+        "#;
+
+const CURRENT_COUNT_SQL: &str = "SELECT COUNT(*) AS total FROM users WHERE current_shells > 0";
+
+/// Each user's latest recorded shells, falling back to the current total for
+/// users without history. This is a point in time, never the all-time peak.
+const LATEST_SHELLS_CTE: &str = r#"
+        WITH latest AS (
+            SELECT u.slack_id, u.username, u.pfp_url, COALESCE(sh.shells, u.current_shells) AS shells
+            FROM users u
+            LEFT JOIN LATERAL (
+                SELECT shells FROM shell_history
+                WHERE slack_id = u.slack_id
+                ORDER BY recorded_at DESC
+                LIMIT 1
+            ) sh ON TRUE
+        )"#;
+
+/// Ranks by latest shells and attaches each ranked user's history as a JSON
+/// array, so the page and its histories come back from one streamed query.
+/// The history join runs after `LIMIT` so only the requested page pays for it.
+fn latest_ranking_sql() -> String {
+    format!(
+        "{LATEST_SHELLS_CTE}
+        SELECT r.*, h.shell_history
+        FROM (
+            SELECT slack_id, username, pfp_url, shells, RANK() OVER (ORDER BY shells DESC) as rank
+            FROM latest
+            WHERE shells > 0
+            ORDER BY shells DESC
+            LIMIT $1 OFFSET $2
+        ) r
+        LEFT JOIN LATERAL (
+            SELECT json_agg(json_build_object(
+                'id', id,
+                'shellsThen', shells_then,
+                'shellDiff', shell_diff,
+                'shells', shells,
+                'recorded_at', recorded_at
+            ) ORDER BY recorded_at ASC) AS shell_history
+            FROM shell_history
+            WHERE slack_id = r.slack_id
+        ) h ON TRUE
+        ORDER BY r.rank, r.slack_id"
+    )
+}
+
+fn latest_count_sql() -> String {
+    format!("{LATEST_SHELLS_CTE} SELECT COUNT(*) AS total FROM latest WHERE shells > 0")
+}
+
 #[utoipa::path(
     get,
     path = "/v1/leaderboard",
@@ -19,7 +93,8 @@ use crate::{
         ("pullAll" = Option<bool>, Query, description = "Pull all entries"),
         ("historicalData" = Option<bool>, Query, description = "Include historical data and payouts"),
         ("page" = Option<i32>, Query, description = "Page number"),
-        ("per_page" = Option<i32>, Query, description = "Items per page")
+        ("per_page" = Option<i32>, Query, description = "Items per page"),
+        ("max_history_points" = Option<usize>, Query, description = "Downsample each entry's shell history to at most this many points, keeping the first and last")
     ),
     responses(
         (status = 200, description = "Leaderboard", body = LeaderboardResponse)
@@ -30,7 +105,7 @@ use crate::{
 pub async fn get_leaderboard(
     State(state): State<AppState>,
     Query(params): Query<HashMap<String, String>>,
-) -> Result<Json<LeaderboardResponse>> {
+) -> Result<Response> {
     let pull_all = params.get("pullAll").is_some_and(|v| v == "true");
 
     let historical_data = params
@@ -48,114 +123,457 @@ pub async fn get_leaderboard(
     };
 
     let offset = (page - 1) * per_page;
-
-    let client = state.pool.get().await?;
-    let count_row = client.query_one("SELECT COUNT(*) FROM users WHERE current_shells > 0", &[]).await?;
-    let total_count: i64 = count_row.get(0);
-
-    let mut entries: Vec<LeaderboardEntry> = if historical_data {
-        let rows = client
-            .query(
-                r#"
-            SELECT 
-                u.slack_id,
-                u.username,
-                u.pfp_url,
-                u.current_shells as shells,
-                RANK() OVER (ORDER BY u.current_shells DESC) as rank
-            FROM users u
-            WHERE u.current_shells > 0
-            ORDER BY u.current_shells DESC
-            LIMIT $1 OFFSET $2
-            "#,
-                &[&i64::from(per_page), &i64::from(offset)],
-            )
-            .await?;
-
-        rows.into_iter()
-            .map(|row| LeaderboardEntry {
-                slack_id: row.get("slack_id"),
-                username: row.get("username"),
-                shells: row.get("shells"),
-                rank: row.get("rank"),
-                payouts: None,
-                pfp_url: row.get("pfp_url"),
-                shell_history: None,
+    let max_history_points = params
+        .get("max_history_points")
+        .map(|v| {
+            v.parse::<usize>().map_err(|_| ApiError::Validation {
+                field: "max_history_points".to_string(),
+                message: "max_history_points must be a non-negative integer".to_string(),
             })
-            .collect()
+        })
+        .transpose()?;
+
+    // With historicalData the ranked shells come from the same history that is
+    // attached, so each entry's value matches the end of its series.
+    let (count_sql, ranking_sql) = if historical_data {
+        (latest_count_sql(), latest_ranking_sql())
     } else {
-        let rows = client
-            .query(
-                r#"
-            SELECT 
-                slack_id,
-                username,
-                pfp_url,
-                current_shells as shells,
-                RANK() OVER (ORDER BY current_shells DESC) as rank
-            FROM users
-            WHERE current_shells > 0
-            ORDER BY current_shells DESC
-            LIMIT $1 OFFSET $2
-            "#,
-                &[&i64::from(per_page), &i64::from(offset)],
+        (CURRENT_COUNT_SQL.to_owned(), CURRENT_RANKING_SQL.to_owned())
+    };
+
+    let client = state.db().await?;
+    let count_row = client.query_one(count_sql.as_str(), &[]).await?;
+    let total_count: i64 = try_column(&count_row, "total")?;
+
+    let rows = client
+        .query_raw(ranking_sql.as_str(), [i64::from(per_page), i64::from(offset)])
+        .await?;
+
+    let prefix = format!(
+        r#"{{"total_count":{},"page":{},"per_page":{},"entries":["#,
+        total_count, page, per_page
+    );
+
+    let body = stream_leaderboard_body(client, prefix, rows, historical_data, max_history_points).await?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/json")],
+        body,
+    )
+        .into_response())
+}
+
+/// Streams `rows` as the entries array, rendering `STREAM_CHUNK_SIZE` rows at a
+/// time so memory is bounded by a chunk rather than the whole leaderboard.
+/// `client` owns the connection `rows` is read from and is held until the body
+/// is finished, so the connection is not handed back to the pool mid-stream.
+async fn stream_leaderboard_body<C: Send + 'static>(
+    client: C,
+    prefix: String,
+    rows: impl Stream<Item = std::result::Result<Row, tokio_postgres::Error>> + Send + 'static,
+    with_history: bool,
+    max_history_points: Option<usize>,
+) -> Result<Body> {
+    let mut entries = Box::pin(rows.chunks(STREAM_CHUNK_SIZE).enumerate().map(
+        move |(chunk_idx, chunk)| {
+            render_entries_chunk(chunk, with_history, max_history_points, chunk_idx == 0)
+        },
+    ));
+
+    // Render the first chunk before committing to a 200 so most failures still
+    // produce a proper error response. A failure in a later chunk can only cut
+    // the body short, leaving truncated JSON for the client to reject.
+    let first_chunk = entries.next().await.transpose()?.unwrap_or_default();
+
+    let body = stream::once(async move { Ok(Bytes::from(prefix)) })
+        .chain(stream::once(async move { Ok(first_chunk) }))
+        .chain(entries.inspect(|chunk| {
+            if let Err(e) = chunk {
+                tracing::error!("Leaderboard stream aborted mid-response: {}", e);
+            }
+        }))
+        .chain(stream::once(async move {
+            drop(client);
+            Ok(Bytes::from_static(b"]}"))
+        }));
+
+    Ok(Body::from_stream(body))
+}
+
+fn render_entries_chunk(
+    rows: Vec<std::result::Result<Row, tokio_postgres::Error>>,
+    with_history: bool,
+    max_history_points: Option<usize>,
+    first_chunk: bool,
+) -> Result<Bytes> {
+    let mut buf = Vec::new();
+    for (i, row) in rows.into_iter().enumerate() {
+        let row = row?;
+        let shell_history = if with_history {
+            try_column::<Option<PgJson<Vec<ShellHistory>>>>(&row, "shell_history")?.map(
+                |PgJson(history)| match max_history_points {
+                    Some(max_points) => downsample(history, max_points),
+                    None => history,
+                },
             )
-            .await?;
-
-        rows.into_iter()
-            .map(|row| LeaderboardEntry {
-                slack_id: row.get("slack_id"),
-                username: row.get("username"),
-                shells: row.get("shells"),
-                rank: row.get("rank"),
-                payouts: None,
-                pfp_url: row.get("pfp_url"),
-                shell_history: None,
+        } else {
+            None
+        };
+        let entry = LeaderboardEntry {
+            slack_id: try_column(&row, "slack_id")?,
+            username: try_column(&row, "username")?,
+            shells: try_column(&row, "shells")?,
+            rank: try_column(&row, "rank")?,
+            payouts: None,
+            pfp_url: try_column(&row, "pfp_url")?,
+            shell_history,
+        };
+
+        if i > 0 || !first_chunk {
+            buf.push(b',');
+        }
+        serde_json::to_writer(&mut buf, &entry).map_err(|e| {
+            ApiError::Database(format!("Failed to serialize leaderboard entry: {e}"))
+        })?;
+    }
+
+    Ok(Bytes::from(buf))
+}
+
+/// Keeps at most `max_points` evenly spaced items, always including the first
+/// and last so a chart keeps its start and end values.
+fn downsample<T>(items: Vec<T>, max_points: usize) -> Vec<T> {
+    let len = items.len();
+    if max_points == 0 || len <= max_points {
+        return items;
+    }
+    if max_points == 1 {
+        return items.into_iter().last().into_iter().collect();
+    }
+
+    let mut next_index = 0;
+    let mut picked = 0;
+    items
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, item)| {
+            if i != next_index {
+                return None;
+            }
+            picked += 1;
+            next_index = picked * (len - 1) / (max_points - 1);
+            Some(item)
+        })
+        .collect()
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/leaderboard/movers",
+    params(LeaderboardMoversFilter),
+    responses(
+        (status = 200, description = "Users ordered by shells gained in the window", body = [LeaderboardMover]),
+        (status = 400, description = "Invalid date range")
+    ),
+    tag = "leaderboard"
+)]
+pub async fn get_leaderboard_movers(
+    State(state): State<AppState>,
+    Query(filter): Query<LeaderboardMoversFilter>,
+) -> Result<Json<Vec<LeaderboardMover>>> {
+    let from = filter.from.as_deref().map(parse_date_string).transpose()?;
+    let to = filter.to.as_deref().map(parse_date_string).transpose()?;
+
+    if let (Some(from), Some(to)) = (from, to)
+        && from > to
+    {
+        return Err(ApiError::Validation {
+            field: "from".to_string(),
+            message: "from must not be after to".to_string(),
+        });
+    }
+
+    let limit = i64::from(filter.limit.unwrap_or(20).min(100));
+    let client = state.db().await?;
+
+    Ok(Json(movers_in_window(&client, from, to, limit).await?))
+}
+
+async fn movers_in_window(
+    client: &Client,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    limit: i64,
+) -> Result<Vec<LeaderboardMover>> {
+    let mut query_builder = QueryBuilder::new();
+
+    if let Some(from) = from {
+        query_builder.add_condition("sh.recorded_at >= ${}", from);
+    }
+
+    if let Some(to) = to {
+        query_builder.add_condition("sh.recorded_at <= ${}", to);
+    }
+
+    let limit_param = query_builder.add_param(limit);
+
+    let where_clause = query_builder.build_where_clause();
+    let params = query_builder.params();
+
+    let query = format!(
+        "SELECT sh.slack_id, u.username, u.pfp_url, SUM(sh.shell_diff)::BIGINT AS gained 
+         FROM shell_history sh 
+         JOIN users u ON u.slack_id = sh.slack_id 
+         {} 
+         GROUP BY sh.slack_id, u.username, u.pfp_url 
+         HAVING SUM(sh.shell_diff) IS NOT NULL 
+         ORDER BY gained DESC, sh.slack_id 
+         LIMIT ${}",
+        where_clause,
+        limit_param
+    );
+
+    let rows = client.query(&query, &params).await?;
+    rows.iter()
+        .map(|row| {
+            Ok(LeaderboardMover {
+                slack_id: try_column(row, "slack_id")?,
+                username: try_column(row, "username")?,
+                gained: try_column(row, "gained")?,
+                pfp_url: try_column(row, "pfp_url")?,
             })
-            .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
     };
 
-    if historical_data {
-        let slack_ids: Vec<&String> = entries.iter().map(|e| &e.slack_id).collect();
-
-        if !slack_ids.is_empty() {
-            let all_histories = client.query(
-                "SELECT id, slack_id, shells_then, shell_diff, shells, recorded_at FROM shell_history 
-                 WHERE slack_id = ANY($1) 
-                 ORDER BY slack_id, recorded_at ASC",
-                &[&slack_ids]
-            ).await?;
-
-            let mut histories_by_slack_id: HashMap<String, Vec<ShellHistory>> =
-                HashMap::with_capacity(slack_ids.len());
-
-            for row in all_histories {
-                let hist = ShellHistory {
-                    id: row.get("id"),
-                    shells_then: row.get("shells_then"),
-                    shell_diff: row.get("shell_diff"),
-                    shells: row.get("shells"),
-                    recorded_at: row.get("recorded_at"),
-                };
-                histories_by_slack_id
-                    .entry(row.get("slack_id"))
-                    .or_default()
-                    .push(hist);
-            }
+    #[test]
+    fn downsample_keeps_at_most_max_points_including_endpoints() {
+        let items: Vec<u32> = (0..100).collect();
+
+        for max_points in [2, 3, 7, 10, 99] {
+            let picked = downsample(items.clone(), max_points);
+            assert_eq!(picked.len(), max_points, "max_points = {max_points}");
+            assert_eq!(picked.first(), Some(&0));
+            assert_eq!(picked.last(), Some(&99));
+            assert!(picked.windows(2).all(|pair| pair[0] < pair[1]));
+        }
 
-            for entry in &mut entries {
-                if let Some(hist) = histories_by_slack_id.get(&entry.slack_id) {
-                    entry.shell_history = Some(hist.clone());
+        assert_eq!(downsample(items.clone(), 4), [0, 33, 66, 99]);
+    }
+
+    #[test]
+    fn downsample_leaves_short_histories_alone() {
+        assert_eq!(downsample(vec![1, 2, 3], 3), [1, 2, 3]);
+        assert_eq!(downsample(vec![1, 2, 3], 10), [1, 2, 3]);
+        assert_eq!(downsample(vec![1, 2, 3], 0), [1, 2, 3]);
+        assert_eq!(downsample(Vec::<u32>::new(), 5), Vec::<u32>::new());
+        assert_eq!(downsample(vec![1, 2, 3], 1), [3]);
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn large_leaderboard_streams_one_chunk_at_a_time() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+        let client = Arc::new(client);
+
+        let users = 2 * STREAM_CHUNK_SIZE + 201;
+        let schema = format!("leaderboard_stream_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE users (slack_id TEXT PRIMARY KEY, username TEXT, pfp_url TEXT, current_shells INTEGER);
+                 INSERT INTO users SELECT 'U' || n, 'user' || n, NULL, n FROM generate_series(1, {users}) AS n;"
+            ))
+            .await
+            .unwrap();
+
+        let pulled = Arc::new(AtomicUsize::new(0));
+        let rows = client
+            .query_raw(CURRENT_RANKING_SQL, [users as i64 + 10, 0])
+            .await
+            .unwrap()
+            .inspect({
+                let pulled = Arc::clone(&pulled);
+                move |_| {
+                    pulled.fetch_add(1, Ordering::SeqCst);
                 }
-            }
+            });
+
+        let body = stream_leaderboard_body(Arc::clone(&client), "{\"entries\":[".to_owned(), rows, false, None)
+            .await
+            .unwrap();
+        assert_eq!(pulled.load(Ordering::SeqCst), STREAM_CHUNK_SIZE, "only the first chunk is read up front");
+        assert_eq!(Arc::strong_count(&client), 2, "the body holds the connection while streaming");
+
+        let mut frames = body.into_data_stream();
+        let mut json = Vec::new();
+        let mut largest_frame = 0;
+        let mut pulled_per_frame = Vec::new();
+        while let Some(frame) = frames.next().await {
+            let frame = frame.unwrap();
+            largest_frame = largest_frame.max(frame.len());
+            json.extend_from_slice(&frame);
+            pulled_per_frame.push(pulled.load(Ordering::SeqCst));
         }
+        drop(frames);
+        assert_eq!(Arc::strong_count(&client), 1, "the connection is released once the body is done");
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            pulled_per_frame,
+            [500, 500, 1000, users, users],
+            "rows are read as the body is consumed, not before"
+        );
+        assert!(largest_frame * 2 < json.len(), "one frame held most of the response");
+
+        let parsed: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let entries = parsed["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), users);
+        assert_eq!(entries[0]["slack_id"], format!("U{users}"));
+        assert_eq!(entries[users - 1]["rank"], users as i64);
     }
 
-    Ok(Json(LeaderboardResponse {
-        entries,
-        total_count,
-        page,
-        per_page,
-    }))
-}
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn movers_are_ordered_by_gain_within_the_window() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("leaderboard_movers_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE users (slack_id TEXT PRIMARY KEY, username TEXT, pfp_url TEXT, current_shells INTEGER);
+                 CREATE TABLE shell_history (slack_id TEXT, shell_diff INTEGER, recorded_at TIMESTAMPTZ NOT NULL);
+                 INSERT INTO users VALUES ('U_SLOW', 'slow', NULL, 0), ('U_FAST', 'fast', 'pfp', 0);
+                 INSERT INTO shell_history VALUES
+                     ('U_SLOW', 500, '2026-01-15'), ('U_SLOW', 10, '2026-02-10'), ('U_SLOW', 15, '2026-02-20'),
+                     ('U_FAST', 40, '2026-02-05'), ('U_FAST', 60, '2026-02-25'), ('U_FAST', 900, '2026-03-10');"
+            ))
+            .await
+            .unwrap();
 
+        let from = parse_date_string("2026-02-01").unwrap();
+        let to = parse_date_string("2026-02-28").unwrap();
+        let in_window = movers_in_window(&client, Some(from), Some(to), 10).await.unwrap();
+        let all_time = movers_in_window(&client, None, None, 10).await.unwrap();
+        let top_one = movers_in_window(&client, Some(from), Some(to), 1).await.unwrap();
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        let gains = |movers: &[LeaderboardMover]| -> Vec<(String, i64)> {
+            movers.iter().map(|m| (m.slack_id.to_string(), m.gained)).collect()
+        };
+        assert_eq!(gains(&in_window), [("U_FAST".to_string(), 100), ("U_SLOW".to_string(), 25)]);
+        assert_eq!(in_window[0].username.as_deref(), Some("fast"));
+        assert_eq!(in_window[0].pfp_url.as_deref(), Some("pfp"));
+        assert_eq!(gains(&all_time), [("U_FAST".to_string(), 1000), ("U_SLOW".to_string(), 525)]);
+        assert_eq!(top_one.len(), 1);
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn historical_ranking_uses_latest_shells_not_peak() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("leaderboard_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE users (slack_id TEXT PRIMARY KEY, username TEXT, pfp_url TEXT, current_shells INTEGER);
+                 CREATE TABLE shell_history (
+                     id SERIAL PRIMARY KEY, slack_id TEXT, shells_then INTEGER, shell_diff INTEGER,
+                     shells INTEGER NOT NULL, recorded_at TIMESTAMPTZ NOT NULL
+                 );
+                 INSERT INTO users VALUES ('U_PEAKED', 'peaked', NULL, 50), ('U_STEADY', 'steady', NULL, 120), ('U_NEW', 'new', NULL, 80);
+                 INSERT INTO shell_history (slack_id, shells_then, shell_diff, shells, recorded_at) VALUES
+                     ('U_PEAKED', 300, -250, 50, '2026-03-01'), ('U_PEAKED', 0, 100, 100, '2026-01-01'),
+                     ('U_PEAKED', 100, 200, 300, '2026-02-01'), ('U_STEADY', NULL, NULL, 120, '2026-03-01');"
+            ))
+            .await
+            .unwrap();
+
+        let rows = client
+            .query(latest_ranking_sql().as_str(), &[&10_i64, &0_i64])
+            .await
+            .unwrap();
+        let ranked: Vec<(String, i32, i64)> = rows
+            .iter()
+            .map(|row| (row.get("slack_id"), row.get("shells"), row.get("rank")))
+            .collect();
+        let body = render_entries_chunk(rows.into_iter().map(Ok).collect(), true, Some(2), true).unwrap();
+        let total: i64 = client
+            .query_one(latest_count_sql().as_str(), &[])
+            .await
+            .unwrap()
+            .get("total");
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            ranked,
+            [
+                ("U_STEADY".to_string(), 120, 1),
+                ("U_NEW".to_string(), 80, 2),
+                ("U_PEAKED".to_string(), 50, 3),
+            ]
+        );
+        assert_eq!(total, 3);
+
+        // The histories ride along on the ranking rows, oldest first, and are
+        // downsampled to the requested number of points.
+        let entries: Vec<LeaderboardEntry> = serde_json::from_slice(&[b"[", &body[..], b"]"].concat()).unwrap();
+        let shells = |entry: &LeaderboardEntry| {
+            entry.shell_history.as_ref().map(|history| history.iter().map(|h| h.shells).collect::<Vec<_>>())
+        };
+        assert_eq!(shells(&entries[0]), Some(vec![120]));
+        assert_eq!(entries[0].shell_history.as_ref().unwrap()[0].shell_diff, None);
+        assert_eq!(shells(&entries[1]), None);
+        assert_eq!(shells(&entries[2]), Some(vec![100, 50]));
+        assert_eq!(entries[2].shell_history.as_ref().unwrap()[1].shell_diff, Some(-250));
+    }
+}
@@ -2,12 +2,69 @@ use std::collections::HashMap;
 
 use axum::Json;
 use pgvector::Vector;
-use axum::extract::{Query, State};
+use axum::extract::{Path, Query, State};
+use axum::response::{IntoResponse, Response};
 
 use crate::AppState;
+use crate::models::debug::DebugParams;
 use crate::utils::error::{ApiError, Result};
-use crate::models::logs::{Log, LogFilter, LogSearchRequest};
-use crate::utils::database::{decode_username, map_log_row, map_project_row, QueryBuilder};
+use crate::models::comment::Comment;
+use crate::models::logs::{Log, LogFilter, LogSearchRequest, RelatedCommentsQuery};
+use crate::utils::database::{
+    build_order_by, decode_username, like_pattern, explain_query, map_comment_row, map_log_row, map_project_row,
+    try_column, QueryBuilder,
+};
+
+/// Nearest comments to a devlog embedding (`$1`), leaving out the devlog's
+/// own comments (`$2`).
+pub(crate) const RELATED_COMMENTS_SQL: &str = r#"
+    SELECT 
+        id, upstream_id, text, devlog_id, slack_id, username, created_at, last_synced,
+        (1 - (text_embedding <=> $1)) as confidence
+    FROM comments 
+    WHERE text_embedding IS NOT NULL AND devlog_id <> $2
+    ORDER BY text_embedding <=> $1, id DESC
+    LIMIT $3
+"#;
+
+async fn devlog_embedding(client: &tokio_postgres::Client, log_id: i64) -> Result<Vector> {
+    let target = client
+        .query_opt("SELECT text_embedding FROM logs WHERE id = $1", &[&log_id])
+        .await?
+        .ok_or_else(|| ApiError::NotFound {
+            resource: "Log".to_string(),
+            id: log_id.to_string(),
+        })?;
+
+    try_column::<Option<Vector>>(&target, "text_embedding")?.ok_or_else(|| ApiError::Validation {
+        field: "id".to_string(),
+        message: "devlog has no embedding yet".to_string(),
+    })
+}
+
+const LOG_SORT_COLUMNS: [&str; 3] = ["created_at", "updated_at", "username"];
+
+pub(crate) const LOG_SEARCH_SQL: &str = r#"
+    SELECT 
+        id, text, attachment, project_id, slack_id, username, 
+        created_at, updated_at, last_synced,
+        (1 - (text_embedding <=> $1)) as confidence
+    FROM logs 
+    WHERE text_embedding IS NOT NULL
+    ORDER BY text_embedding <=> $1, id DESC
+    LIMIT $2
+"#;
+
+const LOG_FULL_TEXT_SQL: &str = r#"
+    SELECT 
+        id, text, attachment, project_id, slack_id, username, 
+        created_at, updated_at, last_synced,
+        ts_rank(to_tsvector('english', text), plainto_tsquery('english', $1))::FLOAT8 as rank
+    FROM logs 
+    WHERE to_tsvector('english', text) @@ plainto_tsquery('english', $1)
+    ORDER BY rank DESC, id DESC
+    LIMIT $2
+"#;
 
 #[utoipa::path(
     post,
@@ -20,39 +77,51 @@ use crate::utils::database::{decode_username, map_log_row, map_project_row, Quer
 )]
 pub async fn search_logs(
     State(state): State<AppState>,
+    Query(debug_params): Query<DebugParams>,
     Json(request): Json<LogSearchRequest>,
-) -> Result<Json<Vec<Log>>> {
-    let embedding_vec = state.embedding_service.embed_text(&request.query).await?;
-    let embedding = Vector::from(embedding_vec);
+) -> Result<Response> {
     let limit = i64::from(request.limit.unwrap_or(20).min(100));
+    if debug_params.explain(state.debug_endpoints) {
+        let embedding = state.embed_query(&request.query).await?;
+        let client = state.db().await?;
+        return explain_query(&client, LOG_SEARCH_SQL, &[&embedding, &limit]).await;
+    }
 
-    let client = state.pool.get().await?;
-
-    let rows = client
-        .query(
-            r#"
-        SELECT 
-            id, text, attachment, project_id, slack_id, username, 
-            created_at, updated_at, last_synced,
-            (1 - (text_embedding <=> $1)) as confidence
-        FROM logs 
-        WHERE text_embedding IS NOT NULL
-        ORDER BY text_embedding <=> $1
-        LIMIT $2
-        "#,
-            &[&embedding, &limit],
-        )
+    let results = state
+        .search_cache
+        .logs
+        .get_or_search(&request.query, limit, || run_log_search(&state, &request.query, limit))
         .await?;
 
-    let logs: Vec<Log> = rows
-        .iter()
+    Ok(Json(results).into_response())
+}
+
+async fn run_log_search(state: &AppState, query: &str, limit: i64) -> Result<Vec<Log>> {
+    let embedding = state.embed_query(query).await?;
+
+    let client = state.db().await?;
+    let rows = client.query(LOG_SEARCH_SQL, &[&embedding, &limit]).await?;
+    let full_text_rows = if rows.is_empty() {
+        tracing::debug!("Vector search returned nothing, falling back to full-text search");
+        Some(client.query(LOG_FULL_TEXT_SQL, &[&query, &limit]).await?)
+    } else {
+        None
+    };
+    drop(client);
+
+    if let Some(rows) = full_text_rows {
+        return rows
+            .iter()
+            .map(|row| Ok(map_log_row(row)?.with_text_rank(state.text_rank(try_column(row, "rank")?))))
+            .collect();
+    }
+
+    rows.iter()
         .map(|row| {
-            let confidence: f64 = row.get("confidence");
-            map_log_row(row).with_confidence(confidence)
+            let confidence = state.confidence(try_column(row, "confidence")?);
+            Ok(map_log_row(row)?.with_confidence(confidence))
         })
-        .collect();
-
-    Ok(Json(logs))
+        .collect()
 }
 
 #[utoipa::path(
@@ -67,8 +136,9 @@ pub async fn search_logs(
 pub async fn filter_logs(
     State(state): State<AppState>,
     Query(filter): Query<LogFilter>,
-) -> Result<Json<Vec<Log>>> {
-    let client = state.pool.get().await?;
+    Query(debug_params): Query<DebugParams>,
+) -> Result<Response> {
+    let client = state.db().await?;
     let mut query_builder = QueryBuilder::new();
 
     if let Some(project_id) = filter.project_id {
@@ -85,7 +155,10 @@ pub async fn filter_logs(
     }
 
     if let Some(text) = filter.text {
-        query_builder.add_condition("text ILIKE ${}", text);
+        query_builder.add_condition(
+            "text ILIKE ${}",
+            like_pattern(&text, filter.text_match.unwrap_or_default()),
+        );
     }
 
     query_builder.add_date_range_condition(
@@ -94,12 +167,18 @@ pub async fn filter_logs(
         filter.to_date.as_deref()
     )?;
 
+    let order_by = build_order_by(
+        filter.sort_by.as_deref(),
+        filter.sort_dir.as_deref(),
+        &LOG_SORT_COLUMNS,
+        "created_at",
+    )?;
+
     let limit = i64::from(filter.limit.unwrap_or(20).min(100));
-    query_builder.add_condition("1=1", limit);
+    let limit_param = query_builder.add_param(limit);
 
     let where_clause = query_builder.build_where_clause();
     let params = query_builder.params();
-    let param_count = query_builder.param_count();
 
     let query = format!(
         r#"
@@ -108,17 +187,22 @@ pub async fn filter_logs(
             created_at, updated_at, last_synced
         FROM logs 
         {}
-        ORDER BY created_at DESC 
+        {}
         LIMIT ${}
         "#,
         where_clause,
-        param_count
+        order_by,
+        limit_param
     );
 
+    if debug_params.explain(state.debug_endpoints) {
+        return explain_query(&client, &query, &params).await;
+    }
+
     let rows = client.query(&query, &params).await?;
-    let logs: Vec<Log> = rows.iter().map(map_log_row).collect();
+    let logs: Vec<Log> = rows.iter().map(map_log_row).collect::<Result<_>>()?;
 
-    Ok(Json(logs))
+    Ok(Json(logs).into_response())
 }
 
 
@@ -150,7 +234,7 @@ pub async fn get_log_details(
             message: "Invalid log ID".to_string(),
         })?;
 
-    let client = state.pool.get().await?;
+    let client = state.db().await?;
     
     let log_rows = client
         .query(
@@ -170,7 +254,7 @@ pub async fn get_log_details(
         id: log_id.to_string(),
     })?;
 
-    let log = map_log_row(log_row);
+    let log = map_log_row(log_row)?;
 
     let project_rows = client
         .query(
@@ -187,7 +271,7 @@ pub async fn get_log_details(
         .await?;
 
     let log_with_project = if let Some(project_row) = project_rows.first() {
-        let project = map_project_row(project_row);
+        let project = map_project_row(project_row)?;
         log.with_project(project)
     } else {
         log
@@ -195,3 +279,102 @@ pub async fn get_log_details(
 
     Ok(Json(log_with_project))
 }
+
+#[utoipa::path(
+    get,
+    path = "/v1/devlogs/{id}/related-comments",
+    params(
+        ("id" = i64, Path, description = "Log ID"),
+        RelatedCommentsQuery
+    ),
+    responses(
+        (status = 200, description = "Comments semantically related to the devlog", body = [Comment]),
+        (status = 400, description = "Devlog has no embedding yet"),
+        (status = 404, description = "Log not found")
+    ),
+    tag = "logs"
+)]
+pub async fn get_related_comments(
+    State(state): State<AppState>,
+    Path(log_id): Path<i64>,
+    Query(params): Query<RelatedCommentsQuery>,
+) -> Result<Json<Vec<Comment>>> {
+    let limit = i64::from(params.limit.unwrap_or(10).min(100));
+
+    let client = state.db().await?;
+    let embedding = devlog_embedding(&client, log_id).await?;
+    let rows = client
+        .query(RELATED_COMMENTS_SQL, &[&embedding, &log_id, &limit])
+        .await?;
+
+    let comments = rows
+        .iter()
+        .map(|row| {
+            let confidence = state.confidence(try_column(row, "confidence")?);
+            Ok(map_comment_row(row, state.hide_comment_serial_id)?.with_confidence(confidence))
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Json(comments))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Needs a scratch database with pgvector: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn related_comments_are_nearest_first_and_skip_the_devlogs_own() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+        if client.batch_execute("CREATE EXTENSION IF NOT EXISTS vector").await.is_err() {
+            eprintln!("pgvector not available, skipping");
+            return;
+        }
+
+        let schema = format!("related_comments_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema}, public;
+                 CREATE TABLE logs (id BIGINT PRIMARY KEY, text TEXT NOT NULL, text_embedding vector(3));
+                 CREATE TABLE comments (
+                     id BIGSERIAL PRIMARY KEY, upstream_id BIGINT, text TEXT NOT NULL, devlog_id BIGINT NOT NULL,
+                     slack_id VARCHAR(50) NOT NULL, username VARCHAR(255), created_at TIMESTAMPTZ DEFAULT now(),
+                     last_synced TIMESTAMPTZ, text_embedding vector(3)
+                 );
+                 INSERT INTO logs (id, text, text_embedding) VALUES
+                     (1, 'built a rover', '[1,0,0]'), (2, 'no embedding yet', NULL);
+                 INSERT INTO comments (text, devlog_id, slack_id, text_embedding) VALUES
+                     ('own comment, identical', 1, 'U1', '[1,0,0]'),
+                     ('close', 7, 'U2', '[0.9,0.1,0]'),
+                     ('far', 8, 'U3', '[0,0,1]'),
+                     ('closest', 9, 'U4', '[1,0.01,0]'),
+                     ('unembedded', 9, 'U5', NULL);"
+            ))
+            .await
+            .unwrap();
+
+        let embedding = devlog_embedding(&client, 1).await.unwrap();
+        let rows = client.query(RELATED_COMMENTS_SQL, &[&embedding, &1_i64, &10_i64]).await;
+        let missing = devlog_embedding(&client, 404).await;
+        let unembedded = devlog_embedding(&client, 2).await;
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        let texts: Vec<String> = rows.unwrap().iter().map(|row| row.get("text")).collect();
+        assert_eq!(texts, ["closest", "close", "far"]);
+        assert!(matches!(missing, Err(ApiError::NotFound { .. })));
+        assert!(matches!(unembedded, Err(ApiError::Validation { .. })));
+    }
+}
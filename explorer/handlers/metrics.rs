@@ -0,0 +1,137 @@
+use axum::{
+    Json,
+    extract::State,
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use chrono::{DateTime, Utc};
+use std::{collections::HashMap, time::Duration};
+use tokio_postgres::Client;
+
+use common::{ConnectionManager, SyncKey};
+
+use crate::AppState;
+use crate::services::metrics::RequestMetrics;
+use crate::utils::database::try_column;
+use crate::utils::error::Result;
+
+pub async fn get_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let body = RequestMetrics::global().render_prometheus(&ConnectionManager::pool_status(&state.pool));
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        body,
+    )
+}
+
+pub async fn get_health() -> impl IntoResponse {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+/// Reports 503 when any ingest key hasn't synced within `SYNC_STALE_AFTER_SECONDS`.
+/// Always healthy when no threshold is configured.
+pub async fn get_sync_health(State(state): State<AppState>) -> Result<impl IntoResponse> {
+    let Some(stale_after) = state.sync_stale_after else {
+        return Ok((StatusCode::OK, Json(serde_json::json!({ "status": "ok", "stale": [] }))));
+    };
+
+    let client = state.db().await?;
+    let stale: Vec<&str> = stale_sync_keys(&client, stale_after)
+        .await?
+        .into_iter()
+        .map(SyncKey::as_str)
+        .collect();
+
+    let (status_code, status) = if stale.is_empty() {
+        (StatusCode::OK, "ok")
+    } else {
+        tracing::warn!("Mirror is stale for: {}", stale.join(", "));
+        (StatusCode::SERVICE_UNAVAILABLE, "stale")
+    };
+
+    Ok((
+        status_code,
+        Json(serde_json::json!({
+            "status": status,
+            "stale": stale,
+            "thresholdSeconds": stale_after.as_secs(),
+        })),
+    ))
+}
+
+/// Keys whose last sync is older than `stale_after`, or that never synced.
+async fn stale_sync_keys(client: &Client, stale_after: Duration) -> Result<Vec<SyncKey>> {
+    let keys: Vec<&str> = SyncKey::ALL.iter().map(|key| key.as_str()).collect();
+    let rows = client
+        .query(
+            "SELECT key, last_sync FROM sync_metadata WHERE key = ANY($1)",
+            &[&keys],
+        )
+        .await?;
+
+    let mut last_syncs = HashMap::with_capacity(rows.len());
+    for row in &rows {
+        let key: &str = try_column(row, "key")?;
+        let last_sync: Option<DateTime<Utc>> = try_column(row, "last_sync")?;
+        if let Some(key) = SyncKey::from_key(key) {
+            last_syncs.insert(key, last_sync);
+        }
+    }
+
+    let cutoff = Utc::now() - stale_after;
+    Ok(SyncKey::ALL
+        .into_iter()
+        .filter(|key| {
+            last_syncs
+                .get(key)
+                .copied()
+                .flatten()
+                .is_none_or(|last_sync| last_sync < cutoff)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn old_and_missing_sync_rows_are_stale() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("sync_health_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE sync_metadata (key TEXT PRIMARY KEY, last_sync TIMESTAMPTZ, last_page INTEGER, status TEXT);
+                 INSERT INTO sync_metadata (key, last_sync) VALUES
+                     ('projects', now() - interval '5 minutes'),
+                     ('comments', now() - interval '3 hours'),
+                     ('leaderboard_forge', now() - interval '3 days');"
+            ))
+            .await
+            .unwrap();
+
+        let stale = stale_sync_keys(&client, Duration::from_secs(3600)).await;
+        let all_stale = stale_sync_keys(&client, Duration::from_secs(60)).await;
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        // devlogs has no row at all; unrelated keys are ignored.
+        assert_eq!(stale.unwrap(), [SyncKey::Comments, SyncKey::Devlogs]);
+        assert_eq!(all_stale.unwrap(), SyncKey::ALL);
+    }
+}
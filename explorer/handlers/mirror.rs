@@ -7,8 +7,14 @@ use serde_json::json;
 use std::collections::HashMap;
 
 use crate::AppState;
-use crate::models::{comment::Comment, logs::Log, project::Project};
-use crate::utils::error::Result;
+use crate::models::{
+    comment::Comment,
+    logs::Log,
+    mirror::{MirrorCommentsResponse, MirrorDevlogsResponse, MirrorProjectsResponse, PaginationMeta},
+    project::Project,
+};
+use crate::utils::database::{map_comment_row, map_log_row, map_project_row};
+use crate::utils::error::{ApiError, Result};
 
 struct PaginationParams {
     page: i32,
@@ -16,6 +22,26 @@ struct PaginationParams {
     offset: i32,
 }
 
+impl PaginationParams {
+    fn meta(&self, total: i64) -> Result<PaginationMeta> {
+        let pages = (total + i64::from(self.per_page) - 1) / i64::from(self.per_page);
+
+        if !validate_pagination(self.page, total, self.per_page) {
+            return Err(ApiError::Validation {
+                field: "page".to_string(),
+                message: format!("Page {} is out of bounds ({} pages available)", self.page, pages),
+            });
+        }
+
+        Ok(PaginationMeta {
+            page: self.page,
+            pages,
+            count: total,
+            items: self.per_page,
+        })
+    }
+}
+
 fn extract_pagination(params: &HashMap<String, String>) -> PaginationParams {
     let page = params.get("page").and_then(|p| p.parse().ok()).unwrap_or(1);
     let per_page = 20;
@@ -35,26 +61,23 @@ fn validate_pagination(page: i32, total: i64, per_page: i32) -> bool {
         ("page" = Option<i32>, Query, description = "Page number")
     ),
     responses(
-        (status = 200, description = "Mirrored projects", body = [Project])
+        (status = 200, description = "Mirrored projects", body = MirrorProjectsResponse),
+        (status = 400, description = "Page out of bounds")
     ),
     tag = "mirror"
 )]
 pub async fn mirror_projects(
     State(state): State<AppState>,
     Query(params): Query<HashMap<String, String>>,
-) -> Result<Json<serde_json::Value>> {
+) -> Result<Json<MirrorProjectsResponse>> {
     let pagination = extract_pagination(&params);
-    let client = state.pool.get().await?;
+    let client = state.db().await?;
     
     let total_row = client
         .query_one("SELECT COUNT(*) FROM projects", &[])
         .await?;
     let total: i64 = total_row.get(0);
-    let total_pages = (total + i64::from(pagination.per_page) - 1) / i64::from(pagination.per_page);
-
-    if !validate_pagination(pagination.page, total, pagination.per_page) {
-        return Ok(Json(json!({"error": "Page out of bounds"})));
-    }
+    let meta = pagination.meta(total)?;
 
     let project_rows = client
         .query(
@@ -71,34 +94,14 @@ pub async fn mirror_projects(
         .await?;
 
     let projects: Vec<Project> = project_rows
-        .into_iter()
-        .map(|row| Project {
-            id: row.get::<_, i64>("id"),
-            title: row.get("title"),
-            description: row.get("description"),
-            category: row.get("category"),
-            readme_link: row.get("readme_link"),
-            demo_link: row.get("demo_link"),
-            repo_link: row.get("repo_link"),
-            slack_id: row.get("slack_id"),
-            username: row.get("username"),
-            created_at: row.get("created_at"),
-            updated_at: row.get("updated_at"),
-            last_synced: row.get("last_synced"),
-            confidence: None,
-            comments: Vec::new(),
-        })
-        .collect();
+        .iter()
+        .map(map_project_row)
+        .collect::<Result<_>>()?;
 
-    Ok(Json(json!({
-        "projects": projects,
-        "pagination": {
-            "page": pagination.page,
-            "pages": total_pages,
-            "count": total,
-            "items": pagination.per_page
-        }
-    })))
+    Ok(Json(MirrorProjectsResponse {
+        projects,
+        pagination: meta,
+    }))
 }
 
 #[utoipa::path(
@@ -116,7 +119,7 @@ pub async fn mirror_project(
     State(state): State<AppState>,
     Path(id): Path<i64>,
 ) -> Result<Json<serde_json::Value>> {
-    let client = state.pool.get().await?;
+    let client = state.db().await?;
     let project_rows = client
         .query(
             r#"
@@ -158,24 +161,21 @@ pub async fn mirror_project(
         ("page" = Option<i32>, Query, description = "Page number")
     ),
     responses(
-        (status = 200, description = "Mirrored devlogs", body = [Log])
+        (status = 200, description = "Mirrored devlogs", body = MirrorDevlogsResponse),
+        (status = 400, description = "Page out of bounds")
     ),
     tag = "mirror"
 )]
 pub async fn mirror_devlogs(
     State(state): State<AppState>,
     Query(params): Query<HashMap<String, String>>,
-) -> Result<Json<serde_json::Value>> {
+) -> Result<Json<MirrorDevlogsResponse>> {
     let pagination = extract_pagination(&params);
-    let client = state.pool.get().await?;
+    let client = state.db().await?;
     
     let total_row = client.query_one("SELECT COUNT(*) FROM logs", &[]).await?;
     let total: i64 = total_row.get(0);
-    let total_pages = (total + i64::from(pagination.per_page) - 1) / i64::from(pagination.per_page);
-
-    if !validate_pagination(pagination.page, total, pagination.per_page) {
-        return Ok(Json(json!({"error": "Page out of bounds"})));
-    }
+    let meta = pagination.meta(total)?;
 
     let devlog_rows = client
         .query(
@@ -192,31 +192,14 @@ pub async fn mirror_devlogs(
         .await?;
 
     let devlogs: Vec<Log> = devlog_rows
-        .into_iter()
-        .map(|row| Log {
-            id: row.get::<_, i64>("id"),
-            text: row.get("text"),
-            attachment: row.get("attachment"),
-            project_id: row.get::<_, i64>("project_id"),
-            slack_id: row.get("slack_id"),
-            username: row.get("username"),
-            created_at: row.get("created_at"),
-            updated_at: row.get("updated_at"),
-            last_synced: row.get("last_synced"),
-            confidence: None,
-            project: None,
-        })
-        .collect();
+        .iter()
+        .map(map_log_row)
+        .collect::<Result<_>>()?;
 
-    Ok(Json(json!({
-        "devlogs": devlogs,
-        "pagination": {
-            "page": pagination.page,
-            "pages": total_pages,
-            "count": total,
-            "items": pagination.per_page
-        }
-    })))
+    Ok(Json(MirrorDevlogsResponse {
+        devlogs,
+        pagination: meta,
+    }))
 }
 
 #[utoipa::path(
@@ -226,32 +209,29 @@ pub async fn mirror_devlogs(
         ("page" = Option<i32>, Query, description = "Page number")
     ),
     responses(
-        (status = 200, description = "Mirrored comments", body = [Comment])
+        (status = 200, description = "Mirrored comments", body = MirrorCommentsResponse),
+        (status = 400, description = "Page out of bounds")
     ),
     tag = "mirror"
 )]
 pub async fn mirror_comments(
     State(state): State<AppState>,
     Query(params): Query<HashMap<String, String>>,
-) -> Result<Json<serde_json::Value>> {
+) -> Result<Json<MirrorCommentsResponse>> {
     let pagination = extract_pagination(&params);
-    let client = state.pool.get().await?;
+    let client = state.db().await?;
     
     let total_row = client
         .query_one("SELECT COUNT(*) FROM comments", &[])
         .await?;
     let total: i64 = total_row.get(0);
-    let total_pages = (total + i64::from(pagination.per_page) - 1) / i64::from(pagination.per_page);
-
-    if !validate_pagination(pagination.page, total, pagination.per_page) {
-        return Ok(Json(json!({"error": "Page out of bounds"})));
-    }
+    let meta = pagination.meta(total)?;
 
     let comment_rows = client
         .query(
             r#"
         SELECT 
-            id, text, devlog_id, slack_id, username, created_at,
+            id, upstream_id, text, devlog_id, slack_id, username, created_at,
             last_synced
         FROM comments 
         ORDER BY created_at DESC
@@ -262,26 +242,49 @@ pub async fn mirror_comments(
         .await?;
 
     let comments: Vec<Comment> = comment_rows
-        .into_iter()
-        .map(|row| Comment {
-            id: row.get::<_, i64>("id"),
-            text: row.get("text"),
-            devlog_id: row.get::<_, i64>("devlog_id"),
-            slack_id: row.get("slack_id"),
-            username: row.get("username"),
-            created_at: row.get("created_at"),
-            last_synced: row.get("last_synced"),
-            confidence: None,
-        })
-        .collect();
+        .iter()
+        .map(|row| map_comment_row(row, state.hide_comment_serial_id))
+        .collect::<Result<_>>()?;
 
-    Ok(Json(json!({
-        "comments": comments,
-        "pagination": {
-            "page": pagination.page,
-            "pages": total_pages,
-            "count": total,
-            "items": pagination.per_page
-        }
-    })))
+    Ok(Json(MirrorCommentsResponse {
+        comments,
+        pagination: meta,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{http::StatusCode, response::IntoResponse};
+
+    use super::*;
+
+    fn page(page: &str) -> PaginationParams {
+        extract_pagination(&HashMap::from([("page".to_string(), page.to_string())]))
+    }
+
+    #[tokio::test]
+    async fn out_of_range_page_is_a_400_in_the_standard_shape() {
+        let error = page("4").meta(45).unwrap_err();
+        let response = error.into_response();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            json!({
+                "error": "Page 4 is out of bounds (3 pages available)",
+                "error_code": "VALIDATION_ERROR",
+                "status": 400,
+            })
+        );
+    }
+
+    #[test]
+    fn pages_within_the_total_are_accepted() {
+        let meta = page("3").meta(45).unwrap();
+        assert_eq!((meta.page, meta.pages, meta.count, meta.items), (3, 3, 45, 20));
+        assert_eq!(page("1").meta(0).unwrap().pages, 0);
+        assert!(matches!(page("0").meta(45), Err(ApiError::Validation { ref field, .. }) if field == "page"));
+    }
 }
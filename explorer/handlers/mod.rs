@@ -1,6 +1,78 @@
+pub mod admin;
 pub mod comments;
+pub mod feedback;
+pub mod jobs;
 pub mod leaderboard;
 pub mod logs;
+pub mod metrics;
 pub mod mirror;
 pub mod projects;
+pub mod search;
+pub mod stats;
 pub mod users;
+
+#[cfg(test)]
+mod tests {
+    const HANDLER_SOURCES: [(&str, &str); 12] = [
+        ("admin.rs", include_str!("admin.rs")),
+        ("comments.rs", include_str!("comments.rs")),
+        ("feedback.rs", include_str!("feedback.rs")),
+        ("jobs.rs", include_str!("jobs.rs")),
+        ("leaderboard.rs", include_str!("leaderboard.rs")),
+        ("logs.rs", include_str!("logs.rs")),
+        ("metrics.rs", include_str!("metrics.rs")),
+        ("mirror.rs", include_str!("mirror.rs")),
+        ("projects.rs", include_str!("projects.rs")),
+        ("search.rs", include_str!("search.rs")),
+        ("stats.rs", include_str!("stats.rs")),
+        ("users.rs", include_str!("users.rs")),
+    ];
+
+    /// Splits a source file into top-level `fn` items, keyed by name.
+    fn functions(source: &str) -> Vec<(&str, &str)> {
+        let mut starts: Vec<usize> = source
+            .match_indices("\nasync fn ")
+            .chain(source.match_indices("\npub async fn "))
+            .chain(source.match_indices("\nfn "))
+            .map(|(start, _)| start + 1)
+            .collect();
+        starts.sort_unstable();
+
+        starts
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let body = &source[start..starts.get(i + 1).copied().unwrap_or(source.len())];
+                let name = body.split("fn ").nth(1).and_then(|rest| rest.split(['(', '<']).next());
+                (name.unwrap_or_default(), body)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn handlers_embed_before_acquiring_a_connection() {
+        let mut embedding_handlers = 0;
+        for (file, source) in HANDLER_SOURCES {
+            assert!(
+                !source.contains("embedding_service.embed_text"),
+                "{file} embeds without going through AppState::embed_query"
+            );
+
+            for (name, body) in functions(source) {
+                let Some(embed) = body.find("embed_query(") else {
+                    continue;
+                };
+                embedding_handlers += 1;
+                let Some(acquire) = body.find(".db().await") else {
+                    continue;
+                };
+                assert!(embed < acquire, "{file}::{name} holds a connection while embedding");
+                assert!(
+                    body.contains("drop(client)") || body.contains("explain_query(&client"),
+                    "{file}::{name} keeps its connection after querying"
+                );
+            }
+        }
+        assert!(embedding_handlers >= 8, "lint no longer sees the search handlers");
+    }
+}
@@ -2,12 +2,76 @@ use std::collections::HashMap;
 
 use axum::Json;
 use pgvector::Vector;
-use axum::extract::{Query, State};
+use axum::extract::{Path, Query, State};
+use axum::response::{IntoResponse, Response};
+use common::services::project_embedding_text_from_parts;
+use tokio_postgres::Row;
 
 use crate::AppState;
+use crate::models::debug::DebugParams;
+use crate::services::calibration::ConfidenceCalibration;
 use crate::utils::error::{ApiError, Result};
-use crate::models::project::{Project, ProjectFilter, ProjectSearchRequest};
-use crate::utils::database::{decode_username, map_comment_row, map_project_row, QueryBuilder};
+use crate::models::comment::Comment;
+use crate::models::project::{
+    Project, ProjectCommentsQuery, ProjectFilter, ProjectSearchExplanation, ProjectSearchRequest,
+    SimilarProjectsQuery, TrendingProject, TrendingProjectsQuery,
+};
+use crate::utils::database::{build_order_by, decode_username, like_pattern, explain_query, map_comment_row, map_project_row, try_column, QueryBuilder};
+
+const PROJECT_SORT_COLUMNS: [&str; 4] = ["created_at", "updated_at", "title", "category"];
+
+const DEFAULT_TRENDING_WINDOW: &str = "7d";
+const MAX_TRENDING_WINDOW_DAYS: i64 = 90;
+
+const TRENDING_PROJECTS_SQL: &str = r#"
+    SELECT 
+        p.id, p.title, p.description, p.category, p.readme_link, p.demo_link, 
+        p.repo_link, p.slack_id, p.username, p.created_at, p.updated_at, p.last_synced,
+        COALESCE(d.devlog_count, 0) AS devlog_count,
+        COALESCE(c.comment_count, 0) AS comment_count
+    FROM projects p
+    LEFT JOIN (
+        SELECT project_id, COUNT(*) AS devlog_count 
+        FROM logs 
+        WHERE created_at >= $1 
+        GROUP BY project_id
+    ) d ON d.project_id = p.id
+    LEFT JOIN (
+        SELECT l.project_id, COUNT(*) AS comment_count 
+        FROM comments c 
+        JOIN logs l ON c.devlog_id = l.id 
+        WHERE c.created_at >= $1 
+        GROUP BY l.project_id
+    ) c ON c.project_id = p.id
+    WHERE d.devlog_count IS NOT NULL OR c.comment_count IS NOT NULL
+    ORDER BY COALESCE(d.devlog_count, 0) + COALESCE(c.comment_count, 0) DESC, p.id DESC
+    LIMIT $2
+"#;
+
+const DEFAULT_DETAILS_COMMENT_LIMIT: i64 = 50;
+const MAX_COMMENT_PAGE_SIZE: i64 = 500;
+
+pub(crate) const PROJECT_SEARCH_SQL: &str = r#"
+    SELECT 
+        id, title, description, category, readme_link, demo_link, 
+        repo_link, slack_id, username, created_at, updated_at, last_synced,
+        (1 - (title_description_embedding <=> $1)) as confidence
+    FROM projects 
+    WHERE title_description_embedding IS NOT NULL
+    ORDER BY title_description_embedding <=> $1, id DESC
+    LIMIT $2
+"#;
+
+const PROJECT_FULL_TEXT_SQL: &str = r#"
+    SELECT 
+        id, title, description, category, readme_link, demo_link, 
+        repo_link, slack_id, username, created_at, updated_at, last_synced,
+        ts_rank(to_tsvector('english', title || ' ' || COALESCE(description, '')), plainto_tsquery('english', $1))::FLOAT8 as rank
+    FROM projects 
+    WHERE to_tsvector('english', title || ' ' || COALESCE(description, '')) @@ plainto_tsquery('english', $1)
+    ORDER BY rank DESC, id DESC
+    LIMIT $2
+"#;
 
 #[utoipa::path(
     post,
@@ -20,13 +84,107 @@ use crate::utils::database::{decode_username, map_comment_row, map_project_row,
 )]
 pub async fn search_projects(
     State(state): State<AppState>,
+    Query(debug_params): Query<DebugParams>,
     Json(request): Json<ProjectSearchRequest>,
-) -> Result<Json<Vec<Project>>> {
-    let embedding_vec = state.embedding_service.embed_text(&request.query).await?;
-    let embedding = Vector::from(embedding_vec);
+) -> Result<Response> {
     let limit = i64::from(request.limit.unwrap_or(20).min(100));
+    if debug_params.explain(state.debug_endpoints) {
+        let embedding = state.embed_query(&request.query).await?;
+        let client = state.db().await?;
+        return explain_query(&client, PROJECT_SEARCH_SQL, &[&embedding, &limit]).await;
+    }
+
+    let mut results = state
+        .search_cache
+        .projects
+        .get_or_search(&request.query, limit, || run_project_search(&state, &request.query, limit))
+        .await?;
+
+    if request.include_counts.unwrap_or(false) {
+        let client = state.db().await?;
+        results = attach_counts(&client, results).await?;
+    }
+
+    Ok(Json(results).into_response())
+}
+
+async fn run_project_search(state: &AppState, query: &str, limit: i64) -> Result<Vec<Project>> {
+    let embedding = state.embed_query(query).await?;
+
+    let client = state.db().await?;
+    let rows = project_search_rows(&client, &embedding, query, limit).await?;
+    drop(client);
+
+    match rows {
+        SearchRows::FullText(rows) => rows
+            .iter()
+            .map(|row| Ok(map_project_row(row)?.with_text_rank(state.text_rank(try_column(row, "rank")?))))
+            .collect(),
+        SearchRows::Vector(rows) => rows
+            .iter()
+            .map(|row| {
+                let confidence = state.confidence(try_column(row, "confidence")?);
+                Ok(map_project_row(row)?.with_confidence(confidence))
+            })
+            .collect(),
+    }
+}
+
+enum SearchRows {
+    Vector(Vec<Row>),
+    FullText(Vec<Row>),
+}
+
+async fn project_search_rows(
+    client: &tokio_postgres::Client,
+    embedding: &Vector,
+    query: &str,
+    limit: i64,
+) -> Result<SearchRows> {
+    let rows = client.query(PROJECT_SEARCH_SQL, &[embedding, &limit]).await?;
+    if !rows.is_empty() {
+        return Ok(SearchRows::Vector(rows));
+    }
+
+    tracing::debug!("Vector search returned nothing, falling back to full-text search");
+    Ok(SearchRows::FullText(client.query(PROJECT_FULL_TEXT_SQL, &[&query, &limit]).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/projects/similar",
+    params(SimilarProjectsQuery),
+    responses(
+        (status = 200, description = "Projects similar to the given project", body = [Project]),
+        (status = 400, description = "Project has no embedding yet"),
+        (status = 404, description = "Project not found")
+    ),
+    tag = "projects"
+)]
+pub async fn get_similar_projects(
+    State(state): State<AppState>,
+    Query(params): Query<SimilarProjectsQuery>,
+) -> Result<Json<Vec<Project>>> {
+    let limit = i64::from(params.limit.unwrap_or(10).min(100));
+
+    let client = state.db().await?;
+
+    let target = client
+        .query_opt(
+            "SELECT title_description_embedding FROM projects WHERE id = $1",
+            &[&params.id],
+        )
+        .await?
+        .ok_or_else(|| ApiError::NotFound {
+            resource: "Project".to_string(),
+            id: params.id.to_string(),
+        })?;
 
-    let client = state.pool.get().await?;
+    let embedding: Vector = try_column::<Option<Vector>>(&target, "title_description_embedding")?
+        .ok_or_else(|| ApiError::Validation {
+            field: "id".to_string(),
+            message: "project has no embedding yet".to_string(),
+        })?;
 
     let rows = client
         .query(
@@ -36,25 +194,95 @@ pub async fn search_projects(
             repo_link, slack_id, username, created_at, updated_at, last_synced,
             (1 - (title_description_embedding <=> $1)) as confidence
         FROM projects 
-        WHERE title_description_embedding IS NOT NULL
-        ORDER BY title_description_embedding <=> $1
-        LIMIT $2
+        WHERE title_description_embedding IS NOT NULL AND id <> $2
+        ORDER BY title_description_embedding <=> $1, id DESC
+        LIMIT $3
         "#,
-            &[&embedding, &limit],
+            &[&embedding, &params.id, &limit],
         )
         .await?;
 
-    let projects: Vec<Project> = rows
+    let projects = rows
         .iter()
         .map(|row| {
-            let confidence: f64 = row.get("confidence");
-            map_project_row(row).with_confidence(confidence)
+            let confidence = state.confidence(try_column(row, "confidence")?);
+            Ok(map_project_row(row)?.with_confidence(confidence))
         })
-        .collect();
+        .collect::<Result<_>>()?;
 
     Ok(Json(projects))
 }
 
+const EMBEDDED_TEXT_PREVIEW_CHARS: usize = 200;
+
+const PROJECT_EXPLAIN_SQL: &str = r#"
+    SELECT 
+        id, title, description, category, readme_link, demo_link, 
+        repo_link, slack_id, username, created_at, updated_at, last_synced,
+        (title_description_embedding <=> $1) as raw_distance,
+        (vector_norm(title_description_embedding) = 0) as zero_vector
+    FROM projects 
+    WHERE title_description_embedding IS NOT NULL
+    ORDER BY title_description_embedding <=> $1, id DESC
+    LIMIT $2
+"#;
+
+#[utoipa::path(
+    post,
+    path = "/v1/projects/search/explain",
+    request_body = ProjectSearchRequest,
+    responses(
+        (status = 200, description = "Search results with ranking diagnostics", body = [ProjectSearchExplanation])
+    ),
+    tag = "projects"
+)]
+pub async fn explain_search_projects(
+    State(state): State<AppState>,
+    Json(request): Json<ProjectSearchRequest>,
+) -> Result<Json<Vec<ProjectSearchExplanation>>> {
+    let query_token_count = state.embedding_service.count_tokens(&request.query)?;
+    let embedding = state.embed_query(&request.query).await?;
+    let limit = i64::from(request.limit.unwrap_or(20).min(100));
+
+    let client = state.db().await?;
+
+    let rows = client.query(PROJECT_EXPLAIN_SQL, &[&embedding, &limit]).await?;
+    drop(client);
+
+    Ok(Json(explain_rows(&rows, &state.confidence_calibration, query_token_count)?))
+}
+
+fn explain_rows(
+    rows: &[Row],
+    calibration: &ConfidenceCalibration,
+    query_token_count: usize,
+) -> Result<Vec<ProjectSearchExplanation>> {
+    rows.iter()
+        .map(|row| {
+            let raw_distance: f64 = try_column(row, "raw_distance")?;
+            let confidence = calibration.apply(1.0 - raw_distance);
+            let project = map_project_row(row)?;
+            let embedded_text_preview = project_embedding_text_from_parts(
+                &project.title,
+                project.description.as_deref(),
+                project.category.as_deref(),
+            )
+            .chars()
+            .take(EMBEDDED_TEXT_PREVIEW_CHARS)
+            .collect();
+
+            Ok(ProjectSearchExplanation {
+                project,
+                raw_distance,
+                confidence,
+                query_token_count,
+                embedded_text_preview,
+                zero_vector: try_column(row, "zero_vector")?,
+            })
+        })
+        .collect()
+}
+
 #[utoipa::path(
     get,
     path = "/v1/projects/filter",
@@ -67,8 +295,37 @@ pub async fn search_projects(
 pub async fn filter_projects(
     State(state): State<AppState>,
     Query(filter): Query<ProjectFilter>,
-) -> Result<Json<Vec<Project>>> {
-    let client = state.pool.get().await?;
+    Query(debug_params): Query<DebugParams>,
+) -> Result<Response> {
+    let include_counts = filter.include_counts.unwrap_or(false);
+    let (query, query_builder) = project_filter_query(filter)?;
+    let params = query_builder.params();
+
+    let client = state.db().await?;
+    if debug_params.explain(state.debug_endpoints) {
+        return explain_query(&client, &query, &params).await;
+    }
+
+    let projects = filtered_projects(&client, &query, &params, include_counts).await?;
+    Ok(Json(projects).into_response())
+}
+
+async fn filtered_projects(
+    client: &tokio_postgres::Client,
+    query: &str,
+    params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+    include_counts: bool,
+) -> Result<Vec<Project>> {
+    let rows = client.query(query, params).await?;
+    let projects: Vec<Project> = rows.iter().map(map_project_row).collect::<Result<_>>()?;
+
+    if include_counts {
+        return attach_counts(client, projects).await;
+    }
+    Ok(projects)
+}
+
+fn project_filter_query(filter: ProjectFilter) -> Result<(String, QueryBuilder)> {
     let mut query_builder = QueryBuilder::new();
 
     if let Some(id) = filter.id {
@@ -85,7 +342,10 @@ pub async fn filter_projects(
     }
 
     if let Some(title) = filter.title {
-        query_builder.add_condition("title ILIKE ${}", title);
+        query_builder.add_condition(
+            "title ILIKE ${}",
+            like_pattern(&title, filter.text_match.unwrap_or_default()),
+        );
     }
 
     if let Some(category) = filter.category {
@@ -106,28 +366,80 @@ pub async fn filter_projects(
         filter.to_date.as_deref()
     )?;
 
+    let order_by = build_order_by(
+        filter.sort_by.as_deref(),
+        filter.sort_dir.as_deref(),
+        &PROJECT_SORT_COLUMNS,
+        "updated_at",
+    )?;
+
     let limit = i64::from(filter.limit.unwrap_or(20).min(100));
-    query_builder.add_condition("1=1", limit);
+    let limit_param = query_builder.add_param(limit);
 
     let where_clause = query_builder.build_where_clause();
-    let params = query_builder.params();
-    let param_count = query_builder.param_count();
 
     let query = format!(
         "SELECT id, title, description, category, readme_link, demo_link, 
          repo_link, slack_id, username, created_at, updated_at, last_synced 
          FROM projects 
          {} 
-         ORDER BY updated_at DESC 
+         {} 
          LIMIT ${}", 
         where_clause,
-        param_count
+        order_by,
+        limit_param
     );
 
-    let rows = client.query(&query, &params).await?;
-    let projects = rows.iter().map(map_project_row).collect();
+    Ok((query, query_builder))
+}
 
-    Ok(Json(projects))
+// Counted in one round trip for the whole page rather than joined into each
+// search query, so callers that don't ask for counts don't pay for them.
+async fn attach_counts(client: &tokio_postgres::Client, projects: Vec<Project>) -> Result<Vec<Project>> {
+    if projects.is_empty() {
+        return Ok(projects);
+    }
+
+    let ids: Vec<i64> = projects.iter().map(|p| p.id).collect();
+    let rows = client
+        .query(
+            r#"
+        SELECT 
+            p.id, 
+            COALESCE(d.devlog_count, 0) AS devlog_count, 
+            COALESCE(c.comment_count, 0) AS comment_count
+        FROM unnest($1::BIGINT[]) AS p(id)
+        LEFT JOIN (
+            SELECT project_id, COUNT(*) AS devlog_count 
+            FROM logs 
+            WHERE project_id = ANY($1) 
+            GROUP BY project_id
+        ) d ON d.project_id = p.id
+        LEFT JOIN (
+            SELECT l.project_id, COUNT(*) AS comment_count 
+            FROM comments c 
+            JOIN logs l ON c.devlog_id = l.id 
+            WHERE l.project_id = ANY($1) 
+            GROUP BY l.project_id
+        ) c ON c.project_id = p.id
+        "#,
+            &[&ids],
+        )
+        .await?;
+
+    let mut counts = HashMap::with_capacity(rows.len());
+    for row in &rows {
+        let id: i64 = try_column(row, "id")?;
+        counts.insert(id, (try_column(row, "devlog_count")?, try_column(row, "comment_count")?));
+    }
+
+    Ok(projects
+        .into_iter()
+        .map(|project| {
+            let (devlogs, comments) = counts.get(&project.id).copied().unwrap_or((0, 0));
+            project.with_counts(devlogs, comments)
+        })
+        .collect())
 }
 
 
@@ -135,7 +447,8 @@ pub async fn filter_projects(
     get,
     path = "/v1/projects/details",
     params(
-        ("id" = i64, Query, description = "Project ID")
+        ("id" = i64, Query, description = "Project ID"),
+        ("comment_limit" = Option<u32>, Query, description = "Maximum number of most recent comments to attach (default 50, max 500)")
     ),
     responses(
         (status = 200, description = "Project details", body = Project),
@@ -159,8 +472,25 @@ pub async fn get_project_details(
             message: "Invalid project ID".to_string(),
         })?;
 
-    let client = state.pool.get().await?;
+    let comment_limit = match params.get("comment_limit") {
+        Some(limit) => limit.parse::<i64>().map_err(|_| ApiError::Validation {
+            field: "comment_limit".to_string(),
+            message: "Invalid comment limit".to_string(),
+        })?,
+        None => DEFAULT_DETAILS_COMMENT_LIMIT,
+    }
+    .clamp(0, MAX_COMMENT_PAGE_SIZE);
 
+    let client = state.db().await?;
+    Ok(Json(project_details(&client, project_id, comment_limit, state.hide_comment_serial_id).await?))
+}
+
+async fn project_details(
+    client: &tokio_postgres::Client,
+    project_id: i64,
+    comment_limit: i64,
+    hide_serial_id: bool,
+) -> Result<Project> {
     let project_rows = client
         .query(
             r#"
@@ -179,24 +509,694 @@ pub async fn get_project_details(
         id: project_id.to_string(),
     })?;
 
-    let project = map_project_row(project_row);
+    let project = map_project_row(project_row)?;
+
+    let comments = fetch_project_comments(client, project_id, comment_limit, 0, hide_serial_id).await?;
 
-    let comment_rows = client
+    Ok(project.with_comments(comments))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/projects/trending",
+    params(TrendingProjectsQuery),
+    responses(
+        (status = 200, description = "Projects ranked by devlogs and comments posted within the window", body = [TrendingProject]),
+        (status = 400, description = "Invalid window")
+    ),
+    tag = "projects"
+)]
+pub async fn get_trending_projects(
+    State(state): State<AppState>,
+    Query(params): Query<TrendingProjectsQuery>,
+) -> Result<Json<Vec<TrendingProject>>> {
+    let window = parse_window(params.window.as_deref().unwrap_or(DEFAULT_TRENDING_WINDOW))?;
+    let since = chrono::Utc::now() - window;
+    let limit = i64::from(params.limit.unwrap_or(20).min(100));
+
+    let client = state.db().await?;
+    Ok(Json(trending_since(&client, since, limit).await?))
+}
+
+async fn trending_since(
+    client: &tokio_postgres::Client,
+    since: chrono::DateTime<chrono::Utc>,
+    limit: i64,
+) -> Result<Vec<TrendingProject>> {
+    let rows = client.query(TRENDING_PROJECTS_SQL, &[&since, &limit]).await?;
+
+    rows.iter()
+        .map(|row| {
+            let devlog_count: i64 = try_column(row, "devlog_count")?;
+            let comment_count: i64 = try_column(row, "comment_count")?;
+            Ok(TrendingProject {
+                project: map_project_row(row)?.with_counts(devlog_count, comment_count),
+                activity: devlog_count + comment_count,
+            })
+        })
+        .collect()
+}
+
+fn parse_window(window: &str) -> Result<chrono::Duration> {
+    let invalid = || ApiError::Validation {
+        field: "window".to_string(),
+        message: format!(
+            "Expected a window like 24h, 7d or 2w of at most {} days",
+            MAX_TRENDING_WINDOW_DAYS
+        ),
+    };
+
+    let window = window.trim();
+    let unit = window.chars().last().ok_or_else(invalid)?;
+    let amount: i64 = window[..window.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| invalid())?;
+    let hours_per_unit = match unit {
+        'h' => 1,
+        'd' => 24,
+        'w' => 24 * 7,
+        _ => return Err(invalid()),
+    };
+
+    let hours = amount.checked_mul(hours_per_unit).ok_or_else(invalid)?;
+    if hours <= 0 || hours > MAX_TRENDING_WINDOW_DAYS * 24 {
+        return Err(invalid());
+    }
+
+    Ok(chrono::Duration::hours(hours))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/projects/{id}/comments",
+    params(
+        ("id" = i64, Path, description = "Project ID"),
+        ProjectCommentsQuery
+    ),
+    responses(
+        (status = 200, description = "Comments on the project's devlogs, newest first", body = [Comment]),
+        (status = 404, description = "Project not found")
+    ),
+    tag = "projects"
+)]
+pub async fn get_project_comments(
+    State(state): State<AppState>,
+    Path(project_id): Path<i64>,
+    Query(params): Query<ProjectCommentsQuery>,
+) -> Result<Json<Vec<Comment>>> {
+    let limit = params
+        .limit
+        .map_or(DEFAULT_DETAILS_COMMENT_LIMIT, i64::from)
+        .min(MAX_COMMENT_PAGE_SIZE);
+    let offset = i64::from(params.offset.unwrap_or(0));
+
+    let client = state.db().await?;
+
+    client
+        .query_opt("SELECT 1 FROM projects WHERE id = $1", &[&project_id])
+        .await?
+        .ok_or_else(|| ApiError::NotFound {
+            resource: "Project".to_string(),
+            id: project_id.to_string(),
+        })?;
+
+    let comments = fetch_project_comments(&client, project_id, limit, offset, state.hide_comment_serial_id).await?;
+
+    Ok(Json(comments))
+}
+
+async fn fetch_project_comments(
+    client: &tokio_postgres::Client,
+    project_id: i64,
+    limit: i64,
+    offset: i64,
+    hide_serial_id: bool,
+) -> Result<Vec<Comment>> {
+    let rows = client
         .query(
             r#"
         SELECT 
-            c.id, c.text, c.devlog_id, c.slack_id, c.username, 
+            c.id, c.upstream_id, c.text, c.devlog_id, c.slack_id, c.username, 
             c.created_at, c.last_synced
         FROM comments c
         JOIN logs l ON c.devlog_id = l.id
         WHERE l.project_id = $1
-        ORDER BY c.created_at DESC
+        ORDER BY c.created_at DESC, c.id DESC
+        LIMIT $2 OFFSET $3
         "#,
-            &[&project_id],
+            &[&project_id, &limit, &offset],
         )
         .await?;
 
-    let comments = comment_rows.iter().map(map_comment_row).collect();
+    rows.iter().map(|row| map_comment_row(row, hide_serial_id)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_window_accepts_hours_days_and_weeks() {
+        assert_eq!(parse_window("24h").unwrap(), chrono::Duration::hours(24));
+        assert_eq!(parse_window("7d").unwrap(), chrono::Duration::days(7));
+        assert_eq!(parse_window(" 2w ").unwrap(), chrono::Duration::weeks(2));
+        assert_eq!(
+            parse_window(&format!("{MAX_TRENDING_WINDOW_DAYS}d")).unwrap(),
+            chrono::Duration::days(MAX_TRENDING_WINDOW_DAYS)
+        );
+    }
+
+    #[test]
+    fn parse_window_rejects_malformed_or_out_of_range_windows() {
+        for window in ["", "d", "7", "7m", "-1d", "0h", "1.5d", "91d", "14w", "9999999999999999999w", "7dd", "7é"] {
+            assert!(
+                matches!(parse_window(window), Err(ApiError::Validation { ref field, .. }) if field == "window"),
+                "{window:?} should be rejected"
+            );
+        }
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn trending_ranks_by_recent_devlogs_and_comments() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("trending_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE projects (
+                     id BIGINT PRIMARY KEY, title TEXT NOT NULL, description TEXT, category TEXT,
+                     readme_link TEXT, demo_link TEXT, repo_link TEXT, slack_id TEXT NOT NULL, username TEXT,
+                     created_at TIMESTAMPTZ DEFAULT now(), updated_at TIMESTAMPTZ DEFAULT now(), last_synced TIMESTAMPTZ
+                 );
+                 CREATE TABLE logs (id BIGINT PRIMARY KEY, project_id BIGINT, created_at TIMESTAMPTZ);
+                 CREATE TABLE comments (id BIGSERIAL PRIMARY KEY, devlog_id BIGINT, created_at TIMESTAMPTZ);
+                 INSERT INTO projects (id, title, slack_id) VALUES (1, 'busy', 'U1'), (2, 'chatty', 'U2'), (3, 'stale', 'U3'), (4, 'quiet', 'U4');
+                 INSERT INTO logs VALUES
+                     (10, 1, now() - interval '1 day'), (11, 1, now() - interval '2 days'), (12, 1, now() - interval '6 days'),
+                     (20, 2, now() - interval '30 days'),
+                     (30, 3, now() - interval '20 days'), (31, 3, now() - interval '25 days');
+                 INSERT INTO comments (devlog_id, created_at) VALUES
+                     (10, now() - interval '1 hour'),
+                     (20, now() - interval '1 day'), (20, now() - interval '2 days'), (20, now() - interval '3 days'),
+                     (30, now() - interval '40 days');"
+            ))
+            .await
+            .unwrap();
+
+        let since = chrono::Utc::now() - parse_window("7d").unwrap();
+        let trending = trending_since(&client, since, 10).await;
+        let top = trending_since(&client, since, 1).await;
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
 
-    Ok(Json(project.with_comments(comments)))
+        let ranked: Vec<(i64, i64, Option<i64>, Option<i64>)> = trending
+            .unwrap()
+            .iter()
+            .map(|t| (t.project.id, t.activity, t.project.devlog_count, t.project.comment_count))
+            .collect();
+        // Project 2's devlog is old but its comments are recent; 3 and 4 had nothing this week.
+        assert_eq!(ranked, [(1, 4, Some(3), Some(1)), (2, 3, Some(0), Some(3))]);
+        assert_eq!(top.unwrap().len(), 1);
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn explain_rows_fill_every_diagnostic() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        // Same columns as PROJECT_EXPLAIN_SQL, without needing pgvector.
+        let rows = client
+            .query(
+                "SELECT 5::BIGINT AS id, 'Rover'::TEXT AS title, 'A tiny robot'::TEXT AS description,
+                        'Hardware'::TEXT AS category, NULL::TEXT AS readme_link, NULL::TEXT AS demo_link,
+                        NULL::TEXT AS repo_link, 'U1'::TEXT AS slack_id, 'rover'::TEXT AS username,
+                        now() AS created_at, now() AS updated_at, NULL::TIMESTAMPTZ AS last_synced,
+                        0.25::FLOAT8 AS raw_distance, false AS zero_vector",
+                &[],
+            )
+            .await
+            .unwrap();
+        let calibration = ConfidenceCalibration::Linear { baseline: 0.5 };
+        let explanations = explain_rows(&rows, &calibration, 3).unwrap();
+
+        let [rover] = explanations.as_slice() else {
+            panic!("expected one explanation, got {}", explanations.len());
+        };
+        assert_eq!(rover.project.id, 5);
+        assert_eq!(rover.raw_distance, 0.25);
+        assert_eq!(rover.confidence, 0.5);
+        assert_eq!(rover.query_token_count, 3);
+        assert_eq!(
+            rover.embedded_text_preview,
+            project_embedding_text_from_parts("Rover", Some("A tiny robot"), Some("Hardware"))
+        );
+        assert!(!rover.zero_vector);
+
+        let json = serde_json::to_value(rover).unwrap();
+        for field in ["title", "raw_distance", "confidence", "query_token_count", "embedded_text_preview", "zero_vector"] {
+            assert!(!json[field].is_null(), "{field} missing from {json}");
+        }
+    }
+
+    /// Needs a scratch database with pgvector: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn explain_sql_reports_distance_and_zero_vectors() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+        if client.batch_execute("CREATE EXTENSION IF NOT EXISTS vector").await.is_err() {
+            eprintln!("pgvector not available, skipping");
+            return;
+        }
+
+        let schema = format!("explain_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema}, public;
+                 CREATE TABLE projects (
+                     id BIGINT PRIMARY KEY, title TEXT NOT NULL, description TEXT, category TEXT,
+                     readme_link TEXT, demo_link TEXT, repo_link TEXT, slack_id TEXT NOT NULL, username TEXT,
+                     created_at TIMESTAMPTZ, updated_at TIMESTAMPTZ, last_synced TIMESTAMPTZ,
+                     title_description_embedding vector(3)
+                 );
+                 INSERT INTO projects (id, title, description, category, slack_id, title_description_embedding) VALUES
+                     (1, 'Rover', ' A tiny robot ', 'Hardware', 'U1', '[1,0,0]'),
+                     (2, 'Blank', NULL, NULL, 'U2', '[0,0,0]');"
+            ))
+            .await
+            .unwrap();
+
+        let query = Vector::from(vec![1.0_f32, 0.0, 0.0]);
+        let rows = client.query(PROJECT_EXPLAIN_SQL, &[&query, &10_i64]).await.unwrap();
+        let calibration = ConfidenceCalibration::Linear { baseline: 0.5 };
+        let explanations = explain_rows(&rows, &calibration, 7).unwrap();
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        let rover = explanations.iter().find(|e| e.project.id == 1).unwrap();
+        assert!(rover.raw_distance.abs() < 1e-6);
+        assert!((rover.confidence - calibration.apply(1.0 - rover.raw_distance)).abs() < 1e-9);
+        assert_eq!(rover.query_token_count, 7);
+        assert_eq!(
+            rover.embedded_text_preview,
+            project_embedding_text_from_parts("Rover", Some("A tiny robot"), Some("Hardware"))
+        );
+        assert!(!rover.zero_vector);
+
+        let blank = explanations.iter().find(|e| e.project.id == 2).unwrap();
+        assert!(blank.zero_vector);
+        assert_eq!(blank.embedded_text_preview, "Blank");
+    }
+
+    fn project_filter(query: serde_json::Value) -> ProjectFilter {
+        serde_json::from_value(query).unwrap()
+    }
+
+    async fn filtered_ids(client: &tokio_postgres::Client, query: serde_json::Value) -> Vec<i64> {
+        let (sql, query_builder) = project_filter_query(project_filter(query)).unwrap();
+        let rows = client.query(&sql, &query_builder.params()).await.unwrap();
+        rows.iter().map(|row| row.get("id")).collect()
+    }
+
+    #[test]
+    fn unknown_sort_column_or_direction_is_rejected() {
+        for (query, rejected) in [
+            (serde_json::json!({ "sortBy": "title; DROP TABLE projects" }), "sortBy"),
+            (serde_json::json!({ "sortBy": "slack_id" }), "sortBy"),
+            (serde_json::json!({ "sortBy": "title", "sortDir": "sideways" }), "sortDir"),
+        ] {
+            assert!(
+                matches!(project_filter_query(project_filter(query.clone())), Err(ApiError::Validation { ref field, .. }) if field == rejected),
+                "{query} should be rejected on {rejected}"
+            );
+        }
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn sort_by_changes_the_order_of_filtered_projects() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("filter_sort_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE projects (
+                     id BIGINT PRIMARY KEY, title TEXT NOT NULL, description TEXT, category TEXT,
+                     readme_link TEXT, demo_link TEXT, repo_link TEXT, slack_id TEXT NOT NULL, username TEXT,
+                     created_at TIMESTAMPTZ, updated_at TIMESTAMPTZ, last_synced TIMESTAMPTZ
+                 );
+                 INSERT INTO projects (id, title, slack_id, created_at, updated_at) VALUES
+                     (1, 'Comet', 'U1', '2025-06-05', '2025-06-20'),
+                     (2, 'Aurora', 'U2', '2025-06-03', '2025-06-10'),
+                     (3, 'Beacon', 'U3', '2025-06-01', '2025-06-30');"
+            ))
+            .await
+            .unwrap();
+
+        let by_default = filtered_ids(&client, serde_json::json!({})).await;
+        let by_title = filtered_ids(&client, serde_json::json!({ "sortBy": "title", "sortDir": "asc" })).await;
+        let by_created = filtered_ids(&client, serde_json::json!({ "sortBy": "created_at" })).await;
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(by_default, [3, 1, 2]);
+        assert_eq!(by_title, [2, 3, 1]);
+        assert_eq!(by_created, [1, 2, 3]);
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn title_filter_matches_substrings_and_literal_wildcards() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("filter_title_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE projects (
+                     id BIGINT PRIMARY KEY, title TEXT NOT NULL, description TEXT, category TEXT,
+                     readme_link TEXT, demo_link TEXT, repo_link TEXT, slack_id TEXT NOT NULL, username TEXT,
+                     created_at TIMESTAMPTZ, updated_at TIMESTAMPTZ, last_synced TIMESTAMPTZ
+                 );
+                 INSERT INTO projects (id, title, slack_id, updated_at) VALUES
+                     (1, 'Summer Rover', 'U1', '2025-06-04'),
+                     (2, 'summer', 'U2', '2025-06-03'),
+                     (3, '100% uptime', 'U3', '2025-06-02'),
+                     (4, '1000 uptime', 'U4', '2025-06-01');"
+            ))
+            .await
+            .unwrap();
+
+        let substring = filtered_ids(&client, serde_json::json!({ "title": "SUMMER" })).await;
+        let exact = filtered_ids(&client, serde_json::json!({ "title": "SUMMER", "match": "exact" })).await;
+        let literal_percent = filtered_ids(&client, serde_json::json!({ "title": "0%" })).await;
+        let pattern = filtered_ids(&client, serde_json::json!({ "title": "10_%uptime", "match": "pattern" })).await;
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(substring, [1, 2]);
+        assert_eq!(exact, [2]);
+        assert_eq!(literal_percent, [3]);
+        assert_eq!(pattern, [3, 4]);
+    }
+
+    /// Needs a scratch database with pgvector: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn unembedded_projects_fall_back_to_keyword_matches() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+        if client.batch_execute("CREATE EXTENSION IF NOT EXISTS vector").await.is_err() {
+            eprintln!("pgvector not available, skipping");
+            return;
+        }
+
+        let schema = format!("fallback_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema}, public;
+                 CREATE TABLE projects (
+                     id BIGINT PRIMARY KEY, title TEXT NOT NULL, description TEXT, category TEXT,
+                     readme_link TEXT, demo_link TEXT, repo_link TEXT, slack_id TEXT NOT NULL, username TEXT,
+                     created_at TIMESTAMPTZ, updated_at TIMESTAMPTZ, last_synced TIMESTAMPTZ,
+                     title_description_embedding vector(3)
+                 );
+                 INSERT INTO projects (id, title, description, slack_id) VALUES
+                     (1, 'Rover', 'A tiny robot that maps the garden', 'U1'),
+                     (2, 'Robots everywhere', 'Robot arms and a robot dog', 'U2'),
+                     (3, 'Recipe book', 'Family recipes', 'U3');"
+            ))
+            .await
+            .unwrap();
+
+        let embedding = Vector::from(vec![1.0_f32, 0.0, 0.0]);
+        let unembedded = project_search_rows(&client, &embedding, "robot", 10).await;
+        client
+            .batch_execute("UPDATE projects SET title_description_embedding = '[1,0,0]' WHERE id = 3")
+            .await
+            .unwrap();
+        let embedded = project_search_rows(&client, &embedding, "robot", 10).await;
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        let SearchRows::FullText(rows) = unembedded.unwrap() else {
+            panic!("an unembedded table should fall back to full-text search");
+        };
+        let ids: Vec<i64> = rows.iter().map(|row| row.get("id")).collect();
+        // Project 2 mentions robots three times, so it outranks project 1.
+        assert_eq!(ids, [2, 1]);
+        assert!(rows.iter().all(|row| row.get::<_, f64>("rank") > 0.0));
+
+        let SearchRows::Vector(rows) = embedded.unwrap() else {
+            panic!("embedded rows should be served by the vector search");
+        };
+        assert_eq!(rows.iter().map(|row| row.get::<_, i64>("id")).collect::<Vec<_>>(), [3]);
+    }
+
+    /// Needs a scratch database with pgvector: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn duplicate_embeddings_are_ordered_by_id() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+        if client.batch_execute("CREATE EXTENSION IF NOT EXISTS vector").await.is_err() {
+            eprintln!("pgvector not available, skipping");
+            return;
+        }
+
+        let schema = format!("search_ties_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema}, public;
+                 CREATE TABLE projects (
+                     id BIGINT PRIMARY KEY, title TEXT NOT NULL, description TEXT, category TEXT,
+                     readme_link TEXT, demo_link TEXT, repo_link TEXT, slack_id TEXT NOT NULL, username TEXT,
+                     created_at TIMESTAMPTZ, updated_at TIMESTAMPTZ, last_synced TIMESTAMPTZ,
+                     title_description_embedding vector(3)
+                 );
+                 INSERT INTO projects (id, title, slack_id, title_description_embedding) VALUES
+                     (3, 'Copy', 'U3', '[0,1,0]'),
+                     (1, 'Copy', 'U1', '[0,1,0]'),
+                     (5, 'Copy', 'U5', '[0,1,0]'),
+                     (2, 'Closest', 'U2', '[1,0,0]'),
+                     (4, 'Copy', 'U4', '[0,1,0]');"
+            ))
+            .await
+            .unwrap();
+
+        let query = Vector::from(vec![1.0_f32, 0.2, 0.0]);
+        let mut orders = Vec::new();
+        for limit in [10_i64, 10, 3] {
+            let rows = client.query(PROJECT_SEARCH_SQL, &[&query, &limit]).await.unwrap();
+            orders.push(rows.iter().map(|row| row.get::<_, i64>("id")).collect::<Vec<_>>());
+        }
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(orders[0], [2, 5, 4, 3, 1]);
+        assert_eq!(orders[1], orders[0]);
+        assert_eq!(orders[2], orders[0][..3]);
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn details_keep_only_the_newest_comments() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("details_comments_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE projects (
+                     id BIGINT PRIMARY KEY, title TEXT NOT NULL, description TEXT, category TEXT,
+                     readme_link TEXT, demo_link TEXT, repo_link TEXT, slack_id TEXT NOT NULL, username TEXT,
+                     created_at TIMESTAMPTZ, updated_at TIMESTAMPTZ, last_synced TIMESTAMPTZ
+                 );
+                 CREATE TABLE logs (id BIGINT PRIMARY KEY, project_id BIGINT);
+                 CREATE TABLE comments (
+                     id BIGSERIAL PRIMARY KEY, upstream_id BIGINT, text TEXT NOT NULL, devlog_id BIGINT NOT NULL,
+                     slack_id TEXT NOT NULL, username TEXT, created_at TIMESTAMPTZ, last_synced TIMESTAMPTZ
+                 );
+                 INSERT INTO projects (id, title, slack_id, created_at, updated_at) VALUES (1, 'Chatty', 'U1', now(), now()), (2, 'Other', 'U2', now(), now());
+                 INSERT INTO logs VALUES (10, 1), (11, 1), (20, 2);
+                 INSERT INTO comments (text, devlog_id, slack_id, created_at)
+                     SELECT 'comment ' || n, CASE WHEN n % 2 = 0 THEN 10 ELSE 11 END, 'U9',
+                            '2026-01-01'::TIMESTAMPTZ + n * interval '1 minute'
+                     FROM generate_series(1, 60) AS n;
+                 INSERT INTO comments (text, devlog_id, slack_id, created_at) VALUES ('elsewhere', 20, 'U9', '2026-02-01');"
+            ))
+            .await
+            .unwrap();
+
+        let by_default = project_details(&client, 1, DEFAULT_DETAILS_COMMENT_LIMIT, false).await;
+        let limited = project_details(&client, 1, 5, false).await;
+        let next_page = fetch_project_comments(&client, 1, 5, 5, false).await;
+        let missing = project_details(&client, 404, 5, false).await;
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        let texts = |comments: &[Comment]| -> Vec<String> { comments.iter().map(|c| c.text.clone()).collect() };
+        let by_default = by_default.unwrap();
+        assert_eq!(by_default.comments.len(), 50);
+        assert_eq!(by_default.comments[0].text, "comment 60");
+        assert_eq!(by_default.comments[49].text, "comment 11");
+        assert_eq!(texts(&limited.unwrap().comments), ["comment 60", "comment 59", "comment 58", "comment 57", "comment 56"]);
+        assert_eq!(texts(&next_page.unwrap()), ["comment 55", "comment 54", "comment 53", "comment 52", "comment 51"]);
+        assert!(matches!(missing, Err(ApiError::NotFound { .. })));
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn counts_are_correct_and_only_present_when_requested() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("project_counts_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE projects (
+                     id BIGINT PRIMARY KEY, title TEXT NOT NULL, description TEXT, category TEXT,
+                     readme_link TEXT, demo_link TEXT, repo_link TEXT, slack_id TEXT NOT NULL, username TEXT,
+                     created_at TIMESTAMPTZ, updated_at TIMESTAMPTZ, last_synced TIMESTAMPTZ
+                 );
+                 CREATE TABLE logs (id BIGINT PRIMARY KEY, project_id BIGINT);
+                 CREATE TABLE comments (id BIGSERIAL PRIMARY KEY, devlog_id BIGINT);
+                 INSERT INTO projects (id, title, slack_id, created_at, updated_at) VALUES
+                     (1, 'Busy', 'U1', now(), '2026-03-03'),
+                     (2, 'Logged', 'U2', now(), '2026-03-02'),
+                     (3, 'Empty', 'U3', now(), '2026-03-01');
+                 INSERT INTO logs VALUES (10, 1), (11, 1), (20, 2);
+                 INSERT INTO comments (devlog_id) VALUES (10), (10), (11);"
+            ))
+            .await
+            .unwrap();
+
+        let mut pages = Vec::new();
+        for include_counts in [false, true] {
+            let filter = project_filter(serde_json::json!({ "includeCounts": include_counts }));
+            let include_counts = filter.include_counts.unwrap_or(false);
+            let (sql, query_builder) = project_filter_query(filter).unwrap();
+            pages.push(filtered_projects(&client, &sql, &query_builder.params(), include_counts).await);
+        }
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        let counts = |page: &Result<Vec<Project>>| -> Vec<(i64, Option<i64>, Option<i64>)> {
+            page.as_ref()
+                .unwrap()
+                .iter()
+                .map(|p| (p.id, p.devlog_count, p.comment_count))
+                .collect()
+        };
+        assert_eq!(counts(&pages[0]), [(1, None, None), (2, None, None), (3, None, None)]);
+        assert_eq!(
+            counts(&pages[1]),
+            [(1, Some(2), Some(3)), (2, Some(1), Some(0)), (3, Some(0), Some(0))]
+        );
+
+        let without = serde_json::to_value(&pages[0].as_ref().unwrap()[0]).unwrap();
+        assert!(without.get("devlog_count").is_none() && without.get("comment_count").is_none(), "{without}");
+        let with = serde_json::to_value(&pages[1].as_ref().unwrap()[0]).unwrap();
+        assert_eq!((&with["devlog_count"], &with["comment_count"]), (&serde_json::json!(2), &serde_json::json!(3)));
+    }
 }
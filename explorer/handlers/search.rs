@@ -0,0 +1,166 @@
+use axum::Json;
+use axum::extract::State;
+use pgvector::Vector;
+use tokio_postgres::{Client, Row};
+use tracing::instrument;
+
+use crate::AppState;
+use crate::handlers::{comments::COMMENT_SEARCH_SQL, logs::LOG_SEARCH_SQL, projects::PROJECT_SEARCH_SQL};
+use crate::models::feedback::SearchResultType;
+use crate::models::search::{SearchItem, TypeWeights, UnifiedSearchRequest, UnifiedSearchResult};
+use crate::utils::database::{map_comment_row, map_log_row, map_project_row, try_column};
+use crate::utils::error::Result;
+
+#[utoipa::path(
+    post,
+    path = "/v1/search",
+    request_body = UnifiedSearchRequest,
+    responses(
+        (status = 200, description = "Projects, devlogs and comments merged by weighted score", body = [UnifiedSearchResult]),
+        (status = 400, description = "Invalid type weights")
+    ),
+    tag = "search"
+)]
+#[instrument(skip(state), fields(query = %request.query, limit = request.limit.unwrap_or(20)))]
+pub async fn unified_search(
+    State(state): State<AppState>,
+    Json(request): Json<UnifiedSearchRequest>,
+) -> Result<Json<Vec<UnifiedSearchResult>>> {
+    let weights = request.type_weights;
+    weights.validate()?;
+    let limit = i64::from(request.limit.unwrap_or(20).min(100));
+
+    let embedding = state.embed_query(&request.query).await?;
+    let client = state.db().await?;
+
+    // Each type contributes up to `limit` hits so any mix can fill the page.
+    let (projects, devlogs, comments) = tokio::try_join!(
+        search_rows(&client, weights.projects, PROJECT_SEARCH_SQL, &embedding, limit),
+        search_rows(&client, weights.devlogs, LOG_SEARCH_SQL, &embedding, limit),
+        search_rows(&client, weights.comments, COMMENT_SEARCH_SQL, &embedding, limit),
+    )?;
+    drop(client);
+
+    let calibrate = |row: &Row| -> Result<(f64, f64)> {
+        let similarity: f64 = try_column(row, "confidence")?;
+        Ok((similarity, state.confidence(similarity)))
+    };
+
+    let mut hits = Vec::with_capacity(projects.len() + devlogs.len() + comments.len());
+    for row in &projects {
+        let (similarity, confidence) = calibrate(row)?;
+        let item = SearchItem::Project(map_project_row(row)?.with_confidence(confidence));
+        hits.push((SearchResultType::Project, similarity, item));
+    }
+    for row in &devlogs {
+        let (similarity, confidence) = calibrate(row)?;
+        let item = SearchItem::Devlog(map_log_row(row)?.with_confidence(confidence));
+        hits.push((SearchResultType::Devlog, similarity, item));
+    }
+    for row in &comments {
+        let (similarity, confidence) = calibrate(row)?;
+        let item = SearchItem::Comment(map_comment_row(row, state.hide_comment_serial_id)?.with_confidence(confidence));
+        hits.push((SearchResultType::Comment, similarity, item));
+    }
+
+    let results = merge_weighted(hits, &weights, limit as usize)
+        .into_iter()
+        .map(|(result_type, score, item)| UnifiedSearchResult { result_type, score, item })
+        .collect();
+
+    Ok(Json(results))
+}
+
+async fn search_rows(client: &Client, weight: f64, sql: &str, embedding: &Vector, limit: i64) -> Result<Vec<Row>> {
+    if weight == 0.0 {
+        return Ok(Vec::new());
+    }
+    Ok(client.query(sql, &[embedding, &limit]).await?)
+}
+
+/// Scores each hit as its cosine similarity times its type's weight and sorts
+/// everything by that score. Similarities share one scale across types, so
+/// scores stay comparable between responses; a weight is the only lever for
+/// favouring a type. Ties keep the input order.
+fn merge_weighted<T>(
+    hits: Vec<(SearchResultType, f64, T)>,
+    weights: &TypeWeights,
+    limit: usize,
+) -> Vec<(SearchResultType, f64, T)> {
+    let mut merged: Vec<_> = hits
+        .into_iter()
+        .filter(|(result_type, ..)| weights.weight(*result_type) > 0.0)
+        .map(|(result_type, similarity, item)| (result_type, similarity * weights.weight(result_type), item))
+        .collect();
+
+    merged.sort_by(|a, b| b.1.total_cmp(&a.1));
+    merged.truncate(limit);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hits() -> Vec<(SearchResultType, f64, &'static str)> {
+        vec![
+            (SearchResultType::Project, 0.90, "p1"),
+            (SearchResultType::Project, 0.72, "p2"),
+            (SearchResultType::Comment, 0.50, "c1"),
+            (SearchResultType::Comment, 0.45, "c2"),
+        ]
+    }
+
+    fn order(merged: &[(SearchResultType, f64, &'static str)]) -> Vec<&'static str> {
+        merged.iter().map(|(.., item)| *item).collect()
+    }
+
+    #[test]
+    fn default_weights_rank_by_raw_similarity() {
+        let merged = merge_weighted(hits(), &TypeWeights::default(), 10);
+
+        assert_eq!(order(&merged), ["p1", "p2", "c1", "c2"]);
+        let scores: Vec<f64> = merged.iter().map(|(_, score, _)| *score).collect();
+        assert_eq!(scores, [0.90, 0.72, 0.50, 0.45]);
+    }
+
+    #[test]
+    fn weights_change_cross_type_order() {
+        let favour_comments = TypeWeights { comments: 1.9, ..TypeWeights::default() };
+        let merged = merge_weighted(hits(), &favour_comments, 10);
+        assert_eq!(order(&merged), ["c1", "p1", "c2", "p2"]);
+        assert_eq!(merged[0].1, 0.50 * 1.9);
+
+        let favour_projects = TypeWeights { comments: 0.5, ..TypeWeights::default() };
+        let merged = merge_weighted(hits(), &favour_projects, 10);
+        assert_eq!(order(&merged), ["p1", "p2", "c1", "c2"]);
+        assert_eq!(merged[2].1, 0.25);
+    }
+
+    #[test]
+    fn a_lone_weak_hit_is_not_inflated() {
+        // Dividing by each type's best would score this comment 1.0 and put it
+        // above a far closer project.
+        let hits = vec![
+            (SearchResultType::Project, 0.90, "p1"),
+            (SearchResultType::Comment, 0.20, "c1"),
+        ];
+        let merged = merge_weighted(hits, &TypeWeights::default(), 10);
+        assert_eq!(order(&merged), ["p1", "c1"]);
+        assert_eq!(merged[1].1, 0.20);
+    }
+
+    #[test]
+    fn zero_weight_drops_type_and_limit_applies() {
+        let no_comments = TypeWeights { comments: 0.0, ..TypeWeights::default() };
+        assert_eq!(order(&merge_weighted(hits(), &no_comments, 10)), ["p1", "p2"]);
+        assert_eq!(order(&merge_weighted(hits(), &TypeWeights::default(), 3)), ["p1", "p2", "c1"]);
+    }
+
+    #[test]
+    fn rejects_negative_and_non_finite_weights() {
+        assert!(TypeWeights::default().validate().is_ok());
+        assert!(TypeWeights { devlogs: -1.0, ..TypeWeights::default() }.validate().is_err());
+        assert!(TypeWeights { projects: f64::NAN, ..TypeWeights::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,216 @@
+use axum::{Json, extract::State};
+
+use crate::AppState;
+use crate::models::stats::{
+    DegenerateEmbeddings, EmbeddingCoverage, EmbeddingHealthResponse, StatsResponse, UserStats,
+};
+use crate::utils::database::try_column;
+use crate::utils::error::Result;
+
+#[utoipa::path(
+    get,
+    path = "/v1/stats",
+    responses(
+        (status = 200, description = "Row counts and embedding coverage", body = StatsResponse)
+    ),
+    tag = "stats"
+)]
+pub async fn get_stats(State(state): State<AppState>) -> Result<Json<StatsResponse>> {
+    let client = state.db().await?;
+    Ok(Json(read_stats(&client).await?))
+}
+
+async fn read_stats(client: &tokio_postgres::Client) -> Result<StatsResponse> {
+    let (projects, devlogs, comments, users) = tokio::try_join!(
+        client.query_one(
+            "SELECT COUNT(*) AS total, COUNT(title_description_embedding) AS embedded FROM projects",
+            &[],
+        ),
+        client.query_one("SELECT COUNT(*) AS total, COUNT(text_embedding) AS embedded FROM logs", &[]),
+        client.query_one("SELECT COUNT(*) AS total, COUNT(text_embedding) AS embedded FROM comments", &[]),
+        client.query_one(
+            "SELECT COUNT(*) AS total, COUNT(*) FILTER (WHERE trust_level IS NOT NULL AND trust_level <> 'unavailable') AS with_trust FROM users",
+            &[],
+        ),
+    )?;
+
+    let coverage = |row: &tokio_postgres::Row| -> Result<EmbeddingCoverage> {
+        Ok(EmbeddingCoverage {
+            total: try_column(row, "total")?,
+            embedded: try_column(row, "embedded")?,
+        })
+    };
+
+    Ok(StatsResponse {
+        projects: coverage(&projects)?,
+        devlogs: coverage(&devlogs)?,
+        comments: coverage(&comments)?,
+        users: UserStats {
+            total: try_column(&users, "total")?,
+            with_trust: try_column(&users, "with_trust")?,
+        },
+    })
+}
+
+const DEGENERATE_SAMPLE_SIZE: i32 = 20;
+
+fn degenerate_embeddings_sql(table: &str, column: &str) -> String {
+    format!(
+        "SELECT 
+            COUNT(*) FILTER (WHERE vector_norm({column}) = 0) AS zero, 
+            COUNT(*) FILTER (WHERE vector_norm({column}) = 'NaN') AS nan, 
+            COALESCE(
+                (ARRAY_AGG(id ORDER BY id) FILTER (
+                    WHERE vector_norm({column}) = 0 OR vector_norm({column}) = 'NaN'
+                ))[1:$1],
+                '{{}}'
+            ) AS sample_ids 
+         FROM {table} 
+         WHERE {column} IS NOT NULL"
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/stats/embeddings",
+    responses(
+        (status = 200, description = "Stored embeddings that are all zeros or contain NaN", body = EmbeddingHealthResponse)
+    ),
+    tag = "stats"
+)]
+pub async fn get_embedding_health(
+    State(state): State<AppState>,
+) -> Result<Json<EmbeddingHealthResponse>> {
+    let client = state.db().await?;
+    Ok(Json(read_embedding_health(&client).await?))
+}
+
+async fn read_embedding_health(client: &tokio_postgres::Client) -> Result<EmbeddingHealthResponse> {
+    let projects_sql = degenerate_embeddings_sql("projects", "title_description_embedding");
+    let devlogs_sql = degenerate_embeddings_sql("logs", "text_embedding");
+    let comments_sql = degenerate_embeddings_sql("comments", "text_embedding");
+
+    let (projects, devlogs, comments) = tokio::try_join!(
+        client.query_one(&projects_sql, &[&DEGENERATE_SAMPLE_SIZE]),
+        client.query_one(&devlogs_sql, &[&DEGENERATE_SAMPLE_SIZE]),
+        client.query_one(&comments_sql, &[&DEGENERATE_SAMPLE_SIZE]),
+    )?;
+
+    let degenerate = |row: &tokio_postgres::Row| -> Result<DegenerateEmbeddings> {
+        Ok(DegenerateEmbeddings {
+            zero: try_column(row, "zero")?,
+            nan: try_column(row, "nan")?,
+            sample_ids: try_column(row, "sample_ids")?,
+        })
+    };
+
+    Ok(EmbeddingHealthResponse {
+        projects: degenerate(&projects)?,
+        devlogs: degenerate(&devlogs)?,
+        comments: degenerate(&comments)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn counts_embedded_rows_per_table() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        // COUNT(column) only looks at NULLs, so plain arrays stand in for vectors.
+        let schema = format!("stats_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE projects (id BIGINT PRIMARY KEY, title_description_embedding REAL[]);
+                 CREATE TABLE logs (id BIGINT PRIMARY KEY, text_embedding REAL[]);
+                 CREATE TABLE comments (id BIGSERIAL PRIMARY KEY, text_embedding REAL[]);
+                 CREATE TABLE users (slack_id VARCHAR(50) PRIMARY KEY, trust_level VARCHAR(50));
+                 INSERT INTO projects VALUES (1, '{{1,0}}'), (2, NULL), (3, '{{0,1}}');
+                 INSERT INTO logs VALUES (1, NULL), (2, NULL);
+                 INSERT INTO comments (text_embedding) VALUES ('{{1,1}}');
+                 INSERT INTO users VALUES ('U1', 'green'), ('U2', 'unavailable'), ('U3', NULL), ('U4', 'blue');"
+            ))
+            .await
+            .unwrap();
+
+        let stats = read_stats(&client).await;
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(stats.unwrap()).unwrap(),
+            json!({
+                "projects": { "total": 3, "embedded": 2 },
+                "devlogs": { "total": 2, "embedded": 0 },
+                "comments": { "total": 1, "embedded": 1 },
+                "users": { "total": 4, "with_trust": 2 },
+            })
+        );
+    }
+
+    /// Needs a scratch database with pgvector: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn zero_vectors_are_flagged_per_table() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+        if client.batch_execute("CREATE EXTENSION IF NOT EXISTS vector").await.is_err() {
+            eprintln!("pgvector not available, skipping");
+            return;
+        }
+
+        let schema = format!("embedding_health_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema}, public;
+                 CREATE TABLE projects (id BIGINT PRIMARY KEY, title_description_embedding vector(3));
+                 CREATE TABLE logs (id BIGINT PRIMARY KEY, text_embedding vector(3));
+                 CREATE TABLE comments (id BIGSERIAL PRIMARY KEY, text_embedding vector(3));
+                 INSERT INTO projects VALUES (1, '[1,0,0]'), (2, '[0,0,0]'), (3, NULL), (4, '[0,0,0]');
+                 INSERT INTO logs VALUES (1, '[0,0,0]'), (2, '[0,1,0]');
+                 INSERT INTO comments (text_embedding) VALUES ('[0,0,1]'), (NULL);"
+            ))
+            .await
+            .unwrap();
+
+        let health = read_embedding_health(&client).await;
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(health.unwrap()).unwrap(),
+            json!({
+                "projects": { "zero": 2, "nan": 0, "sample_ids": [2, 4] },
+                "devlogs": { "zero": 1, "nan": 0, "sample_ids": [1] },
+                "comments": { "zero": 0, "nan": 0, "sample_ids": [] },
+            })
+        );
+    }
+}
@@ -5,8 +5,14 @@ use axum::{
 
 use crate::{
     AppState,
-    models::user::{ShellHistory, User, UserFilter, UserProject},
-    utils::{database::decode_username, error::{ApiError, Result}},
+    models::user::{ShellHistory, ShellHistoryFilter, User, UserFilter},
+    utils::{
+        database::{
+            decode_username, map_shell_history_row, map_user_project_row, map_user_row,
+            try_column, QueryBuilder,
+        },
+        error::{ApiError, Result},
+    },
 };
 
 #[utoipa::path(
@@ -19,13 +25,16 @@ use crate::{
     ),
     tag = "users"
 )]
-#[allow(clippy::too_many_lines, clippy::items_after_statements)]
 pub async fn get_user_details(
     State(state): State<AppState>,
     Query(filter): Query<UserFilter>,
 ) -> Result<Json<User>> {
-    let client = state.pool.get().await?;
+    let client = state.db().await?;
+    Ok(Json(user_details(&client, filter).await?))
+}
 
+#[allow(clippy::too_many_lines, clippy::items_after_statements)]
+async fn user_details(client: &tokio_postgres::Client, filter: UserFilter) -> Result<User> {
     let mut conditions = Vec::with_capacity(2);
     let mut params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::with_capacity(2);
     let mut param_count = 0;
@@ -83,66 +92,236 @@ pub async fn get_user_details(
         });
     }
 
-    let first_row = &rows[0];
-    let user = User {
-        slack_id: first_row.get("slack_id"),
-        username: first_row.get("username"),
-        trust_level: first_row.get("trust_level"),
-        trust_value: first_row.get("trust_value"),
-        current_shells: first_row.get("current_shells"),
-        last_synced: first_row.get("last_synced"),
-        shell_history: Vec::new(),
-        projects: Vec::new(),
-        pfp_url: first_row.get("pfp_url"),
-        image_24: first_row.get("image_24"),
-        image_32: first_row.get("image_32"),
-        image_48: first_row.get("image_48"),
-        image_72: first_row.get("image_72"),
-        image_192: first_row.get("image_192"),
-        image_512: first_row.get("image_512"),
-    };
-
-    let shell_history: Vec<ShellHistory> = rows
-        .iter()
-        .filter_map(|row| {
-            row.get::<_, Option<i32>>("id").map(|id| ShellHistory {
-                id,
-                shells_then: row.get("shells_then"),
-                shell_diff: row.get("shell_diff"),
-                shells: row.get("shells"),
-                recorded_at: row.get("recorded_at"),
-            })
-        })
-        .collect();
+    let mut user = map_user_row(&rows[0])?;
+
+    // A user without history still comes back once, with NULLs from the LEFT JOIN.
+    for row in &rows {
+        if try_column::<Option<i64>>(row, "id")?.is_some() {
+            user.shell_history.push(map_shell_history_row(row)?);
+        }
+    }
 
     let project_statement = client.prepare(
         "SELECT id, title FROM projects WHERE slack_id = $1 ORDER BY created_at DESC"
     ).await?;
     let project_rows = client.query(&project_statement, &[&user.slack_id]).await?;
 
-    let projects: Vec<UserProject> = project_rows
+    user.projects = project_rows
+        .iter()
+        .map(map_user_project_row)
+        .collect::<Result<_>>()?;
+
+    Ok(user)
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/users/shell-history",
+    params(ShellHistoryFilter),
+    responses(
+        (status = 200, description = "Shell history ordered by recorded_at ascending", body = [ShellHistory]),
+        (status = 400, description = "Invalid date range"),
+        (status = 404, description = "User not found")
+    ),
+    tag = "users"
+)]
+pub async fn get_user_shell_history(
+    State(state): State<AppState>,
+    Query(filter): Query<ShellHistoryFilter>,
+) -> Result<Json<Vec<ShellHistory>>> {
+    let client = state.db().await?;
+    Ok(Json(shell_history(&client, filter).await?))
+}
+
+async fn shell_history(client: &tokio_postgres::Client, filter: ShellHistoryFilter) -> Result<Vec<ShellHistory>> {
+    let user_exists = client
+        .query_opt("SELECT 1 FROM users WHERE slack_id = $1", &[&filter.slack_id])
+        .await?
+        .is_some();
+
+    if !user_exists {
+        return Err(ApiError::NotFound {
+            resource: "User".to_owned(),
+            id: filter.slack_id.to_string(),
+        });
+    }
+
+    let mut query_builder = QueryBuilder::new();
+    query_builder.add_condition("slack_id = ${}", filter.slack_id);
+    query_builder.add_date_range_condition(
+        "recorded_at",
+        filter.from.as_deref(),
+        filter.to.as_deref(),
+    )?;
+
+    let query = format!(
+        "SELECT id, shells_then, shell_diff, shells, recorded_at 
+         FROM shell_history 
+         {} 
+         ORDER BY recorded_at ASC",
+        query_builder.build_where_clause()
+    );
+
+    let rows = client.query(&query, &query_builder.params()).await?;
+    let history = rows
         .iter()
-        .map(|row| UserProject {
-            id: row.get("id"),
-            title: row.get("title"),
-        })
-        .collect();
-
-    Ok(Json(User {
-        slack_id: user.slack_id,
-        username: user.username,
-        trust_level: user.trust_level,
-        trust_value: user.trust_value,
-        current_shells: user.current_shells,
-        last_synced: user.last_synced,
-        shell_history,
-        projects,
-        pfp_url: user.pfp_url,
-        image_24: user.image_24,
-        image_32: user.image_32,
-        image_48: user.image_48,
-        image_72: user.image_72,
-        image_192: user.image_192,
-        image_512: user.image_512,
-    }))
+        .map(map_shell_history_row)
+        .collect::<Result<_>>()?;
+
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn shell_history_range_includes_both_bounds() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("shell_history_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE users (slack_id VARCHAR(50) PRIMARY KEY);
+                 CREATE TABLE shell_history (
+                     id BIGSERIAL PRIMARY KEY, slack_id VARCHAR(50) NOT NULL REFERENCES users(slack_id),
+                     shells_then INTEGER, shell_diff INTEGER, shells INTEGER NOT NULL, recorded_at TIMESTAMPTZ DEFAULT NOW()
+                 );
+                 INSERT INTO users VALUES ('U1'), ('U2');
+                 INSERT INTO shell_history (slack_id, shells, recorded_at) VALUES
+                     ('U1', 30, '2026-03-02T00:00:00Z'),
+                     ('U1', 10, '2026-03-01T00:00:00Z'),
+                     ('U1', 20, '2026-03-01T12:00:00Z'),
+                     ('U1', 40, '2026-03-02T06:00:00Z'),
+                     ('U1', 50, '2026-03-03T00:00:00Z'),
+                     ('U2', 99, '2026-03-01T12:00:00Z');"
+            ))
+            .await
+            .unwrap();
+
+        let query = |slack_id: &str, from: Option<&str>, to: Option<&str>| {
+            shell_history(
+                &client,
+                ShellHistoryFilter {
+                    slack_id: slack_id.into(),
+                    from: from.map(str::to_owned),
+                    to: to.map(str::to_owned),
+                },
+            )
+        };
+        let shells = |history: Result<Vec<ShellHistory>>| -> Vec<i32> {
+            history.unwrap().iter().map(|entry| entry.shells).collect()
+        };
+
+        let everything = shells(query("U1", None, None).await);
+        let bounded = shells(query("U1", Some("2026-03-01T12:00:00Z"), Some("2026-03-02")).await);
+        let from_only = shells(query("U1", Some("02/03/2026"), None).await);
+        let inverted = shells(query("U1", Some("2026-03-03"), Some("2026-03-01")).await);
+        let unknown = query("U404", None, None).await;
+        let malformed = query("U1", Some("March 1st"), None).await;
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(everything, [10, 20, 30, 40, 50]);
+        assert_eq!(bounded, [20, 30]);
+        assert_eq!(from_only, [30, 40, 50]);
+        assert!(inverted.is_empty());
+        assert!(matches!(unknown, Err(ApiError::NotFound { .. })));
+        assert!(matches!(malformed, Err(ApiError::Validation { .. })));
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn user_details_survive_missing_history_and_report_schema_drift() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("user_details_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE users (
+                     slack_id VARCHAR(50) PRIMARY KEY, username VARCHAR(255), pfp_url VARCHAR(500),
+                     image_24 VARCHAR(500), image_32 VARCHAR(500), image_48 VARCHAR(500), image_72 VARCHAR(500),
+                     image_192 VARCHAR(500), image_512 VARCHAR(500), trust_level VARCHAR(50), trust_value INTEGER,
+                     current_shells INTEGER, last_synced TIMESTAMPTZ
+                 );
+                 CREATE TABLE shell_history (
+                     id BIGSERIAL PRIMARY KEY, slack_id VARCHAR(50) NOT NULL REFERENCES users(slack_id),
+                     shells_then INTEGER, shell_diff INTEGER, shells INTEGER NOT NULL, recorded_at TIMESTAMPTZ DEFAULT NOW()
+                 );
+                 CREATE TABLE projects (id BIGINT PRIMARY KEY, title TEXT NOT NULL, slack_id VARCHAR(50), created_at TIMESTAMPTZ);
+                 INSERT INTO users (slack_id, username, current_shells) VALUES ('U1', 'ada', 20), ('U2', 'grace', NULL);
+                 INSERT INTO shell_history (slack_id, shells, recorded_at) VALUES
+                     ('U1', 10, '2026-03-01T00:00:00Z'), ('U1', 20, '2026-03-02T00:00:00Z');
+                 INSERT INTO projects VALUES (7, 'Rover', 'U1', '2026-03-01');"
+            ))
+            .await
+            .unwrap();
+
+        let details = |slack_id: &str| {
+            user_details(
+                &client,
+                UserFilter { slack_id: Some(slack_id.into()), username: None, limit: None },
+            )
+        };
+
+        let with_history = details("U1").await;
+        let without_history = details("U2").await;
+        client
+            .batch_execute("ALTER TABLE users ALTER COLUMN trust_value TYPE TEXT")
+            .await
+            .unwrap();
+        let drifted = details("U1").await;
+        let missing_column = client
+            .query_one("SELECT slack_id, username, trust_level FROM users WHERE slack_id = 'U1'", &[])
+            .await
+            .map(|row| map_user_row(&row));
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        let with_history = with_history.unwrap();
+        let shells: Vec<i32> = with_history.shell_history.iter().map(|entry| entry.shells).collect();
+        assert_eq!(shells, [20, 10]);
+        assert_eq!(with_history.projects.len(), 1);
+
+        let without_history = without_history.unwrap();
+        assert_eq!(without_history.username.as_deref(), Some("grace"));
+        assert!(without_history.shell_history.is_empty());
+
+        let Err(ApiError::Database(message)) = drifted else {
+            panic!("expected a database error, got {drifted:?}");
+        };
+        assert!(message.contains("trust_value"), "{message}");
+
+        let Err(ApiError::Database(message)) = missing_column.unwrap() else {
+            panic!("expected a database error for the missing column");
+        };
+        assert!(message.contains("trust_value"), "{message}");
+    }
 }
@@ -2,15 +2,18 @@ mod utils;
 mod models;
 mod handlers;
 mod services;
+mod middleware;
 
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 use axum::{
     Json, Router,
+    error_handling::HandleErrorLayer,
+    http::{HeaderName, header},
     response::Html,
     routing::{get, post},
 };
-use tokio::net::TcpListener;
+use tokio::{net::TcpListener, sync::Semaphore};
 use tower::ServiceBuilder;
 use utoipa::OpenApi;
 use utoipa_scalar::Scalar;
@@ -19,21 +22,85 @@ use tower_http::{cors::CorsLayer, services::ServeDir};
 use common::utils::config::Config;
 use common::database::connection::DbPool;
 
-use utils::error::Result;
+use utils::error::{ApiError, Result};
+use services::calibration::ConfidenceCalibration;
 use services::embedding::EmbeddingService;
+use services::idempotency::IdempotencyStore;
+use services::maintenance::MaintenanceMode;
+use services::rate_limit::RateLimiter;
+use services::search_cache::SearchCaches;
+use middleware::{
+    REQUEST_ID_HEADER, handle_layer_error, idempotency, maintenance_gate, request_logger,
+    require_admin_key, search_concurrency_limit, search_rate_limit, track_requests,
+};
 use handlers::{
-    users::get_user_details,
-    leaderboard::get_leaderboard,
-    comments::{filter_comments, search_comments},
-    logs::{filter_logs, get_log_details, search_logs},
-    projects::{filter_projects, get_project_details, search_projects},
+    admin::{get_audit_log, get_sync_status, reset_user_sync},
+    users::{get_user_details, get_user_shell_history},
+    jobs::get_job_history,
+    leaderboard::{get_leaderboard, get_leaderboard_movers},
+    metrics::{get_health, get_metrics, get_sync_health},
+    stats::{get_embedding_health, get_stats},
+    comments::{filter_comments, get_comment_details, search_comments},
+    feedback::record_search_feedback,
+    logs::{filter_logs, get_log_details, get_related_comments, search_logs},
+    projects::{
+        explain_search_projects, filter_projects, get_project_comments, get_project_details,
+        get_similar_projects, get_trending_projects, search_projects,
+    },
     mirror::{mirror_comments, mirror_devlogs, mirror_project, mirror_projects},
+    search::unified_search,
 };
 
 #[derive(Clone)]
 pub struct AppState {
     pub pool: DbPool,
     pub embedding_service: Arc<EmbeddingService>,
+    pub confidence_calibration: ConfidenceCalibration,
+    pub confidence_decimals: u32,
+    pub search_cache: Arc<SearchCaches>,
+    pub debug_endpoints: bool,
+    pub sync_stale_after: Option<Duration>,
+    pub db_acquire_timeout: Duration,
+    pub hide_comment_serial_id: bool,
+}
+
+impl AppState {
+    /// Embeds a search query. Handlers call this before [`AppState::db`] so a
+    /// pooled connection is never held idle through an ONNX run.
+    pub async fn embed_query(&self, query: &str) -> Result<pgvector::Vector> {
+        Ok(pgvector::Vector::from(self.embedding_service.embed_text(query).await?))
+    }
+
+    /// Calibrates a raw cosine similarity into the confidence shown to
+    /// clients, rounded to `CONFIDENCE_DECIMALS`.
+    pub fn confidence(&self, similarity: f64) -> f64 {
+        models::confidence::round(self.confidence_calibration.apply(similarity), self.confidence_decimals)
+    }
+
+    /// Rounds a full-text rank the same way as a confidence.
+    pub fn text_rank(&self, rank: f64) -> f64 {
+        models::confidence::round(rank, self.confidence_decimals)
+    }
+
+    /// Checks out a pooled connection, giving up with a 503 after
+    /// `db_acquire_timeout` instead of queueing behind the pool-wide wait.
+    pub async fn db(&self) -> Result<deadpool_postgres::Object> {
+        acquire_within(&self.pool, self.db_acquire_timeout).await
+    }
+}
+
+async fn acquire_within(pool: &DbPool, wait: Duration) -> Result<deadpool_postgres::Object> {
+    let timeouts = deadpool_postgres::Timeouts {
+        wait: Some(wait),
+        ..pool.timeouts()
+    };
+
+    pool.timeout_get(&timeouts).await.map_err(|e| match e {
+        deadpool_postgres::PoolError::Timeout(_) => {
+            ApiError::ServiceUnavailable("Database is busy, please retry shortly".to_owned())
+        }
+        e => e.into(),
+    })
 }
 
 #[derive(OpenApi)]
@@ -52,15 +119,31 @@ pub struct AppState {
     ),
     paths(
         handlers::projects::search_projects,
+        handlers::projects::get_project_comments,
+        handlers::projects::get_trending_projects,
+        handlers::projects::explain_search_projects,
         handlers::projects::filter_projects,
         handlers::projects::get_project_details,
+        handlers::projects::get_similar_projects,
         handlers::comments::search_comments,
         handlers::comments::filter_comments,
+        handlers::comments::get_comment_details,
         handlers::logs::search_logs,
+        handlers::search::unified_search,
+        handlers::feedback::record_search_feedback,
         handlers::logs::filter_logs,
         handlers::logs::get_log_details,
+        handlers::logs::get_related_comments,
         handlers::users::get_user_details,
+        handlers::users::get_user_shell_history,
         handlers::leaderboard::get_leaderboard,
+        handlers::leaderboard::get_leaderboard_movers,
+        handlers::jobs::get_job_history,
+        handlers::stats::get_stats,
+        handlers::stats::get_embedding_health,
+        handlers::admin::reset_user_sync,
+        handlers::admin::get_audit_log,
+        handlers::admin::get_sync_status,
         handlers::mirror::mirror_projects,
         handlers::mirror::mirror_project,
         handlers::mirror::mirror_devlogs,
@@ -71,16 +154,49 @@ pub struct AppState {
             models::project::Project,
             models::project::ProjectFilter,
             models::project::ProjectSearchRequest,
+            models::project::ProjectSearchExplanation,
+            models::project::SimilarProjectsQuery,
+            models::project::ProjectCommentsQuery,
+            models::project::TrendingProjectsQuery,
+            models::project::TrendingProject,
             models::comment::Comment,
             models::comment::CommentFilter,
             models::comment::CommentSearchRequest,
             models::logs::Log,
             models::logs::LogFilter,
             models::logs::LogSearchRequest,
+            models::logs::RelatedCommentsQuery,
             models::user::User,
             models::user::UserFilter,
+            models::filter::TextMatch,
+            models::confidence::ConfidenceSource,
+            models::user::ShellHistoryFilter,
             models::user::LeaderboardEntry,
             models::user::LeaderboardResponse,
+            models::user::LeaderboardMover,
+            models::user::LeaderboardMoversFilter,
+            models::job::JobRun,
+            models::job::JobHistoryFilter,
+            models::stats::StatsResponse,
+            models::stats::DegenerateEmbeddings,
+            models::stats::EmbeddingHealthResponse,
+            models::stats::EmbeddingCoverage,
+            models::stats::UserStats,
+            models::admin::ResetSyncRequest,
+            models::admin::ResetSyncResponse,
+            models::admin::AuditEntry,
+            models::admin::AuditLogFilter,
+            models::admin::SyncStatus,
+            models::mirror::PaginationMeta,
+            models::mirror::MirrorProjectsResponse,
+            models::mirror::MirrorDevlogsResponse,
+            models::mirror::MirrorCommentsResponse,
+            models::feedback::SearchResultType,
+            models::feedback::SearchFeedbackRequest,
+            models::search::TypeWeights,
+            models::search::UnifiedSearchRequest,
+            models::search::UnifiedSearchResult,
+            models::search::SearchItem,
         )
     ),
     tags(
@@ -90,6 +206,10 @@ pub struct AppState {
         (name = "users", description = "User management endpoints"),
         (name = "leaderboard", description = "Leaderboard endpoints"),
         (name = "mirror", description = "Mirror proxy endpoints"),
+        (name = "search", description = "Unified search and relevance feedback endpoints"),
+        (name = "jobs", description = "Background job history endpoints"),
+        (name = "stats", description = "Dataset statistics endpoints"),
+        (name = "admin", description = "Operational endpoints (require x-api-key)"),
     )
 )]
 struct ApiDoc;
@@ -102,26 +222,110 @@ async fn serve_openapi_json() -> Json<utoipa::openapi::OpenApi> {
     Json(ApiDoc::openapi())
 }
 
-fn create_router() -> Router<AppState> {
-    Router::new()
+fn cors_layer(config: &Config) -> CorsLayer {
+    // Browsers only let JS read non-safelisted response headers that are
+    // listed explicitly; some still ignore the `*` that permissive() sends.
+    CorsLayer::permissive()
+        .expose_headers([
+            HeaderName::from_static(REQUEST_ID_HEADER),
+            header::RETRY_AFTER,
+        ])
+        .max_age(Duration::from_secs(config.cors_max_age_seconds))
+}
+
+fn create_router(config: &Config) -> Router<AppState> {
+    let search_semaphore = Arc::new(Semaphore::new(config.search_max_concurrency));
+    let search_rate_limiter = Arc::new(
+        RateLimiter::per_minute(config.search_rate_limit_per_minute)
+            .with_trusted_proxies(config.trusted_proxies.clone()),
+    );
+
+    let search_routes = Router::new()
         .route("/v1/projects/search", post(search_projects))
+        .route("/v1/projects/search/explain", post(explain_search_projects))
+        .route("/v1/comments/search", post(search_comments))
+        .route("/v1/devlogs/search", post(search_logs))
+        .route("/v1/search", post(unified_search))
+        .route_layer(axum::middleware::from_fn_with_state(
+            search_semaphore,
+            search_concurrency_limit,
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(
+            search_rate_limiter,
+            search_rate_limit,
+        ));
+
+    let idempotency_store = Arc::new(IdempotencyStore::new(Duration::from_secs(
+        config.idempotency_ttl_seconds,
+    )));
+
+    let admin_routes = Router::new()
+        .route("/v1/admin/users/reset-sync", post(reset_user_sync))
+        .route("/v1/admin/audit", get(get_audit_log))
+        .route("/v1/admin/sync-status", get(get_sync_status))
+        .route_layer(axum::middleware::from_fn_with_state(
+            idempotency_store,
+            idempotency,
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(
+            config.admin_api_key.as_deref().map(Arc::<str>::from),
+            require_admin_key,
+        ));
+
+    let maintenance = Arc::new(MaintenanceMode::new(
+        config.maintenance_mode,
+        config.maintenance_file.as_deref().map(Into::into),
+        config.maintenance_retry_after_seconds,
+    ));
+
+    let data_routes = Router::new()
+        .merge(search_routes)
+        .route("/v1/search/feedback", post(record_search_feedback))
         .route("/v1/projects/filter", get(filter_projects))
         .route("/v1/projects/details", get(get_project_details))
-        .route("/v1/comments/search", post(search_comments))
+        .route("/v1/projects/similar", get(get_similar_projects))
+        .route("/v1/projects/trending", get(get_trending_projects))
+        .route("/v1/projects/{id}/comments", get(get_project_comments))
         .route("/v1/comments/filter", get(filter_comments))
-        .route("/v1/devlogs/search", post(search_logs))
+        .route("/v1/comments/details", get(get_comment_details))
         .route("/v1/devlogs/filter", get(filter_logs))
         .route("/v1/devlogs/details", get(get_log_details))
+        .route("/v1/devlogs/{id}/related-comments", get(get_related_comments))
         .route("/v1/users/details", get(get_user_details))
+        .route("/v1/users/shell-history", get(get_user_shell_history))
         .route("/v1/leaderboard", get(get_leaderboard))
+        .route("/v1/leaderboard/movers", get(get_leaderboard_movers))
+        .route("/v1/jobs/history", get(get_job_history))
+        .route("/v1/stats", get(get_stats))
+        .route("/v1/stats/embeddings", get(get_embedding_health))
         .route("/v1/mirror/projects", get(mirror_projects))
         .route("/v1/mirror/projects/{id}", get(mirror_project))
         .route("/v1/mirror/devlogs", get(mirror_devlogs))
         .route("/v1/mirror/comments", get(mirror_comments))
+        .route_layer(axum::middleware::from_fn_with_state(
+            maintenance,
+            maintenance_gate,
+        ));
+
+    Router::new()
+        .merge(data_routes)
+        .merge(admin_routes)
+        .route("/health", get(get_health))
+        .route("/health/sync", get(get_sync_health))
         .nest_service("/static", ServeDir::new("static"))
         .route("/api-docs/openapi.json", get(serve_openapi_json))
         .route("/v1/docs", get(serve_docs))
-        .layer(ServiceBuilder::new().layer(CorsLayer::permissive()))
+        .route("/metrics", get(get_metrics))
+        .layer(
+            ServiceBuilder::new()
+                .layer(cors_layer(config))
+                .layer(axum::middleware::from_fn(track_requests))
+                .layer(axum::middleware::from_fn(request_logger))
+                .layer(HandleErrorLayer::new(handle_layer_error))
+                .load_shed()
+                .concurrency_limit(config.max_concurrent_requests)
+                .timeout(Duration::from_secs(config.request_timeout_seconds)),
+        )
 }
 
 #[tokio::main]
@@ -135,16 +339,30 @@ async fn main() -> Result<()> {
     let config = Config::from_env()?;
 
     let pool = common::database::connection::create_pool(&config).await?;
+    common::ConnectionManager::spawn_pool_monitor(pool.clone(), Duration::from_secs(30));
 
     let embedding_service =
         Arc::new(EmbeddingService::new(false)?);
+    embedding_service.warmup().await;
+
+    let confidence_calibration = ConfidenceCalibration::from_config(&config)?;
 
     let app_state = AppState {
         pool,
         embedding_service,
+        confidence_calibration,
+        confidence_decimals: models::confidence::clamp_decimals(config.confidence_decimals),
+        search_cache: Arc::new(SearchCaches::new(Duration::from_secs(
+            config.search_cache_ttl_seconds,
+        ))),
+        debug_endpoints: config.debug_endpoints,
+        sync_stale_after: (config.sync_stale_after_seconds > 0)
+            .then(|| Duration::from_secs(config.sync_stale_after_seconds)),
+        db_acquire_timeout: Duration::from_millis(config.db_acquire_timeout_ms),
+        hide_comment_serial_id: config.hide_comment_serial_id,
     };
 
-    let app = create_router().with_state(app_state);
+    let app = create_router(&config).with_state(app_state);
 
     let addr = format!("0.0.0.0:{}", config.api_port);
     let listener = TcpListener::bind(&addr).await?;
@@ -155,7 +373,108 @@ async fn main() -> Result<()> {
         config.api_port
     );
 
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, routing::get};
+    use tower::ServiceExt;
+
+    fn cors_app(max_age: u64) -> Router {
+        let config = Config {
+            cors_max_age_seconds: max_age,
+            ..Config::default()
+        };
+        Router::new()
+            .route("/v1/projects/filter", get(|| async { "ok" }))
+            .layer(cors_layer(&config))
+    }
+
+    #[tokio::test]
+    async fn cors_preflight_sets_max_age() {
+        let response = cors_app(600)
+            .oneshot(
+                Request::builder()
+                    .method("OPTIONS")
+                    .uri("/v1/projects/filter")
+                    .header(header::ORIGIN, "https://docs.example.com")
+                    .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert!(response.status().is_success());
+        assert_eq!(response.headers()[header::ACCESS_CONTROL_MAX_AGE], "600");
+    }
+
+    #[test]
+    fn openapi_doc_builds_with_every_component() {
+        let doc = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        for name in ["Comment", "Log", "Project", "MirrorCommentsResponse"] {
+            assert!(doc["components"]["schemas"][name].is_object(), "{name} missing");
+        }
+    }
+
+    // Expose-Headers belongs on the actual response; preflights never carry it.
+    #[tokio::test]
+    async fn cors_exposes_request_id_and_retry_after() {
+        let response = cors_app(600)
+            .oneshot(
+                Request::builder()
+                    .uri("/v1/projects/filter")
+                    .header(header::ORIGIN, "https://docs.example.com")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let exposed = response.headers()[header::ACCESS_CONTROL_EXPOSE_HEADERS]
+            .to_str()
+            .unwrap()
+            .to_ascii_lowercase();
+        let exposed: Vec<&str> = exposed.split(',').map(str::trim).collect();
+        assert!(exposed.contains(&REQUEST_ID_HEADER), "{exposed:?}");
+        assert!(exposed.contains(&"retry-after"), "{exposed:?}");
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn busy_pool_is_a_503_after_the_acquire_timeout() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let mut cfg = deadpool_postgres::Config::new();
+        cfg.url = Some(database_url);
+        cfg.pool = Some(deadpool_postgres::PoolConfig {
+            max_size: 1,
+            timeouts: deadpool_postgres::Timeouts {
+                wait: Some(Duration::from_secs(10)),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let pool = cfg
+            .create_pool(Some(deadpool_postgres::Runtime::Tokio1), tokio_postgres::NoTls)
+            .unwrap();
+
+        let held = acquire_within(&pool, Duration::from_secs(1)).await.unwrap();
+        let started = std::time::Instant::now();
+        let busy = acquire_within(&pool, Duration::from_millis(100)).await;
+        let waited = started.elapsed();
+        drop(held);
+        let freed = acquire_within(&pool, Duration::from_millis(100)).await;
+
+        assert!(matches!(busy, Err(ApiError::ServiceUnavailable(_))), "{busy:?}");
+        // The handler's timeout wins over the pool-wide ten second wait.
+        assert!(waited < Duration::from_secs(2), "{waited:?}");
+        assert!(freed.is_ok());
+    }
+}
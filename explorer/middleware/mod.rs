@@ -1,33 +1,572 @@
+use std::{net::{IpAddr, SocketAddr}, sync::Arc};
+
 use axum::{
+    BoxError,
     body::Body,
-    http::Request,
+    extract::{ConnectInfo, State},
+    http::{HeaderValue, Request},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
+use tokio::sync::Semaphore;
+use tracing::Instrument;
+use uuid::Uuid;
+use tower::{load_shed::error::Overloaded, timeout::error::Elapsed};
+
+use crate::services::audit::AdminActor;
+use crate::services::idempotency::{Claim, IdempotencyStore, StoredResponse, fingerprint};
+use crate::services::maintenance::MaintenanceMode;
+use crate::services::metrics::RequestMetrics;
+use crate::services::rate_limit::RateLimiter;
+use crate::utils::error::ApiError;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+pub const ADMIN_KEY_HEADER: &str = "x-api-key";
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+const MAX_IDEMPOTENT_BODY_BYTES: usize = 1024 * 1024;
 
 pub async fn request_logger(
-    req: Request<Body>,
+    mut req: Request<Body>,
     next: Next,
 ) -> Response {
+    let request_id = Uuid::new_v4().to_string();
+    let header_value = HeaderValue::from_str(&request_id).expect("UUID is a valid header value");
+    req.headers_mut().insert(REQUEST_ID_HEADER, header_value.clone());
+
     let method = req.method().clone();
     let uri = req.uri().clone();
     let start = std::time::Instant::now();
+    let span = tracing::info_span!("request", request_id = %request_id);
 
-    tracing::info!(
-        method = %method,
-        uri = %uri,
-        "Request started"
-    );
+    async move {
+        tracing::info!(
+            method = %method,
+            uri = %uri,
+            "Request started"
+        );
 
-    let response = next.run(req).await;
-    let status = response.status();
-    let duration = start.elapsed();
+        let mut response = next.run(req).await;
+        let status = response.status();
+        let duration = start.elapsed();
+
+        tracing::info!(
+            status = status.as_u16(),
+            duration_ms = duration.as_millis(),
+            "Request completed"
+        );
+
+        response.headers_mut().insert(REQUEST_ID_HEADER, header_value);
+        response
+    }
+    .instrument(span)
+    .await
+}
+
+pub async fn search_concurrency_limit(
+    State(semaphore): State<Arc<Semaphore>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let Ok(_permit) = semaphore.try_acquire() else {
+        tracing::warn!(uri = %req.uri(), "Search concurrency limit reached, rejecting request");
+        return ApiError::ServiceUnavailable(
+            "Search is at capacity, please retry shortly".to_owned(),
+        )
+        .into_response();
+    };
+
+    next.run(req).await
+}
+
+pub async fn search_rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(client_ip) = client_ip(&req, limiter.trusted_proxies()) else {
+        return next.run(req).await;
+    };
+
+    if let Err(retry_after) = limiter.check(client_ip) {
+        tracing::warn!(client = %client_ip, "Search rate limit exceeded");
+        return ApiError::RateLimit {
+            retry_after,
+            message: "Too many search requests, please slow down".to_owned(),
+        }
+        .into_response();
+    }
+
+    next.run(req).await
+}
+
+/// The peer address, unless the peer is a trusted proxy: then the rightmost
+/// `X-Forwarded-For` hop that isn't itself a trusted proxy. Hops further left
+/// are supplied by the client and can't be believed.
+fn client_ip(req: &Request<Body>, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())?;
+
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+
+    let forwarded = req
+        .headers()
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|ip| ip.trim().parse::<IpAddr>().ok())
+        .collect::<Vec<_>>();
+
+    Some(
+        forwarded
+            .iter()
+            .rev()
+            .find(|ip| !trusted_proxies.contains(ip))
+            .or_else(|| forwarded.first())
+            .copied()
+            .unwrap_or(peer),
+    )
+}
+
+pub async fn maintenance_gate(
+    State(maintenance): State<Arc<MaintenanceMode>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    if !maintenance.is_active() {
+        return next.run(req).await;
+    }
 
-    tracing::info!(
-        status = status.as_u16(),
-        duration_ms = duration.as_millis(),
-        "Request completed"
-    );
+    let mut response = ApiError::ServiceUnavailable(
+        "The explorer is undergoing maintenance, please check back soon".to_owned(),
+    )
+    .into_response();
+    response
+        .headers_mut()
+        .insert("Retry-After", HeaderValue::from(maintenance.retry_after()));
+    response
+}
+
+pub async fn track_requests(
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let request = RequestMetrics::global().request_started();
+
+    let response = next.run(req).await;
+    request.finish(response.status());
 
     response
-}
\ No newline at end of file
+}
+
+pub async fn handle_layer_error(err: BoxError) -> ApiError {
+    if err.is::<Elapsed>() {
+        ApiError::GatewayTimeout("Request took too long to complete".to_owned())
+    } else if err.is::<Overloaded>() {
+        ApiError::ServiceUnavailable("Server is at capacity, please retry shortly".to_owned())
+    } else {
+        tracing::error!("Unhandled middleware error: {}", err);
+        ApiError::ServiceUnavailable("Request could not be processed".to_owned())
+    }
+}
+
+pub async fn require_admin_key(
+    State(admin_key): State<Option<Arc<str>>>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(admin_key) = admin_key else {
+        return ApiError::Unauthorized("Admin API is not configured".to_owned()).into_response();
+    };
+
+    let provided = req
+        .headers()
+        .get(ADMIN_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+
+    if !constant_time_eq(provided.as_bytes(), admin_key.as_bytes()) {
+        tracing::warn!(uri = %req.uri(), "Rejected admin request with invalid API key");
+        return ApiError::Unauthorized("Invalid admin API key".to_owned()).into_response();
+    }
+
+    let actor = AdminActor::from_key(provided);
+    req.extensions_mut().insert(actor);
+    next.run(req).await
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+pub async fn idempotency(
+    State(store): State<Arc<IdempotencyStore>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(key) = req
+        .headers()
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|key| format!("{} {} {}", req.method(), req.uri().path(), key))
+    else {
+        return next.run(req).await;
+    };
+
+    let (parts, body) = req.into_parts();
+    let body = match axum::body::to_bytes(body, MAX_IDEMPOTENT_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(_) => {
+            return ApiError::Validation {
+                field: "body".to_owned(),
+                message: "Request body is too large for an idempotent request".to_owned(),
+            }
+            .into_response();
+        }
+    };
+
+    let guard = match store.claim(&key, fingerprint(&body)) {
+        Claim::Execute(guard) => guard,
+        Claim::Replay(stored) => return stored.to_response(),
+        Claim::InProgress => {
+            return ApiError::Conflict(
+                "A request with this Idempotency-Key is still in progress".to_owned(),
+            )
+            .into_response();
+        }
+        Claim::BodyMismatch => {
+            return ApiError::Unprocessable(
+                "Idempotency-Key was already used with a different request body".to_owned(),
+            )
+            .into_response();
+        }
+    };
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if response.status().is_server_error() {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    match axum::body::to_bytes(body, MAX_IDEMPOTENT_BODY_BYTES).await {
+        Ok(bytes) => {
+            let stored = StoredResponse::new(parts.status, parts.headers, bytes);
+            let response = stored.to_response();
+            guard.complete(stored);
+            response
+        }
+        Err(e) => {
+            tracing::error!("Failed to buffer response for idempotency key: {}", e);
+            ApiError::ServiceUnavailable("Response could not be stored".to_owned()).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, http::StatusCode, routing::get};
+    use tower::ServiceExt;
+
+    const PROXY: &str = "10.0.0.1";
+
+    fn request(peer: &str, forwarded_for: Option<&str>) -> Request<Body> {
+        let mut req = Request::builder().uri("/");
+        if let Some(forwarded_for) = forwarded_for {
+            req = req.header("x-forwarded-for", forwarded_for);
+        }
+        let mut req = req.body(Body::empty()).unwrap();
+        req.extensions_mut()
+            .insert(ConnectInfo(SocketAddr::new(peer.parse().unwrap(), 443)));
+        req
+    }
+
+    #[test]
+    fn client_ip_ignores_forwarded_for_without_trusted_proxy() {
+        let req = request("203.0.113.9", Some("1.2.3.4"));
+        assert_eq!(client_ip(&req, &[]), Some("203.0.113.9".parse().unwrap()));
+    }
+
+    #[test]
+    fn client_ip_takes_rightmost_untrusted_hop_behind_trusted_proxy() {
+        let trusted = [PROXY.parse().unwrap()];
+        let req = request(PROXY, Some("1.2.3.4, 198.51.100.7"));
+        assert_eq!(client_ip(&req, &trusted), Some("198.51.100.7".parse().unwrap()));
+    }
+
+    fn idempotent_app(calls: Arc<std::sync::atomic::AtomicUsize>) -> Router {
+        use axum::routing::post;
+
+        let store = Arc::new(IdempotencyStore::new(std::time::Duration::from_secs(60)));
+        Router::new()
+            .route(
+                "/",
+                post(move |body: String| async move {
+                    calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                    body
+                }),
+            )
+            .route_layer(axum::middleware::from_fn_with_state(store, idempotency))
+    }
+
+    fn idempotent_request(key: &str, body: &'static str) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri("/")
+            .header(IDEMPOTENCY_KEY_HEADER, key)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn idempotency_key_executes_once() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let app = idempotent_app(Arc::clone(&calls));
+
+        let first = app.clone().oneshot(idempotent_request("k", "a")).await.unwrap();
+        let second = app.oneshot(idempotent_request("k", "a")).await.unwrap();
+
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(second.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(second.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"a");
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn concurrent_duplicate_is_rejected_while_in_flight() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let app = idempotent_app(Arc::clone(&calls));
+
+        let (first, second) = tokio::join!(
+            app.clone().oneshot(idempotent_request("k", "a")),
+            app.clone().oneshot(idempotent_request("k", "a")),
+        );
+
+        let mut statuses = [first.unwrap().status(), second.unwrap().status()];
+        statuses.sort();
+        assert_eq!(statuses, [StatusCode::OK, StatusCode::CONFLICT]);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn reused_key_with_different_body_is_rejected() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let app = idempotent_app(Arc::clone(&calls));
+
+        app.clone().oneshot(idempotent_request("k", "a")).await.unwrap();
+        let response = app.oneshot(idempotent_request("k", "b")).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn search_concurrency_limit_rejects_requests_over_cap() {
+        let semaphore = Arc::new(Semaphore::new(2));
+        let app = Router::new()
+            .route(
+                "/",
+                get(|| async {
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                    "ok"
+                }),
+            )
+            .route_layer(axum::middleware::from_fn_with_state(semaphore, search_concurrency_limit));
+
+        let responses = tokio::join!(
+            app.clone().oneshot(request("203.0.113.9", None)),
+            app.clone().oneshot(request("203.0.113.9", None)),
+            app.clone().oneshot(request("203.0.113.9", None)),
+            app.clone().oneshot(request("203.0.113.9", None)),
+            app.clone().oneshot(request("203.0.113.9", None)),
+        );
+        let mut statuses = [responses.0, responses.1, responses.2, responses.3, responses.4]
+            .map(|response| response.unwrap().status());
+        statuses.sort();
+        assert_eq!(
+            statuses,
+            [
+                StatusCode::OK,
+                StatusCode::OK,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::SERVICE_UNAVAILABLE,
+            ]
+        );
+
+        let response = app.oneshot(request("203.0.113.9", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "permits are released once requests finish");
+    }
+
+    #[tokio::test]
+    async fn search_rate_limit_rejects_request_over_budget() {
+        let limiter = Arc::new(RateLimiter::per_minute(2));
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route_layer(axum::middleware::from_fn_with_state(limiter, search_rate_limit));
+
+        for forwarded_for in ["1.1.1.1", "2.2.2.2"] {
+            let response = app.clone().oneshot(request("203.0.113.9", Some(forwarded_for))).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = app.oneshot(request("203.0.113.9", Some("3.3.3.3"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key("retry-after"));
+    }
+
+    fn maintenance_app(maintenance: MaintenanceMode) -> Router {
+        let data_routes = Router::new()
+            .route("/v1/stats", get(|| async { "stats" }))
+            .route_layer(axum::middleware::from_fn_with_state(Arc::new(maintenance), maintenance_gate));
+        Router::new()
+            .merge(data_routes)
+            .route("/health", get(|| async { "healthy" }))
+    }
+
+    async fn fetch(app: &Router, uri: &str) -> Response {
+        app.clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn maintenance_blocks_data_routes_but_not_health() {
+        let app = maintenance_app(MaintenanceMode::new(true, None, 120));
+
+        let data = fetch(&app, "/v1/stats").await;
+        assert_eq!(data.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(data.headers()["retry-after"], "120");
+        let body = axum::body::to_bytes(data.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error_code"], "SERVICE_UNAVAILABLE");
+        assert!(body["error"].as_str().unwrap().contains("maintenance"), "{body}");
+
+        assert_eq!(fetch(&app, "/health").await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn maintenance_file_toggles_the_gate_live() {
+        let flag = std::env::temp_dir().join(format!("maintenance-test-{}", std::process::id()));
+        let _ = std::fs::remove_file(&flag);
+        let app = maintenance_app(MaintenanceMode::new(false, Some(flag.clone()), 60));
+
+        assert_eq!(fetch(&app, "/v1/stats").await.status(), StatusCode::OK);
+
+        std::fs::write(&flag, "").unwrap();
+        let during = fetch(&app, "/v1/stats").await.status();
+        std::fs::remove_file(&flag).unwrap();
+
+        assert_eq!(during, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(fetch(&app, "/v1/stats").await.status(), StatusCode::OK);
+        assert_eq!(fetch(&app, "/health").await.status(), StatusCode::OK);
+    }
+
+    /// A slow route behind the same error handling, load shedding, concurrency
+    /// limit and timeout stack as `create_router`.
+    fn limited_app(max_concurrent: usize, timeout: std::time::Duration) -> Router {
+        use axum::error_handling::HandleErrorLayer;
+
+        Router::new()
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+                    "done"
+                }),
+            )
+            .layer(
+                tower::ServiceBuilder::new()
+                    .layer(HandleErrorLayer::new(handle_layer_error))
+                    .load_shed()
+                    .concurrency_limit(max_concurrent)
+                    .timeout(timeout),
+            )
+            // Builds the layered routes once, as serving does; otherwise every
+            // request would get its own concurrency limit.
+            .with_state(())
+    }
+
+    #[tokio::test]
+    async fn handler_past_the_timeout_is_a_gateway_timeout() {
+        let app = limited_app(8, std::time::Duration::from_millis(50));
+
+        let response = fetch(&app, "/slow").await;
+
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error_code"], "GATEWAY_TIMEOUT");
+    }
+
+    #[tokio::test]
+    async fn requests_over_the_concurrency_limit_are_shed() {
+        let app = limited_app(1, std::time::Duration::from_secs(5));
+
+        let (first, second) = tokio::join!(fetch(&app, "/slow"), fetch(&app, "/slow"));
+
+        let mut statuses = [first.status(), second.status()];
+        statuses.sort();
+        assert_eq!(statuses, [StatusCode::OK, StatusCode::SERVICE_UNAVAILABLE]);
+    }
+
+    #[derive(Clone, Default)]
+    struct Captured(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn request_id_is_returned_and_tags_both_log_lines() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .without_time()
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = Router::new()
+            .route(
+                "/",
+                get(|req: Request<Body>| async move {
+                    req.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_owned()
+                }),
+            )
+            .layer(axum::middleware::from_fn(request_logger));
+
+        let response = fetch(&app, "/").await;
+        let request_id = response.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_owned();
+        let seen_by_handler = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        assert!(Uuid::parse_str(&request_id).is_ok(), "{request_id} is not a UUID");
+        assert_eq!(seen_by_handler, request_id.as_bytes());
+
+        let logs = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let tagged = format!("request_id={request_id}");
+        for message in ["Request started", "Request completed"] {
+            let line = logs
+                .lines()
+                .find(|line| line.contains(message))
+                .unwrap_or_else(|| panic!("no {message:?} in {logs}"));
+            assert!(line.contains(&tagged), "{line}");
+        }
+    }
+}
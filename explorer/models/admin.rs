@@ -0,0 +1,49 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use common::SlackId;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ResetSyncRequest {
+    #[serde(rename = "slackIds")]
+    #[schema(value_type = Option<Vec<String>>)]
+    pub slack_ids: Option<Vec<SlackId>>,
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ResetSyncResponse {
+    pub reset: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub at: DateTime<Utc>,
+    #[serde(rename = "actorKeyHash")]
+    pub actor_key_hash: String,
+    pub action: String,
+    pub target: Option<String>,
+    #[schema(value_type = Object)]
+    pub details: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, IntoParams)]
+pub struct AuditLogFilter {
+    pub action: Option<String>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SyncStatus {
+    #[serde(rename = "lastSync")]
+    pub last_sync: Option<DateTime<Utc>>,
+    #[serde(rename = "lastPage")]
+    pub last_page: Option<i32>,
+    pub status: Option<String>,
+}
+
+pub type SyncStatusResponse = BTreeMap<String, SyncStatus>;
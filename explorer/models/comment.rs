@@ -1,23 +1,52 @@
 use chrono::{DateTime, Utc};
+use common::SlackId;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+use crate::models::{confidence::ConfidenceSource, filter::TextMatch};
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Comment {
-    pub id: i64,
+    /// Local serial. It has no meaning upstream, where a comment is identified
+    /// by `upstream_id` (or `devlog_id` and `slack_id`). Left out when the
+    /// server runs with `HIDE_COMMENT_SERIAL_ID`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    /// The comment's id in the upstream API, when it provides one. Accepted by
+    /// `/v1/comments/details` as `upstreamId`.
+    pub upstream_id: Option<i64>,
     pub text: String,
     pub devlog_id: i64,
-    pub slack_id: String,
+    #[schema(value_type = String)]
+    pub slack_id: SlackId,
     pub username: Option<String>,
     pub created_at: DateTime<Utc>,
     pub last_synced: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub confidence: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence_source: Option<ConfidenceSource>,
+    // `Log` embeds `Project`, which lists its `Comment`s; stop the schema there.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(no_recursion)]
+    pub devlog: Option<crate::models::logs::Log>,
 }
 
 impl Comment {
     pub fn with_confidence(mut self, confidence: f64) -> Self {
         self.confidence = Some(confidence);
+        self.confidence_source = Some(ConfidenceSource::Vector);
+        self
+    }
+
+    pub fn with_text_rank(mut self, rank: f64) -> Self {
+        self.confidence = Some(rank);
+        self.confidence_source = Some(ConfidenceSource::FullText);
+        self
+    }
+
+    pub fn with_devlog(mut self, devlog: crate::models::logs::Log) -> Self {
+        self.devlog = Some(devlog);
         self
     }
 
@@ -29,7 +58,9 @@ pub struct CommentFilter {
     #[serde(rename = "projectId")]
     pub project_id: Option<i64>,
     #[serde(rename = "slackId")]
-    pub slack_id: Option<String>,
+    #[schema(value_type = Option<String>)]
+    #[param(value_type = Option<String>)]
+    pub slack_id: Option<SlackId>,
     pub username: Option<String>,
     #[serde(rename = "devlogId")]
     pub devlog_id: Option<i64>,
@@ -40,6 +71,12 @@ pub struct CommentFilter {
     pub from_date: Option<String>,
     #[serde(rename = "toDate")]
     pub to_date: Option<String>,
+    #[serde(rename = "match")]
+    pub text_match: Option<TextMatch>,
+    #[serde(rename = "sortBy")]
+    pub sort_by: Option<String>,
+    #[serde(rename = "sortDir")]
+    pub sort_dir: Option<String>,
     pub limit: Option<u32>,
 }
 
@@ -48,3 +85,49 @@ pub struct CommentSearchRequest {
     pub query: String,
     pub limit: Option<u32>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(id: Option<i64>, upstream_id: Option<i64>) -> Comment {
+        Comment {
+            id,
+            upstream_id,
+            text: "nice".to_string(),
+            devlog_id: 3,
+            slack_id: SlackId::new("U123"),
+            username: None,
+            created_at: DateTime::default(),
+            last_synced: None,
+            confidence: None,
+            confidence_source: None,
+            devlog: None,
+        }
+    }
+
+    #[test]
+    fn exposes_upstream_id_and_optionally_hides_the_serial() {
+        let raw: common::utils::modal::RawComment = serde_json::from_str(
+            r#"{"id": 9001, "text": "nice", "devlog_id": 3, "slack_id": "U123", "created_at": "2026-01-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+        let json = serde_json::to_value(comment(Some(7), raw.id)).unwrap();
+        assert_eq!(json["upstream_id"], 9001);
+        assert_eq!(json["id"], 7);
+
+        let hidden = serde_json::to_value(comment(None, raw.id)).unwrap();
+        assert!(hidden.get("id").is_none());
+        assert_eq!(hidden["upstream_id"], 9001);
+    }
+
+    #[test]
+    fn upstream_id_is_null_when_the_api_sends_none() {
+        let raw: common::utils::modal::RawComment = serde_json::from_str(
+            r#"{"text": "nice", "devlog_id": 3, "slack_id": "U123", "created_at": "2026-01-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(raw.id, None);
+        assert!(serde_json::to_value(comment(Some(7), raw.id)).unwrap()["upstream_id"].is_null());
+    }
+}
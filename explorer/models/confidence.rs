@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfidenceSource {
+    Vector,
+    FullText,
+}
+
+// pgvector computes `<=>` in single precision and widens the result to f64, so
+// digits past the sixth decimal are noise that can differ between identical
+// query/item pairs.
+const MAX_DECIMALS: u32 = 6;
+
+/// The `CONFIDENCE_DECIMALS` setting, capped at the precision search
+/// distances actually carry.
+pub fn clamp_decimals(decimals: u32) -> u32 {
+    if decimals > MAX_DECIMALS {
+        tracing::warn!(
+            "CONFIDENCE_DECIMALS={} exceeds the {} decimals search distances carry, using {}",
+            decimals,
+            MAX_DECIMALS,
+            MAX_DECIMALS
+        );
+    }
+    decimals.min(MAX_DECIMALS)
+}
+
+pub fn round(value: f64, decimals: u32) -> f64 {
+    let factor = 10f64.powi(decimals.min(MAX_DECIMALS) as i32);
+    (value * factor).round() / factor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_to_the_configured_decimals() {
+        assert_eq!(round(0.123_456_789, 4), 0.1235);
+        assert_eq!(round(0.123_456_789, 2), 0.12);
+        assert_eq!(round(0.5, 0), 1.0);
+        assert_eq!(round(-0.000_04, 4), -0.0);
+    }
+
+    #[test]
+    fn caps_decimals_at_search_precision() {
+        assert_eq!(clamp_decimals(4), 4);
+        assert_eq!(clamp_decimals(12), MAX_DECIMALS);
+        assert_eq!(round(0.123_456_789, 12), 0.123_457);
+    }
+
+    #[test]
+    fn identical_query_and_item_get_a_stable_confidence() {
+        let raw: Vec<f32> = (0..384).map(|i| (i as f32 * 0.37).sin()).collect();
+        let norm = raw.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let embedding: Vec<f32> = raw.iter().map(|x| x / norm).collect();
+
+        // The same pair scored twice, with the single-precision sum accumulated
+        // in a different order, as a vectorised distance kernel may do.
+        let forward: f32 = embedding.iter().map(|x| x * x).sum();
+        let backward: f32 = embedding.iter().rev().map(|x| x * x).sum();
+        let confidences = [forward, backward].map(|dot| round(1.0 - f64::from(1.0 - dot), MAX_DECIMALS));
+
+        assert_eq!(confidences[0], confidences[1]);
+        assert_eq!(confidences[0], 1.0);
+    }
+}
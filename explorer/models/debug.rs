@@ -0,0 +1,12 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct DebugParams {
+    pub debug: Option<String>,
+}
+
+impl DebugParams {
+    pub fn explain(&self, debug_endpoints: bool) -> bool {
+        debug_endpoints && self.debug.as_deref() == Some("explain")
+    }
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchResultType {
+    Project,
+    Devlog,
+    Comment,
+}
+
+impl SearchResultType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchResultType::Project => "project",
+            SearchResultType::Devlog => "devlog",
+            SearchResultType::Comment => "comment",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SearchFeedbackRequest {
+    pub query: String,
+    pub result_type: SearchResultType,
+    pub result_id: i64,
+    pub rank: i32,
+    pub clicked: bool,
+}
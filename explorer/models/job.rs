@@ -0,0 +1,21 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct JobRun {
+    pub id: i64,
+    pub job_name: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub status: String,
+    pub error_message: Option<String>,
+    pub items_processed: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, IntoParams)]
+pub struct JobHistoryFilter {
+    #[serde(rename = "jobName")]
+    pub job_name: Option<String>,
+    pub limit: Option<u32>,
+}
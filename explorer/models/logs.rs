@@ -1,14 +1,18 @@
 use chrono::{DateTime, Utc};
+use common::SlackId;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+use crate::models::{confidence::ConfidenceSource, filter::TextMatch};
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Log {
     pub id: i64,
     pub text: String,
     pub attachment: Option<String>,
     pub project_id: i64,
-    pub slack_id: String,
+    #[schema(value_type = String)]
+    pub slack_id: SlackId,
     pub username: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -16,12 +20,21 @@ pub struct Log {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub confidence: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence_source: Option<ConfidenceSource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub project: Option<crate::models::project::Project>,
 }
 
 impl Log {
     pub fn with_confidence(mut self, confidence: f64) -> Self {
         self.confidence = Some(confidence);
+        self.confidence_source = Some(ConfidenceSource::Vector);
+        self
+    }
+
+    pub fn with_text_rank(mut self, rank: f64) -> Self {
+        self.confidence = Some(rank);
+        self.confidence_source = Some(ConfidenceSource::FullText);
         self
     }
 
@@ -38,7 +51,9 @@ pub struct LogFilter {
     #[serde(rename = "projectId")]
     pub project_id: Option<i64>,
     #[serde(rename = "slackId")]
-    pub slack_id: Option<String>,
+    #[schema(value_type = Option<String>)]
+    #[param(value_type = Option<String>)]
+    pub slack_id: Option<SlackId>,
     pub username: Option<String>,
     #[serde(rename = "devlogId")]
     pub devlog_id: Option<i64>,
@@ -49,6 +64,12 @@ pub struct LogFilter {
     pub from_date: Option<String>,
     #[serde(rename = "toDate")]
     pub to_date: Option<String>,
+    #[serde(rename = "match")]
+    pub text_match: Option<TextMatch>,
+    #[serde(rename = "sortBy")]
+    pub sort_by: Option<String>,
+    #[serde(rename = "sortDir")]
+    pub sort_dir: Option<String>,
     pub limit: Option<u32>,
 }
 
@@ -57,3 +78,8 @@ pub struct LogSearchRequest {
     pub query: String,
     pub limit: Option<u32>,
 }
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, utoipa::IntoParams)]
+pub struct RelatedCommentsQuery {
+    pub limit: Option<u32>,
+}
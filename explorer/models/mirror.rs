@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::{comment::Comment, logs::Log, project::Project};
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PaginationMeta {
+    pub page: i32,
+    pub pages: i64,
+    pub count: i64,
+    pub items: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MirrorProjectsResponse {
+    pub projects: Vec<Project>,
+    pub pagination: PaginationMeta,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MirrorDevlogsResponse {
+    pub devlogs: Vec<Log>,
+    pub pagination: PaginationMeta,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MirrorCommentsResponse {
+    pub comments: Vec<Comment>,
+    pub pagination: PaginationMeta,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use utoipa::OpenApi;
+
+    use super::*;
+
+    /// Checks `value` against an OpenAPI schema: declared types, required
+    /// properties, and no properties the schema does not list.
+    fn check(schemas: &Value, schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+        if let Some(reference) = schema["$ref"].as_str() {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            return check(schemas, &schemas[name], value, path);
+        }
+        if let Some(branches) = schema["oneOf"].as_array().or(schema["anyOf"].as_array()) {
+            return match branches.iter().any(|branch| check(schemas, branch, value, path).is_ok()) {
+                true => Ok(()),
+                false => Err(format!("{path}: {value} matches none of {branches:?}")),
+            };
+        }
+        if let Some(parts) = schema["allOf"].as_array() {
+            return parts.iter().try_for_each(|part| check(schemas, part, value, path));
+        }
+
+        let types: Vec<&str> = match &schema["type"] {
+            Value::String(ty) => vec![ty.as_str()],
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            _ => return Ok(()),
+        };
+        let actual = match value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        };
+        let integer_as_number = actual == "integer" && types.contains(&"number");
+        if !types.contains(&actual) && !integer_as_number {
+            return Err(format!("{path}: expected {types:?}, got {value}"));
+        }
+
+        match value {
+            Value::Object(fields) => {
+                for required in schema["required"].as_array().into_iter().flatten() {
+                    let required = required.as_str().unwrap_or_default();
+                    if !fields.contains_key(required) {
+                        return Err(format!("{path}: missing required '{required}'"));
+                    }
+                }
+                fields.iter().try_for_each(|(name, field)| match schema["properties"].get(name) {
+                    Some(property) => check(schemas, property, field, &format!("{path}.{name}")),
+                    None => Err(format!("{path}: '{name}' is not in the schema")),
+                })
+            }
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .try_for_each(|(i, item)| check(schemas, &schema["items"], item, &format!("{path}[{i}]"))),
+            _ => Ok(()),
+        }
+    }
+
+    fn assert_matches_component<T: Serialize>(name: &str, response: &T) {
+        let doc = serde_json::to_value(crate::ApiDoc::openapi()).unwrap();
+        let schemas = &doc["components"]["schemas"];
+        assert!(schemas[name].is_object(), "{name} is not in the OpenAPI components");
+        if let Err(mismatch) = check(schemas, &schemas[name], &serde_json::to_value(response).unwrap(), name) {
+            panic!("{mismatch}");
+        }
+    }
+
+    fn pagination() -> PaginationMeta {
+        PaginationMeta { page: 2, pages: 3, count: 41, items: 20 }
+    }
+
+    #[test]
+    fn mirror_envelopes_match_their_schemas() {
+        let project: Project = serde_json::from_value(json!({
+            "id": 1, "title": "Rover", "description": null, "category": "Hardware",
+            "readme_link": null, "demo_link": null, "repo_link": "https://example.com/rover",
+            "slack_id": "U1", "username": "rover", "created_at": "2026-01-01T00:00:00Z",
+            "updated_at": "2026-01-02T00:00:00Z", "last_synced": null, "comments": []
+        }))
+        .unwrap();
+        let devlog: Log = serde_json::from_value(json!({
+            "id": 2, "text": "Wired the motors", "attachment": null, "project_id": 1,
+            "slack_id": "U1", "username": "rover", "created_at": "2026-01-01T00:00:00Z",
+            "updated_at": "2026-01-01T00:00:00Z", "last_synced": "2026-01-03T00:00:00Z"
+        }))
+        .unwrap();
+        let comment: Comment = serde_json::from_value(json!({
+            "id": 3, "upstream_id": 77, "text": "Nice!", "devlog_id": 2, "slack_id": "U2",
+            "username": null, "created_at": "2026-01-01T00:00:00Z", "last_synced": null
+        }))
+        .unwrap();
+
+        assert_matches_component(
+            "MirrorProjectsResponse",
+            &MirrorProjectsResponse { projects: vec![project.clone()], pagination: pagination() },
+        );
+        assert_matches_component(
+            "MirrorDevlogsResponse",
+            &MirrorDevlogsResponse { devlogs: vec![devlog], pagination: pagination() },
+        );
+        assert_matches_component(
+            "MirrorCommentsResponse",
+            &MirrorCommentsResponse { comments: vec![comment], pagination: pagination() },
+        );
+
+        let envelope = serde_json::to_value(MirrorProjectsResponse { projects: vec![project], pagination: pagination() })
+            .unwrap();
+        assert_eq!(envelope["pagination"], json!({ "page": 2, "pages": 3, "count": 41, "items": 20 }));
+    }
+
+    #[test]
+    fn schema_check_rejects_untyped_envelopes() {
+        let doc = serde_json::to_value(crate::ApiDoc::openapi()).unwrap();
+        let schemas = &doc["components"]["schemas"];
+        let schema = &schemas["MirrorProjectsResponse"];
+
+        assert!(check(schemas, schema, &json!({ "error": "Page out of bounds" }), "envelope").is_err());
+        assert!(check(schemas, schema, &json!({ "projects": [], "pagination": { "page": 1 } }), "envelope").is_err());
+        assert!(check(schemas, schema, &json!({ "projects": [], "pagination": { "page": "1", "pages": 1, "count": 0, "items": 20 } }), "envelope").is_err());
+    }
+}
@@ -1,4 +1,13 @@
+pub mod admin;
 pub mod comment;
+pub mod confidence;
+pub mod debug;
+pub mod feedback;
+pub mod filter;
+pub mod job;
 pub mod logs;
+pub mod mirror;
 pub mod project;
+pub mod search;
+pub mod stats;
 pub mod user;
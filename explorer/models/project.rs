@@ -1,7 +1,10 @@
 use chrono::{DateTime, Utc};
+use common::SlackId;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+use crate::models::{confidence::ConfidenceSource, filter::TextMatch};
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Project {
     pub id: i64,
@@ -11,20 +14,34 @@ pub struct Project {
     pub readme_link: Option<String>,
     pub demo_link: Option<String>,
     pub repo_link: Option<String>,
-    pub slack_id: String,
+    #[schema(value_type = String)]
+    pub slack_id: SlackId,
     pub username: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub last_synced: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub confidence: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence_source: Option<ConfidenceSource>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub comments: Vec<crate::models::comment::Comment>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub devlog_count: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment_count: Option<i64>,
 }
 
 impl Project {
     pub fn with_confidence(mut self, confidence: f64) -> Self {
         self.confidence = Some(confidence);
+        self.confidence_source = Some(ConfidenceSource::Vector);
+        self
+    }
+
+    pub fn with_text_rank(mut self, rank: f64) -> Self {
+        self.confidence = Some(rank);
+        self.confidence_source = Some(ConfidenceSource::FullText);
         self
     }
 
@@ -33,6 +50,12 @@ impl Project {
         self
     }
 
+    pub fn with_counts(mut self, devlog_count: i64, comment_count: i64) -> Self {
+        self.devlog_count = Some(devlog_count);
+        self.comment_count = Some(comment_count);
+        self
+    }
+
 }
 
 
@@ -40,7 +63,9 @@ impl Project {
 pub struct ProjectFilter {
     pub id: Option<i64>,
     #[serde(rename = "slackId")]
-    pub slack_id: Option<String>,
+    #[schema(value_type = Option<String>)]
+    #[param(value_type = Option<String>)]
+    pub slack_id: Option<SlackId>,
     pub username: Option<String>,
     pub title: Option<String>,
     pub category: Option<String>,
@@ -52,11 +77,58 @@ pub struct ProjectFilter {
     pub from_date: Option<String>,
     #[serde(rename = "toDate")]
     pub to_date: Option<String>,
+    #[serde(rename = "match")]
+    pub text_match: Option<TextMatch>,
+    #[serde(rename = "sortBy")]
+    pub sort_by: Option<String>,
+    #[serde(rename = "sortDir")]
+    pub sort_dir: Option<String>,
+    pub limit: Option<u32>,
+    #[serde(rename = "includeCounts")]
+    pub include_counts: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ProjectSearchExplanation {
+    #[serde(flatten)]
+    pub project: Project,
+    pub raw_distance: f64,
+    pub confidence: f64,
+    pub query_token_count: usize,
+    pub embedded_text_preview: String,
+    pub zero_vector: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, utoipa::IntoParams)]
+pub struct SimilarProjectsQuery {
+    pub id: i64,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, utoipa::IntoParams)]
+pub struct TrendingProjectsQuery {
+    /// Activity window such as `24h`, `7d` or `2w` (default `7d`, max 90 days).
+    pub window: Option<String>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TrendingProject {
+    #[serde(flatten)]
+    pub project: Project,
+    pub activity: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, utoipa::IntoParams)]
+pub struct ProjectCommentsQuery {
     pub limit: Option<u32>,
+    pub offset: Option<u32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ProjectSearchRequest {
     pub query: String,
     pub limit: Option<u32>,
+    #[serde(rename = "includeCounts")]
+    pub include_counts: Option<bool>,
 }
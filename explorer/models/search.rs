@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::{comment::Comment, feedback::SearchResultType, logs::Log, project::Project};
+use crate::utils::error::{ApiError, Result};
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+/// Multipliers applied to each hit's similarity before the merged sort.
+/// A weight of 0 leaves that type out entirely.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct TypeWeights {
+    #[serde(default = "default_weight")]
+    pub projects: f64,
+    #[serde(default = "default_weight")]
+    pub devlogs: f64,
+    #[serde(default = "default_weight")]
+    pub comments: f64,
+}
+
+impl Default for TypeWeights {
+    fn default() -> Self {
+        Self {
+            projects: default_weight(),
+            devlogs: default_weight(),
+            comments: default_weight(),
+        }
+    }
+}
+
+impl TypeWeights {
+    pub fn weight(&self, result_type: SearchResultType) -> f64 {
+        match result_type {
+            SearchResultType::Project => self.projects,
+            SearchResultType::Devlog => self.devlogs,
+            SearchResultType::Comment => self.comments,
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for (name, weight) in [("projects", self.projects), ("devlogs", self.devlogs), ("comments", self.comments)] {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(ApiError::Validation {
+                    field: format!("type_weights.{}", name),
+                    message: "Weight must be a finite, non-negative number".to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UnifiedSearchRequest {
+    pub query: String,
+    pub limit: Option<u32>,
+    #[serde(default)]
+    pub type_weights: TypeWeights,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum SearchItem {
+    Project(Project),
+    Devlog(Log),
+    Comment(Comment),
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct UnifiedSearchResult {
+    #[serde(rename = "type")]
+    pub result_type: SearchResultType,
+    /// Cosine similarity to the query times the type's weight.
+    pub score: f64,
+    pub item: SearchItem,
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EmbeddingCoverage {
+    pub total: i64,
+    pub embedded: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserStats {
+    pub total: i64,
+    pub with_trust: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StatsResponse {
+    pub projects: EmbeddingCoverage,
+    pub devlogs: EmbeddingCoverage,
+    pub comments: EmbeddingCoverage,
+    pub users: UserStats,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DegenerateEmbeddings {
+    pub zero: i64,
+    pub nan: i64,
+    pub sample_ids: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EmbeddingHealthResponse {
+    pub projects: DegenerateEmbeddings,
+    pub devlogs: DegenerateEmbeddings,
+    pub comments: DegenerateEmbeddings,
+}
@@ -1,10 +1,12 @@
 use chrono::{DateTime, Utc};
+use common::SlackId;
 use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct User {
-    pub slack_id: String,
+    #[schema(value_type = String)]
+    pub slack_id: SlackId,
     pub username: Option<String>,
     pub trust_level: Option<String>,
     pub trust_value: Option<i32>,
@@ -35,7 +37,7 @@ impl User {
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ShellHistory {
-    pub id: i32,
+    pub id: i64,
     #[serde(rename = "shellsThen")]
     pub shells_then: Option<i32>,
     #[serde(rename = "shellDiff")]
@@ -47,11 +49,23 @@ pub struct ShellHistory {
 #[derive(Debug, Serialize, Deserialize, ToSchema, IntoParams)]
 pub struct UserFilter {
     #[serde(rename = "slackId")]
-    pub slack_id: Option<String>,
+    #[schema(value_type = Option<String>)]
+    #[param(value_type = Option<String>)]
+    pub slack_id: Option<SlackId>,
     pub username: Option<String>,
     pub limit: Option<u32>,
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema, IntoParams)]
+pub struct ShellHistoryFilter {
+    #[serde(rename = "slackId")]
+    #[schema(value_type = String)]
+    #[param(value_type = String)]
+    pub slack_id: SlackId,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UserProject {
     pub id: i64,
@@ -70,7 +84,8 @@ pub struct Payout {
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct LeaderboardEntry {
-    pub slack_id: String,
+    #[schema(value_type = String)]
+    pub slack_id: SlackId,
     pub username: Option<String>,
     pub shells: Option<i32>,
     pub rank: Option<i64>,
@@ -87,3 +102,19 @@ pub struct LeaderboardResponse {
     pub page: i32,
     pub per_page: i32,
 }
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LeaderboardMover {
+    #[schema(value_type = String)]
+    pub slack_id: SlackId,
+    pub username: Option<String>,
+    pub gained: i64,
+    pub pfp_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, IntoParams)]
+pub struct LeaderboardMoversFilter {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub limit: Option<u32>,
+}
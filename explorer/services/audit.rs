@@ -0,0 +1,35 @@
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio_postgres::GenericClient;
+
+#[derive(Debug, Clone)]
+pub struct AdminActor {
+    pub key_hash: String,
+}
+
+impl AdminActor {
+    pub fn from_key(key: &str) -> Self {
+        Self {
+            key_hash: format!("{:x}", Sha256::digest(key.as_bytes())),
+        }
+    }
+}
+
+pub async fn record_admin_action<C: GenericClient>(
+    client: &C,
+    actor: &AdminActor,
+    action: &str,
+    target: Option<&str>,
+    details: Value,
+) -> Result<(), tokio_postgres::Error> {
+    client
+        .execute(
+            "INSERT INTO audit_log (actor_key_hash, action, target, details_json) 
+             VALUES ($1, $2, $3, $4)",
+            &[&actor.key_hash, &action, &target, &details],
+        )
+        .await?;
+
+    tracing::info!(action = action, target = target, "Recorded admin action");
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use common::utils::config::Config;
+
+use crate::utils::error::{ApiError, Result};
+
+/// Rescales raw cosine similarity into the confidence shown to clients.
+///
+/// This is a display transform only: every mode is monotonic, so result
+/// ordering (which is decided by the database on raw distance) never changes.
+#[derive(Debug, Clone, Copy)]
+pub enum ConfidenceCalibration {
+    None,
+    Linear { baseline: f64 },
+    Sigmoid { midpoint: f64, steepness: f64 },
+}
+
+impl ConfidenceCalibration {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        match config.confidence_calibration.as_str() {
+            "none" => Ok(Self::None),
+            "linear" if config.confidence_baseline < 1.0 => Ok(Self::Linear {
+                baseline: config.confidence_baseline,
+            }),
+            "sigmoid" if config.confidence_sigmoid_steepness > 0.0 => Ok(Self::Sigmoid {
+                midpoint: config.confidence_baseline,
+                steepness: config.confidence_sigmoid_steepness,
+            }),
+            other => Err(ApiError::Config(format!(
+                "Invalid CONFIDENCE_CALIBRATION settings for mode '{}'",
+                other
+            ))),
+        }
+    }
+
+    pub fn apply(&self, similarity: f64) -> f64 {
+        match *self {
+            Self::None => similarity,
+            Self::Linear { baseline } => ((similarity - baseline) / (1.0 - baseline)).clamp(0.0, 1.0),
+            Self::Sigmoid { midpoint, steepness } => {
+                1.0 / (1.0 + (-steepness * (similarity - midpoint)).exp())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calibration(mode: &str, baseline: f64, steepness: f64) -> Result<ConfidenceCalibration> {
+        ConfidenceCalibration::from_config(&Config {
+            confidence_calibration: mode.to_owned(),
+            confidence_baseline: baseline,
+            confidence_sigmoid_steepness: steepness,
+            ..Config::default()
+        })
+    }
+
+    #[test]
+    fn every_mode_preserves_the_order_of_similarities() {
+        let similarities: Vec<f64> = (-100..=100).map(|i| f64::from(i) / 100.0).collect();
+
+        for calibration in [
+            calibration("none", 0.0, 0.0).unwrap(),
+            calibration("linear", 0.4, 0.0).unwrap(),
+            calibration("sigmoid", 0.5, 12.0).unwrap(),
+        ] {
+            let confidences: Vec<f64> = similarities.iter().map(|&s| calibration.apply(s)).collect();
+            assert!(
+                confidences.windows(2).all(|pair| pair[0] <= pair[1]),
+                "{calibration:?} reordered results"
+            );
+        }
+    }
+
+    #[test]
+    fn linear_maps_the_baseline_to_zero_and_one_to_one() {
+        let linear = calibration("linear", 0.4, 0.0).unwrap();
+
+        assert_eq!(linear.apply(0.2), 0.0);
+        assert_eq!(linear.apply(0.4), 0.0);
+        assert!((linear.apply(0.7) - 0.5).abs() < 1e-12);
+        assert_eq!(linear.apply(1.0), 1.0);
+    }
+
+    #[test]
+    fn rejects_settings_that_break_the_transform() {
+        assert!(calibration("linear", 1.0, 0.0).is_err());
+        assert!(calibration("sigmoid", 0.5, 0.0).is_err());
+        assert!(calibration("isotonic", 0.5, 1.0).is_err());
+    }
+}
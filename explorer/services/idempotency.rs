@@ -0,0 +1,141 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::{Body, Bytes},
+    http::{HeaderMap, StatusCode},
+    response::Response,
+};
+use sha2::{Digest, Sha256};
+
+const MAX_IDEMPOTENCY_KEYS: usize = 10_000;
+
+#[derive(Clone)]
+pub struct StoredResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl StoredResponse {
+    pub fn new(status: StatusCode, headers: HeaderMap, body: Bytes) -> Self {
+        Self { status, headers, body }
+    }
+
+    pub fn to_response(&self) -> Response {
+        let mut response = Response::new(Body::from(self.body.clone()));
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers.clone();
+        response
+    }
+}
+
+/// Hash of the request body a key was first used with.
+pub type Fingerprint = [u8; 32];
+
+pub fn fingerprint(body: &[u8]) -> Fingerprint {
+    Sha256::digest(body).into()
+}
+
+enum State {
+    InFlight,
+    Done(StoredResponse),
+}
+
+struct Entry {
+    fingerprint: Fingerprint,
+    state: State,
+    created_at: Instant,
+}
+
+pub enum Claim {
+    /// The caller owns the key and must run the request.
+    Execute(InFlightGuard),
+    Replay(StoredResponse),
+    InProgress,
+    BodyMismatch,
+}
+
+pub struct IdempotencyStore {
+    entries: Mutex<HashMap<String, Entry>>,
+    ttl: Duration,
+}
+
+impl IdempotencyStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    /// Marks `key` as in flight before the request runs, so a concurrent
+    /// duplicate sees it instead of executing a second time.
+    pub fn claim(self: &Arc<Self>, key: &str, fingerprint: Fingerprint) -> Claim {
+        let mut entries = self.entries.lock().unwrap();
+
+        if entries.len() >= MAX_IDEMPOTENCY_KEYS {
+            entries.retain(|_, entry| {
+                matches!(entry.state, State::InFlight) || entry.created_at.elapsed() < self.ttl
+            });
+        }
+
+        if let Some(entry) = entries.get(key) {
+            let expired = matches!(entry.state, State::Done(_)) && entry.created_at.elapsed() >= self.ttl;
+            if !expired {
+                return if entry.fingerprint != fingerprint {
+                    Claim::BodyMismatch
+                } else {
+                    match &entry.state {
+                        State::InFlight => Claim::InProgress,
+                        State::Done(response) => Claim::Replay(response.clone()),
+                    }
+                };
+            }
+        }
+
+        entries.insert(
+            key.to_owned(),
+            Entry {
+                fingerprint,
+                state: State::InFlight,
+                created_at: Instant::now(),
+            },
+        );
+
+        Claim::Execute(InFlightGuard {
+            store: Arc::clone(self),
+            key: key.to_owned(),
+            completed: false,
+        })
+    }
+}
+
+/// Releases the key if dropped before [`InFlightGuard::complete`], so a failed
+/// or abandoned request can be retried with the same key.
+pub struct InFlightGuard {
+    store: Arc<IdempotencyStore>,
+    key: String,
+    completed: bool,
+}
+
+impl InFlightGuard {
+    pub fn complete(mut self, response: StoredResponse) {
+        if let Some(entry) = self.store.entries.lock().unwrap().get_mut(&self.key) {
+            entry.state = State::Done(response);
+            entry.created_at = Instant::now();
+        }
+        self.completed = true;
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.store.entries.lock().unwrap().remove(&self.key);
+        }
+    }
+}
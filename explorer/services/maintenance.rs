@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+
+/// Maintenance is on when `MAINTENANCE_MODE` was set at startup or, if
+/// configured, while `MAINTENANCE_FILE` exists so it can be toggled live.
+pub struct MaintenanceMode {
+    forced: bool,
+    flag_file: Option<PathBuf>,
+    retry_after: u64,
+}
+
+impl MaintenanceMode {
+    pub fn new(forced: bool, flag_file: Option<PathBuf>, retry_after: u64) -> Self {
+        Self {
+            forced,
+            flag_file,
+            retry_after,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.forced || self.flag_file.as_deref().is_some_and(|path| path.exists())
+    }
+
+    pub fn retry_after(&self) -> u64 {
+        self.retry_after
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt::Write as _;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use axum::http::StatusCode;
+use common::PoolStatus;
+
+static GLOBAL_REQUEST_METRICS: OnceLock<RequestMetrics> = OnceLock::new();
+
+const STATUS_CLASSES: [&str; 5] = ["1xx", "2xx", "3xx", "4xx", "5xx"];
+
+#[derive(Default)]
+pub struct RequestMetrics {
+    requests_by_class: [AtomicU64; STATUS_CLASSES.len()],
+    in_flight: AtomicU64,
+}
+
+impl RequestMetrics {
+    pub fn global() -> &'static RequestMetrics {
+        GLOBAL_REQUEST_METRICS.get_or_init(RequestMetrics::default)
+    }
+
+    /// Counts a request as in flight until the returned guard is dropped, so a
+    /// request abandoned by a disconnecting client is not left counted.
+    pub fn request_started(&self) -> InFlightRequest<'_> {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlightRequest { metrics: self }
+    }
+
+    pub fn render_prometheus(&self, pool_status: &PoolStatus) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# HELP explorer_http_requests_total Total HTTP requests handled");
+        let _ = writeln!(out, "# TYPE explorer_http_requests_total counter");
+        for (class, counter) in STATUS_CLASSES.iter().zip(&self.requests_by_class) {
+            let _ = writeln!(
+                out,
+                "explorer_http_requests_total{{status=\"{class}\"}} {}",
+                counter.load(Ordering::Relaxed)
+            );
+        }
+
+        let _ = writeln!(out, "# HELP explorer_http_requests_in_flight HTTP requests currently being handled");
+        let _ = writeln!(out, "# TYPE explorer_http_requests_in_flight gauge");
+        let _ = writeln!(out, "explorer_http_requests_in_flight {}", self.in_flight.load(Ordering::Relaxed));
+
+        let pool_gauges = [
+            ("explorer_db_pool_size", "Current number of connections in the pool", pool_status.size),
+            ("explorer_db_pool_available", "Idle connections available in the pool", pool_status.available),
+            ("explorer_db_pool_waiting", "Requests waiting for a pooled connection", pool_status.waiting),
+            ("explorer_db_pool_max_size", "Maximum size of the pool", pool_status.max_size),
+        ];
+        for (name, help, value) in pool_gauges {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} gauge");
+            let _ = writeln!(out, "{name} {value}");
+        }
+
+        out
+    }
+}
+
+#[must_use = "the request stops counting as in flight when this is dropped"]
+pub struct InFlightRequest<'a> {
+    metrics: &'a RequestMetrics,
+}
+
+impl InFlightRequest<'_> {
+    pub fn finish(self, status: StatusCode) {
+        let class = usize::from(status.as_u16() / 100).clamp(1, STATUS_CLASSES.len()) - 1;
+        self.metrics.requests_by_class[class].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Drop for InFlightRequest<'_> {
+    fn drop(&mut self) {
+        self.metrics.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_flight(metrics: &RequestMetrics) -> u64 {
+        metrics.in_flight.load(Ordering::Relaxed)
+    }
+
+    #[test]
+    fn finished_requests_are_counted_by_status_class() {
+        let metrics = RequestMetrics::default();
+        let request = metrics.request_started();
+        assert_eq!(in_flight(&metrics), 1);
+
+        request.finish(StatusCode::NOT_FOUND);
+        assert_eq!(in_flight(&metrics), 0);
+        assert_eq!(metrics.requests_by_class[3].load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn abandoned_requests_leave_the_in_flight_gauge() {
+        let metrics = RequestMetrics::default();
+
+        // A client disconnect drops the handler future before it completes.
+        let handler = async {
+            let request = metrics.request_started();
+            std::future::pending::<()>().await;
+            request.finish(StatusCode::OK);
+        };
+        let timed_out = tokio::time::timeout(std::time::Duration::from_millis(10), handler).await;
+
+        assert!(timed_out.is_err());
+        assert_eq!(in_flight(&metrics), 0);
+        assert!(metrics.requests_by_class.iter().all(|c| c.load(Ordering::Relaxed) == 0));
+    }
+}
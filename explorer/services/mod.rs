@@ -1 +1,8 @@
+pub mod audit;
+pub mod calibration;
 pub mod embedding;
+pub mod idempotency;
+pub mod maintenance;
+pub mod metrics;
+pub mod rate_limit;
+pub mod search_cache;
@@ -0,0 +1,127 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+const IDLE_CLIENT_EXPIRY: Duration = Duration::from_secs(600);
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+    capacity: f64,
+    refill_per_sec: f64,
+    trusted_proxies: Vec<IpAddr>,
+}
+
+impl RateLimiter {
+    pub fn per_minute(requests_per_minute: u32) -> Self {
+        let capacity = f64::from(requests_per_minute);
+        Self {
+            buckets: Mutex::new(HashMap::new()),
+            capacity,
+            refill_per_sec: capacity / 60.0,
+            trusted_proxies: Vec::new(),
+        }
+    }
+
+    /// Proxies whose `X-Forwarded-For` entries are believed when identifying
+    /// the client. With none configured the peer address is always used.
+    #[must_use]
+    pub fn with_trusted_proxies(mut self, trusted_proxies: Vec<IpAddr>) -> Self {
+        self.trusted_proxies = trusted_proxies;
+        self
+    }
+
+    pub fn trusted_proxies(&self) -> &[IpAddr] {
+        &self.trusted_proxies
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0.0
+    }
+
+    pub fn check(&self, client: IpAddr) -> Result<(), u64> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(&client) {
+            buckets.retain(|_, bucket| now.duration_since(bucket.last_refill) < IDLE_CLIENT_EXPIRY);
+
+            // Still full of active clients: evict the least recently seen one
+            // so the map stays bounded however many addresses show up.
+            if buckets.len() >= MAX_TRACKED_CLIENTS
+                && let Some(lru) = buckets
+                    .iter()
+                    .min_by_key(|(_, bucket)| bucket.last_refill)
+                    .map(|(ip, _)| *ip)
+            {
+                buckets.remove(&lru);
+            }
+        }
+
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: self.capacity,
+            last_refill: now,
+        });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let retry_after = ((1.0 - bucket.tokens) / self.refill_per_sec).ceil() as u64;
+            Err(retry_after.max(1))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn ip(n: u32) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::from(n))
+    }
+
+    #[test]
+    fn rejects_the_request_after_the_budget() {
+        let limiter = RateLimiter::per_minute(3);
+        for _ in 0..3 {
+            assert!(limiter.check(ip(1)).is_ok());
+        }
+        assert_eq!(limiter.check(ip(1)), Err(20));
+        assert!(limiter.check(ip(2)).is_ok());
+    }
+
+    #[test]
+    fn zero_budget_disables_limiting() {
+        let limiter = RateLimiter::per_minute(0);
+        for _ in 0..100 {
+            assert!(limiter.check(ip(1)).is_ok());
+        }
+    }
+
+    #[test]
+    fn tracked_clients_never_exceed_the_cap() {
+        let limiter = RateLimiter::per_minute(10);
+        for n in 0..(MAX_TRACKED_CLIENTS as u32 + 50) {
+            assert!(limiter.check(ip(n)).is_ok());
+        }
+        assert_eq!(limiter.buckets.lock().unwrap().len(), MAX_TRACKED_CLIENTS);
+    }
+}
@@ -0,0 +1,202 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use tokio::sync::OnceCell;
+
+use crate::models::{comment::Comment, logs::Log, project::Project};
+use crate::utils::error::Result;
+
+const MAX_CACHED_SEARCHES: usize = 1000;
+
+struct CachedSearch<T> {
+    results: Vec<T>,
+    created_at: Instant,
+}
+
+type SearchKey = (String, i64);
+
+pub struct SearchCache<T> {
+    entries: Mutex<HashMap<SearchKey, CachedSearch<T>>>,
+    in_flight: Mutex<HashMap<SearchKey, Arc<OnceCell<Vec<T>>>>>,
+    ttl: Duration,
+}
+
+impl<T: Clone> SearchCache<T> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            in_flight: Mutex::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    pub async fn get_or_search<F, Fut>(&self, query: &str, limit: i64, search: F) -> Result<Vec<T>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Vec<T>>>,
+    {
+        let key = (normalize_query(query), limit);
+        if let Some(cached) = self.get(&key) {
+            return Ok(cached);
+        }
+
+        let flight = Arc::clone(self.in_flight.lock().unwrap().entry(key.clone()).or_default());
+        let result = flight.get_or_try_init(search).await.cloned();
+
+        {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            if in_flight.get(&key).is_some_and(|current| Arc::ptr_eq(current, &flight)) {
+                in_flight.remove(&key);
+            }
+        }
+
+        if let Ok(results) = &result {
+            self.insert(key, results);
+        }
+        result
+    }
+
+    fn get(&self, key: &SearchKey) -> Option<Vec<T>> {
+        if self.ttl.is_zero() {
+            return None;
+        }
+
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(key)
+            .filter(|entry| entry.created_at.elapsed() < self.ttl)
+            .map(|entry| entry.results.clone())
+    }
+
+    fn insert(&self, key: SearchKey, results: &[T]) {
+        if self.ttl.is_zero() {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_CACHED_SEARCHES {
+            entries.retain(|_, entry| entry.created_at.elapsed() < self.ttl);
+            if entries.len() >= MAX_CACHED_SEARCHES {
+                entries.clear();
+            }
+        }
+
+        entries.insert(
+            key,
+            CachedSearch {
+                results: results.to_vec(),
+                created_at: Instant::now(),
+            },
+        );
+    }
+}
+
+pub struct SearchCaches {
+    pub projects: SearchCache<Project>,
+    pub comments: SearchCache<Comment>,
+    pub logs: SearchCache<Log>,
+}
+
+impl SearchCaches {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            projects: SearchCache::new(ttl),
+            comments: SearchCache::new(ttl),
+            logs: SearchCache::new(ttl),
+        }
+    }
+}
+
+fn normalize_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::utils::error::ApiError;
+
+    /// Runs `query` through `cache`, counting how often the search itself runs.
+    async fn search(cache: &SearchCache<String>, query: &str, limit: i64, runs: &AtomicUsize) -> Result<Vec<String>> {
+        cache
+            .get_or_search(query, limit, || async {
+                runs.fetch_add(1, Ordering::SeqCst);
+                Ok(Vec::new())
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn repeated_empty_search_within_the_ttl_skips_the_query() {
+        let cache = SearchCache::new(Duration::from_secs(60));
+        let runs = AtomicUsize::new(0);
+
+        assert!(search(&cache, "quantum  Toaster", 10, &runs).await.unwrap().is_empty());
+        assert!(search(&cache, "quantum toaster", 10, &runs).await.unwrap().is_empty());
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        search(&cache, "quantum toaster", 20, &runs).await.unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 2, "a different limit was served from the cache");
+    }
+
+    #[tokio::test]
+    async fn expired_or_disabled_entries_query_again() {
+        let runs = AtomicUsize::new(0);
+
+        let short_lived = SearchCache::new(Duration::from_millis(20));
+        search(&short_lived, "typo", 10, &runs).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        search(&short_lived, "typo", 10, &runs).await.unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+        let disabled = SearchCache::new(Duration::ZERO);
+        search(&disabled, "typo", 10, &runs).await.unwrap();
+        search(&disabled, "typo", 10, &runs).await.unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn failed_searches_are_not_cached() {
+        let cache = SearchCache::<String>::new(Duration::from_secs(60));
+        let failed = cache
+            .get_or_search("typo", 10, || async { Err(ApiError::Database("connection reset".to_owned())) })
+            .await;
+        assert!(failed.is_err());
+
+        let runs = AtomicUsize::new(0);
+        search(&cache, "typo", 10, &runs).await.unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    // With the cache disabled, only in-flight sharing can keep this at one run.
+    #[tokio::test]
+    async fn concurrent_identical_searches_share_one_run() {
+        let cache = SearchCache::new(Duration::ZERO);
+        let runs = AtomicUsize::new(0);
+
+        let results = futures::future::join_all((0..20).map(|_| {
+            cache.get_or_search("viral query", 10, || async {
+                runs.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok(vec!["hit".to_owned()])
+            })
+        }))
+        .await;
+
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert!(results.iter().all(|result| result.as_ref().unwrap() == &["hit"]));
+
+        search(&cache, "viral query", 10, &runs).await.unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 2, "a finished flight was reused");
+    }
+}
@@ -1,10 +1,14 @@
 use chrono::{DateTime, NaiveDate, Utc};
 use serde_urlencoded;
 use std::collections::HashMap;
-use tokio_postgres::{types::ToSql, Row};
+use axum::{Json, response::{IntoResponse, Response}};
+use tokio_postgres::{types::ToSql, Client, Row};
 
 use super::error::{ApiError, Result};
-use crate::models::{comment::Comment, logs::Log, project::Project};
+use crate::models::{
+    admin::AuditEntry, comment::Comment, filter::TextMatch, job::JobRun, logs::Log, project::Project,
+    user::{ShellHistory, User, UserProject},
+};
 
 pub fn parse_date_string(date_str: &str) -> Result<DateTime<Utc>> {
     if let Ok(dt) = DateTime::parse_from_rfc3339(date_str) {
@@ -43,6 +47,19 @@ pub fn decode_username(username: &str) -> String {
         .unwrap_or_else(|| username.to_string())
 }
 
+pub async fn explain_query(
+    client: &Client,
+    query: &str,
+    params: &[&(dyn ToSql + Sync)],
+) -> Result<Response> {
+    let row = client
+        .query_one(&format!("EXPLAIN (ANALYZE, FORMAT JSON) {}", query), params)
+        .await?;
+    let plan: serde_json::Value = try_column(&row, "QUERY PLAN")?;
+
+    Ok(Json(plan).into_response())
+}
+
 pub struct QueryBuilder {
     conditions: Vec<String>,
     params: Vec<Box<dyn ToSql + Send + Sync>>,
@@ -62,6 +79,13 @@ impl QueryBuilder {
         self.params.push(Box::new(value));
     }
 
+    /// Binds `value` without adding a condition, e.g. for `LIMIT`, and returns
+    /// its placeholder index.
+    pub fn add_param<T: ToSql + Send + Sync + 'static>(&mut self, value: T) -> usize {
+        self.params.push(Box::new(value));
+        self.params.len()
+    }
+
     pub fn add_date_condition(&mut self, field: &str, operator: &str, date_str: &str) -> Result<()> {
         let parsed = parse_date_string(date_str)?;
         self.add_condition(&format!("{} {} ${}", field, operator, "{}"), parsed);
@@ -101,51 +125,265 @@ impl Default for QueryBuilder {
     }
 }
 
-pub fn map_project_row(row: &Row) -> Project {
-    Project {
-        id: row.get::<_, i64>("id"),
-        title: row.get("title"),
-        description: row.get("description"),
-        category: row.get("category"),
-        readme_link: row.get("readme_link"),
-        demo_link: row.get("demo_link"),
-        repo_link: row.get("repo_link"),
-        slack_id: row.get("slack_id"),
-        username: row.get("username"),
-        created_at: row.get("created_at"),
-        updated_at: row.get("updated_at"),
-        last_synced: row.get("last_synced"),
+pub fn like_pattern(value: &str, mode: TextMatch) -> String {
+    let escape = |value: &str| {
+        value
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    };
+
+    match mode {
+        TextMatch::Contains => format!("%{}%", escape(value)),
+        TextMatch::Exact => escape(value),
+        TextMatch::Pattern => value.to_owned(),
+    }
+}
+
+pub fn build_order_by(
+    sort_by: Option<&str>,
+    sort_dir: Option<&str>,
+    allowed_columns: &[&str],
+    default_column: &str,
+) -> Result<String> {
+    let column = match sort_by {
+        Some(column) if allowed_columns.contains(&column) => column,
+        Some(column) => {
+            return Err(ApiError::Validation {
+                field: "sortBy".to_string(),
+                message: format!(
+                    "Cannot sort by '{}', expected one of: {}",
+                    column,
+                    allowed_columns.join(", ")
+                ),
+            });
+        }
+        None => default_column,
+    };
+
+    let direction = match sort_dir {
+        None => "DESC",
+        Some(dir) if dir.eq_ignore_ascii_case("desc") => "DESC",
+        Some(dir) if dir.eq_ignore_ascii_case("asc") => "ASC",
+        Some(_) => {
+            return Err(ApiError::Validation {
+                field: "sortDir".to_string(),
+                message: "sortDir must be 'asc' or 'desc'".to_string(),
+            });
+        }
+    };
+
+    Ok(format!("ORDER BY {} {}", column, direction))
+}
+
+/// Reads a column without panicking, so schema drift surfaces as a database
+/// error naming the offending column.
+pub fn try_column<'a, T>(row: &'a Row, column: &str) -> Result<T>
+where
+    T: tokio_postgres::types::FromSql<'a>,
+{
+    row.try_get(column)
+        .map_err(|e| ApiError::Database(format!("Failed to read column '{column}': {e}")))
+}
+
+pub fn map_project_row(row: &Row) -> Result<Project> {
+    Ok(Project {
+        id: try_column::<i64>(row, "id")?,
+        title: try_column(row, "title")?,
+        description: try_column(row, "description")?,
+        category: try_column(row, "category")?,
+        readme_link: try_column(row, "readme_link")?,
+        demo_link: try_column(row, "demo_link")?,
+        repo_link: try_column(row, "repo_link")?,
+        slack_id: try_column(row, "slack_id")?,
+        username: try_column(row, "username")?,
+        created_at: try_column(row, "created_at")?,
+        updated_at: try_column(row, "updated_at")?,
+        last_synced: try_column(row, "last_synced")?,
         confidence: None,
+        confidence_source: None,
         comments: Vec::new(),
-    }
+        devlog_count: None,
+        comment_count: None,
+    })
 }
 
-pub fn map_comment_row(row: &Row) -> Comment {
-    Comment {
-        id: row.get::<_, i64>("id"),
-        text: row.get("text"),
-        devlog_id: row.get::<_, i64>("devlog_id"),
-        slack_id: row.get("slack_id"),
-        username: row.get("username"),
-        created_at: row.get("created_at"),
-        last_synced: row.get("last_synced"),
+/// With `hide_serial_id` the local serial is left out of the mapped comment.
+pub fn map_comment_row(row: &Row, hide_serial_id: bool) -> Result<Comment> {
+    Ok(Comment {
+        id: if hide_serial_id { None } else { Some(try_column::<i64>(row, "id")?) },
+        upstream_id: try_column(row, "upstream_id")?,
+        text: try_column(row, "text")?,
+        devlog_id: try_column::<i64>(row, "devlog_id")?,
+        slack_id: try_column(row, "slack_id")?,
+        username: try_column(row, "username")?,
+        created_at: try_column(row, "created_at")?,
+        last_synced: try_column(row, "last_synced")?,
         confidence: None,
-    }
+        confidence_source: None,
+        devlog: None,
+    })
 }
 
-pub fn map_log_row(row: &Row) -> Log {
-    Log {
-        id: row.get::<_, i64>("id"),
-        text: row.get("text"),
-        attachment: row.get("attachment"),
-        project_id: row.get::<_, i64>("project_id"),
-        slack_id: row.get("slack_id"),
-        username: row.get("username"),
-        created_at: row.get("created_at"),
-        updated_at: row.get("updated_at"),
-        last_synced: row.get("last_synced"),
+pub fn map_log_row(row: &Row) -> Result<Log> {
+    Ok(Log {
+        id: try_column::<i64>(row, "id")?,
+        text: try_column(row, "text")?,
+        attachment: try_column(row, "attachment")?,
+        project_id: try_column::<i64>(row, "project_id")?,
+        slack_id: try_column(row, "slack_id")?,
+        username: try_column(row, "username")?,
+        created_at: try_column(row, "created_at")?,
+        updated_at: try_column(row, "updated_at")?,
+        last_synced: try_column(row, "last_synced")?,
         confidence: None,
+        confidence_source: None,
         project: None,
-    }
+    })
 }
 
+pub fn map_audit_entry_row(row: &Row) -> Result<AuditEntry> {
+    Ok(AuditEntry {
+        id: try_column::<i64>(row, "id")?,
+        at: try_column(row, "at")?,
+        actor_key_hash: try_column(row, "actor_key_hash")?,
+        action: try_column(row, "action")?,
+        target: try_column(row, "target")?,
+        details: try_column(row, "details_json")?,
+    })
+}
+
+pub fn map_user_row(row: &Row) -> Result<User> {
+    Ok(User {
+        slack_id: try_column(row, "slack_id")?,
+        username: try_column(row, "username")?,
+        trust_level: try_column(row, "trust_level")?,
+        trust_value: try_column(row, "trust_value")?,
+        current_shells: try_column(row, "current_shells")?,
+        last_synced: try_column(row, "last_synced")?,
+        shell_history: Vec::new(),
+        projects: Vec::new(),
+        pfp_url: try_column(row, "pfp_url")?,
+        image_24: try_column(row, "image_24")?,
+        image_32: try_column(row, "image_32")?,
+        image_48: try_column(row, "image_48")?,
+        image_72: try_column(row, "image_72")?,
+        image_192: try_column(row, "image_192")?,
+        image_512: try_column(row, "image_512")?,
+    })
+}
+
+pub fn map_shell_history_row(row: &Row) -> Result<ShellHistory> {
+    Ok(ShellHistory {
+        id: try_column(row, "id")?,
+        shells_then: try_column(row, "shells_then")?,
+        shell_diff: try_column(row, "shell_diff")?,
+        shells: try_column(row, "shells")?,
+        recorded_at: try_column(row, "recorded_at")?,
+    })
+}
+
+pub fn map_user_project_row(row: &Row) -> Result<UserProject> {
+    Ok(UserProject {
+        id: try_column(row, "id")?,
+        title: try_column(row, "title")?,
+    })
+}
+
+pub fn map_job_run_row(row: &Row) -> Result<JobRun> {
+    Ok(JobRun {
+        id: try_column::<i64>(row, "id")?,
+        job_name: try_column(row, "job_name")?,
+        started_at: try_column(row, "started_at")?,
+        finished_at: try_column(row, "finished_at")?,
+        status: try_column(row, "status")?,
+        error_message: try_column(row, "error_message")?,
+        items_processed: try_column(row, "items_processed")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_param_binds_without_a_condition() {
+        let mut query_builder = QueryBuilder::new();
+        query_builder.add_condition("job_name = ${}", "forge".to_string());
+        let limit_param = query_builder.add_param(20_i64);
+
+        assert_eq!(query_builder.build_where_clause(), "WHERE job_name = $1");
+        assert_eq!(limit_param, 2);
+        assert_eq!(query_builder.param_count(), 2);
+    }
+
+    #[test]
+    fn add_param_alone_leaves_where_clause_empty() {
+        let mut query_builder = QueryBuilder::new();
+        assert_eq!(query_builder.add_param(20_i64), 1);
+        assert_eq!(query_builder.build_where_clause(), "");
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn explain_query_returns_the_json_plan() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let response = explain_query(&client, "SELECT $1::BIGINT + 1", &[&1_i64]).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let plan: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert!(plan[0]["Plan"]["Node Type"].is_string(), "{plan}");
+        assert!(plan[0]["Execution Time"].is_number(), "{plan}");
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn unexpected_null_is_an_error_not_a_panic() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, tokio_postgres::NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+
+        let project = client
+            .query_one(
+                "SELECT 1::BIGINT AS id, NULL::TEXT AS title, NULL::TEXT AS description, NULL::TEXT AS category,
+                        NULL::TEXT AS readme_link, NULL::TEXT AS demo_link, NULL::TEXT AS repo_link,
+                        'U1'::TEXT AS slack_id, NULL::TEXT AS username, now() AS created_at,
+                        now() AS updated_at, NULL::TIMESTAMPTZ AS last_synced",
+                &[],
+            )
+            .await
+            .unwrap();
+        let comment = client
+            .query_one(
+                "SELECT 1::BIGINT AS id, NULL::BIGINT AS upstream_id, 'hi'::TEXT AS text,
+                        NULL::BIGINT AS devlog_id, 'U1'::TEXT AS slack_id, NULL::TEXT AS username,
+                        now() AS created_at, NULL::TIMESTAMPTZ AS last_synced",
+                &[],
+            )
+            .await
+            .unwrap();
+
+        match map_project_row(&project) {
+            Err(ApiError::Database(message)) => assert!(message.contains("'title'"), "{message}"),
+            other => panic!("expected a database error, got {other:?}"),
+        }
+        match map_comment_row(&comment, false) {
+            Err(ApiError::Database(message)) => assert!(message.contains("'devlog_id'"), "{message}"),
+            other => panic!("expected a database error, got {other:?}"),
+        }
+        assert!(try_column::<i64>(&project, "no_such_column").is_err());
+    }
+}
@@ -0,0 +1,2 @@
+ALTER TABLE sync_metadata DROP COLUMN IF EXISTS last_modified;
+ALTER TABLE sync_metadata DROP COLUMN IF EXISTS etag;
@@ -0,0 +1,2 @@
+ALTER TABLE sync_metadata ADD COLUMN IF NOT EXISTS etag TEXT;
+ALTER TABLE sync_metadata ADD COLUMN IF NOT EXISTS last_modified TEXT;
@@ -0,0 +1,19 @@
+-- migrate:no-transaction
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_projects_embedding ON projects 
+USING ivfflat (title_description_embedding vector_cosine_ops) 
+WITH (lists = 100)
+WHERE title_description_embedding IS NOT NULL;
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_comments_embedding ON comments 
+USING ivfflat (text_embedding vector_cosine_ops) 
+WITH (lists = 100)
+WHERE text_embedding IS NOT NULL;
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_logs_embedding ON logs 
+USING ivfflat (text_embedding vector_cosine_ops) 
+WITH (lists = 100)
+WHERE text_embedding IS NOT NULL;
+
+DROP INDEX CONCURRENTLY IF EXISTS idx_projects_embedding_hnsw;
+DROP INDEX CONCURRENTLY IF EXISTS idx_comments_embedding_hnsw;
+DROP INDEX CONCURRENTLY IF EXISTS idx_logs_embedding_hnsw;
@@ -0,0 +1,23 @@
+-- migrate:no-transaction
+-- Builds the HNSW indexes without blocking writes, and only then drops the
+-- IVFFlat indexes they replace, so search keeps an index throughout. The build
+-- can take a while on large tables. If it is interrupted, drop any INVALID
+-- *_hnsw index before re-running.
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_projects_embedding_hnsw ON projects 
+USING hnsw (title_description_embedding vector_cosine_ops) 
+WITH (m = 16, ef_construction = 64)
+WHERE title_description_embedding IS NOT NULL;
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_comments_embedding_hnsw ON comments 
+USING hnsw (text_embedding vector_cosine_ops) 
+WITH (m = 16, ef_construction = 64)
+WHERE text_embedding IS NOT NULL;
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_logs_embedding_hnsw ON logs 
+USING hnsw (text_embedding vector_cosine_ops) 
+WITH (m = 16, ef_construction = 64)
+WHERE text_embedding IS NOT NULL;
+
+DROP INDEX CONCURRENTLY IF EXISTS idx_projects_embedding;
+DROP INDEX CONCURRENTLY IF EXISTS idx_comments_embedding;
+DROP INDEX CONCURRENTLY IF EXISTS idx_logs_embedding;
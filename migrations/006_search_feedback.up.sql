@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS search_feedback (
+    id BIGSERIAL PRIMARY KEY,
+    at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    query TEXT NOT NULL,
+    result_type VARCHAR(20) NOT NULL,
+    result_id BIGINT NOT NULL,
+    rank INTEGER NOT NULL,
+    clicked BOOLEAN NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_search_feedback_at ON search_feedback(at DESC);
+CREATE INDEX IF NOT EXISTS idx_search_feedback_result ON search_feedback(result_type, result_id);
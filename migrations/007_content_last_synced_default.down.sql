@@ -0,0 +1,3 @@
+ALTER TABLE projects ALTER COLUMN last_synced DROP DEFAULT;
+ALTER TABLE logs ALTER COLUMN last_synced DROP DEFAULT;
+ALTER TABLE comments ALTER COLUMN last_synced DROP DEFAULT;
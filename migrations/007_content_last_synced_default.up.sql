@@ -0,0 +1,3 @@
+ALTER TABLE projects ALTER COLUMN last_synced SET DEFAULT NOW();
+ALTER TABLE logs ALTER COLUMN last_synced SET DEFAULT NOW();
+ALTER TABLE comments ALTER COLUMN last_synced SET DEFAULT NOW();
@@ -0,0 +1,2 @@
+-- Attachments longer than the old limit are truncated to fit.
+ALTER TABLE logs ALTER COLUMN attachment TYPE VARCHAR(500) USING LEFT(attachment, 500);
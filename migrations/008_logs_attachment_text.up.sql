@@ -0,0 +1 @@
+ALTER TABLE logs ALTER COLUMN attachment TYPE TEXT;
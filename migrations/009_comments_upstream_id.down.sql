@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_comments_upstream_id;
+ALTER TABLE comments DROP COLUMN IF EXISTS upstream_id;
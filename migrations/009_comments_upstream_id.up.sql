@@ -0,0 +1,4 @@
+-- The upstream id of a comment, when the API provides one. comments.id stays a
+-- local serial.
+ALTER TABLE comments ADD COLUMN IF NOT EXISTS upstream_id BIGINT;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_comments_upstream_id ON comments(upstream_id) WHERE upstream_id IS NOT NULL;
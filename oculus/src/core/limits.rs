@@ -0,0 +1,220 @@
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use common::utils::{config::Config, error::ApiError};
+
+use super::{get_db_concurrency, get_embedding_concurrency, get_fetch_concurrency, JobError};
+
+static GLOBAL_LIMITS: OnceLock<ResourceLimits> = OnceLock::new();
+
+// Matches the MAX_DB_CONNECTIONS default for callers that run before `install`.
+const DEFAULT_POOL_SIZE: usize = 50;
+
+/// Process-wide caps on upstream fetches, embedding work and database writes.
+/// Every job draws from the same semaphores, so jobs that overlap share one
+/// budget instead of each sizing its own from the CPU count.
+pub struct ResourceLimits {
+    fetch: Arc<Semaphore>,
+    embed: Arc<Semaphore>,
+    db: Arc<Semaphore>,
+    fetch_permits: usize,
+    embed_permits: usize,
+}
+
+impl ResourceLimits {
+    fn new(pool_max_size: usize) -> Self {
+        let fetch_permits = std::env::var("FETCH_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|&v| v > 0)
+            .unwrap_or_else(get_fetch_concurrency);
+        let embed_permits = get_embedding_concurrency();
+        let db_permits = get_db_concurrency(pool_max_size);
+
+        tracing::info!(
+            "Process resource limits: {} fetches, {} embeddings, {} database writes",
+            fetch_permits,
+            embed_permits,
+            db_permits
+        );
+
+        Self {
+            fetch: Arc::new(Semaphore::new(fetch_permits)),
+            embed: Arc::new(Semaphore::new(embed_permits)),
+            db: Arc::new(Semaphore::new(db_permits)),
+            fetch_permits,
+            embed_permits,
+        }
+    }
+
+    /// Sizes the process-wide limits from `config`. Only the first call has any
+    /// effect; it should happen in `main` before any job starts.
+    pub fn install(config: &Config) -> &'static Self {
+        GLOBAL_LIMITS.get_or_init(|| Self::new(config.max_db_connections as usize))
+    }
+
+    pub fn global() -> &'static Self {
+        GLOBAL_LIMITS.get_or_init(|| Self::new(DEFAULT_POOL_SIZE))
+    }
+
+    pub fn fetch_concurrency(&self) -> usize {
+        self.fetch_permits
+    }
+
+    pub fn embed(&self) -> Arc<Semaphore> {
+        Arc::clone(&self.embed)
+    }
+
+    pub fn db(&self) -> Arc<Semaphore> {
+        Arc::clone(&self.db)
+    }
+
+    /// Runs one upstream request while holding a fetch permit.
+    pub async fn limit_fetch<T, Fut>(&self, request: Fut) -> Result<T, ApiError>
+    where
+        Fut: Future<Output = Result<T, ApiError>>,
+    {
+        let _permit = self
+            .fetch
+            .acquire()
+            .await
+            .map_err(|e| ApiError::ExternalApi(format!("Fetch limiter closed: {}", e)))?;
+        request.await
+    }
+
+    /// Holds a fetch permit for requests that don't go through `limit_fetch`,
+    /// such as Slack and Hackatime lookups.
+    pub async fn fetch_permit(&self) -> Result<SemaphorePermit<'_>, JobError> {
+        self.fetch
+            .acquire()
+            .await
+            .map_err(|e| JobError::ExternalApi(format!("Fetch limiter closed: {}", e)))
+    }
+
+    /// Runs embedding work for `texts` inputs while holding one embed permit per
+    /// input, capped at the whole budget so a large batch can still proceed.
+    pub async fn limit_embed<T, Fut>(&self, texts: usize, work: Fut) -> Result<T, ApiError>
+    where
+        Fut: Future<Output = Result<T, ApiError>>,
+    {
+        let permits = u32::try_from(texts.clamp(1, self.embed_permits)).unwrap_or(u32::MAX);
+        let _permit = self
+            .embed
+            .acquire_many(permits)
+            .await
+            .map_err(|e| ApiError::Embedding(format!("Embed limiter closed: {}", e)))?;
+        work.await
+    }
+
+    /// Runs one database write while holding a DB permit.
+    pub async fn limit_db<T, Fut>(&self, write: Fut) -> Result<T, JobError>
+    where
+        Fut: Future<Output = Result<T, tokio_postgres::Error>>,
+    {
+        let _permit = self
+            .db
+            .acquire()
+            .await
+            .map_err(|e| JobError::Database(format!("Semaphore error: {}", e)))?;
+        write.await.map_err(|e| JobError::Database(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn embed_batch_larger_than_budget_still_runs() {
+        let limits = ResourceLimits::new(4);
+        let texts = limits.embed_permits + 10;
+        let result = limits.limit_embed(texts, async { Ok(texts) }).await;
+        assert_eq!(result.unwrap(), texts);
+        assert_eq!(limits.embed.available_permits(), limits.embed_permits);
+    }
+
+    #[tokio::test]
+    async fn embed_permits_are_held_for_the_whole_batch() {
+        let limits = ResourceLimits::new(4);
+        let budget = limits.embed_permits;
+        limits
+            .limit_embed(budget, async {
+                assert_eq!(limits.embed.available_permits(), 0);
+                Ok(())
+            })
+            .await
+            .unwrap();
+    }
+
+    /// Runs `requests` fetches through `limits`, tracking the most in flight at
+    /// once in `peak`. `in_flight` is shared with any other concurrent caller.
+    async fn run_fetches(limits: &ResourceLimits, requests: usize, in_flight: &AtomicUsize, peak: &AtomicUsize) {
+        let fetches = (0..requests).map(|_| {
+            limits.limit_fetch(async {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(())
+            })
+        });
+        for result in futures::future::join_all(fetches).await {
+            result.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_cap_is_shared_by_concurrent_jobs() {
+        let limits = ResourceLimits::new(4);
+        let cap = limits.fetch_concurrency();
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        // Two jobs, each with more work than the whole budget, run side by side.
+        tokio::join!(
+            run_fetches(&limits, cap * 3, &in_flight, &peak),
+            run_fetches(&limits, cap * 3, &in_flight, &peak),
+        );
+
+        assert_eq!(peak.load(Ordering::SeqCst), cap, "the jobs together never exceed one budget");
+        assert_eq!(limits.fetch.available_permits(), cap);
+    }
+
+    #[tokio::test]
+    async fn db_cap_is_shared_by_concurrent_jobs() {
+        let limits = ResourceLimits::new(4);
+        let cap = limits.db.available_permits();
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        let job = || {
+            futures::future::join_all((0..cap * 2).map(|_| {
+                limits.limit_db(async {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    Ok::<_, tokio_postgres::Error>(())
+                })
+            }))
+        };
+        let (first, second) = tokio::join!(job(), job());
+
+        assert!(first.into_iter().chain(second).all(|result| result.is_ok()));
+        assert_eq!(peak.load(Ordering::SeqCst), cap);
+    }
+
+    #[test]
+    fn db_writes_leave_pool_headroom() {
+        for pool_size in 1..=64 {
+            let permits = ResourceLimits::new(pool_size).db.available_permits();
+            assert!(permits >= 1, "pool of {pool_size} got no write permits");
+            if pool_size > 1 {
+                assert!(permits < pool_size, "{permits} writes would use the whole pool of {pool_size}");
+            }
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use dashmap::DashMap;
+
+static GLOBAL_JOB_METRICS: OnceLock<JobMetrics> = OnceLock::new();
+
+const DURATION_BUCKETS_SECS: [f64; 10] = [1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0];
+
+type CounterAccessor = fn(&JobStats) -> &AtomicU64;
+
+#[derive(Default)]
+struct JobStats {
+    runs: AtomicU64,
+    successes: AtomicU64,
+    failures: AtomicU64,
+    duration_buckets: [AtomicU64; DURATION_BUCKETS_SECS.len()],
+    duration_count: AtomicU64,
+    duration_sum_ms: AtomicU64,
+}
+
+#[derive(Default)]
+pub struct JobMetrics {
+    jobs: DashMap<String, Arc<JobStats>>,
+}
+
+impl JobMetrics {
+    pub fn global() -> &'static JobMetrics {
+        GLOBAL_JOB_METRICS.get_or_init(JobMetrics::default)
+    }
+
+    fn stats(&self, job_name: &str) -> Arc<JobStats> {
+        if let Some(stats) = self.jobs.get(job_name) {
+            return Arc::clone(&stats);
+        }
+        self.jobs
+            .entry(job_name.to_owned())
+            .or_default()
+            .clone()
+    }
+
+    pub fn record_success(&self, job_name: &str, duration: Duration) {
+        let stats = self.stats(job_name);
+        stats.runs.fetch_add(1, Ordering::Relaxed);
+        stats.successes.fetch_add(1, Ordering::Relaxed);
+        Self::observe_duration(&stats, duration);
+    }
+
+    pub fn record_failure(&self, job_name: &str, duration: Duration) {
+        let stats = self.stats(job_name);
+        stats.runs.fetch_add(1, Ordering::Relaxed);
+        stats.failures.fetch_add(1, Ordering::Relaxed);
+        Self::observe_duration(&stats, duration);
+    }
+
+    fn observe_duration(stats: &JobStats, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        for (bucket, upper_bound) in stats.duration_buckets.iter().zip(DURATION_BUCKETS_SECS) {
+            if secs <= upper_bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        stats.duration_count.fetch_add(1, Ordering::Relaxed);
+        stats
+            .duration_sum_ms
+            .fetch_add(duration.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub fn render_prometheus(&self) -> String {
+        let mut jobs: Vec<(String, Arc<JobStats>)> = self
+            .jobs
+            .iter()
+            .map(|entry| (entry.key().clone(), Arc::clone(entry.value())))
+            .collect();
+        jobs.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut out = String::new();
+        let counters: [(&str, &str, CounterAccessor); 3] = [
+            ("oculus_job_runs_total", "Total job executions", |s| &s.runs),
+            ("oculus_job_successes_total", "Total job executions that succeeded", |s| &s.successes),
+            ("oculus_job_failures_total", "Total job executions that failed", |s| &s.failures),
+        ];
+
+        for (name, help, counter) in counters {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} counter");
+            for (job, stats) in &jobs {
+                let _ = writeln!(out, "{name}{{job=\"{job}\"}} {}", counter(stats).load(Ordering::Relaxed));
+            }
+        }
+
+        let _ = writeln!(out, "# HELP oculus_job_duration_seconds Job execution duration");
+        let _ = writeln!(out, "# TYPE oculus_job_duration_seconds histogram");
+        for (job, stats) in &jobs {
+            for (bucket, upper_bound) in stats.duration_buckets.iter().zip(DURATION_BUCKETS_SECS) {
+                let _ = writeln!(
+                    out,
+                    "oculus_job_duration_seconds_bucket{{job=\"{job}\",le=\"{upper_bound}\"}} {}",
+                    bucket.load(Ordering::Relaxed)
+                );
+            }
+            let count = stats.duration_count.load(Ordering::Relaxed);
+            let _ = writeln!(out, "oculus_job_duration_seconds_bucket{{job=\"{job}\",le=\"+Inf\"}} {count}");
+            let _ = writeln!(
+                out,
+                "oculus_job_duration_seconds_sum{{job=\"{job}\"}} {}",
+                stats.duration_sum_ms.load(Ordering::Relaxed) as f64 / 1000.0
+            );
+            let _ = writeln!(out, "oculus_job_duration_seconds_count{{job=\"{job}\"}} {count}");
+        }
+
+        out
+    }
+
+    pub fn flush_to_file(&self) {
+        let Ok(path) = std::env::var("METRICS_FILE") else {
+            return;
+        };
+
+        let tmp_path = format!("{}.tmp", path);
+        let result = std::fs::write(&tmp_path, self.render_prometheus())
+            .and_then(|()| std::fs::rename(&tmp_path, &path));
+        if let Err(e) = result {
+            tracing::warn!("Failed to write metrics to {}: {}", path, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Parses Prometheus text exposition into `name{labels}` -> value, checking
+    /// that every sample belongs to a family declared with HELP and TYPE.
+    fn parse_exposition(body: &str) -> HashMap<String, f64> {
+        let mut families = HashMap::new();
+        let mut samples = HashMap::new();
+        for line in body.lines() {
+            if let Some(rest) = line.strip_prefix("# TYPE ") {
+                let (name, kind) = rest.split_once(' ').expect("TYPE has a metric type");
+                assert!(["counter", "gauge", "histogram"].contains(&kind), "{line}");
+                families.insert(name.to_owned(), kind.to_owned());
+            } else if line.starts_with("# HELP ") {
+                continue;
+            } else {
+                let (series, value) = line.rsplit_once(' ').expect("sample has a value");
+                let name = series.split('{').next().unwrap();
+                let family = ["_bucket", "_sum", "_count"]
+                    .iter()
+                    .find_map(|suffix| name.strip_suffix(suffix).filter(|base| families.contains_key(*base)))
+                    .unwrap_or(name);
+                assert!(families.contains_key(family), "sample before its TYPE: {line}");
+                samples.insert(series.to_owned(), value.parse().expect("numeric sample value"));
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn job_runs_show_up_in_the_rendered_metrics() {
+        let metrics = JobMetrics::default();
+        metrics.record_success("ForgeJob", Duration::from_secs(3));
+        metrics.record_success("ForgeJob", Duration::from_secs(45));
+        metrics.record_failure("ForgeJob", Duration::from_millis(500));
+        metrics.record_success("TraceJob", Duration::from_secs(7200));
+
+        let samples = parse_exposition(&metrics.render_prometheus());
+        let sample = |series: &str| samples.get(series).copied().unwrap_or_else(|| panic!("missing {series}"));
+
+        assert_eq!(sample(r#"oculus_job_runs_total{job="ForgeJob"}"#), 3.0);
+        assert_eq!(sample(r#"oculus_job_successes_total{job="ForgeJob"}"#), 2.0);
+        assert_eq!(sample(r#"oculus_job_failures_total{job="ForgeJob"}"#), 1.0);
+        assert_eq!(sample(r#"oculus_job_duration_seconds_bucket{job="ForgeJob",le="1"}"#), 1.0);
+        assert_eq!(sample(r#"oculus_job_duration_seconds_bucket{job="ForgeJob",le="5"}"#), 2.0);
+        assert_eq!(sample(r#"oculus_job_duration_seconds_bucket{job="ForgeJob",le="60"}"#), 3.0);
+        assert_eq!(sample(r#"oculus_job_duration_seconds_bucket{job="ForgeJob",le="+Inf"}"#), 3.0);
+        assert_eq!(sample(r#"oculus_job_duration_seconds_sum{job="ForgeJob"}"#), 48.5);
+        assert_eq!(sample(r#"oculus_job_duration_seconds_count{job="ForgeJob"}"#), 3.0);
+
+        // Longer than the largest bucket: only +Inf counts it.
+        assert_eq!(sample(r#"oculus_job_duration_seconds_bucket{job="TraceJob",le="3600"}"#), 0.0);
+        assert_eq!(sample(r#"oculus_job_duration_seconds_bucket{job="TraceJob",le="+Inf"}"#), 1.0);
+    }
+
+    #[test]
+    fn no_runs_renders_only_headers() {
+        let samples = parse_exposition(&JobMetrics::default().render_prometheus());
+        assert!(samples.is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use dashmap::DashMap;
 use async_trait::async_trait;
@@ -7,10 +7,19 @@ use tokio::{
     time::{sleep, Duration},
 };
 
-use common::database::DbPool;
+use common::{
+    database::DbPool,
+    services::CacheValidators,
+    services::Embedding,
+    utils::modal::{LeaderboardResponse, RawProject},
+};
 
+pub mod limits;
+pub mod metrics;
 pub mod progress;
 
+use metrics::JobMetrics;
+
 const MAX_JOB_TYPES: usize = 6;
 const MAX_RETRIES: u32 = 3;
 const RETRY_DELAY: Duration = Duration::from_secs(30);
@@ -20,13 +29,80 @@ pub fn get_base_concurrency() -> usize {
 }
 
 pub fn get_embedding_concurrency() -> usize {
-    get_base_concurrency() * 2
+    common::services::embed_concurrency_override()
+        .unwrap_or_else(|| common::services::memory_aware_concurrency(get_base_concurrency() * 2))
 }
 
 pub fn get_fetch_concurrency() -> usize {
     (get_base_concurrency() * 4).min(20)
 }
 
+const DEV_MODE_MAX_PAGES: i32 = 5;
+
+/// Page cap applied to every upstream fetch when `DEV_MODE=true`, overridable
+/// with `DEV_MAX_PAGES`.
+pub fn dev_mode_max_pages() -> Option<i32> {
+    let enabled = std::env::var("DEV_MODE").is_ok_and(|v| v.eq_ignore_ascii_case("true"));
+    enabled.then(|| {
+        std::env::var("DEV_MAX_PAGES")
+            .ok()
+            .and_then(|v| v.parse::<i32>().ok())
+            .filter(|pages| *pages >= 1)
+            .unwrap_or(DEV_MODE_MAX_PAGES)
+    })
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FetchRange {
+    pub start_page: Option<i32>,
+    pub max_page: Option<i32>,
+    pub dev_max_pages: Option<i32>,
+}
+
+impl FetchRange {
+    pub fn from_env() -> Self {
+        let page_env = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse::<i32>().ok())
+                .filter(|page| *page >= 1)
+        };
+
+        Self {
+            start_page: page_env("FETCH_START_PAGE"),
+            max_page: page_env("FETCH_MAX_PAGE"),
+            dev_max_pages: dev_mode_max_pages(),
+        }
+    }
+
+    pub fn start_page(&self, default: i32) -> i32 {
+        self.start_page.unwrap_or(default)
+    }
+
+    pub fn is_empty(&self, start_page: i32) -> bool {
+        self.max_page.is_some_and(|max_page| max_page < start_page)
+    }
+
+    pub fn max_pages(&self, start_page: i32) -> Option<i32> {
+        let range_max = self.max_page.map(|max_page| (max_page - start_page + 1).max(1));
+        match (range_max, self.dev_max_pages) {
+            (Some(range_max), Some(dev_max)) => Some(range_max.min(dev_max)),
+            (range_max, dev_max) => range_max.or(dev_max),
+        }
+    }
+}
+
+pub fn get_db_concurrency(pool_max_size: usize) -> usize {
+    let headroom = (pool_max_size / 4).max(1);
+    let pool_limit = pool_max_size.saturating_sub(headroom).max(1);
+
+    std::env::var("DB_EMBED_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|&v| v > 0)
+        .map_or(pool_limit, |v| v.min(pool_limit))
+}
+
 pub async fn with_retry<T, F, Fut>(operation_name: &str, operation: F) -> Result<T, JobError>
 where
     F: Fn() -> Fut,
@@ -55,16 +131,115 @@ where
 }
 
 
+/// Converts an embedding for storage. With `SKIP_DEGENERATE_EMBEDDINGS=true` the
+/// zero vector produced for empty or too-short text becomes `None` (NULL), which
+/// keeps the row out of semantic search while leaving it reachable via filters.
+pub fn storable_embedding(embedding: Embedding) -> Option<pgvector::Vector> {
+    let skip_degenerate = std::env::var("SKIP_DEGENERATE_EMBEDDINGS")
+        .is_ok_and(|v| v.eq_ignore_ascii_case("true"));
+    storable_embedding_with(embedding, skip_degenerate)
+}
+
+fn storable_embedding_with(embedding: Embedding, skip_degenerate: bool) -> Option<pgvector::Vector> {
+    if skip_degenerate && embedding.is_zero() {
+        return None;
+    }
+    Some(pgvector::Vector::from(embedding))
+}
+
+const COMMENT_CONTEXT_MAX_CHARS: usize = 500;
+
+pub use common::services::project_embedding_text_from_parts;
+
+pub fn project_embedding_text(project: &RawProject) -> String {
+    project_embedding_text_from_parts(
+        &project.title,
+        project.description.as_deref(),
+        project.category.as_deref(),
+    )
+}
+
+pub fn comment_context_enabled() -> bool {
+    std::env::var("EMBED_COMMENT_CONTEXT").is_ok_and(|v| v.eq_ignore_ascii_case("true"))
+}
+
+pub fn comment_embedding_text(comment_text: &str, devlog_text: Option<&str>) -> String {
+    match devlog_text.map(str::trim).filter(|text| !text.is_empty()) {
+        Some(devlog_text) => {
+            let context: String = devlog_text.chars().take(COMMENT_CONTEXT_MAX_CHARS).collect();
+            format!("{}\n\n{}", context, comment_text)
+        }
+        None => comment_text.to_owned(),
+    }
+}
+
 pub fn parse_datetime(datetime_str: &str) -> Result<chrono::DateTime<chrono::Utc>, JobError> {
     chrono::DateTime::parse_from_rfc3339(datetime_str)
         .map_err(|e| JobError::Database(format!("Invalid datetime format: {}", e)))
         .map(|dt| dt.with_timezone(&chrono::Utc))
 }
 
+pub async fn load_cache_validators(pool: &DbPool, key: &str) -> Result<Option<CacheValidators>, JobError> {
+    let client = pool
+        .get()
+        .await
+        .map_err(|e| JobError::Database(e.to_string()))?;
+
+    let row = client
+        .query_opt(
+            "SELECT etag, last_modified FROM sync_metadata WHERE key = $1",
+            &[&key],
+        )
+        .await
+        .map_err(|e| JobError::Database(e.to_string()))?;
+
+    Ok(row.map(|row| CacheValidators {
+        etag: row.get("etag"),
+        last_modified: row.get("last_modified"),
+    }))
+}
+
+pub async fn store_cache_validators(
+    pool: &DbPool,
+    key: &str,
+    validators: &CacheValidators,
+) -> Result<(), JobError> {
+    let client = pool
+        .get()
+        .await
+        .map_err(|e| JobError::Database(e.to_string()))?;
+
+    client.execute(
+        "INSERT INTO sync_metadata (key, last_sync, status, etag, last_modified) VALUES ($1, NOW(), 'completed', $2, $3) ON CONFLICT (key) DO UPDATE SET last_sync = NOW(), status = 'completed', etag = $2, last_modified = $3",
+        &[&key, &validators.etag, &validators.last_modified]
+    ).await
+    .map_err(|e| JobError::Database(e.to_string()))?;
+
+    Ok(())
+}
+
+/// An empty leaderboard is almost always an upstream glitch; fail so the job
+/// retries instead of treating it as "no users". `LEADERBOARD_ALLOW_EMPTY=true`
+/// opts out.
+pub fn ensure_leaderboard_not_empty(leaderboard: &LeaderboardResponse) -> Result<(), JobError> {
+    let allow_empty =
+        std::env::var("LEADERBOARD_ALLOW_EMPTY").is_ok_and(|v| v.eq_ignore_ascii_case("true"));
+    check_leaderboard(leaderboard, allow_empty)
+}
+
+fn check_leaderboard(leaderboard: &LeaderboardResponse, allow_empty: bool) -> Result<(), JobError> {
+    if leaderboard.users.is_empty() && !allow_empty {
+        return Err(JobError::ExternalApi(
+            "Leaderboard returned no users, treating as a transient upstream failure".to_string(),
+        ));
+    }
+    Ok(())
+}
 
 #[async_trait]
 pub trait Job: Send + Sync + 'static {
-    async fn execute(&self, pool: &DbPool) -> Result<(), JobError>;
+    /// Runs the job once and returns how many items it processed.
+    async fn execute(&self, pool: &DbPool) -> Result<usize, JobError>;
     fn name(&self) -> &str;
 }
 
@@ -82,6 +257,18 @@ pub enum JobError {
     Other(String),
 }
 
+impl JobError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            JobError::Database(_) => 2,
+            JobError::ExternalApi(_) => 3,
+            JobError::Embedding(_) => 4,
+            JobError::Io(_) => 5,
+            JobError::Other(_) => 1,
+        }
+    }
+}
+
 pub struct JobScheduler {
     jobs: Vec<Arc<dyn Job>>,
     job_locks: Arc<DashMap<String, Arc<AsyncMutex<()>>>>,
@@ -112,10 +299,61 @@ impl JobScheduler {
         self.jobs.push(job);
     }
 
+    async fn execute_with_metrics(&self, job: &Arc<dyn Job>) -> Result<(), JobError> {
+        let started_at = chrono::Utc::now();
+        let started = Instant::now();
+        let result = job.execute(&self.pool).await;
+
+        // An idle poll is not a run; recording it would bury the real history.
+        if matches!(&result, Err(JobError::Other(msg)) if msg == "no_work") {
+            return result.map(|_| ());
+        }
+
+        let metrics = JobMetrics::global();
+        match &result {
+            Ok(_) => metrics.record_success(job.name(), started.elapsed()),
+            Err(_) => metrics.record_failure(job.name(), started.elapsed()),
+        }
+        metrics.flush_to_file();
+
+        self.record_job_run(job.name(), started_at, &result).await;
+
+        result.map(|_| ())
+    }
+
+    async fn record_job_run(
+        &self,
+        job_name: &str,
+        started_at: chrono::DateTime<chrono::Utc>,
+        result: &Result<usize, JobError>,
+    ) {
+        let (status, error_message, items_processed) = match result {
+            Ok(processed) => ("completed", None, Some(*processed as i64)),
+            Err(e) => ("failed", Some(e.to_string()), None),
+        };
+
+        let result = async {
+            let client = self.pool.get().await.map_err(|e| e.to_string())?;
+            client
+                .execute(
+                    "INSERT INTO job_runs (job_name, started_at, finished_at, status, error_message, items_processed)
+                     VALUES ($1, $2, NOW(), $3, $4, $5)",
+                    &[&job_name, &started_at, &status, &error_message, &items_processed],
+                )
+                .await
+                .map_err(|e| e.to_string())
+        }
+        .await;
+
+        if let Err(e) = result {
+            tracing::warn!("Failed to record run of job {}: {}", job_name, e);
+        }
+    }
+
     pub async fn run_all_sequential(&self) -> Result<(), JobError> {
         for job in &self.jobs {
             tracing::info!("Starting job: {}", job.name());
-            job.execute(&self.pool).await?;
+            self.execute_with_metrics(job).await?;
             tracing::info!("Completed job: {}", job.name());
         }
         Ok(())
@@ -136,7 +374,7 @@ impl JobScheduler {
 
             loop {
                 attempts += 1;
-                match job.execute(&self.pool).await {
+                match self.execute_with_metrics(&job).await {
                     Ok(()) => {
                         tracing::info!("Completed recurring job: {}", job.name());
                         break;
@@ -179,7 +417,7 @@ impl JobScheduler {
             let _guard = job_lock.lock().await;
 
             tracing::info!("Checking for work in continuous job: {}", job.name());
-            match job.execute(&self.pool).await {
+            match self.execute_with_metrics(&job).await {
                 Ok(()) => continue,
                 Err(JobError::Other(ref msg)) if msg == "no_work" => {
                     tracing::debug!(
@@ -197,3 +435,151 @@ impl JobScheduler {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{database::connection::create_pool, utils::config::Config};
+
+    struct FixedJob {
+        name: String,
+        result: fn() -> Result<usize, JobError>,
+    }
+
+    #[async_trait]
+    impl Job for FixedJob {
+        async fn execute(&self, _: &DbPool) -> Result<usize, JobError> {
+            (self.result)()
+        }
+
+        fn name(&self) -> &str {
+            &self.name
+        }
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn records_processed_items_and_skips_idle_polls() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let config = Config {
+            database_url,
+            max_db_connections: 2,
+            ..Config::default()
+        };
+        let pool = Arc::new(create_pool(&config).await.unwrap());
+        let client = pool.get().await.unwrap();
+        client
+            .batch_execute(include_str!("../../../migrations/002_job_runs.up.sql"))
+            .await
+            .unwrap();
+
+        let prefix = format!("scheduler-test-{}", std::process::id());
+        let busy: Arc<dyn Job> = Arc::new(FixedJob {
+            name: format!("{prefix}-busy"),
+            result: || Ok(3),
+        });
+        let idle: Arc<dyn Job> = Arc::new(FixedJob {
+            name: format!("{prefix}-idle"),
+            result: || Err(JobError::Other("no_work".to_string())),
+        });
+
+        let scheduler = JobScheduler::new(Arc::clone(&pool));
+        scheduler.execute_with_metrics(&busy).await.unwrap();
+        assert!(scheduler.execute_with_metrics(&idle).await.is_err());
+
+        let rows = client
+            .query(
+                "SELECT job_name, status, items_processed FROM job_runs WHERE job_name LIKE $1 || '%'",
+                &[&prefix],
+            )
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get::<_, String>("job_name"), busy.name());
+        assert_eq!(rows[0].get::<_, String>("status"), "completed");
+        assert_eq!(rows[0].get::<_, Option<i64>>("items_processed"), Some(3));
+
+        let rendered = JobMetrics::global().render_prometheus();
+        assert!(rendered.contains(&format!("oculus_job_runs_total{{job=\"{}\"}} 1\n", busy.name())));
+        assert!(!rendered.contains(idle.name()), "idle polls are not recorded as runs");
+
+        client
+            .execute("DELETE FROM job_runs WHERE job_name LIKE $1 || '%'", &[&prefix])
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn comment_text_leads_with_devlog_context() {
+        assert_eq!(
+            comment_embedding_text("love the soldering", Some("  Built the sensor board  ")),
+            "Built the sensor board\n\nlove the soldering"
+        );
+        assert_eq!(comment_embedding_text("no context", Some("   ")), "no context");
+        assert_eq!(comment_embedding_text("no context", None), "no context");
+
+        let long_devlog = "é".repeat(COMMENT_CONTEXT_MAX_CHARS + 20);
+        let text = comment_embedding_text("hi", Some(&long_devlog));
+        assert_eq!(text.chars().count(), COMMENT_CONTEXT_MAX_CHARS + "\n\nhi".len());
+    }
+
+    #[test]
+    fn degenerate_embeddings_are_stored_as_null_when_skipped() {
+        // `embed_text` returns zeros for a project title shorter than 8 tokens.
+        let too_short = Embedding::zeros();
+        assert_eq!(storable_embedding_with(too_short.clone(), true), None);
+        assert_eq!(
+            storable_embedding_with(too_short, false).map(|v| v.to_vec()),
+            Some(vec![0.0; common::services::embedding::EMBEDDING_DIM])
+        );
+
+        let mut values = vec![0.0; common::services::embedding::EMBEDDING_DIM];
+        values[0] = 1.0;
+        let real = Embedding::try_from_vec(values.clone()).unwrap();
+        assert_eq!(storable_embedding_with(real, true).map(|v| v.to_vec()), Some(values));
+    }
+
+    #[test]
+    fn project_text_matches_the_shared_builder() {
+        let project = RawProject {
+            title: "Weather station".to_owned(),
+            description: Some("  Logs sensor readings  ".to_owned()),
+            category: Some("hardware".to_owned()),
+            ..RawProject::default()
+        };
+
+        assert_eq!(
+            project_embedding_text(&project),
+            project_embedding_text_from_parts("Weather station", Some("  Logs sensor readings  "), Some("hardware"))
+        );
+        assert_eq!(
+            project_embedding_text(&RawProject { title: "Bare".to_owned(), ..RawProject::default() }),
+            project_embedding_text_from_parts("Bare", None, None)
+        );
+    }
+
+    #[test]
+    fn job_errors_map_to_distinct_exit_codes() {
+        let codes = [
+            JobError::Other("boom".to_string()).exit_code(),
+            JobError::Database("down".to_string()).exit_code(),
+            JobError::ExternalApi("502".to_string()).exit_code(),
+            JobError::Embedding("model".to_string()).exit_code(),
+            JobError::Io(std::io::Error::other("disk")).exit_code(),
+        ];
+
+        assert_eq!(codes, [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn empty_leaderboard_fails_unless_allowed() {
+        let empty = LeaderboardResponse { users: Vec::new() };
+
+        assert!(matches!(check_leaderboard(&empty, false), Err(JobError::ExternalApi(_))));
+        assert!(check_leaderboard(&empty, true).is_ok());
+    }
+}
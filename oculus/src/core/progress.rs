@@ -1,4 +1,5 @@
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::io::IsTerminal;
 use std::sync::{Arc, OnceLock, RwLock};
 use std::collections::HashMap;
 
@@ -7,17 +8,40 @@ static GLOBAL_PROGRESS_TREE: OnceLock<Arc<ProgressTree>> = OnceLock::new();
 const DEFAULT_TEMPLATE: &str = "[{job_name}] [{bar:40.green/blue}] {pos}/{len} ({percent}%) {msg} ETA: {eta}";
 const PROGRESS_CHARS: &str = "#>-";
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressFormat {
+    Bar,
+    Json,
+}
+
+impl ProgressFormat {
+    fn from_env() -> Self {
+        match std::env::var("PROGRESS_FORMAT").as_deref() {
+            Ok("json") => Self::Json,
+            Ok("bar") => Self::Bar,
+            _ if std::io::stderr().is_terminal() => Self::Bar,
+            _ => Self::Json,
+        }
+    }
+}
+
 pub struct ProgressTree {
     multi: MultiProgress,
+    format: ProgressFormat,
     job_bars: RwLock<HashMap<String, Arc<JobProgressBar>>>,
 }
 
 impl ProgressTree {
     fn new() -> Self {
-        let multi = MultiProgress::new();
+        let format = ProgressFormat::from_env();
+        let multi = match format {
+            ProgressFormat::Bar => MultiProgress::new(),
+            ProgressFormat::Json => MultiProgress::with_draw_target(ProgressDrawTarget::hidden()),
+        };
         
         Self {
             multi,
+            format,
             job_bars: RwLock::new(HashMap::new()),
         }
     }
@@ -46,7 +70,8 @@ impl ProgressTree {
         let managed_bar = self.multi.add(progress_bar);
         let job_bar = Arc::new(JobProgressBar { 
             bar: managed_bar, 
-            job_name: job_name.to_string() 
+            job_name: job_name.to_string(),
+            format: self.format,
         });
         
         bars.insert(job_name.to_string(), Arc::clone(&job_bar));
@@ -58,7 +83,7 @@ impl ProgressTree {
         bar.set_message(description.to_string());
         
         let managed_bar = self.multi.add(bar);
-        JobProgressBar { bar: managed_bar, job_name: job_name.to_string() }
+        JobProgressBar { bar: managed_bar, job_name: job_name.to_string(), format: self.format }
     }
     
     pub fn add_embedding_progress(&self, job_name: &str, item_type: &str) -> EmbeddingProgressBar {
@@ -89,6 +114,7 @@ impl ProgressTree {
 pub struct JobProgressBar {
     bar: ProgressBar,
     job_name: String,
+    format: ProgressFormat,
 }
 
 impl JobProgressBar {
@@ -109,14 +135,40 @@ impl JobProgressBar {
         self.bar.set_length(total as u64);
         self.bar.set_position(current as u64);
         self.bar.set_message(message.to_string());
+
+        if self.format == ProgressFormat::Json {
+            tracing::info!(target: "oculus::progress", "{}", progress_json(&self.job_name, current, total, message));
+        }
     }
     
     pub fn done(&self, message: String) {
         self.bar.set_message(format!("{} ✓", message));
-        tracing::info!("[{}] {}", self.job_name, message);
+        match self.format {
+            ProgressFormat::Bar => tracing::info!("[{}] {}", self.job_name, message),
+            ProgressFormat::Json => {
+                let total = self.bar.length().unwrap_or_default() as usize;
+                tracing::info!(target: "oculus::progress", "{}", progress_json(&self.job_name, total, total, &message));
+            }
+        }
     }
 }
 
+fn progress_json(job_name: &str, current: usize, total: usize, message: &str) -> serde_json::Value {
+    let percent = if total == 0 {
+        100.0
+    } else {
+        (current as f64 / total as f64 * 100.0).min(100.0)
+    };
+
+    serde_json::json!({
+        "job": job_name,
+        "current": current,
+        "total": total,
+        "percent": (percent * 10.0).round() / 10.0,
+        "message": message,
+    })
+}
+
 #[derive(Clone)]
 pub struct EmbeddingProgressBar {
     bar: ProgressBar,
@@ -175,4 +227,61 @@ impl ProgressReporter {
     pub fn finish(&self) {
         self.bar.done("Completed".to_string());
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn json_format_logs_one_object_per_update() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .without_time()
+            .with_level(false)
+            .with_target(false)
+            .with_ansi(false)
+            .finish();
+        let bar = JobProgressBar {
+            bar: ProgressBar::hidden(),
+            job_name: "forge".to_string(),
+            format: ProgressFormat::Json,
+        };
+
+        tracing::subscriber::with_default(subscriber, || {
+            bar.update_progress(1, 4, "Fetching projects");
+            bar.update_progress(3, 4, "Fetching projects");
+            bar.done("Found 12 new projects".to_string());
+        });
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(&line[line.find('{').unwrap()..]).unwrap())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                serde_json::json!({"job": "forge", "current": 1, "total": 4, "percent": 25.0, "message": "Fetching projects"}),
+                serde_json::json!({"job": "forge", "current": 3, "total": 4, "percent": 75.0, "message": "Fetching projects"}),
+                serde_json::json!({"job": "forge", "current": 4, "total": 4, "percent": 100.0, "message": "Found 12 new projects"}),
+            ]
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::{BTreeMap, HashSet};
+
+use common::{database::DbPool, services::EmbeddingService};
+
+use crate::core::JobError;
+
+/// Results past this rank are treated as a miss, matching the default search limit.
+const EVAL_CUTOFF: i64 = 20;
+
+const RESULT_TYPES: [(&str, &str); 3] = [
+    ("project", "SELECT id FROM projects WHERE title_description_embedding IS NOT NULL ORDER BY title_description_embedding <=> $1, id DESC LIMIT $2"),
+    ("devlog", "SELECT id FROM logs WHERE text_embedding IS NOT NULL ORDER BY text_embedding <=> $1, id DESC LIMIT $2"),
+    ("comment", "SELECT id FROM comments WHERE text_embedding IS NOT NULL ORDER BY text_embedding <=> $1, id DESC LIMIT $2"),
+];
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EvalScore {
+    pub queries: usize,
+    pub mrr: f64,
+    pub ndcg: f64,
+}
+
+impl EvalScore {
+    fn add(&mut self, reciprocal_rank: f64, ndcg: f64) {
+        self.queries += 1;
+        self.mrr += reciprocal_rank;
+        self.ndcg += ndcg;
+    }
+
+    fn finish(mut self) -> Self {
+        if self.queries > 0 {
+            self.mrr /= self.queries as f64;
+            self.ndcg /= self.queries as f64;
+        }
+        self
+    }
+}
+
+/// Reciprocal of the 1-based rank of the first relevant result, or 0 if none was returned.
+pub fn reciprocal_rank(ranked: &[i64], relevant: &HashSet<i64>) -> f64 {
+    ranked
+        .iter()
+        .position(|id| relevant.contains(id))
+        .map_or(0.0, |idx| 1.0 / (idx + 1) as f64)
+}
+
+/// Binary-relevance NDCG over the first `k` results.
+pub fn ndcg(ranked: &[i64], relevant: &HashSet<i64>, k: usize) -> f64 {
+    let discount = |idx: usize| 1.0 / ((idx + 2) as f64).log2();
+
+    let dcg: f64 = ranked
+        .iter()
+        .take(k)
+        .enumerate()
+        .filter(|(_, id)| relevant.contains(id))
+        .map(|(idx, _)| discount(idx))
+        .sum();
+    let ideal: f64 = (0..relevant.len().min(k)).map(discount).sum();
+
+    if ideal == 0.0 { 0.0 } else { dcg / ideal }
+}
+
+/// Re-runs every logged query with at least one click through the current vector
+/// search and scores the new ranking against the clicked results.
+pub async fn evaluate_search(
+    pool: &DbPool,
+    embedding_service: &EmbeddingService,
+) -> Result<BTreeMap<&'static str, EvalScore>, JobError> {
+    let client = pool
+        .get()
+        .await
+        .map_err(|e| JobError::Database(e.to_string()))?;
+
+    let mut report = BTreeMap::new();
+    for (result_type, search_sql) in RESULT_TYPES {
+        let rows = client
+            .query(
+                "SELECT query, array_agg(DISTINCT result_id) AS clicked_ids 
+                 FROM search_feedback 
+                 WHERE result_type = $1 AND clicked 
+                 GROUP BY query",
+                &[&result_type],
+            )
+            .await
+            .map_err(|e| JobError::Database(e.to_string()))?;
+
+        let mut score = EvalScore::default();
+        for row in rows {
+            let query: String = row.get("query");
+            let relevant: HashSet<i64> = row.get::<_, Vec<i64>>("clicked_ids").into_iter().collect();
+
+            let embedding = embedding_service
+                .embed_text(&query)
+                .await
+                .map_err(|e| JobError::Embedding(e.to_string()))?;
+            let embedding = pgvector::Vector::from(embedding);
+
+            let ranked: Vec<i64> = client
+                .query(search_sql, &[&embedding, &EVAL_CUTOFF])
+                .await
+                .map_err(|e| JobError::Database(e.to_string()))?
+                .iter()
+                .map(|row| row.get("id"))
+                .collect();
+
+            score.add(
+                reciprocal_rank(&ranked, &relevant),
+                ndcg(&ranked, &relevant, EVAL_CUTOFF as usize),
+            );
+        }
+
+        report.insert(result_type, score.finish());
+    }
+
+    Ok(report)
+}
+
+pub fn print_report(report: &BTreeMap<&'static str, EvalScore>) {
+    println!("Search evaluation (cutoff {}):", EVAL_CUTOFF);
+    println!("  {:<8} {:>8} {:>8} {:>8}", "type", "queries", "MRR", "NDCG");
+    for (result_type, score) in report {
+        println!(
+            "  {:<8} {:>8} {:>8.4} {:>8.4}",
+            result_type, score.queries, score.mrr, score.ndcg
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[i64]) -> HashSet<i64> {
+        ids.iter().copied().collect()
+    }
+
+    #[test]
+    fn scores_a_small_fixture_by_hand() {
+        let fixture = [
+            (vec![3, 1, 2], ids(&[1])),
+            (vec![5, 6], ids(&[5, 6])),
+            (vec![7, 8], ids(&[9])),
+        ];
+
+        let mut score = EvalScore::default();
+        for (ranked, relevant) in &fixture {
+            score.add(reciprocal_rank(ranked, relevant), ndcg(ranked, relevant, 20));
+        }
+        let score = score.finish();
+
+        let second_place = 1.0 / 3f64.log2();
+        assert_eq!(score.queries, 3);
+        assert!((score.mrr - 0.5).abs() < 1e-12);
+        assert!((score.ndcg - (second_place + 1.0) / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn ndcg_ignores_hits_past_the_cutoff() {
+        assert_eq!(ndcg(&[4, 5, 1], &ids(&[1]), 2), 0.0);
+        assert_eq!(ndcg(&[4, 5], &HashSet::new(), 2), 0.0);
+        assert_eq!(reciprocal_rank(&[], &ids(&[1])), 0.0);
+        assert_eq!(EvalScore::default().finish().mrr, 0.0);
+    }
+}
@@ -1,13 +1,16 @@
-use crate::core::{get_fetch_concurrency, progress::create_progress_with_job, JobError};
+use crate::core::{limits::ResourceLimits, progress::create_progress_with_job, FetchRange, JobError};
 use common::{
     database::connection,
     services::external::ExternalApiService,
-    utils::modal::{RawComment, RawDevlog, RawProject},
+    utils::{
+        error::ApiError,
+        modal::{RawComment, RawDevlog, RawProject},
+        pagination::{extend_unique, paginate, PageItem, PaginateOptions, Paginated},
+    },
 };
-use futures::stream::{FuturesUnordered, StreamExt};
+use futures::StreamExt;
 use std::collections::HashSet;
-use std::sync::Arc;
-use tokio::sync::Semaphore;
+use std::pin::pin;
 
 pub struct DataFetcher;
 
@@ -19,6 +22,14 @@ pub enum DataType {
 }
 
 impl DataType {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Projects => "projects",
+            Self::Comments => "comments",
+            Self::Devlogs => "devlogs",
+        }
+    }
+
     fn capacity_hint(&self) -> usize {
         match self {
             Self::Projects => 100,
@@ -36,77 +47,89 @@ impl DataType {
     }
 }
 
-async fn fetch_with_concurrency<T, F, Fut>(
+async fn fetch_new_pages<R, F, Fut>(
     data_type: DataType,
     start_page: i32,
-    total_pages: i32,
-    fetcher: F,
-    _existing_filter: Option<Arc<HashSet<i64>>>,
-) -> Result<(Vec<T>, i32), JobError>
+    range: FetchRange,
+    fetch_page: F,
+) -> Result<(Vec<R::Item>, i32), JobError>
 where
-    T: Send + 'static,
-    F: Fn(i32) -> Fut + Send + Sync + Clone,
-    Fut: std::future::Future<Output = Result<Vec<T>, JobError>> + Send,
+    R: Paginated,
+    R::Item: PageItem,
+    F: Fn(i32) -> Fut + Clone,
+    Fut: std::future::Future<Output = Result<R, ApiError>>,
 {
-    let concurrency_limit = std::env::var("FETCH_CONCURRENCY")
-        .ok()
-        .and_then(|v| v.parse::<usize>().ok())
-        .unwrap_or_else(get_fetch_concurrency);
+    let limits = ResourceLimits::global();
 
-    let semaphore = Arc::new(Semaphore::new(concurrency_limit));
-    let mut futures = FuturesUnordered::new();
-    let progress = create_progress_with_job("forge", data_type.progress_name());
-    progress.init(Some((total_pages - start_page + 1) as usize), Some("pages"));
-
-    for page in start_page..=total_pages {
-        let semaphore = semaphore.clone();
-        let fetcher = fetcher.clone();
-
-        let future = async move {
-            let _permit = semaphore
-                .acquire()
-                .await
-                .map_err(|e| JobError::ExternalApi(format!("Semaphore error: {}", e)))?;
+    if range.is_empty(start_page) {
+        tracing::warn!(
+            "FETCH_MAX_PAGE is before start page {}, skipping {} fetch",
+            start_page,
+            data_type.name()
+        );
+        return Ok((Vec::new(), start_page - 1));
+    }
 
-            let items = fetcher(page).await?;
-            
-            Result::<(i32, Vec<T>), JobError>::Ok((page, items))
-        };
+    let mut pages = pin!(paginate(
+        move |page| limits.limit_fetch(fetch_page(page)),
+        PaginateOptions {
+            start_page,
+            max_pages: range.max_pages(start_page),
+            concurrency: limits.fetch_concurrency(),
+        },
+    ));
+
+    let Some(first_page) = pages.next().await else {
+        return Ok((Vec::new(), start_page - 1));
+    };
+    let first_page = first_page.map_err(|e| JobError::ExternalApi(e.to_string()))?;
+    if first_page.items.is_empty() {
+        return Ok((Vec::new(), start_page - 1));
+    }
 
-        futures.push(future);
+    if first_page.total_pages.is_none() {
+        tracing::info!(
+            "{} response has no page count, fetching sequentially until an empty page",
+            data_type.name()
+        );
     }
 
+    let remaining_pages = first_page
+        .total_pages
+        .map(|total_pages| (total_pages - start_page).max(0) as usize);
+    let progress = create_progress_with_job("forge", data_type.progress_name());
+    progress.init(remaining_pages, Some("pages"));
+
     let mut all_items = Vec::with_capacity(data_type.capacity_hint());
+    let mut seen = HashSet::with_capacity(data_type.capacity_hint());
+    let mut duplicates = extend_unique(&mut all_items, &mut seen, first_page.items);
     let mut current_page = start_page;
     let mut pages_processed = 0;
 
-    while let Some(result) = futures.next().await {
+    while let Some(result) = pages.next().await {
         match result {
-            Ok((page, items)) => {
-                all_items.extend(items);
+            Ok(page) => {
+                duplicates += extend_unique(&mut all_items, &mut seen, page.items);
                 pages_processed += 1;
                 progress.set(pages_processed);
-                current_page = current_page.max(page);
+                current_page = current_page.max(page.number);
             }
             Err(e) => {
-                tracing::warn!("Failed to fetch {} page: {}", 
-                    match data_type {
-                        DataType::Projects => "projects",
-                        DataType::Comments => "comments", 
-                        DataType::Devlogs => "devlogs",
-                    }, e);
-                continue;
+                tracing::warn!("Failed to fetch {} page: {}", data_type.name(), e);
             }
         }
     }
 
-    progress.done(format!("Found {} new {}", all_items.len(), 
-        match data_type {
-            DataType::Projects => "projects",
-            DataType::Comments => "comments",
-            DataType::Devlogs => "devlogs", 
-        }));
-    
+    if duplicates > 0 {
+        tracing::info!(
+            "Skipped {} duplicate {} repeated across shifted pages",
+            duplicates,
+            data_type.name()
+        );
+    }
+
+    progress.done(format!("Found {} new {}", all_items.len(), data_type.name()));
+
     Ok((all_items, current_page))
 }
 
@@ -115,7 +138,8 @@ impl DataFetcher {
         external_api: &ExternalApiService,
         pool: &connection::DbPool,
     ) -> Result<(Vec<RawProject>, i32), JobError> {
-        let start_page = super::sync::DataSyncer::calculate_start_page(pool).await?;
+        let range = FetchRange::from_env();
+        let start_page = range.start_page(super::sync::DataSyncer::calculate_start_page(pool).await?);
 
         tracing::info!(
             "Starting project fetch from page {} (calculated from existing project count)",
@@ -142,154 +166,210 @@ impl DataFetcher {
             existing_ids.len()
         );
 
-        let first_response = external_api
-            .fetch_projects(Some(start_page))
-            .await
-            .map_err(|e| JobError::ExternalApi(e.to_string()))?;
-
-        if first_response.projects.is_empty() {
-            return Ok((Vec::new(), start_page - 1));
-        }
+        let external_api = external_api.clone();
+        let (projects, last_page) = fetch_new_pages(DataType::Projects, start_page, range, move |page| {
+            let external_api = external_api.clone();
+            async move {
+                if page == start_page {
+                    external_api.fetch_projects(Some(page)).await.map(Some)
+                } else {
+                    external_api.fetch_projects_if_modified(page).await
+                }
+            }
+        })
+        .await?;
 
-        let total_pages = first_response.pagination.and_then(|p| p.pages).unwrap_or(start_page);
-        
-        let mut new_projects: Vec<RawProject> = first_response.projects
+        let new_projects = projects
             .into_iter()
             .filter(|project| !existing_ids.contains(&project.id))
             .collect();
 
-        if start_page >= total_pages {
-            return Ok((new_projects, start_page));
-        }
-
-        let existing_ids = Arc::new(existing_ids);
-        let existing_ids_clone = existing_ids.clone();
-        let external_api_clone = external_api.clone();
-        let (additional_projects, last_page) = fetch_with_concurrency(
-            DataType::Projects,
-            start_page + 1,
-            total_pages,
-            move |page| {
-                let existing_ids = existing_ids_clone.clone();
-                let external_api = external_api_clone.clone();
-                async move {
-                    let response = external_api
-                        .fetch_projects(Some(page))
-                        .await
-                        .map_err(|e| JobError::ExternalApi(e.to_string()))?;
-
-                    let filtered_projects: Vec<RawProject> = response.projects
-                        .into_iter()
-                        .filter(|project| !existing_ids.contains(&project.id))
-                        .collect();
-
-                    Ok(filtered_projects)
-                }
-            },
-            Some(existing_ids),
-        ).await?;
-
-        new_projects.extend(additional_projects);
-        Ok((new_projects, last_page.max(start_page)))
+        Ok((new_projects, last_page))
     }
 
     pub async fn fetch_new_comments(
         external_api: &ExternalApiService,
         last_page: Option<i32>,
     ) -> Result<(Vec<RawComment>, i32), JobError> {
-        let start_page = last_page.map(|p| p + 1).unwrap_or(1);
+        let range = FetchRange::from_env();
+        let start_page = range.start_page(last_page.map(|p| p + 1).unwrap_or(1));
 
         tracing::info!("Starting comment fetch from page {}", start_page);
 
-        let first_response = external_api
-            .fetch_comments(Some(start_page))
-            .await
-            .map_err(|e| JobError::ExternalApi(e.to_string()))?;
-
-        if first_response.comments.is_empty() {
-            return Ok((Vec::new(), start_page - 1));
-        }
-
-        let total_pages = first_response.pagination
-            .and_then(|p| p.pages)
-            .unwrap_or(start_page);
-
-        let mut all_comments = first_response.comments;
-
-        if start_page >= total_pages {
-            return Ok((all_comments, start_page));
-        }
-
-        let external_api_clone = external_api.clone();
-        let (additional_comments, last_page) = fetch_with_concurrency(
-            DataType::Comments,
-            start_page + 1,
-            total_pages,
-            move |page| {
-                let external_api = external_api_clone.clone();
-                async move {
-                    let response = external_api
-                        .fetch_comments(Some(page))
-                        .await
-                        .map_err(|e| JobError::ExternalApi(e.to_string()))?;
-
-                    Ok(response.comments)
+        let external_api = external_api.clone();
+        fetch_new_pages(DataType::Comments, start_page, range, move |page| {
+            let external_api = external_api.clone();
+            async move {
+                if page == start_page {
+                    external_api.fetch_comments(Some(page)).await.map(Some)
+                } else {
+                    external_api.fetch_comments_if_modified(page).await
                 }
-            },
-            None,
-        ).await?;
-
-        all_comments.extend(additional_comments);
-        Ok((all_comments, last_page.max(start_page)))
+            }
+        })
+        .await
     }
 
     pub async fn fetch_new_devlogs(
         external_api: &ExternalApiService,
         last_page: Option<i32>,
     ) -> Result<(Vec<RawDevlog>, i32), JobError> {
-        let start_page = last_page.map(|p| p + 1).unwrap_or(1);
+        let range = FetchRange::from_env();
+        let start_page = range.start_page(last_page.map(|p| p + 1).unwrap_or(1));
 
         tracing::info!("Starting devlog fetch from page {}", start_page);
 
-        let first_response = external_api
-            .fetch_devlogs(Some(start_page))
-            .await
-            .map_err(|e| JobError::ExternalApi(e.to_string()))?;
+        let external_api = external_api.clone();
+        fetch_new_pages(DataType::Devlogs, start_page, range, move |page| {
+            let external_api = external_api.clone();
+            async move {
+                if page == start_page {
+                    external_api.fetch_devlogs(Some(page)).await.map(Some)
+                } else {
+                    external_api.fetch_devlogs_if_modified(page).await
+                }
+            }
+        })
+        .await
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::utils::modal::{CommentsResponse, PaginationInfo, ProjectsResponse};
+    use parking_lot::Mutex;
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    const TOTAL_PAGES: i32 = 10;
+
+    /// Fetches pages of a 10-page project feed, recording every page requested.
+    async fn fetch_recorded(start_page: i32, range: FetchRange) -> (Vec<i64>, i32, Vec<i32>) {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&requested);
+        let (projects, last_page) = fetch_new_pages(DataType::Projects, start_page, range, move |page| {
+            recorder.lock().push(page);
+            async move {
+                Ok::<_, ApiError>(ProjectsResponse {
+                    projects: vec![RawProject {
+                        id: i64::from(page),
+                        ..RawProject::default()
+                    }],
+                    pagination: Some(PaginationInfo {
+                        pages: Some(TOTAL_PAGES),
+                        count: None,
+                        page: Some(page),
+                        items: None,
+                    }),
+                })
+            }
+        })
+        .await
+        .unwrap();
+
+        let mut ids: Vec<i64> = projects.iter().map(|project| project.id).collect();
+        ids.sort_unstable();
+        let mut requested = requested.lock().clone();
+        requested.sort_unstable();
+        (ids, last_page, requested)
+    }
 
-        if first_response.devlogs.is_empty() {
-            return Ok((Vec::new(), start_page - 1));
-        }
+    #[tokio::test]
+    async fn only_the_requested_range_is_fetched() {
+        let range = FetchRange {
+            start_page: Some(3),
+            max_page: Some(5),
+            dev_max_pages: None,
+        };
 
-        let total_pages = first_response.pagination
-            .and_then(|p| p.pages)
-            .unwrap_or(start_page);
+        let (ids, last_page, requested) = fetch_recorded(range.start_page(1), range).await;
 
-        let mut all_devlogs = first_response.devlogs;
+        assert_eq!(requested, [3, 4, 5]);
+        assert_eq!(ids, [3, 4, 5]);
+        assert_eq!(last_page, 5);
+    }
 
-        if start_page >= total_pages {
-            return Ok((all_devlogs, start_page));
-        }
+    #[tokio::test]
+    async fn a_range_ending_before_the_start_fetches_nothing() {
+        let range = FetchRange {
+            start_page: Some(6),
+            max_page: Some(4),
+            dev_max_pages: None,
+        };
 
-        let external_api_clone = external_api.clone();
-        let (additional_devlogs, last_page) = fetch_with_concurrency(
-            DataType::Devlogs,
-            start_page + 1,
-            total_pages,
-            move |page| {
-                let external_api = external_api_clone.clone();
-                async move {
-                    let response = external_api
-                        .fetch_devlogs(Some(page))
-                        .await
-                        .map_err(|e| JobError::ExternalApi(e.to_string()))?;
-
-                    Ok(response.devlogs)
-                }
-            },
-            None,
-        ).await?;
+        let (ids, last_page, requested) = fetch_recorded(range.start_page(1), range).await;
+
+        assert!(requested.is_empty());
+        assert!(ids.is_empty());
+        assert_eq!(last_page, 5);
+    }
 
-        all_devlogs.extend(additional_devlogs);
-        Ok((all_devlogs, last_page.max(start_page)))
+    #[tokio::test]
+    async fn dev_mode_caps_the_pages_requested() {
+        // No other test reads these variables.
+        std::env::set_var("DEV_MODE", "true");
+        std::env::set_var("DEV_MAX_PAGES", "2");
+        let dev_only = FetchRange::from_env();
+        std::env::remove_var("DEV_MODE");
+        std::env::remove_var("DEV_MAX_PAGES");
+        assert_eq!(dev_only.dev_max_pages, Some(2));
+
+        let (ids, last_page, requested) = fetch_recorded(1, dev_only).await;
+        assert_eq!(requested, [1, 2]);
+        assert_eq!(ids, [1, 2]);
+        assert_eq!(last_page, 2);
+
+        let dev_within_range = FetchRange {
+            start_page: Some(4),
+            max_page: Some(9),
+            dev_max_pages: Some(3),
+        };
+        let (_, _, requested) = fetch_recorded(4, dev_within_range).await;
+        assert_eq!(requested, [4, 5, 6]);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn comments_shifted_by_an_upstream_insert_are_kept_once() {
+        const PAGE_SIZE: usize = 3;
+        let comment = |n: i64| RawComment {
+            text: format!("comment {n}"),
+            devlog_id: n,
+            slack_id: "U123".into(),
+            ..RawComment::default()
+        };
+        // Newest first, like the upstream feed. Once page 1 has been served a
+        // new comment lands at the head and shifts every later page by one.
+        let original: Vec<RawComment> = (0..9).map(comment).collect();
+        let shifted: Vec<RawComment> = std::iter::once(comment(100)).chain(original.iter().cloned()).collect();
+        let served_first = Arc::new(AtomicBool::new(false));
+
+        let range = FetchRange {
+            start_page: None,
+            max_page: None,
+            dev_max_pages: None,
+        };
+        let (comments, _) = fetch_new_pages(DataType::Comments, 1, range, move |page| {
+            let feed = if served_first.swap(true, Ordering::SeqCst) { &shifted } else { &original };
+            let comments = feed
+                .iter()
+                .skip((page as usize - 1) * PAGE_SIZE)
+                .take(PAGE_SIZE)
+                .cloned()
+                .collect();
+            async move {
+                Ok::<_, ApiError>(CommentsResponse {
+                    comments,
+                    pagination: None,
+                })
+            }
+        })
+        .await
+        .unwrap();
+
+        let mut devlog_ids: Vec<i64> = comments.iter().map(|comment| comment.devlog_id).collect();
+        devlog_ids.sort_unstable();
+        assert_eq!(devlog_ids, (0..9).collect::<Vec<_>>());
+    }
+}
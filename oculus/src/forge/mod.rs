@@ -2,205 +2,197 @@ mod sync;
 mod fetch;
 mod store;
 
+use std::future::Future;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use tokio::sync::Semaphore;
 use futures::stream::{FuturesUnordered, StreamExt};
 
 use common::{
     database::DbPool,
     utils::config::Config,
     services::{EmbeddingService, external::ExternalApiService},
+    utils::modal::{RawComment, RawDevlog, RawProject},
+    utils::types::SyncKey,
 };
 
-use crate::core::{Job, JobError, get_embedding_concurrency, progress::get_job_progress, progress::create_embedding_progress};
+use crate::core::{
+    Job, JobError, limits::ResourceLimits, progress::create_embedding_progress, progress::get_job_progress,
+};
 
 use fetch::DataFetcher;
 use store::DataStore;
 use sync::DataSyncer;
 
-pub struct ForgeJob {
-    config: Config,
-    embedding_service: Arc<EmbeddingService>,
-}
+const MAX_STORE_FAILURE_RATIO: f64 = 0.1;
+const MAX_LOGGED_FAILURES: usize = 10;
 
-impl ForgeJob {
-    pub fn new(config: Config, embedding_service: Arc<EmbeddingService>) -> Self {
-        Self {
-            config,
-            embedding_service,
-        }
+fn report_store_failures(
+    item_type: &str,
+    total: usize,
+    failures: &[(String, String)],
+) -> Result<(), JobError> {
+    if failures.is_empty() {
+        return Ok(());
     }
 
-    async fn store_projects_with_parallel_embeddings(
-        &self,
-        projects: Vec<common::utils::modal::RawProject>,
-        pool: &DbPool,
-    ) -> Result<(), JobError> {
-        if projects.is_empty() {
-            return Ok(());
-        }
-
-        let embedding_progress = create_embedding_progress("forge", "projects");
-        embedding_progress.init(projects.len());
-
-        let concurrency = get_embedding_concurrency();
-        let semaphore = Arc::new(Semaphore::new(concurrency));
-        let embedding_service = Arc::clone(&self.embedding_service);
-        let pool = Arc::new(pool.clone());
-
-        let mut futures = FuturesUnordered::new();
-
-        for project in projects {
-            let semaphore = Arc::clone(&semaphore);
-            let embedding_service = Arc::clone(&embedding_service);
-            let pool = Arc::clone(&pool);
-            let embedding_progress = embedding_progress.clone();
-
-            let future = async move {
-                let _permit = semaphore
-                    .acquire()
-                    .await
-                    .map_err(|e| JobError::Embedding(format!("Semaphore error: {}", e)))?;
-
-                let result = DataStore::store_project_with_embedding(&project, &embedding_service, &pool).await;
-                embedding_progress.increment();
-                result
-            };
-
-            futures.push(future);
-        }
-
-        while let Some(result) = futures.next().await {
-            if let Err(_e) = result {
-            }
-        }
+    for (item_id, error) in failures.iter().take(MAX_LOGGED_FAILURES) {
+        tracing::warn!("Failed to store {} {}: {}", item_type, item_id, error);
+    }
 
-        embedding_progress.done("All projects processed".to_string());
-        Ok(())
+    let failure_ratio = failures.len() as f64 / total.max(1) as f64;
+    tracing::warn!(
+        "{} of {} {} failed to store ({:.1}%)",
+        failures.len(),
+        total,
+        item_type,
+        failure_ratio * 100.0
+    );
+
+    if failure_ratio > MAX_STORE_FAILURE_RATIO {
+        return Err(JobError::Other(format!(
+            "{} of {} {} failed to store, exceeding the {:.0}% failure threshold",
+            failures.len(),
+            total,
+            item_type,
+            MAX_STORE_FAILURE_RATIO * 100.0
+        )));
     }
 
-    async fn store_comments_with_parallel_embeddings(
-        &self,
-        comments: Vec<common::utils::modal::RawComment>,
-        pool: &DbPool,
-    ) -> Result<(), JobError> {
-        if comments.is_empty() {
-            return Ok(());
-        }
+    Ok(())
+}
 
-        let embedding_progress = create_embedding_progress("forge", "comments");
-        embedding_progress.init(comments.len());
+/// Stores every item through `store`, holding an embed permit per item, and
+/// returns how many failed. Too many failures is an error for the whole batch.
+async fn store_concurrently<T, Fut>(
+    item_type: &str,
+    items: Vec<T>,
+    item_id: impl Fn(&T) -> String,
+    store: impl Fn(T) -> Fut,
+) -> Result<usize, JobError>
+where
+    Fut: Future<Output = Result<(), JobError>>,
+{
+    if items.is_empty() {
+        return Ok(0);
+    }
 
-        let concurrency = get_embedding_concurrency();
-        let semaphore = Arc::new(Semaphore::new(concurrency));
-        let embedding_service = Arc::clone(&self.embedding_service);
-        let pool = Arc::new(pool.clone());
+    let embedding_progress = create_embedding_progress("forge", item_type);
+    let total = items.len();
+    embedding_progress.init(total);
 
-        let mut futures = FuturesUnordered::new();
+    let semaphore = ResourceLimits::global().embed();
 
-        for comment in comments {
+    let mut futures: FuturesUnordered<_> = items
+        .into_iter()
+        .map(|item| {
             let semaphore = Arc::clone(&semaphore);
-            let embedding_service = Arc::clone(&embedding_service);
-            let pool = Arc::clone(&pool);
             let embedding_progress = embedding_progress.clone();
-
-            let future = async move {
-                let _permit = semaphore
-                    .acquire()
-                    .await
-                    .map_err(|e| JobError::Embedding(format!("Semaphore error: {}", e)))?;
-
-                let result = DataStore::store_comment_with_embedding(&comment, &embedding_service, &pool).await;
+            let item_id = item_id(&item);
+            let stored = store(item);
+
+            async move {
+                let result = async {
+                    let _permit = semaphore
+                        .acquire()
+                        .await
+                        .map_err(|e| JobError::Embedding(format!("Semaphore error: {}", e)))?;
+
+                    stored.await
+                }
+                .await;
                 embedding_progress.increment();
-                result
-            };
-
-            futures.push(future);
-        }
-
-        while let Some(result) = futures.next().await {
-            if let Err(_e) = result {
+                (item_id, result)
             }
-        }
-
-        embedding_progress.done("All comments processed".to_string());
-        Ok(())
-    }
+        })
+        .collect();
 
-    async fn store_devlogs_with_parallel_embeddings(
-        &self,
-        devlogs: Vec<common::utils::modal::RawDevlog>,
-        pool: &DbPool,
-    ) -> Result<(), JobError> {
-        if devlogs.is_empty() {
-            return Ok(());
+    let mut failures = Vec::new();
+    while let Some((item_id, result)) = futures.next().await {
+        if let Err(e) = result {
+            failures.push((item_id, e.to_string()));
         }
+    }
 
-        let embedding_progress = create_embedding_progress("forge", "devlogs");
-        embedding_progress.init(devlogs.len());
-
-        let concurrency = get_embedding_concurrency();
-        let semaphore = Arc::new(Semaphore::new(concurrency));
-        let embedding_service = Arc::clone(&self.embedding_service);
-        let pool = Arc::new(pool.clone());
-
-        let mut futures = FuturesUnordered::new();
-
-        for devlog in devlogs {
-            let semaphore = Arc::clone(&semaphore);
-            let embedding_service = Arc::clone(&embedding_service);
-            let pool = Arc::clone(&pool);
-            let embedding_progress = embedding_progress.clone();
+    embedding_progress.done(format!("All {} processed", item_type));
+    report_store_failures(item_type, total, &failures)?;
+    Ok(failures.len())
+}
 
-            let future = async move {
-                let _permit = semaphore
-                    .acquire()
-                    .await
-                    .map_err(|e| JobError::Embedding(format!("Semaphore error: {}", e)))?;
+/// The page to record for a key after storing what was fetched from it. Items
+/// are not tracked back to their page, so any failure keeps the old page and
+/// the next run refetches from there.
+fn page_to_record(last_page: i32, failed_stores: usize) -> Option<i32> {
+    (last_page > 0 && failed_stores == 0).then_some(last_page)
+}
 
-                let result = DataStore::store_devlog_with_embedding(&devlog, &embedding_service, &pool).await;
-                embedding_progress.increment();
-                result
-            };
+pub struct ForgeJob {
+    config: Config,
+    embedding_service: Arc<EmbeddingService>,
+}
 
-            futures.push(future);
+impl ForgeJob {
+    pub fn new(config: Config, embedding_service: Arc<EmbeddingService>) -> Self {
+        Self {
+            config,
+            embedding_service,
         }
+    }
 
-        while let Some(result) = futures.next().await {
-            if let Err(_e) = result {
-            }
-        }
+    async fn store_projects(&self, projects: Vec<RawProject>, pool: &DbPool) -> Result<usize, JobError> {
+        let embedding_service = &*self.embedding_service;
+        store_concurrently("projects", projects, |project| project.id.to_string(), |project| async move {
+            DataStore::store_project_with_embedding(&project, embedding_service, pool).await
+        })
+        .await
+    }
 
-        embedding_progress.done("All devlogs processed".to_string());
-        Ok(())
+    async fn store_comments(&self, comments: Vec<RawComment>, pool: &DbPool) -> Result<usize, JobError> {
+        let embedding_service = &*self.embedding_service;
+        store_concurrently(
+            "comments",
+            comments,
+            |comment| format!("{}/{}", comment.devlog_id, comment.slack_id),
+            |comment| async move {
+                DataStore::store_comment_with_embedding(&comment, embedding_service, pool).await
+            },
+        )
+        .await
     }
 
-    async fn store_with_parallel_embeddings(
-        &self,
-        projects: Vec<common::utils::modal::RawProject>,
-        comments: Vec<common::utils::modal::RawComment>,
-        devlogs: Vec<common::utils::modal::RawDevlog>,
-        pool: &DbPool,
-    ) -> Result<(), JobError> {
-        self.store_projects_with_parallel_embeddings(projects, pool).await?;
-        self.store_comments_with_parallel_embeddings(comments, pool).await?;
-        self.store_devlogs_with_parallel_embeddings(devlogs, pool).await?;
-        Ok(())
+    async fn store_devlogs(&self, devlogs: Vec<RawDevlog>, pool: &DbPool) -> Result<usize, JobError> {
+        let embedding_service = &*self.embedding_service;
+        store_concurrently("devlogs", devlogs, |devlog| devlog.id.to_string(), |devlog| async move {
+            DataStore::store_devlog_with_embedding(&devlog, embedding_service, pool).await
+        })
+        .await
     }
 }
 
 #[async_trait]
 impl Job for ForgeJob {
-    async fn execute(&self, pool: &DbPool) -> Result<(), JobError> {
+    async fn execute(&self, pool: &DbPool) -> Result<usize, JobError> {
         let pool = Arc::new(pool.clone());
 
         let external_api = Arc::new(
-            ExternalApiService::new(self.config.journey_session_cookie.clone())
+            ExternalApiService::new(&self.config)
                 .map_err(|e| JobError::ExternalApi(e.to_string()))?,
         );
 
+        // Validators left over from a run that failed before storing its pages
+        // must not suppress those pages now.
+        external_api.discard_page_validators();
+
+        for meta in DataSyncer::get_all_sync_metadata(&pool).await? {
+            tracing::debug!(
+                "Sync state for {}: last page {:?}, last sync {:?}, status {:?}",
+                meta.key,
+                meta.last_page,
+                meta.last_sync,
+                meta.status
+            );
+        }
+
         let progress = get_job_progress("forge");
         progress.update_progress(0, 3, "Fetching new projects");
 
@@ -208,12 +200,12 @@ impl Job for ForgeJob {
             DataFetcher::fetch_new_projects(&external_api, &pool).await?;
 
         progress.update_progress(1, 3, "Fetching new comments");
-        let comments_meta = DataSyncer::get_last_sync_metadata(&pool, "comments").await?;
+        let comments_meta = DataSyncer::get_last_sync_metadata(&pool, SyncKey::Comments).await?;
         let (new_comments, comments_last_page) =
             DataFetcher::fetch_new_comments(&external_api, comments_meta.map(|(_, p)| p)).await?;
 
         progress.update_progress(2, 3, "Fetching new devlogs");
-        let devlogs_meta = DataSyncer::get_last_sync_metadata(&pool, "devlogs").await?;
+        let devlogs_meta = DataSyncer::get_last_sync_metadata(&pool, SyncKey::Devlogs).await?;
         let (new_devlogs, devlogs_last_page) =
             DataFetcher::fetch_new_devlogs(&external_api, devlogs_meta.map(|(_, p)| p)).await?;
 
@@ -228,27 +220,79 @@ impl Job for ForgeJob {
             ),
         );
 
-        if !new_projects.is_empty() || !new_comments.is_empty() || !new_devlogs.is_empty() {
-            self.store_with_parallel_embeddings(new_projects, new_comments, new_devlogs, &pool)
-                .await?;
-
-            if projects_last_page > 0 {
-                DataSyncer::update_sync_metadata(&pool, "projects", projects_last_page).await?;
-            }
-            if comments_last_page > 0 {
-                DataSyncer::update_sync_metadata(&pool, "comments", comments_last_page).await?;
-            }
-            if devlogs_last_page > 0 {
-                DataSyncer::update_sync_metadata(&pool, "devlogs", devlogs_last_page).await?;
+        let fetched = new_projects.len() + new_comments.len() + new_devlogs.len();
+        let projects_failed = self.store_projects(new_projects, &pool).await?;
+        let comments_failed = self.store_comments(new_comments, &pool).await?;
+        let devlogs_failed = self.store_devlogs(new_devlogs, &pool).await?;
+        let failed_stores = projects_failed + comments_failed + devlogs_failed;
+
+        if fetched > 0 {
+            for (key, last_page, failed) in [
+                (SyncKey::Projects, projects_last_page, projects_failed),
+                (SyncKey::Comments, comments_last_page, comments_failed),
+                (SyncKey::Devlogs, devlogs_last_page, devlogs_failed),
+            ] {
+                match page_to_record(last_page, failed) {
+                    Some(page) => DataSyncer::update_sync_metadata(&pool, key, page).await?,
+                    None if failed > 0 => {
+                        tracing::info!("Keeping the {} sync page so the next run refetches {} failed stores", key, failed);
+                    }
+                    None => {}
+                }
             }
         }
+        if failed_stores == 0 {
+            external_api.commit_page_validators();
+        } else {
+            tracing::info!("Not caching page validators so the next run refetches items that failed to store");
+            external_api.discard_page_validators();
+        }
 
         DataSyncer::sync_user_shell_data(&external_api, &pool).await?;
 
-        Ok(())
+        Ok(fetched.saturating_sub(failed_stores))
     }
 
     fn name(&self) -> &str {
         "ForgeJob"
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn store_numbers(count: u32, failing: impl Fn(u32) -> bool) -> Result<usize, JobError> {
+        let items: Vec<u32> = (0..count).collect();
+        store_concurrently("numbers", items, u32::to_string, |n| {
+            let fails = failing(n);
+            async move {
+                if fails {
+                    Err(JobError::Database(format!("item {} rejected", n)))
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn failed_stores_are_counted_under_the_threshold() {
+        assert_eq!(store_numbers(20, |_| false).await.unwrap(), 0);
+        assert_eq!(store_numbers(20, |n| n == 7).await.unwrap(), 1);
+        assert_eq!(store_numbers(20, |n| n < 2).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn too_many_failed_stores_fail_the_batch() {
+        let err = store_numbers(20, |n| n % 4 == 0).await.unwrap_err();
+        assert!(err.to_string().contains("5 of 20 numbers failed to store"), "{}", err);
+    }
+
+    #[test]
+    fn sync_page_only_advances_without_failures() {
+        assert_eq!(page_to_record(12, 0), Some(12));
+        assert_eq!(page_to_record(12, 1), None);
+        assert_eq!(page_to_record(0, 0), None);
+    }
+}
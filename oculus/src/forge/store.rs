@@ -1,38 +1,58 @@
-use crate::core::JobError;
+use crate::core::{
+    comment_context_enabled, comment_embedding_text, limits::ResourceLimits, project_embedding_text,
+    storable_embedding, JobError,
+};
 use common::{
-    database::DbPool,
+    database::{get_client_with_retry, DbErrorKind, DbPool, RetryPolicy},
     services::EmbeddingService,
     utils::modal::{RawComment, RawDevlog, RawProject},
 };
 
 pub struct DataStore;
 
+fn ignore_unique_violation(e: tokio_postgres::Error) -> Result<u64, JobError> {
+    match DbErrorKind::of(&e) {
+        DbErrorKind::UniqueViolation => {
+            tracing::debug!("Ignoring duplicate row: {}", e);
+            Ok(0)
+        }
+        _ => Err(JobError::Database(e.to_string())),
+    }
+}
+
 impl DataStore {
     pub async fn store_project_with_embedding(
         project: &RawProject,
         embedding_service: &EmbeddingService,
         pool: &DbPool,
     ) -> Result<(), JobError> {
-        let text = format!(
-            "{} {}",
-            project.title,
-            project.description.as_deref().unwrap_or_default()
-        )
-        .trim()
-        .to_string();
+        let text = project_embedding_text(project);
 
         let embedding_vec = embedding_service
             .embed_text(&text)
             .await
             .map_err(|e| JobError::Embedding(e.to_string()))?;
 
-        let embedding = pgvector::Vector::from(embedding_vec);
+        let embedding = storable_embedding(embedding_vec);
+
+        let db_semaphore = ResourceLimits::global().db();
+        let _permit = db_semaphore
+            .acquire()
+            .await
+            .map_err(|e| JobError::Database(format!("Semaphore error: {}", e)))?;
 
-        let client = pool
-            .get()
+        let client = get_client_with_retry(pool, RetryPolicy::default())
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
 
+        Self::insert_project(&client, project, embedding).await
+    }
+
+    async fn insert_project(
+        client: &tokio_postgres::Client,
+        project: &RawProject,
+        embedding: Option<pgvector::Vector>,
+    ) -> Result<(), JobError> {
         let created_at = crate::core::parse_datetime(&project.created_at)?;
         let updated_at = crate::core::parse_datetime(&project.updated_at)?;
 
@@ -40,9 +60,9 @@ impl DataStore {
             .execute(
                 r#"
             INSERT INTO projects (
-                id, title, description, readme_link, slack_id, created_at, updated_at, 
-                title_description_embedding
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                id, title, description, readme_link, category, demo_link, repo_link,
+                slack_id, created_at, updated_at, title_description_embedding, last_synced
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, NOW())
             ON CONFLICT (id) DO NOTHING
             "#,
                 &[
@@ -50,6 +70,9 @@ impl DataStore {
                     &project.title,
                     &project.description,
                     &project.readme_link,
+                    &project.category,
+                    &project.demo_link,
+                    &project.repo_link,
                     &project.slack_id,
                     &created_at,
                     &updated_at,
@@ -57,7 +80,7 @@ impl DataStore {
                 ],
             )
             .await
-            .map_err(|e| JobError::Database(e.to_string()))?;
+            .or_else(ignore_unique_violation)?;
 
         Ok(())
     }
@@ -67,15 +90,33 @@ impl DataStore {
         embedding_service: &EmbeddingService,
         pool: &DbPool,
     ) -> Result<(), JobError> {
+        let devlog_text = if comment_context_enabled() {
+            let client = get_client_with_retry(pool, RetryPolicy::default())
+                .await
+                .map_err(|e| JobError::Database(e.to_string()))?;
+            client
+                .query_opt("SELECT text FROM logs WHERE id = $1", &[&comment.devlog_id])
+                .await
+                .map_err(|e| JobError::Database(e.to_string()))?
+                .map(|row| row.get::<_, String>("text"))
+        } else {
+            None
+        };
+
         let embedding_vec = embedding_service
-            .embed_text(&comment.text)
+            .embed_text(&comment_embedding_text(&comment.text, devlog_text.as_deref()))
             .await
             .map_err(|e| JobError::Embedding(e.to_string()))?;
 
-        let embedding = pgvector::Vector::from(embedding_vec);
+        let embedding = storable_embedding(embedding_vec);
+
+        let db_semaphore = ResourceLimits::global().db();
+        let _permit = db_semaphore
+            .acquire()
+            .await
+            .map_err(|e| JobError::Database(format!("Semaphore error: {}", e)))?;
 
-        let client = pool
-            .get()
+        let client = get_client_with_retry(pool, RetryPolicy::default())
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
 
@@ -91,8 +132,8 @@ impl DataStore {
                 .execute(
                     r#"
                 INSERT INTO comments (
-                    text, devlog_id, slack_id, created_at, text_embedding
-                ) VALUES ($1, $2, $3, $4, $5)
+                    text, devlog_id, slack_id, created_at, text_embedding, last_synced, upstream_id
+                ) VALUES ($1, $2, $3, $4, $5, NOW(), $6)
                 ON CONFLICT (devlog_id, slack_id) DO NOTHING
                 "#,
                     &[
@@ -101,10 +142,11 @@ impl DataStore {
                         &comment.slack_id,
                         &created_at,
                         &embedding,
+                        &comment.id,
                     ],
                 )
                 .await
-                .map_err(|e| JobError::Database(e.to_string()))?;
+                .or_else(ignore_unique_violation)?;
         } else {
             tracing::debug!(
                 "Skipping comment for devlog {} - devlog no longer exists",
@@ -125,10 +167,15 @@ impl DataStore {
             .await
             .map_err(|e| JobError::Embedding(e.to_string()))?;
 
-        let embedding = pgvector::Vector::from(embedding_vec);
+        let embedding = storable_embedding(embedding_vec);
+
+        let db_semaphore = ResourceLimits::global().db();
+        let _permit = db_semaphore
+            .acquire()
+            .await
+            .map_err(|e| JobError::Database(format!("Semaphore error: {}", e)))?;
 
-        let client = pool
-            .get()
+        let client = get_client_with_retry(pool, RetryPolicy::default())
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
 
@@ -148,13 +195,14 @@ impl DataStore {
                 .execute(
                     r#"
                 INSERT INTO logs (
-                    id, text, project_id, slack_id, created_at, updated_at, text_embedding
-                ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+                    id, text, attachment, project_id, slack_id, created_at, updated_at, text_embedding, last_synced
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, NOW())
                 ON CONFLICT (id) DO NOTHING
                 "#,
                     &[
                         &devlog.id,
                         &devlog.text,
+                        &devlog.attachment,
                         &devlog.project_id,
                         &devlog.slack_id,
                         &created_at,
@@ -163,7 +211,7 @@ impl DataStore {
                     ],
                 )
                 .await
-                .map_err(|e| JobError::Database(e.to_string()))?;
+                .or_else(ignore_unique_violation)?;
         } else {
             tracing::debug!(
                 "Skipping devlog {} for project {} - project no longer exists",
@@ -175,3 +223,102 @@ impl DataStore {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_postgres::NoTls;
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn only_unique_violations_are_ignored() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, NoTls).await.unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("store_errors_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE projects (id BIGINT PRIMARY KEY);
+                 CREATE TABLE logs (id BIGINT PRIMARY KEY, project_id BIGINT NOT NULL REFERENCES projects(id));
+                 INSERT INTO projects VALUES (1);"
+            ))
+            .await
+            .unwrap();
+
+        let duplicate = client
+            .execute("INSERT INTO projects VALUES (1)", &[])
+            .await
+            .or_else(ignore_unique_violation);
+        let orphan = client
+            .execute("INSERT INTO logs VALUES (1, 2)", &[])
+            .await
+            .or_else(ignore_unique_violation);
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(duplicate.unwrap(), 0);
+        assert!(matches!(orphan, Err(JobError::Database(_))));
+    }
+
+    /// Needs a scratch database with pgvector: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn stored_project_has_last_synced() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, NoTls).await.unwrap();
+        tokio::spawn(connection);
+        if client.batch_execute("CREATE EXTENSION IF NOT EXISTS vector").await.is_err() {
+            eprintln!("pgvector not available, skipping");
+            return;
+        }
+
+        let schema = format!("store_last_synced_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema}, public;
+                 CREATE TABLE projects (
+                     id BIGINT PRIMARY KEY, title TEXT NOT NULL, description TEXT, readme_link TEXT, category TEXT,
+                     demo_link TEXT, repo_link TEXT, slack_id TEXT NOT NULL, created_at TIMESTAMPTZ,
+                     updated_at TIMESTAMPTZ, title_description_embedding vector(3), last_synced TIMESTAMPTZ
+                 );"
+            ))
+            .await
+            .unwrap();
+
+        let project = RawProject {
+            id: 1,
+            title: "Weather station".to_owned(),
+            slack_id: "U1".into(),
+            created_at: "2025-06-01T12:00:00Z".to_owned(),
+            updated_at: "2025-06-01T12:00:00Z".to_owned(),
+            ..RawProject::default()
+        };
+        DataStore::insert_project(&client, &project, None).await.unwrap();
+        let synced: bool = client
+            .query_one("SELECT last_synced IS NOT NULL FROM projects WHERE id = 1", &[])
+            .await
+            .unwrap()
+            .get(0);
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert!(synced);
+    }
+}
@@ -1,12 +1,28 @@
-use crate::core::JobError;
-use common::{database::connection, services::external::ExternalApiService};
+use crate::core::{
+    ensure_leaderboard_not_empty, limits::ResourceLimits, load_cache_validators, store_cache_validators, JobError,
+};
+use common::{
+    database::connection,
+    services::external::ExternalApiService,
+    utils::types::{SlackId, SyncKey},
+};
+
+const LEADERBOARD_SYNC_KEY: &str = "leaderboard_forge";
+
+#[derive(Debug)]
+pub struct SyncMetadata {
+    pub key: SyncKey,
+    pub last_sync: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_page: Option<i32>,
+    pub status: Option<String>,
+}
 
 pub struct DataSyncer;
 
 impl DataSyncer {
     pub async fn get_last_sync_metadata(
         pool: &connection::DbPool,
-        key: &str,
+        key: SyncKey,
     ) -> Result<Option<(chrono::DateTime<chrono::Utc>, i32)>, JobError> {
         let client = pool
             .get()
@@ -16,7 +32,7 @@ impl DataSyncer {
         let rows = client
             .query(
                 "SELECT last_sync, last_page FROM sync_metadata WHERE key = $1",
-                &[&key],
+                &[&key.as_str()],
             )
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
@@ -32,7 +48,7 @@ impl DataSyncer {
 
     pub async fn update_sync_metadata(
         pool: &connection::DbPool,
-        key: &str,
+        key: SyncKey,
         page: i32,
     ) -> Result<(), JobError> {
         let client = pool
@@ -40,17 +56,46 @@ impl DataSyncer {
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
 
-        client.execute(
+        ResourceLimits::global().limit_db(client.execute(
             "INSERT INTO sync_metadata (key, last_sync, last_page, status) VALUES ($1, NOW(), $2, 'completed') ON CONFLICT (key) DO UPDATE SET last_sync = NOW(), last_page = $2, status = 'completed'",
-            &[&key, &page]
-        ).await
-        .map_err(|e| JobError::Database(e.to_string()))?;
+            &[&key.as_str(), &page]
+        )).await?;
 
         Ok(())
     }
 
+    pub async fn get_all_sync_metadata(
+        pool: &connection::DbPool,
+    ) -> Result<Vec<SyncMetadata>, JobError> {
+        let client = pool
+            .get()
+            .await
+            .map_err(|e| JobError::Database(e.to_string()))?;
+
+        let keys: Vec<&str> = SyncKey::ALL.iter().map(|k| k.as_str()).collect();
+        let rows = client
+            .query(
+                "SELECT key, last_sync, last_page, status FROM sync_metadata WHERE key = ANY($1) ORDER BY key",
+                &[&keys],
+            )
+            .await
+            .map_err(|e| JobError::Database(e.to_string()))?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                Some(SyncMetadata {
+                    key: SyncKey::from_key(row.get(0))?,
+                    last_sync: row.get(1),
+                    last_page: row.get(2),
+                    status: row.get(3),
+                })
+            })
+            .collect())
+    }
+
     pub async fn calculate_start_page(pool: &connection::DbPool) -> Result<i32, JobError> {
-        if let Some((_, last_page)) = Self::get_last_sync_metadata(pool, "projects").await? {
+        if let Some((_, last_page)) = Self::get_last_sync_metadata(pool, SyncKey::Projects).await? {
             Ok(last_page + 1)
         } else {
             Ok(1)
@@ -63,10 +108,16 @@ impl DataSyncer {
     ) -> Result<(), JobError> {
         tracing::info!("Syncing user shell data from leaderboard");
 
-        let leaderboard_response = external_api
-            .fetch_leaderboard()
+        let cached_validators = load_cache_validators(pool, LEADERBOARD_SYNC_KEY).await?;
+        let Some((leaderboard_response, validators)) = external_api
+            .fetch_leaderboard(cached_validators.as_ref())
             .await
-            .map_err(|e| JobError::ExternalApi(format!("Failed to fetch leaderboard: {}", e)))?;
+            .map_err(|e| JobError::ExternalApi(format!("Failed to fetch leaderboard: {}", e)))?
+        else {
+            tracing::info!("Leaderboard not modified since last sync, skipping shell data sync");
+            return Ok(());
+        };
+        ensure_leaderboard_not_empty(&leaderboard_response)?;
 
         let client = pool
             .get()
@@ -88,8 +139,8 @@ impl DataSyncer {
                 .first()
                 .and_then(|row| row.get::<_, Option<i32>>(0));
 
-            let rows_affected = client
-                .execute(
+            let rows_affected = ResourceLimits::global()
+                .limit_db(client.execute(
                     r#"
                 INSERT INTO users (slack_id, username, current_shells, last_synced, pfp_url)
                 VALUES ($1, $2, $3, NOW(), 'notfound')
@@ -100,9 +151,8 @@ impl DataSyncer {
                 WHERE users.current_shells IS DISTINCT FROM EXCLUDED.current_shells
                 "#,
                     &[&user.slack_id, &user.username, &user.shells],
-                )
-                .await
-                .map_err(|e| JobError::Database(e.to_string()))?;
+                ))
+                .await?;
 
             if rows_affected > 0 {
                 updated_count += 1;
@@ -119,12 +169,14 @@ impl DataSyncer {
             }
         }
 
+        store_cache_validators(pool, LEADERBOARD_SYNC_KEY, &validators).await?;
+
         tracing::info!("Updated shell data for {} users", updated_count);
         Ok(())
     }
 
     async fn process_new_payouts(
-        slack_id: &str,
+        slack_id: &SlackId,
         previous_shells: Option<i32>,
         payouts: &[common::utils::modal::RawPayout],
         client: &tokio_postgres::Client,
@@ -184,8 +236,8 @@ impl DataSyncer {
                 })?
                 .with_timezone(&chrono::Utc);
 
-            client
-                .execute(
+            ResourceLimits::global()
+                .limit_db(client.execute(
                     r#"
                 INSERT INTO shell_history (slack_id, shells_then, shell_diff, shells, recorded_at)
                 VALUES ($1, $2, $3, $4, $5)
@@ -198,9 +250,8 @@ impl DataSyncer {
                         &running_shells,
                         &recorded_at,
                     ],
-                )
-                .await
-                .map_err(|e| JobError::Database(e.to_string()))?;
+                ))
+                .await?;
         }
 
         Ok(())
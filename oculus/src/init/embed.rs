@@ -1,16 +1,20 @@
-use crate::core::{get_base_concurrency, JobError};
+use crate::core::{
+    comment_context_enabled, comment_embedding_text, limits::ResourceLimits, project_embedding_text,
+    storable_embedding, JobError,
+};
 use common::{
-    database::connection,
+    database::{connection, get_client_with_retry, RetryPolicy},
     services::EmbeddingService,
     utils::modal::{RawProject, RawComment, RawDevlog}
 };
 use futures::stream::{FuturesUnordered, StreamExt};
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::Semaphore;
 use indicatif::{ProgressBar, ProgressStyle};
 
+use super::state::InitStateFile;
+
 const DEFAULT_EMBED_BATCH_SIZE: usize = 32;
-const MAX_DB_EMBED_CONCURRENCY: usize = 8;
 
 pub struct InitEmbedder;
 
@@ -22,24 +26,17 @@ impl InitEmbedder {
             .unwrap_or(DEFAULT_EMBED_BATCH_SIZE) 
     }
 
-    fn get_db_concurrency() -> usize {
-        std::env::var("DB_EMBED_CONCURRENCY")
-            .ok()
-            .and_then(|v| v.parse::<usize>().ok())
-            .unwrap_or_else(|| get_base_concurrency().min(MAX_DB_EMBED_CONCURRENCY))
-    }
-
     pub async fn embed_projects(
         projects: &[RawProject],
         embedding_service: Arc<EmbeddingService>,
         pool: &connection::DbPool,
+        state: &InitStateFile,
     ) -> Result<(), JobError> {
         if projects.is_empty() {
             return Ok(());
         }
 
         let embed_batch_size = Self::get_embed_batch_size();
-        let db_concurrency = Self::get_db_concurrency();
         
         let start_time = std::time::Instant::now();
         let progress = ProgressBar::new(projects.len() as u64);
@@ -54,38 +51,42 @@ impl InitEmbedder {
         let pool = Arc::new(pool.clone());
         
         
-        let db_semaphore = Arc::new(Semaphore::new(db_concurrency));
+        let db_semaphore = ResourceLimits::global().db();
         
         
-        let mut processed = 0;
+        let mut processed = state.embedded("projects").min(projects.len());
+        progress.set_position(processed as u64);
         
-        for chunk in projects.chunks(embed_batch_size) {
+        for chunk in projects[processed..].chunks(embed_batch_size) {
             
             let texts: Vec<String> = chunk.iter()
-                .map(|p| format!("{} {}", p.title, p.description.as_deref().unwrap_or("")))
+                .map(project_embedding_text)
                 .collect();
             
-            let embeddings = embedding_service.embed_batch(texts).await
+            let embeddings = ResourceLimits::global()
+                .limit_embed(texts.len(), embedding_service.embed_batch(texts))
+                .await
                 .map_err(|e| JobError::Embedding(e.to_string()))?;
             
             
             let mut futures = FuturesUnordered::new();
             
-            for (project, embedding) in chunk.iter().zip(embeddings.iter()) {
+            for (project, embedding) in chunk.iter().zip(embeddings) {
                 let db_semaphore = db_semaphore.clone();
                 let pool = pool.clone();
                 let project_id = project.id;
-                let embedding = serde_json::to_string(embedding)
-                    .map_err(|e| JobError::Embedding(format!("Failed to serialize embedding: {}", e)))?;
+                let embedding = storable_embedding(embedding);
                 
                 let future = async move {
                     let _permit = db_semaphore.acquire().await.map_err(|e| {
                         JobError::Database(format!("Semaphore error: {}", e))
                     })?;
                     
-                    let client = pool.get().await.map_err(|e| JobError::Database(e.to_string()))?;
+                    let client = get_client_with_retry(&pool, RetryPolicy::default())
+                        .await
+                        .map_err(|e| JobError::Database(e.to_string()))?;
                     client.execute(
-                        "UPDATE projects SET embedding = $2 WHERE id = $1",
+                        "UPDATE projects SET title_description_embedding = $2 WHERE id = $1",
                         &[&project_id, &embedding],
                     ).await.map_err(|e| JobError::Database(e.to_string()))?;
                     
@@ -102,6 +103,7 @@ impl InitEmbedder {
                 progress.set_position(processed as u64);
                 progress.set_message(format!("Processed {} projects", processed));
             }
+            state.record_embedded("projects", processed).await;
         }
         
         let elapsed = start_time.elapsed();
@@ -111,15 +113,21 @@ impl InitEmbedder {
 
     pub async fn embed_comments(
         comments: &[RawComment],
+        devlogs: &[RawDevlog],
         embedding_service: Arc<EmbeddingService>,
         pool: &connection::DbPool,
+        state: &InitStateFile,
     ) -> Result<(), JobError> {
         if comments.is_empty() {
             return Ok(());
         }
 
         let embed_batch_size = Self::get_embed_batch_size();
-        let db_concurrency = Self::get_db_concurrency();
+        let devlog_texts: HashMap<i64, &String> = if comment_context_enabled() {
+            devlogs.iter().map(|devlog| (devlog.id, &devlog.text)).collect()
+        } else {
+            HashMap::new()
+        };
         
         let start_time = std::time::Instant::now();
         let progress = ProgressBar::new(comments.len() as u64);
@@ -134,39 +142,46 @@ impl InitEmbedder {
         let pool = Arc::new(pool.clone());
         
         
-        let db_semaphore = Arc::new(Semaphore::new(db_concurrency));
+        let db_semaphore = ResourceLimits::global().db();
         
         
-        let mut processed = 0;
+        let mut processed = state.embedded("comments").min(comments.len());
+        progress.set_position(processed as u64);
         
-        for chunk in comments.chunks(embed_batch_size) {
+        for chunk in comments[processed..].chunks(embed_batch_size) {
             
             let texts: Vec<String> = chunk.iter()
-                .map(|c| c.text.clone())
+                .map(|c| {
+                    let devlog_text = devlog_texts.get(&c.devlog_id).map(|text| text.as_str());
+                    comment_embedding_text(&c.text, devlog_text)
+                })
                 .collect();
             
-            let embeddings = embedding_service.embed_batch(texts).await
+            let embeddings = ResourceLimits::global()
+                .limit_embed(texts.len(), embedding_service.embed_batch(texts))
+                .await
                 .map_err(|e| JobError::Embedding(e.to_string()))?;
             
             
             let mut futures = FuturesUnordered::new();
             
-            for (comment, embedding) in chunk.iter().zip(embeddings.iter()) {
+            for (comment, embedding) in chunk.iter().zip(embeddings) {
                 let db_semaphore = db_semaphore.clone();
                 let pool = pool.clone();
                 let devlog_id = comment.devlog_id;
                 let slack_id = comment.slack_id.clone();
-                let embedding = serde_json::to_string(embedding)
-                    .map_err(|e| JobError::Embedding(format!("Failed to serialize embedding: {}", e)))?;
+                let embedding = storable_embedding(embedding);
                 
                 let future = async move {
                     let _permit = db_semaphore.acquire().await.map_err(|e| {
                         JobError::Database(format!("Semaphore error: {}", e))
                     })?;
                     
-                    let client = pool.get().await.map_err(|e| JobError::Database(e.to_string()))?;
+                    let client = get_client_with_retry(&pool, RetryPolicy::default())
+                        .await
+                        .map_err(|e| JobError::Database(e.to_string()))?;
                     client.execute(
-                        "UPDATE comments SET embedding = $3 WHERE devlog_id = $1 AND slack_id = $2",
+                        "UPDATE comments SET text_embedding = $3 WHERE devlog_id = $1 AND slack_id = $2",
                         &[&devlog_id, &slack_id, &embedding],
                     ).await.map_err(|e| JobError::Database(e.to_string()))?;
                     
@@ -183,6 +198,7 @@ impl InitEmbedder {
                 progress.set_position(processed as u64);
                 progress.set_message(format!("Processed {} comments", processed));
             }
+            state.record_embedded("comments", processed).await;
         }
         
         let elapsed = start_time.elapsed();
@@ -194,13 +210,13 @@ impl InitEmbedder {
         devlogs: &[RawDevlog],
         embedding_service: Arc<EmbeddingService>,
         pool: &connection::DbPool,
+        state: &InitStateFile,
     ) -> Result<(), JobError> {
         if devlogs.is_empty() {
             return Ok(());
         }
 
         let embed_batch_size = Self::get_embed_batch_size();
-        let db_concurrency = Self::get_db_concurrency();
         
         let start_time = std::time::Instant::now();
         let progress = ProgressBar::new(devlogs.len() as u64);
@@ -215,38 +231,42 @@ impl InitEmbedder {
         let pool = Arc::new(pool.clone());
         
         
-        let db_semaphore = Arc::new(Semaphore::new(db_concurrency));
+        let db_semaphore = ResourceLimits::global().db();
         
         
-        let mut processed = 0;
+        let mut processed = state.embedded("devlogs").min(devlogs.len());
+        progress.set_position(processed as u64);
         
-        for chunk in devlogs.chunks(embed_batch_size) {
+        for chunk in devlogs[processed..].chunks(embed_batch_size) {
             
             let texts: Vec<String> = chunk.iter()
                 .map(|d| d.text.clone())
                 .collect();
             
-            let embeddings = embedding_service.embed_batch(texts).await
+            let embeddings = ResourceLimits::global()
+                .limit_embed(texts.len(), embedding_service.embed_batch(texts))
+                .await
                 .map_err(|e| JobError::Embedding(e.to_string()))?;
             
             
             let mut futures = FuturesUnordered::new();
             
-            for (devlog, embedding) in chunk.iter().zip(embeddings.iter()) {
+            for (devlog, embedding) in chunk.iter().zip(embeddings) {
                 let db_semaphore = db_semaphore.clone();
                 let pool = pool.clone();
                 let devlog_id = devlog.id;
-                let embedding = serde_json::to_string(embedding)
-                    .map_err(|e| JobError::Embedding(format!("Failed to serialize embedding: {}", e)))?;
+                let embedding = storable_embedding(embedding);
                 
                 let future = async move {
                     let _permit = db_semaphore.acquire().await.map_err(|e| {
                         JobError::Database(format!("Semaphore error: {}", e))
                     })?;
                     
-                    let client = pool.get().await.map_err(|e| JobError::Database(e.to_string()))?;
+                    let client = get_client_with_retry(&pool, RetryPolicy::default())
+                        .await
+                        .map_err(|e| JobError::Database(e.to_string()))?;
                     client.execute(
-                        "UPDATE devlogs SET embedding = $2 WHERE id = $1",
+                        "UPDATE logs SET text_embedding = $2 WHERE id = $1",
                         &[&devlog_id, &embedding],
                     ).await.map_err(|e| JobError::Database(e.to_string()))?;
                     
@@ -263,10 +283,95 @@ impl InitEmbedder {
                 progress.set_position(processed as u64);
                 progress.set_message(format!("Processed {} devlogs", processed));
             }
+            state.record_embedded("devlogs", processed).await;
         }
         
         let elapsed = start_time.elapsed();
         progress.finish_with_message(format!("✅ All {} devlog embeddings completed in {:.2}s", devlogs.len(), elapsed.as_secs_f64()));
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{
+        database::connection::create_pool, services::embedding::EMBEDDING_DIM, utils::config::Config,
+    };
+    use tokio_postgres::NoTls;
+
+    /// Needs a scratch database with pgvector and the embedding model: set
+    /// `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn devlog_embeddings_land_where_search_reads_them() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, NoTls).await.unwrap();
+        tokio::spawn(connection);
+        if client.batch_execute("CREATE EXTENSION IF NOT EXISTS vector").await.is_err() {
+            eprintln!("pgvector not available, skipping");
+            return;
+        }
+        let Ok(embedding_service) = EmbeddingService::new(true) else {
+            eprintln!("embedding model not available, skipping");
+            return;
+        };
+
+        let schema = format!("init_embed_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema}, public;
+                 CREATE TABLE logs (id BIGINT PRIMARY KEY, text TEXT NOT NULL, text_embedding vector({EMBEDDING_DIM}));
+                 INSERT INTO logs (id, text) VALUES
+                     (1, 'Soldered the sensor board and wired it to the microcontroller today'),
+                     (2, 'Wrote the firmware that uploads readings to the dashboard every minute');"
+            ))
+            .await
+            .unwrap();
+
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let separator = if database_url.contains('?') { '&' } else { '?' };
+        let pool = create_pool(&Config {
+            database_url: format!("{database_url}{separator}options=-csearch_path%3D{schema}%2Cpublic"),
+            max_db_connections: 2,
+            ..Config::default()
+        })
+        .await
+        .unwrap();
+        let devlogs: Vec<RawDevlog> = client
+            .query("SELECT id, text FROM logs", &[])
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| RawDevlog {
+                id: row.get("id"),
+                text: row.get("text"),
+                ..RawDevlog::default()
+            })
+            .collect();
+
+        let result = InitEmbedder::embed_devlogs(
+            &devlogs,
+            Arc::new(embedding_service),
+            &pool,
+            &InitStateFile::open(None, None),
+        )
+        .await;
+        let embedded: i64 = client
+            .query_one("SELECT COUNT(*) FROM logs WHERE text_embedding IS NOT NULL", &[])
+            .await
+            .unwrap()
+            .get(0);
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        result.unwrap();
+        assert_eq!(embedded, 2);
+    }
+}
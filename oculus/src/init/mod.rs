@@ -1,24 +1,31 @@
 pub mod embed;
+pub mod state;
 
 use crate::core::progress::ProgressReporter;
-use crate::core::{with_retry, Job, JobError};
+use crate::core::{limits::ResourceLimits, with_retry, FetchRange, Job, JobError};
 use async_trait::async_trait;
 use common::{
     database::connection::{create_pool, run_migrations},
     services::{external::ExternalApiService, EmbeddingService},
-    utils::config::Config,
+    utils::{
+        config::Config,
+        pagination::{extend_unique, paginate, PageItem, PaginateOptions, Paginated},
+        types::SlackId,
+    },
     DbPool,
 };
+use futures::StreamExt;
 use std::collections::HashSet;
+use std::pin::pin;
 use std::sync::Arc;
 
 use self::embed::InitEmbedder;
-
-const DEV_MODE_MAX_PAGES: i32 = 5;
+use self::state::InitStateFile;
 
 pub struct InitJob {
     config: Config,
     embedding_service: Arc<EmbeddingService>,
+    state: InitStateFile,
 }
 
 impl InitJob {
@@ -26,142 +33,18 @@ impl InitJob {
         Self {
             config,
             embedding_service,
+            state: InitStateFile::from_env(),
         }
     }
 
-    async fn fetch_all_projects(
-        &self,
-        external_api: &ExternalApiService,
-    ) -> Result<Vec<common::utils::modal::RawProject>, JobError> {
-        let mut all_projects = Vec::new();
-        let mut page = 1;
-        let max_pages = if std::env::var("DEV_MODE").unwrap_or_default().to_lowercase() == "true" {
-            DEV_MODE_MAX_PAGES
-        } else {
-            i32::MAX
-        };
-
-        loop {
-            let response = with_retry(&format!("fetch_projects_page_{}", page), || {
-                external_api.fetch_projects(Some(page))
-            })
-            .await
-            .map_err(|e| JobError::ExternalApi(e.to_string()))?;
-
-            if response.projects.is_empty() || page >= max_pages {
-                break;
-            }
-
-            all_projects.extend(response.projects);
-
-            if let Some(pagination) = response.pagination {
-                if let Some(total_pages) = pagination.pages {
-                    let progress = (page as f64 / total_pages as f64 * 100.0) as u32;
-                    print!(
-                        "\rFetching projects: {}% ({}/{})",
-                        progress, page, total_pages
-                    );
-                    std::io::Write::flush(&mut std::io::stdout()).ok();
-                    if page >= total_pages {
-                        break;
-                    }
-                }
-            }
-
-            page += 1;
-        }
-        println!();
-        Ok(all_projects)
-    }
-
-    async fn fetch_all_comments(
-        &self,
-        external_api: &ExternalApiService,
-    ) -> Result<Vec<common::utils::modal::RawComment>, JobError> {
-        let mut all_comments = Vec::new();
-        let mut page = 1;
-        let max_pages = if std::env::var("DEV_MODE").unwrap_or_default().to_lowercase() == "true" {
-            DEV_MODE_MAX_PAGES
-        } else {
-            i32::MAX
-        };
-
-        loop {
-            let response = with_retry(&format!("fetch_comments_page_{}", page), || {
-                external_api.fetch_comments(Some(page))
-            })
-            .await
-            .map_err(|e| JobError::ExternalApi(e.to_string()))?;
-
-            if response.comments.is_empty() || page >= max_pages {
-                break;
-            }
-
-            all_comments.extend(response.comments);
-
-            if let Some(pagination) = response.pagination {
-                if let Some(total_pages) = pagination.pages {
-                    let progress = (page as f64 / total_pages as f64 * 100.0) as u32;
-                    print!(
-                        "\rFetching comments: {}% ({}/{})",
-                        progress, page, total_pages
-                    );
-                    std::io::Write::flush(&mut std::io::stdout()).ok();
-                    if page >= total_pages {
-                        break;
-                    }
-                }
-            }
-
-            page += 1;
-        }
-        println!();
-        Ok(all_comments)
-    }
-
-    async fn fetch_all_devlogs(
-        &self,
-        external_api: &ExternalApiService,
-    ) -> Result<Vec<common::utils::modal::RawDevlog>, JobError> {
-        let mut all_devlogs = Vec::new();
-        let mut page = 1;
-        let max_pages = if std::env::var("DEV_MODE").unwrap_or_default().to_lowercase() == "true" {
-            DEV_MODE_MAX_PAGES
-        } else {
-            i32::MAX
-        };
-
-        loop {
-            let response = with_retry(&format!("fetch_devlogs_page_{}", page), || {
-                external_api.fetch_devlogs(Some(page))
-            })
-            .await
-            .map_err(|e| JobError::ExternalApi(e.to_string()))?;
-
-            if response.devlogs.is_empty() || page >= max_pages {
-                break;
-            }
-
-            all_devlogs.extend(response.devlogs);
-
-            if let Some(pagination) = response.pagination {
-                if let Some(total_pages) = pagination.pages {
-                    let progress = (page as f64 / total_pages as f64 * 100.0) as u32;
-                    print!(
-                        "\rFetching devlogs: {}% ({}/{})",
-                        progress, page, total_pages
-                    );
-                    std::io::Write::flush(&mut std::io::stdout()).ok();
-                    if page >= total_pages {
-                        break;
-                    }
-                }
-            }
-
-            page += 1;
-        }
-        println!();
-        Ok(all_devlogs)
+    async fn fetch_all<R, F, Fut>(&self, name: &str, fetch_page: F) -> Result<Vec<R::Item>, JobError>
+    where
+        R: Paginated,
+        R::Item: PageItem + serde::Serialize + serde::de::DeserializeOwned,
+        F: Fn(i32) -> Fut + Clone,
+        Fut: std::future::Future<Output = Result<R, common::utils::error::ApiError>>,
+    {
+        fetch_all_pages(&self.state, FetchRange::from_env(), name, fetch_page).await
     }
 
     async fn store_raw_data(
@@ -175,89 +58,11 @@ impl InitJob {
             .get()
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
-        let tx = client
-            .transaction()
-            .await
-            .map_err(|e| JobError::Database(e.to_string()))?;
-
-        let total_projects = projects.len();
-        let projects_progress = ProgressReporter::new_with_job("init", "Storing projects");
-        for (i, project) in projects.iter().enumerate() {
-            projects_progress.report(i + 1, total_projects);
-            tx.execute(
-                r#"INSERT INTO projects (id, title, description, readme_link, slack_id, created_at, updated_at)
-                   VALUES ($1, $2, $3, $4, $5, $6, $7)
-                   ON CONFLICT (id) DO UPDATE SET 
-                       title = EXCLUDED.title,
-                       description = COALESCE(EXCLUDED.description, projects.description),
-                       readme_link = COALESCE(EXCLUDED.readme_link, projects.readme_link),
-                       updated_at = EXCLUDED.updated_at"#,
-                &[
-                    &project.id,
-                    &project.title,
-                    &project.description,
-                    &project.readme_link,
-                    &project.slack_id,
-                    &crate::core::parse_datetime(&project.created_at)?,
-                    &crate::core::parse_datetime(&project.updated_at)?,
-                ]
-            ).await.map_err(|e| JobError::Database(e.to_string()))?;
-        }
-        projects_progress.finish();
+        let stored = store_raw_rows(&mut client, &projects, &devlogs, &comments).await?;
 
-        let project_ids: HashSet<i64> = projects.iter().map(|p| p.id).collect();
-
-        let total_devlogs = devlogs.len();
-        let devlogs_progress = ProgressReporter::new_with_job("init", "Storing devlogs");
-        let mut valid_devlog_ids: HashSet<i64> = HashSet::with_capacity(total_devlogs);
-        for (i, devlog) in devlogs.iter().enumerate() {
-            if !project_ids.contains(&devlog.project_id) {
-                continue;
-            }
-            devlogs_progress.report(i + 1, total_devlogs);
-            tx.execute(
-                r#"INSERT INTO logs (id, text, project_id, slack_id, created_at, updated_at)
-                   VALUES ($1, $2, $3, $4, $5, $6)
-                   ON CONFLICT (id) DO UPDATE SET text = EXCLUDED.text, updated_at = EXCLUDED.updated_at"#,
-                &[
-                    &devlog.id,
-                    &devlog.text,
-                    &devlog.project_id,
-                    &devlog.slack_id,
-                    &crate::core::parse_datetime(&devlog.created_at)?,
-                    &crate::core::parse_datetime(&devlog.updated_at)?,
-                ]
-            ).await.map_err(|e| JobError::Database(e.to_string()))?;
-            valid_devlog_ids.insert(devlog.id);
-        }
-        devlogs_progress.finish();
-
-        let total_comments = comments.len();
-        let comments_progress = ProgressReporter::new_with_job("init", "Storing comments");
-        for (i, comment) in comments.iter().enumerate() {
-            if !valid_devlog_ids.contains(&comment.devlog_id) {
-                continue;
-            }
-            comments_progress.report(i + 1, total_comments);
-            tx.execute(
-                r#"INSERT INTO comments (text, devlog_id, slack_id, created_at)
-                   VALUES ($1, $2, $3, $4)
-                   ON CONFLICT (devlog_id, slack_id) DO UPDATE SET text = EXCLUDED.text"#,
-                &[
-                    &comment.text,
-                    &comment.devlog_id,
-                    &comment.slack_id,
-                    &crate::core::parse_datetime(&comment.created_at)?,
-                ],
-            )
-            .await
-            .map_err(|e| JobError::Database(e.to_string()))?;
-        }
-        comments_progress.finish();
-
-        tx.commit()
-            .await
-            .map_err(|e| JobError::Database(e.to_string()))?;
+        self.state.record_stored("projects", stored.projects).await;
+        self.state.record_stored("devlogs", stored.devlogs).await;
+        self.state.record_stored("comments", stored.comments).await;
         Ok(())
     }
 
@@ -272,10 +77,12 @@ impl InitJob {
     ) -> Result<(), JobError> {
         tracing::info!("Syncing user data from leaderboard");
 
-        let leaderboard_response = external_api
-            .fetch_leaderboard()
+        let (leaderboard_response, _) = external_api
+            .fetch_leaderboard(None)
             .await
-            .map_err(|e| JobError::ExternalApi(format!("Failed to fetch leaderboard: {}", e)))?;
+            .map_err(|e| JobError::ExternalApi(format!("Failed to fetch leaderboard: {}", e)))?
+            .ok_or_else(|| JobError::ExternalApi("Leaderboard returned 304 without validators".to_string()))?;
+        crate::core::ensure_leaderboard_not_empty(&leaderboard_response)?;
 
         let client = pool
             .get()
@@ -288,8 +95,8 @@ impl InitJob {
         for (i, user) in leaderboard_response.users.iter().enumerate() {
             progress.report(i + 1, total);
 
-            client
-                .execute(
+            ResourceLimits::global()
+                .limit_db(client.execute(
                     r#"
                 INSERT INTO users (slack_id, username, current_shells, last_synced, pfp_url) 
                 VALUES ($1, $2, $3, NOW(), 'notfound')
@@ -299,9 +106,8 @@ impl InitJob {
                     last_synced = EXCLUDED.last_synced
                 "#,
                     &[&user.slack_id, &user.username, &user.shells],
-                )
-                .await
-                .map_err(|e| JobError::Database(e.to_string()))?;
+                ))
+                .await?;
 
             if let Some(payouts) = &user.payouts {
                 self.process_user_payouts(&user.slack_id, user.shells, payouts, &client)
@@ -315,7 +121,7 @@ impl InitJob {
 
     async fn process_user_payouts(
         &self,
-        slack_id: &str,
+        slack_id: &SlackId,
         final_shells: i32,
         payouts: &[common::utils::modal::RawPayout],
         client: &tokio_postgres::Client,
@@ -346,8 +152,8 @@ impl InitJob {
         shell_history_entries.reverse();
 
         for (recorded_at, shells_then, shell_diff, shells) in shell_history_entries {
-            client
-                .execute(
+            ResourceLimits::global()
+                .limit_db(client.execute(
                     r#"
                 INSERT INTO shell_history (slack_id, shells_then, shell_diff, shells, recorded_at)
                 VALUES ($1, $2, $3, $4, $5)
@@ -360,9 +166,8 @@ impl InitJob {
                         &shells,
                         &recorded_at,
                     ],
-                )
-                .await
-                .map_err(|e| JobError::Database(e.to_string()))?;
+                ))
+                .await?;
         }
 
         Ok(())
@@ -400,17 +205,16 @@ impl InitJob {
         for (i, slack_id) in slack_ids.iter().enumerate() {
             users_progress.report(i + 1, total);
 
-            client
-                .execute(
+            ResourceLimits::global()
+                .limit_db(client.execute(
                     r#"
                 INSERT INTO users (slack_id, pfp_url) 
                 VALUES ($1, 'notfound')
                 ON CONFLICT (slack_id) DO NOTHING
                 "#,
                     &[slack_id],
-                )
-                .await
-                .map_err(|e| JobError::Database(e.to_string()))?;
+                ))
+                .await?;
         }
         users_progress.finish();
 
@@ -418,9 +222,228 @@ impl InitJob {
     }
 }
 
+#[derive(Debug, Default, PartialEq, Eq)]
+struct StoredRows {
+    projects: usize,
+    devlogs: usize,
+    comments: usize,
+    dropped_devlogs: usize,
+    dropped_comments: usize,
+}
+
+/// Writes fetched rows in one transaction, dropping devlogs and comments whose
+/// parent was not fetched.
+async fn store_raw_rows(
+    client: &mut tokio_postgres::Client,
+    projects: &[common::utils::modal::RawProject],
+    devlogs: &[common::utils::modal::RawDevlog],
+    comments: &[common::utils::modal::RawComment],
+) -> Result<StoredRows, JobError> {
+    let tx = client
+        .transaction()
+        .await
+        .map_err(|e| JobError::Database(e.to_string()))?;
+
+    let total_projects = projects.len();
+    let mut stored_devlogs = 0;
+    let mut stored_comments = 0;
+    let projects_progress = ProgressReporter::new_with_job("init", "Storing projects");
+    for (i, project) in projects.iter().enumerate() {
+        projects_progress.report(i + 1, total_projects);
+        ResourceLimits::global().limit_db(tx.execute(
+            r#"INSERT INTO projects (id, title, description, readme_link, category, demo_link, repo_link, slack_id, created_at, updated_at, last_synced)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, NOW())
+               ON CONFLICT (id) DO UPDATE SET 
+                   title = EXCLUDED.title,
+                   description = COALESCE(EXCLUDED.description, projects.description),
+                   readme_link = COALESCE(EXCLUDED.readme_link, projects.readme_link),
+                   category = COALESCE(EXCLUDED.category, projects.category),
+                   demo_link = COALESCE(EXCLUDED.demo_link, projects.demo_link),
+                   repo_link = COALESCE(EXCLUDED.repo_link, projects.repo_link),
+                   updated_at = EXCLUDED.updated_at,
+                   last_synced = EXCLUDED.last_synced"#,
+            &[
+                &project.id,
+                &project.title,
+                &project.description,
+                &project.readme_link,
+                &project.category,
+                &project.demo_link,
+                &project.repo_link,
+                &project.slack_id,
+                &crate::core::parse_datetime(&project.created_at)?,
+                &crate::core::parse_datetime(&project.updated_at)?,
+            ]
+        )).await?;
+    }
+    projects_progress.finish();
+
+    let project_ids: HashSet<i64> = projects.iter().map(|p| p.id).collect();
+
+    let total_devlogs = devlogs.len();
+    let devlogs_progress = ProgressReporter::new_with_job("init", "Storing devlogs");
+    let mut valid_devlog_ids: HashSet<i64> = HashSet::with_capacity(total_devlogs);
+    let mut dropped_devlogs = 0;
+    for (i, devlog) in devlogs.iter().enumerate() {
+        if !project_ids.contains(&devlog.project_id) {
+            dropped_devlogs += 1;
+            continue;
+        }
+        devlogs_progress.report(i + 1, total_devlogs);
+        ResourceLimits::global().limit_db(tx.execute(
+            r#"INSERT INTO logs (id, text, attachment, project_id, slack_id, created_at, updated_at, last_synced)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
+               ON CONFLICT (id) DO UPDATE SET 
+                   text = EXCLUDED.text,
+                   attachment = COALESCE(EXCLUDED.attachment, logs.attachment),
+                   updated_at = EXCLUDED.updated_at,
+                   last_synced = EXCLUDED.last_synced"#,
+            &[
+                &devlog.id,
+                &devlog.text,
+                &devlog.attachment,
+                &devlog.project_id,
+                &devlog.slack_id,
+                &crate::core::parse_datetime(&devlog.created_at)?,
+                &crate::core::parse_datetime(&devlog.updated_at)?,
+            ]
+        )).await?;
+        valid_devlog_ids.insert(devlog.id);
+        stored_devlogs += 1;
+    }
+    devlogs_progress.finish();
+    report_dropped_orphans("devlogs", "project", dropped_devlogs, total_devlogs);
+
+    let total_comments = comments.len();
+    let comments_progress = ProgressReporter::new_with_job("init", "Storing comments");
+    let mut dropped_comments = 0;
+    for (i, comment) in comments.iter().enumerate() {
+        if !valid_devlog_ids.contains(&comment.devlog_id) {
+            dropped_comments += 1;
+            continue;
+        }
+        comments_progress.report(i + 1, total_comments);
+        ResourceLimits::global().limit_db(tx.execute(
+            r#"INSERT INTO comments (text, devlog_id, slack_id, created_at, last_synced, upstream_id)
+               VALUES ($1, $2, $3, $4, NOW(), $5)
+               ON CONFLICT (devlog_id, slack_id) DO UPDATE SET 
+                   text = EXCLUDED.text, 
+                   last_synced = EXCLUDED.last_synced,
+                   upstream_id = COALESCE(EXCLUDED.upstream_id, comments.upstream_id)"#,
+            &[
+                &comment.text,
+                &comment.devlog_id,
+                &comment.slack_id,
+                &crate::core::parse_datetime(&comment.created_at)?,
+                &comment.id,
+            ],
+        )).await?;
+        stored_comments += 1;
+    }
+    comments_progress.finish();
+    report_dropped_orphans("comments", "devlog", dropped_comments, total_comments);
+
+    tx.commit()
+        .await
+        .map_err(|e| JobError::Database(e.to_string()))?;
+
+    Ok(StoredRows {
+        projects: total_projects,
+        devlogs: stored_devlogs,
+        comments: stored_comments,
+        dropped_devlogs,
+        dropped_comments,
+    })
+}
+
+/// Fetches every page of one upstream list, reusing pages saved by an earlier
+/// run and dropping items repeated across shifted pages.
+async fn fetch_all_pages<R, F, Fut>(
+    state: &InitStateFile,
+    range: FetchRange,
+    name: &str,
+    fetch_page: F,
+) -> Result<Vec<R::Item>, JobError>
+where
+    R: Paginated,
+    R::Item: PageItem + serde::Serialize + serde::de::DeserializeOwned,
+    F: Fn(i32) -> Fut + Clone,
+    Fut: std::future::Future<Output = Result<R, common::utils::error::ApiError>>,
+{
+    let mut start_page = range.start_page(1);
+    let mut all_items = Vec::new();
+    let mut seen = HashSet::new();
+    let mut duplicates = 0;
+
+    let resumed_page = state.pages_fetched(name);
+    if resumed_page >= start_page {
+        match state.load_pages(name, start_page..=resumed_page).await {
+            Some(items) => {
+                duplicates += extend_unique(&mut all_items, &mut seen, items);
+                tracing::info!(
+                    "Reusing {} {} from saved pages {}..={}",
+                    all_items.len(),
+                    name,
+                    start_page,
+                    resumed_page
+                );
+                start_page = resumed_page + 1;
+            }
+            None => tracing::warn!("Saved {} pages are incomplete, fetching them again", name),
+        }
+    }
+
+    if range.is_empty(start_page) {
+        if all_items.is_empty() {
+            tracing::warn!("FETCH_MAX_PAGE is before start page {}, skipping {}", start_page, name);
+        }
+        return Ok(all_items);
+    }
+
+    let options = PaginateOptions {
+        start_page,
+        max_pages: range.max_pages(start_page),
+        ..PaginateOptions::default()
+    };
+
+    let mut pages = pin!(paginate(
+        move |page| {
+            let fetch_page = fetch_page.clone();
+            async move {
+                with_retry(&format!("fetch_{}_page_{}", name, page), || {
+                    ResourceLimits::global().limit_fetch(fetch_page(page))
+                })
+                .await
+            }
+        },
+        options,
+    ));
+
+    while let Some(page) = pages.next().await {
+        let page = page?;
+        if let Some(total_pages) = page.total_pages {
+            let progress = (page.number as f64 / total_pages as f64 * 100.0) as u32;
+            print!(
+                "\rFetching {}: {}% ({}/{})",
+                name, progress, page.number, total_pages
+            );
+            std::io::Write::flush(&mut std::io::stdout()).ok();
+        }
+        if state.save_page(name, page.number, &page.items).await {
+            state.record_page(name, page.number, page.items.len()).await;
+        }
+        duplicates += extend_unique(&mut all_items, &mut seen, page.items);
+    }
+    println!();
+    if duplicates > 0 {
+        tracing::info!("Skipped {} duplicate {} repeated across shifted pages", duplicates, name);
+    }
+    Ok(all_items)
+}
+
 #[async_trait]
 impl Job for InitJob {
-    async fn execute(&self, _: &DbPool) -> Result<(), JobError> {
+    async fn execute(&self, _: &DbPool) -> Result<usize, JobError> {
         let pool = Arc::new(
             create_pool(&self.config)
                 .await
@@ -438,48 +461,373 @@ impl Job for InitJob {
         if should_wipe {
             tracing::warn!("WIPING DATABASE - This will delete ALL data!");
             self.wipe_database().await?;
+            self.state.reset().await;
             tracing::warn!("Database wipe completed");
         }
 
         let external_api = Arc::new(
-            ExternalApiService::new(self.config.journey_session_cookie.clone())
+            ExternalApiService::new(&self.config)
                 .map_err(|e| JobError::ExternalApi(e.to_string()))?,
         );
 
+        self.state.set_stage("fetching").await;
         tracing::info!("Fetching all projects from API");
-        let projects = self.fetch_all_projects(&external_api).await?;
+        let projects = self
+            .fetch_all("projects", |page| external_api.fetch_projects(Some(page)))
+            .await?;
         tracing::info!("Fetched {} projects", projects.len());
 
         tracing::info!("Fetching all comments from API");
-        let comments = self.fetch_all_comments(&external_api).await?;
+        let comments = self
+            .fetch_all("comments", |page| external_api.fetch_comments(Some(page)))
+            .await?;
         tracing::info!("Fetched {} comments", comments.len());
 
         tracing::info!("Fetching all devlogs from API");
-        let devlogs = self.fetch_all_devlogs(&external_api).await?;
-        tracing::info!("Fetched {} devlogs", devlogs.len());
-
-        tracing::info!("Creating user records from extracted slack_ids");
-        self.ensure_users_exist(&projects, &comments, &devlogs, &pool)
-            .await?;
-
-        tracing::info!("Syncing user shell data from leaderboard");
-        self.sync_user_data_from_leaderboard(&external_api, &pool)
+        let devlogs = self
+            .fetch_all("devlogs", |page| external_api.fetch_devlogs(Some(page)))
             .await?;
+        tracing::info!("Fetched {} devlogs", devlogs.len());
 
-        tracing::info!("Storing raw data in database");
-        self.store_raw_data(projects.clone(), devlogs.clone(), comments.clone(), &pool)
-            .await?;
+        if self.state.stage_done("storing") {
+            tracing::info!("Users and raw data were stored by the previous run, skipping to embeddings");
+        } else {
+            self.state.set_stage("syncing_users").await;
+            tracing::info!("Creating user records from extracted slack_ids");
+            self.ensure_users_exist(&projects, &comments, &devlogs, &pool)
+                .await?;
+
+            tracing::info!("Syncing user shell data from leaderboard");
+            self.sync_user_data_from_leaderboard(&external_api, &pool)
+                .await?;
+
+            self.state.set_stage("storing").await;
+            tracing::info!("Storing raw data in database");
+            self.store_raw_data(projects.clone(), devlogs.clone(), comments.clone(), &pool)
+                .await?;
+        }
 
+        self.state.set_stage("embedding").await;
         tracing::info!("Embedding all data");
-        InitEmbedder::embed_projects(&projects, Arc::clone(&self.embedding_service), &pool).await?;
-        InitEmbedder::embed_devlogs(&devlogs, Arc::clone(&self.embedding_service), &pool).await?;
-        InitEmbedder::embed_comments(&comments, Arc::clone(&self.embedding_service), &pool).await?;
+        InitEmbedder::embed_projects(&projects, Arc::clone(&self.embedding_service), &pool, &self.state).await?;
+        InitEmbedder::embed_devlogs(&devlogs, Arc::clone(&self.embedding_service), &pool, &self.state).await?;
+        InitEmbedder::embed_comments(&comments, &devlogs, Arc::clone(&self.embedding_service), &pool, &self.state).await?;
+
+        self.state.set_stage("completed").await;
 
         tracing::info!("Initial synchronization completed successfully");
-        Ok(())
+        Ok(projects.len() + comments.len() + devlogs.len())
     }
 
     fn name(&self) -> &str {
         "InitJob"
     }
-}
\ No newline at end of file
+}
+
+const DEFAULT_ORPHAN_WARN_RATIO: f64 = 0.05;
+
+fn report_dropped_orphans(kind: &str, parent: &str, dropped: usize, total: usize) {
+    if dropped == 0 {
+        return;
+    }
+
+    tracing::info!("Dropped {} of {} {} with no matching {}", dropped, total, kind, parent);
+
+    let warn_ratio = std::env::var("ORPHAN_WARN_RATIO")
+        .ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .unwrap_or(DEFAULT_ORPHAN_WARN_RATIO);
+    let ratio = dropped as f64 / total as f64;
+    if ratio > warn_ratio {
+        tracing::warn!(
+            "{:.1}% of {} were dropped for a missing {}; the {} fetch may be incomplete, consider re-running init",
+            ratio * 100.0,
+            kind,
+            parent,
+            parent
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::utils::{
+        error::ApiError,
+        modal::{DevlogsResponse, PaginationInfo, RawComment, RawDevlog, RawProject},
+    };
+    use parking_lot::Mutex;
+    use tokio_postgres::{Client, NoTls};
+
+    /// A three-page devlog feed where page 2 repeats the last item of page 1,
+    /// as offset pagination does when a devlog is added mid-sync.
+    fn three_pages(requested: Arc<Mutex<Vec<i32>>>) -> impl Fn(i32) -> std::future::Ready<Result<DevlogsResponse, ApiError>> + Clone {
+        move |page| {
+            requested.lock().push(page);
+            let ids: &[i64] = match page {
+                1 => &[1, 2],
+                2 => &[2, 3],
+                3 => &[4],
+                _ => &[],
+            };
+            std::future::ready(Ok(DevlogsResponse {
+                devlogs: ids.iter().map(|&id| RawDevlog { id, ..RawDevlog::default() }).collect(),
+                pagination: Some(PaginationInfo {
+                    pages: Some(3),
+                    count: None,
+                    page: Some(page),
+                    items: None,
+                }),
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn fetches_every_page_once_and_drops_repeats() {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let state = InitStateFile::open(None, None);
+
+        let devlogs = fetch_all_pages(&state, FetchRange::default(), "devlogs", three_pages(Arc::clone(&requested)))
+            .await
+            .unwrap();
+
+        assert_eq!(devlogs.iter().map(|devlog| devlog.id).collect::<Vec<_>>(), [1, 2, 3, 4]);
+        assert_eq!(*requested.lock(), [1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn resumed_run_reuses_saved_pages() {
+        let dir = std::env::temp_dir().join(format!("oculus-init-fetch-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("init.json");
+
+        let first_run = InitStateFile::open(Some(path.clone()), None);
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        fetch_all_pages(&first_run, FetchRange::default(), "devlogs", three_pages(Arc::clone(&requested)))
+            .await
+            .unwrap();
+        drop(first_run);
+
+        let resumed = InitStateFile::open(Some(path), None);
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let devlogs = fetch_all_pages(&resumed, FetchRange::default(), "devlogs", three_pages(Arc::clone(&requested)))
+            .await
+            .unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(devlogs.iter().map(|devlog| devlog.id).collect::<Vec<_>>(), [1, 2, 3, 4]);
+        assert_eq!(*requested.lock(), [4], "only the page after the saved ones is requested");
+    }
+
+    const CREATED_AT: &str = "2025-06-01T12:00:00Z";
+
+    /// Connects to a fresh schema holding the tables init writes raw rows to.
+    async fn raw_rows_schema(database_url: &str, schema: &str) -> Client {
+        let (client, connection) = tokio_postgres::connect(database_url, NoTls).await.unwrap();
+        tokio::spawn(connection);
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE projects (
+                     id BIGINT PRIMARY KEY, title TEXT NOT NULL, description TEXT, category TEXT, readme_link TEXT,
+                     demo_link TEXT, repo_link TEXT, slack_id TEXT NOT NULL, created_at TIMESTAMPTZ,
+                     updated_at TIMESTAMPTZ, last_synced TIMESTAMPTZ
+                 );
+                 CREATE TABLE logs (
+                     id BIGINT PRIMARY KEY, text TEXT NOT NULL, attachment TEXT,
+                     project_id BIGINT NOT NULL REFERENCES projects(id), slack_id TEXT NOT NULL,
+                     created_at TIMESTAMPTZ, updated_at TIMESTAMPTZ, last_synced TIMESTAMPTZ
+                 );
+                 CREATE TABLE comments (
+                     id BIGSERIAL PRIMARY KEY, text TEXT NOT NULL, devlog_id BIGINT NOT NULL REFERENCES logs(id),
+                     slack_id TEXT NOT NULL, created_at TIMESTAMPTZ, last_synced TIMESTAMPTZ, upstream_id BIGINT,
+                     UNIQUE (devlog_id, slack_id)
+                 );"
+            ))
+            .await
+            .unwrap();
+        client
+    }
+
+    fn project(id: i64) -> RawProject {
+        RawProject {
+            id,
+            title: format!("project {id}"),
+            slack_id: "U1".into(),
+            created_at: CREATED_AT.to_owned(),
+            updated_at: CREATED_AT.to_owned(),
+            ..RawProject::default()
+        }
+    }
+
+    fn devlog(id: i64, project_id: i64) -> RawDevlog {
+        RawDevlog {
+            id,
+            text: format!("devlog {id}"),
+            project_id,
+            slack_id: "U1".into(),
+            created_at: CREATED_AT.to_owned(),
+            updated_at: CREATED_AT.to_owned(),
+            ..RawDevlog::default()
+        }
+    }
+
+    fn comment(devlog_id: i64, slack_id: &str) -> RawComment {
+        RawComment {
+            text: format!("comment on {devlog_id}"),
+            devlog_id,
+            slack_id: slack_id.into(),
+            created_at: CREATED_AT.to_owned(),
+            ..RawComment::default()
+        }
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn orphaned_rows_are_counted_as_dropped() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let schema = format!("init_orphans_test_{}", std::process::id());
+        let mut client = raw_rows_schema(&database_url, &schema).await;
+
+        let stored = store_raw_rows(
+            &mut client,
+            &[project(1)],
+            &[devlog(10, 1), devlog(11, 99)],
+            &[comment(10, "U2"), comment(11, "U2"), comment(99, "U3")],
+        )
+        .await;
+        let comment_rows: i64 = client
+            .query_one("SELECT COUNT(*) FROM comments", &[])
+            .await
+            .unwrap()
+            .get(0);
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            stored.unwrap(),
+            StoredRows {
+                projects: 1,
+                devlogs: 1,
+                comments: 1,
+                dropped_devlogs: 1,
+                dropped_comments: 2,
+            }
+        );
+        assert_eq!(comment_rows, 1);
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn project_links_and_category_persist_from_the_payload() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let payload: common::utils::modal::ProjectsResponse = serde_json::from_str(
+            r#"{"projects": [{
+                "id": 7, "title": "Weather station", "description": "Logs sensor readings",
+                "readme_link": null, "category": "hardware",
+                "demo_link": "https://example.com/demo", "repo_link": "https://github.com/example/station",
+                "slack_id": "U1", "created_at": "2025-06-01T12:00:00Z", "updated_at": "2025-06-02T12:00:00Z"
+            }], "pagination": null}"#,
+        )
+        .unwrap();
+        let schema = format!("init_project_fields_test_{}", std::process::id());
+        let mut client = raw_rows_schema(&database_url, &schema).await;
+
+        store_raw_rows(&mut client, &payload.projects, &[], &[]).await.unwrap();
+        let row = client
+            .query_one("SELECT category, demo_link, repo_link FROM projects WHERE id = 7", &[])
+            .await
+            .unwrap();
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(row.get::<_, Option<String>>("category").as_deref(), Some("hardware"));
+        assert_eq!(row.get::<_, Option<String>>("demo_link").as_deref(), Some("https://example.com/demo"));
+        assert_eq!(
+            row.get::<_, Option<String>>("repo_link").as_deref(),
+            Some("https://github.com/example/station")
+        );
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn devlog_attachment_persists_from_the_payload() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let payload: DevlogsResponse = serde_json::from_str(
+            r#"{"devlogs": [
+                {"id": 10, "text": "Soldered the board", "attachment": "https://cdn.example.com/board.png",
+                 "project_id": 1, "slack_id": "U1", "created_at": "2025-06-01T12:00:00Z", "updated_at": "2025-06-01T12:00:00Z"},
+                {"id": 11, "text": "No picture today", "project_id": 1, "slack_id": "U1",
+                 "created_at": "2025-06-02T12:00:00Z", "updated_at": "2025-06-02T12:00:00Z"}
+            ], "pagination": null}"#,
+        )
+        .unwrap();
+        let schema = format!("init_attachment_test_{}", std::process::id());
+        let mut client = raw_rows_schema(&database_url, &schema).await;
+
+        store_raw_rows(&mut client, &[project(1)], &payload.devlogs, &[]).await.unwrap();
+        let attachments: Vec<Option<String>> = client
+            .query("SELECT attachment FROM logs ORDER BY id", &[])
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(attachments, [Some("https://cdn.example.com/board.png".to_owned()), None]);
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn stored_rows_have_last_synced() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let schema = format!("init_last_synced_test_{}", std::process::id());
+        let mut client = raw_rows_schema(&database_url, &schema).await;
+
+        store_raw_rows(&mut client, &[project(1)], &[devlog(10, 1)], &[comment(10, "U2")])
+            .await
+            .unwrap();
+        let unsynced: i64 = client
+            .query_one(
+                "SELECT (SELECT COUNT(*) FROM projects WHERE last_synced IS NULL)
+                      + (SELECT COUNT(*) FROM logs WHERE last_synced IS NULL)
+                      + (SELECT COUNT(*) FROM comments WHERE last_synced IS NULL)",
+                &[],
+            )
+            .await
+            .unwrap()
+            .get(0);
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(unsynced, 0);
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+const STAGES: [&str; 5] = ["fetching", "syncing_users", "storing", "embedding", "completed"];
+
+fn stage_index(stage: &str) -> Option<usize> {
+    STAGES.iter().position(|s| *s == stage)
+}
+
+/// Progress snapshot written to `INIT_STATE_FILE` so an interrupted init can
+/// be inspected and resumed.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct InitState {
+    pub stage: String,
+    pub pages_fetched: BTreeMap<String, i32>,
+    pub items_fetched: BTreeMap<String, usize>,
+    pub items_stored: BTreeMap<String, usize>,
+    pub embeddings_done: BTreeMap<String, usize>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+pub struct InitStateFile {
+    path: Option<PathBuf>,
+    pages_dir: Option<PathBuf>,
+    state: Mutex<InitState>,
+    /// Serializes file writes so an older snapshot never replaces a newer one.
+    write_lock: tokio::sync::Mutex<()>,
+}
+
+impl InitStateFile {
+    /// Reads `INIT_STATE_FILE` and `INIT_OUTPUT_DIR`. Without a state file
+    /// progress is only tracked in memory and nothing is resumed.
+    pub fn from_env() -> Self {
+        Self::open(
+            std::env::var("INIT_STATE_FILE").ok().map(PathBuf::from),
+            std::env::var("INIT_OUTPUT_DIR").ok().map(PathBuf::from),
+        )
+    }
+
+    /// Seeds progress from `path` unless the previous run completed. Fetched
+    /// pages are kept under `output_dir`, or next to the state file by default.
+    pub fn open(path: Option<PathBuf>, output_dir: Option<PathBuf>) -> Self {
+        let state = match path.as_deref().and_then(Self::load) {
+            Some(previous) if previous.stage != "completed" => {
+                tracing::info!(
+                    "Resuming init from stage '{}' (pages fetched: {:?}, embeddings done: {:?})",
+                    previous.stage,
+                    previous.pages_fetched,
+                    previous.embeddings_done
+                );
+                previous
+            }
+            _ => InitState::default(),
+        };
+        let pages_dir = path
+            .as_ref()
+            .map(|path| output_dir.unwrap_or_else(|| path.with_extension("pages")));
+
+        Self {
+            path,
+            pages_dir,
+            state: Mutex::new(state),
+            write_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn load(path: &Path) -> Option<InitState> {
+        let contents = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&contents)
+            .map_err(|e| tracing::warn!("Ignoring unreadable init state file {}: {}", path.display(), e))
+            .ok()
+    }
+
+    fn snapshot<T>(&self, read: impl FnOnce(&InitState) -> T) -> T {
+        read(&self.state.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Whether a previous run already moved past `stage`.
+    pub fn stage_done(&self, stage: &str) -> bool {
+        self.snapshot(|state| match (stage_index(&state.stage), stage_index(stage)) {
+            (Some(current), Some(stage)) => current > stage,
+            _ => false,
+        })
+    }
+
+    pub fn pages_fetched(&self, kind: &str) -> i32 {
+        self.snapshot(|state| state.pages_fetched.get(kind).copied().unwrap_or(0))
+    }
+
+    pub fn embedded(&self, kind: &str) -> usize {
+        self.snapshot(|state| state.embeddings_done.get(kind).copied().unwrap_or(0))
+    }
+
+    /// Drops any resumed progress, e.g. after the database was wiped.
+    pub async fn reset(&self) {
+        self.update(|state| *state = InitState::default()).await;
+    }
+
+    /// Moves to `stage`; never goes back to a stage a resumed run already passed.
+    pub async fn set_stage(&self, stage: &str) {
+        self.update(|state| {
+            if stage_index(stage) > stage_index(&state.stage) {
+                state.stage = stage.to_owned();
+            }
+        })
+        .await;
+    }
+
+    pub async fn record_page(&self, kind: &str, page: i32, items: usize) {
+        self.update(|state| {
+            let last = state.pages_fetched.entry(kind.to_owned()).or_default();
+            *last = (*last).max(page);
+            *state.items_fetched.entry(kind.to_owned()).or_default() += items;
+        })
+        .await;
+    }
+
+    pub async fn record_stored(&self, kind: &str, count: usize) {
+        self.update(|state| {
+            state.items_stored.insert(kind.to_owned(), count);
+        })
+        .await;
+    }
+
+    pub async fn record_embedded(&self, kind: &str, count: usize) {
+        self.update(|state| {
+            state.embeddings_done.insert(kind.to_owned(), count);
+        })
+        .await;
+    }
+
+    /// Keeps a fetched page on disk so a resumed run does not fetch it again.
+    /// Returns whether the page was saved.
+    pub async fn save_page<T: Serialize>(&self, kind: &str, page: i32, items: &[T]) -> bool {
+        let Some(dir) = &self.pages_dir else {
+            return false;
+        };
+
+        let path = dir.join(kind).join(format!("{}.json", page));
+        let result = match serde_json::to_vec(items) {
+            Ok(json) => write_atomic(&path, json).await,
+            Err(e) => Err(std::io::Error::other(e)),
+        };
+        if let Err(e) = &result {
+            tracing::warn!("Failed to save {} page {} to {}: {}", kind, page, path.display(), e);
+        }
+        result.is_ok()
+    }
+
+    /// Items of the saved `pages`, or `None` if any of them is missing.
+    pub async fn load_pages<T: DeserializeOwned>(&self, kind: &str, pages: RangeInclusive<i32>) -> Option<Vec<T>> {
+        let dir = self.pages_dir.as_ref()?.join(kind);
+
+        let mut items = Vec::new();
+        for page in pages {
+            let path = dir.join(format!("{}.json", page));
+            let page_items: Vec<T> = match tokio::fs::read(&path).await {
+                Ok(json) => serde_json::from_slice(&json).map_err(std::io::Error::other),
+                Err(e) => Err(e),
+            }
+            .map_err(|e| tracing::warn!("Cannot reuse saved {} page {}: {}", kind, page, e))
+            .ok()?;
+            items.extend(page_items);
+        }
+        Some(items)
+    }
+
+    async fn update(&self, apply: impl FnOnce(&mut InitState)) {
+        let _write = self.write_lock.lock().await;
+
+        let json = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            apply(&mut state);
+            state.updated_at = Some(Utc::now());
+            self.path.as_ref().map(|_| serde_json::to_vec_pretty(&*state))
+        };
+
+        let (Some(path), Some(json)) = (&self.path, json) else {
+            return;
+        };
+        let result = match json {
+            Ok(json) => write_atomic(path, json).await,
+            Err(e) => Err(std::io::Error::other(e)),
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to write init state to {}: {}", path.display(), e);
+        }
+    }
+}
+
+async fn write_atomic(path: &Path, contents: Vec<u8>) -> std::io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    tokio::fs::write(&tmp_path, contents).await?;
+    tokio::fs::rename(&tmp_path, path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("oculus-init-state-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn state_file_tracks_progress_and_survives_restart() {
+        let dir = scratch_dir("restart");
+        let path = dir.join("init.json");
+
+        let state = InitStateFile::open(Some(path.clone()), None);
+        state.set_stage("fetching").await;
+        assert!(state.save_page("projects", 1, &[1, 2]).await);
+        state.record_page("projects", 1, 2).await;
+        assert!(state.save_page("projects", 2, &[3]).await);
+        state.record_page("projects", 2, 1).await;
+        state.set_stage("storing").await;
+        state.record_stored("projects", 3).await;
+        state.set_stage("embedding").await;
+        state.record_embedded("projects", 2).await;
+
+        let on_disk: InitState = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(on_disk.stage, "embedding");
+        assert_eq!(on_disk.pages_fetched["projects"], 2);
+        assert_eq!(on_disk.items_fetched["projects"], 3);
+        assert_eq!(on_disk.items_stored["projects"], 3);
+        assert_eq!(on_disk.embeddings_done["projects"], 2);
+        drop(state);
+
+        let resumed = InitStateFile::open(Some(path.clone()), None);
+        assert!(resumed.stage_done("storing"));
+        assert!(!resumed.stage_done("embedding"));
+        assert_eq!(resumed.pages_fetched("projects"), 2);
+        assert_eq!(resumed.embedded("projects"), 2);
+        assert_eq!(resumed.load_pages::<i32>("projects", 1..=2).await, Some(vec![1, 2, 3]));
+        assert_eq!(resumed.load_pages::<i32>("projects", 1..=3).await, None);
+
+        resumed.set_stage("fetching").await;
+        assert!(resumed.stage_done("storing"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn completed_run_starts_fresh() {
+        let dir = scratch_dir("completed");
+        let path = dir.join("init.json");
+
+        let state = InitStateFile::open(Some(path.clone()), None);
+        state.record_page("devlogs", 4, 10).await;
+        state.set_stage("completed").await;
+        drop(state);
+
+        let next = InitStateFile::open(Some(path.clone()), None);
+        assert!(!next.stage_done("fetching"));
+        assert_eq!(next.pages_fetched("devlogs"), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
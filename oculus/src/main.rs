@@ -1,5 +1,6 @@
 mod init;
 mod core;
+mod eval;
 mod forge;
 mod prune;
 mod trace;
@@ -18,25 +19,62 @@ use common::{
 };
 
 use init::InitJob;
-use core::{Job, JobError, JobScheduler, progress::init_global_progress};
+use core::{Job, JobError, JobScheduler, limits::ResourceLimits, progress::init_global_progress};
 use forge::ForgeJob;
 use prune::PruneJob;
 use trace::TraceJob;
 use reform::ReformJob;
 use zenith::ZenithJob;
 
+const JOB_DESCRIPTIONS: &[(&str, &str)] = &[
+    ("init", "Initialize database with fresh data"),
+    ("reform", "Reform/rebuild embeddings"),
+    ("forge", "Process and generate embeddings"),
+    ("prune", "Clean up old/invalid data"),
+    ("trace", "Continuous data monitoring"),
+    ("zenith", "Peak performance optimization"),
+];
+
+fn job_list_json() -> serde_json::Value {
+    JOB_DESCRIPTIONS
+        .iter()
+        .map(|(name, description)| serde_json::json!({ "name": name, "description": description }))
+        .collect()
+}
+
 fn parse_disabled_jobs(matches: &clap::ArgMatches) -> HashSet<String> {
-    let mut disabled = HashSet::with_capacity(6);
+    let from_flag = matches.get_one::<String>("disable").map(String::as_str);
+    let from_env = std::env::var("DISABLE_JOBS").ok();
 
-    if let Some(disabled_str) = matches.get_one::<String>("disable") {
-        disabled.extend(disabled_str.split(',').map(|s| s.trim().to_owned()));
+    let (disabled, unknown) = known_job_names([from_flag, from_env.as_deref()].into_iter().flatten());
+    for name in unknown {
+        tracing::warn!("{}", unknown_job_warning(&name));
     }
+    disabled
+}
+
+/// Splits comma-separated job lists into known job names and unknown ones.
+fn known_job_names<'a>(lists: impl IntoIterator<Item = &'a str>) -> (HashSet<String>, Vec<String>) {
+    let mut known = HashSet::with_capacity(JOB_DESCRIPTIONS.len());
+    let mut unknown = Vec::new();
 
-    if let Ok(env_disabled) = std::env::var("DISABLE_JOBS") {
-        disabled.extend(env_disabled.split(',').map(|s| s.trim().to_owned()));
+    for name in lists.into_iter().flat_map(|list| list.split(',')).map(str::trim) {
+        if name.is_empty() {
+            continue;
+        }
+        if JOB_DESCRIPTIONS.iter().any(|(job, _)| *job == name) {
+            known.insert(name.to_owned());
+        } else {
+            unknown.push(name.to_owned());
+        }
     }
 
-    disabled
+    (known, unknown)
+}
+
+fn unknown_job_warning(name: &str) -> String {
+    let valid: Vec<&str> = JOB_DESCRIPTIONS.iter().map(|(job, _)| *job).collect();
+    format!("Ignoring unknown job '{}' in disabled jobs. Valid options: {}", name, valid.join(", "))
 }
 
 async fn create_shared_pool(config: &Config) -> Result<Arc<DbPool>> {
@@ -50,7 +88,7 @@ fn create_job(
     job_type: &str,
     config: Config,
     embedding_service: Arc<EmbeddingService>,
-) -> Result<Arc<dyn Job>> {
+) -> std::result::Result<Arc<dyn Job>, JobError> {
     let job: Arc<dyn Job> = match job_type {
         "forge" => Arc::new(ForgeJob::new(config, embedding_service)),
         "prune" => Arc::new(PruneJob::new(config, embedding_service)),
@@ -59,11 +97,10 @@ fn create_job(
         "reform" => Arc::new(ReformJob::new(config, embedding_service)),
         "zenith" => Arc::new(ZenithJob::new(config)),
         _ => {
-            eprintln!(
+            return Err(JobError::Other(format!(
                 "Invalid job type: {}. Valid options: forge, prune, trace, init, reform, zenith",
                 job_type
-            );
-            std::process::exit(1);
+            )));
         }
     };
     Ok(job)
@@ -73,8 +110,10 @@ async fn run_jobs_sequential(
     job_types: &[&str],
     config: &Config,
     embedding_service: &Arc<EmbeddingService>,
-) -> Result<()> {
-    let shared_pool = create_shared_pool(config).await?;
+) -> std::result::Result<(), JobError> {
+    let shared_pool = create_shared_pool(config)
+        .await
+        .map_err(|e| JobError::Database(e.to_string()))?;
     let mut scheduler = JobScheduler::new(Arc::clone(&shared_pool));
     scheduler.reserve_jobs(job_types.len());
 
@@ -84,29 +123,28 @@ async fn run_jobs_sequential(
     }
 
     tracing::info!("Running jobs: {}", job_types.join(", "));
-    if let Err(e) = scheduler.run_all_sequential().await {
-        tracing::error!("Job execution failed: {}", e);
-        std::process::exit(1);
-    }
-    Ok(())
+    scheduler.run_all_sequential().await
 }
 
 async fn run_single_job(
     job_type: &str,
     config: &Config, 
     embedding_service: &Arc<EmbeddingService>,
-) -> Result<()> {
+) -> std::result::Result<(), JobError> {
     let job = create_job(job_type, config.clone(), embedding_service.clone())?;
-    let shared_pool = create_shared_pool(config).await?;
+    let shared_pool = create_shared_pool(config)
+        .await
+        .map_err(|e| JobError::Database(e.to_string()))?;
     let mut scheduler = JobScheduler::new(Arc::clone(&shared_pool));
     scheduler.add_job(job);
     
     tracing::info!("Running {} job", job_type);
-    if let Err(e) = scheduler.run_all_sequential().await {
-        tracing::error!("{} job failed: {}", job_type, e);
-        std::process::exit(1);
-    }
-    Ok(())
+    scheduler.run_all_sequential().await
+}
+
+fn exit_with_job_error(context: &str, error: &JobError) -> ! {
+    tracing::error!("{} failed: {}", context, error);
+    std::process::exit(error.exit_code());
 }
 
 #[tokio::main]
@@ -130,6 +168,16 @@ async fn main() -> Result<()> {
                 .help("List all available jobs and exit")
                 .action(clap::ArgAction::SetTrue)
         )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help("Output format for --list")
+                .value_parser(["text", "json"])
+                .default_value("text")
+                .requires("list")
+                .action(clap::ArgAction::Set)
+        )
         .arg(
             Arg::new("disable")
                 .long("disable")
@@ -137,8 +185,37 @@ async fn main() -> Result<()> {
                 .help("Disable specific jobs (comma-separated: forge,prune,trace,zenith)")
                 .action(clap::ArgAction::Set)
         )
+        .arg(
+            Arg::new("rollback")
+                .long("rollback")
+                .value_name("N")
+                .help("Roll back the last N applied migrations (default 1) and exit")
+                .num_args(0..=1)
+                .default_missing_value("1")
+                .value_parser(clap::value_parser!(usize))
+                .action(clap::ArgAction::Set)
+        )
+        .arg(
+            Arg::new("eval-search")
+                .long("eval-search")
+                .help("Score current search rankings against recorded click feedback and exit")
+                .action(clap::ArgAction::SetTrue)
+        )
         .get_matches();
 
+    if matches.get_flag("list") {
+        if matches.get_one::<String>("format").is_some_and(|f| f == "json") {
+            println!("{}", job_list_json());
+            return Ok(());
+        }
+
+        println!("Available jobs:");
+        for (name, desc) in JOB_DESCRIPTIONS {
+            println!("  {:<8} - {}", name, desc);
+        }
+        return Ok(());
+    }
+
     if let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
         let env_path = std::path::Path::new(&manifest_dir).join(".env");
         if env_path.exists() {
@@ -158,24 +235,16 @@ async fn main() -> Result<()> {
 
     init_global_progress();
 
-    if matches.get_flag("list") {
-        const JOB_DESCRIPTIONS: &[(&str, &str)] = &[
-            ("init", "Initialize database with fresh data"),
-            ("reform", "Reform/rebuild embeddings"),
-            ("forge", "Process and generate embeddings"),
-            ("prune", "Clean up old/invalid data"),
-            ("trace", "Continuous data monitoring"),
-            ("zenith", "Peak performance optimization"),
-        ];
+    let config = Config::from_env()?;
+    ResourceLimits::install(&config);
 
-        println!("Available jobs:");
-        for (name, desc) in JOB_DESCRIPTIONS {
-            println!("  {:<8} - {}", name, desc);
-        }
+    if let Some(&count) = matches.get_one::<usize>("rollback") {
+        let pool = create_shared_pool(&config).await?;
+        let rolled_back = common::database::connection::rollback_migrations(&pool, count).await?;
+        tracing::info!("Rolled back {} migration(s)", rolled_back.len());
         return Ok(());
     }
 
-    let config = Config::from_env()?;
     let disabled_jobs = parse_disabled_jobs(&matches);
 
     let embedding_service = Arc::new(EmbeddingService::new(false).map_err(|e| {
@@ -185,9 +254,20 @@ async fn main() -> Result<()> {
         ))
     })?);
 
+    if matches.get_flag("eval-search") {
+        let pool = create_shared_pool(&config).await?;
+        match eval::evaluate_search(&pool, &embedding_service).await {
+            Ok(report) => eval::print_report(&report),
+            Err(e) => exit_with_job_error("Search evaluation", &e),
+        }
+        return Ok(());
+    }
+
     if let Some(job_types_str) = matches.get_one::<String>("jobs") {
         let job_types: Vec<&str> = job_types_str.split(',').map(str::trim).collect();
-        run_jobs_sequential(&job_types, &config, &embedding_service).await?;
+        if let Err(e) = run_jobs_sequential(&job_types, &config, &embedding_service).await {
+            exit_with_job_error("Job execution", &e);
+        }
         return Ok(());
     }
 
@@ -196,7 +276,9 @@ async fn main() -> Result<()> {
         .to_lowercase()
         == "true"
     {
-        run_single_job("reform", &config, &embedding_service).await?;
+        if let Err(e) = run_single_job("reform", &config, &embedding_service).await {
+            exit_with_job_error("reform job", &e);
+        }
         return Ok(());
     }
 
@@ -249,7 +331,9 @@ async fn main() -> Result<()> {
     let shared_pool = pool.clone();
 
     if should_run_init {
-        run_single_job("init", &config, &embedding_service).await?;
+        if let Err(e) = run_single_job("init", &config, &embedding_service).await {
+            exit_with_job_error("init job", &e);
+        }
         if force_wipe {
             tracing::info!("Initialization complete - exiting due to WIPE=true");
             return Ok(());
@@ -347,3 +431,41 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_jobs_keep_known_names_and_drop_the_rest() {
+        let (known, unknown) = known_job_names(["forge, nope ,trace", "", "trace,zenith,,frog"]);
+
+        let mut known: Vec<_> = known.into_iter().collect();
+        known.sort();
+        assert_eq!(known, ["forge", "trace", "zenith"]);
+        assert_eq!(unknown, ["nope", "frog"]);
+    }
+
+    #[test]
+    fn unknown_job_warning_lists_every_job() {
+        let warning = unknown_job_warning("frog");
+        assert!(warning.contains("'frog'"));
+        for (job, _) in JOB_DESCRIPTIONS {
+            assert!(warning.contains(job), "{job} missing from: {warning}");
+        }
+    }
+
+    #[test]
+    fn job_list_json_describes_every_job() {
+        let jobs = job_list_json();
+        let jobs = jobs.as_array().unwrap();
+
+        assert_eq!(jobs.len(), 6);
+        for job in jobs {
+            assert!(job["name"].as_str().is_some_and(|name| !name.is_empty()), "{job}");
+            assert!(job["description"].as_str().is_some_and(|text| !text.is_empty()), "{job}");
+        }
+        let names: Vec<_> = jobs.iter().map(|job| job["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["init", "reform", "forge", "prune", "trace", "zenith"]);
+    }
+}
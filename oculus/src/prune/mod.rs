@@ -1,11 +1,20 @@
 use crate::core::progress::ProgressReporter;
-use crate::core::{with_retry, Job, JobError};
+use crate::core::{
+    dev_mode_max_pages, limits::ResourceLimits, project_embedding_text, project_embedding_text_from_parts, storable_embedding,
+    with_retry, Job, JobError,
+};
 use async_trait::async_trait;
 use common::{
     database::manager::ConnectionManager,
     services::{external::ExternalApiService, EmbeddingService},
-    utils::config::Config,
+    utils::{
+        config::Config,
+        pagination::{paginate, PageItem, PaginateOptions, Paginated},
+    },
 };
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::pin::pin;
 use std::sync::Arc;
 
 
@@ -44,96 +53,95 @@ impl PruneJob {
         Ok(())
     }
 
-    async fn fetch_all_external_projects(
-        &self,
-        external_api: &ExternalApiService,
-    ) -> Result<std::collections::HashMap<i64, common::utils::modal::RawProject>, JobError> {
-        let mut all_projects = std::collections::HashMap::new();
-        let mut page = 1;
-
-        loop {
-            let response = with_retry(&format!("fetch_external_projects_page_{}", page), || {
-                external_api.fetch_projects(Some(page))
-            })
-            .await
-            .map_err(|e| JobError::ExternalApi(e.to_string()))?;
-
-            if response.projects.is_empty() {
-                break;
-            }
-
-            for project in response.projects {
-                all_projects.insert(project.id, project);
-            }
-
-            if let Some(pagination) = response.pagination {
-                if let Some(total_pages) = pagination.pages {
-                    if page >= total_pages {
-                        break;
-                    }
-                }
-            }
+    /// Collapses `shell_history` rows older than `SHELL_HISTORY_RETENTION_DAYS`
+    /// into one row per user per UTC day. The surviving row is the day's last
+    /// value, with `shells_then`/`shell_diff` widened to span the whole day so
+    /// the series keeps its shape. Disabled unless the retention is set.
+    async fn compact_shell_history(&self, pool: &common::database::DbPool) -> Result<(), JobError> {
+        let Some(retention_days) = std::env::var("SHELL_HISTORY_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse::<i32>().ok())
+            .filter(|&days| days > 0)
+        else {
+            return Ok(());
+        };
 
-            page += 1;
+        let mut client = pool
+            .get()
+            .await
+            .map_err(|e| JobError::Database(e.to_string()))?;
+        let removed = compact_shell_history_older_than(&mut client, retention_days).await?;
+
+        if removed > 0 {
+            tracing::info!(
+                "Compacted shell history older than {} days, removed {} rows",
+                retention_days,
+                removed
+            );
         }
 
-        Ok(all_projects)
+        Ok(())
     }
 
-    async fn fetch_all_external_devlogs(
+    async fn fetch_all_external<R, F, Fut>(
         &self,
-        external_api: &ExternalApiService,
-    ) -> Result<std::collections::HashMap<i64, common::utils::modal::RawDevlog>, JobError> {
-        let mut all_devlogs = std::collections::HashMap::new();
-        let mut page = 1;
-
-        loop {
-            let response = with_retry(&format!("fetch_external_devlogs_page_{}", page), || {
-                external_api.fetch_devlogs(Some(page))
-            })
-            .await
-            .map_err(|e| JobError::ExternalApi(e.to_string()))?;
-
-            if response.devlogs.is_empty() {
-                break;
-            }
-
-            for devlog in response.devlogs {
-                all_devlogs.insert(devlog.id, devlog);
-            }
-
-            if let Some(pagination) = response.pagination {
-                if let Some(total_pages) = pagination.pages {
-                    if page >= total_pages {
-                        break;
-                    }
+        name: &str,
+        fetch_page: F,
+    ) -> Result<HashMap<<R::Item as PageItem>::Key, R::Item>, JobError>
+    where
+        R: Paginated,
+        R::Item: PageItem,
+        F: Fn(i32) -> Fut + Clone,
+        Fut: std::future::Future<Output = Result<R, common::utils::error::ApiError>>,
+    {
+        let mut all_items = HashMap::new();
+        let mut pages = pin!(paginate(
+            move |page| {
+                let fetch_page = fetch_page.clone();
+                async move {
+                    with_retry(&format!("fetch_external_{}_page_{}", name, page), || {
+                        ResourceLimits::global().limit_fetch(fetch_page(page))
+                    })
+                    .await
                 }
+            },
+            PaginateOptions {
+                max_pages: dev_mode_max_pages(),
+                ..PaginateOptions::default()
+            },
+        ));
+
+        while let Some(page) = pages.next().await {
+            for item in page?.items {
+                all_items.insert(item.key(), item);
             }
-
-            page += 1;
         }
 
-        Ok(all_devlogs)
+        Ok(all_items)
     }
 
     async fn prune_and_update_projects(
         &self,
-        external_projects: &std::collections::HashMap<i64, common::utils::modal::RawProject>,
+        external_projects: &HashMap<i64, common::utils::modal::RawProject>,
         embedding_service: &EmbeddingService,
         pool: &common::database::DbPool,
-    ) -> Result<(), JobError> {
+        allow_deletes: bool,
+    ) -> Result<usize, JobError> {
         let mut client = pool
             .get()
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
 
         let db_items = client
-            .query("SELECT id, title, description, updated_at FROM projects", &[])
+            .query("SELECT id, title, description, updated_at, category FROM projects", &[])
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
 
+        let limits = ResourceLimits::global();
         let total_items = db_items.len();
         let progress = ProgressReporter::new_with_job("prune", "Pruning and updating projects");
+        let mut unchanged_ids = Vec::with_capacity(total_items);
+        let mut changed = 0;
 
         for (i, row) in db_items.iter().enumerate() {
             progress.report(i + 1, total_items);
@@ -141,56 +149,57 @@ impl PruneJob {
             let db_title: String = row.get(1);
             let db_description: Option<String> = row.get(2);
             let db_updated_at: chrono::DateTime<chrono::Utc> = row.get(3);
+            let db_category: Option<String> = row.get(4);
 
             if let Some(external_project) = external_projects.get(&item_id) {
-                let external_content = format!("{} {}", external_project.title, external_project.description.as_deref().unwrap_or_default()).trim().to_string();
-                let db_content = format!("{} {}", db_title, db_description.as_deref().unwrap_or_default()).trim().to_string();
+                let external_content = project_embedding_text(external_project);
+                let db_content = project_embedding_text_from_parts(&db_title, db_description.as_deref(), db_category.as_deref());
                 
                 let external_updated_at = crate::core::parse_datetime(&external_project.updated_at)?;
                 let needs_update = external_updated_at > db_updated_at || db_content != external_content;
 
                 if needs_update {
-                    let embedding_vec = embedding_service
-                        .embed_text(&external_content)
+                    let embedding_vec = limits
+                        .limit_embed(1, embedding_service.embed_text(&external_content))
                         .await
                         .map_err(|e| JobError::Embedding(e.to_string()))?;
 
-                    let embedding = pgvector::Vector::from(embedding_vec);
+                    let embedding = storable_embedding(embedding_vec);
 
-                    client.execute(
-                        "UPDATE projects SET title = $1, description = $2, updated_at = $3, title_description_embedding = $4 WHERE id = $5",
-                        &[&external_project.title, &external_project.description, &external_updated_at, &embedding, &item_id]
-                    ).await
-                    .map_err(|e| JobError::Database(e.to_string()))?;
+                    limits.limit_db(client.execute(
+                        "UPDATE projects SET title = $1, description = $2, updated_at = $3, title_description_embedding = $4, category = COALESCE($6, category), demo_link = COALESCE($7, demo_link), repo_link = COALESCE($8, repo_link), last_synced = NOW() WHERE id = $5",
+                        &[&external_project.title, &external_project.description, &external_updated_at, &embedding, &item_id, &external_project.category, &external_project.demo_link, &external_project.repo_link]
+                    )).await?;
+                    changed += 1;
+                } else {
+                    unchanged_ids.push(item_id);
                 }
-            } else {
-                let tx_client = client
-                    .transaction()
-                    .await
-                    .map_err(|e| JobError::Database(e.to_string()))?;
-
-                tx_client
-                    .execute("DELETE FROM projects WHERE id = $1", &[&item_id])
-                    .await
-                    .map_err(|e| JobError::Database(e.to_string()))?;
-
-                tx_client
-                    .commit()
-                    .await
-                    .map_err(|e| JobError::Database(e.to_string()))?;
+            } else if allow_deletes {
+                limits
+                    .limit_db(async {
+                        let tx_client = client.transaction().await?;
+                        tx_client
+                            .execute("DELETE FROM projects WHERE id = $1", &[&item_id])
+                            .await?;
+                        tx_client.commit().await
+                    })
+                    .await?;
+                changed += 1;
             }
         }
 
         progress.finish();
-        Ok(())
+        mark_synced(&client, "projects", &unchanged_ids).await?;
+        Ok(changed)
     }
 
     async fn prune_and_update_devlogs(
         &self,
-        external_devlogs: &std::collections::HashMap<i64, common::utils::modal::RawDevlog>,
+        external_devlogs: &HashMap<i64, common::utils::modal::RawDevlog>,
         embedding_service: &EmbeddingService,
         pool: &common::database::DbPool,
-    ) -> Result<(), JobError> {
+        allow_deletes: bool,
+    ) -> Result<usize, JobError> {
         let mut client = pool
             .get()
             .await
@@ -201,8 +210,11 @@ impl PruneJob {
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
 
+        let limits = ResourceLimits::global();
         let total_items = db_items.len();
         let progress = ProgressReporter::new_with_job("prune", "Pruning and updating devlogs");
+        let mut unchanged_ids = Vec::with_capacity(total_items);
+        let mut changed = 0;
 
         for (i, row) in db_items.iter().enumerate() {
             progress.report(i + 1, total_items);
@@ -216,45 +228,111 @@ impl PruneJob {
                 let needs_update = external_updated_at > db_updated_at || db_content != external_content;
 
                 if needs_update {
-                    let embedding_vec = embedding_service
-                        .embed_text(&external_content)
+                    let embedding_vec = limits
+                        .limit_embed(1, embedding_service.embed_text(&external_content))
                         .await
                         .map_err(|e| JobError::Embedding(e.to_string()))?;
 
-                    let embedding = pgvector::Vector::from(embedding_vec);
+                    let embedding = storable_embedding(embedding_vec);
 
-                    client.execute(
-                        "UPDATE logs SET text = $1, updated_at = $2, text_embedding = $3 WHERE id = $4",
+                    limits.limit_db(client.execute(
+                        "UPDATE logs SET text = $1, updated_at = $2, text_embedding = $3, last_synced = NOW() WHERE id = $4",
                         &[&external_content, &external_updated_at, &embedding, &item_id]
-                    ).await
-                    .map_err(|e| JobError::Database(e.to_string()))?;
+                    )).await?;
+                    changed += 1;
+                } else {
+                    unchanged_ids.push(item_id);
                 }
-            } else {
-                let tx_client = client
-                    .transaction()
-                    .await
-                    .map_err(|e| JobError::Database(e.to_string()))?;
-
-                tx_client
-                    .execute("DELETE FROM logs WHERE id = $1", &[&item_id])
-                    .await
-                    .map_err(|e| JobError::Database(e.to_string()))?;
-
-                tx_client
-                    .commit()
-                    .await
-                    .map_err(|e| JobError::Database(e.to_string()))?;
+            } else if allow_deletes {
+                limits
+                    .limit_db(async {
+                        let tx_client = client.transaction().await?;
+                        tx_client
+                            .execute("DELETE FROM logs WHERE id = $1", &[&item_id])
+                            .await?;
+                        tx_client.commit().await
+                    })
+                    .await?;
+                changed += 1;
             }
         }
 
         progress.finish();
-        Ok(())
+        mark_synced(&client, "logs", &unchanged_ids).await?;
+        Ok(changed)
     }
 }
 
+// Rows that matched upstream were still verified by this run.
+async fn mark_synced(
+    client: &tokio_postgres::Client,
+    table: &str,
+    ids: &[i64],
+) -> Result<(), JobError> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    ResourceLimits::global()
+        .limit_db(client.execute(
+            &format!("UPDATE {table} SET last_synced = NOW() WHERE id = ANY($1)"),
+            &[&ids],
+        ))
+        .await?;
+    Ok(())
+}
+
+async fn compact_shell_history_older_than(
+    client: &mut tokio_postgres::Client,
+    retention_days: i32,
+) -> Result<u64, JobError> {
+    let tx = client
+        .transaction()
+        .await
+        .map_err(|e| JobError::Database(e.to_string()))?;
+
+    let removed = tx
+        .execute(
+            r#"
+        WITH days AS (
+            SELECT
+                slack_id,
+                date_trunc('day', recorded_at AT TIME ZONE 'UTC') AS day,
+                (array_agg(id ORDER BY recorded_at DESC))[1] AS keep_id,
+                (array_agg(shells_then ORDER BY recorded_at ASC))[1] AS first_shells_then,
+                SUM(shell_diff) AS total_diff
+            FROM shell_history
+            WHERE recorded_at < NOW() - make_interval(days => $1)
+            GROUP BY slack_id, date_trunc('day', recorded_at AT TIME ZONE 'UTC')
+            HAVING COUNT(*) > 1
+        ), kept AS (
+            UPDATE shell_history sh
+            SET shells_then = d.first_shells_then, shell_diff = d.total_diff
+            FROM days d
+            WHERE sh.id = d.keep_id
+        )
+        DELETE FROM shell_history sh
+        USING days d
+        WHERE sh.slack_id = d.slack_id
+          AND date_trunc('day', sh.recorded_at AT TIME ZONE 'UTC') = d.day
+          AND sh.recorded_at < NOW() - make_interval(days => $1)
+          AND sh.id <> d.keep_id
+        "#,
+            &[&retention_days],
+        )
+        .await
+        .map_err(|e| JobError::Database(e.to_string()))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| JobError::Database(e.to_string()))?;
+
+    Ok(removed)
+}
+
 #[async_trait]
 impl Job for PruneJob {
-    async fn execute(&self, _: &common::database::DbPool) -> Result<(), JobError> {
+    async fn execute(&self, _: &common::database::DbPool) -> Result<usize, JobError> {
         let pool = Arc::new(
             ConnectionManager::get_dedicated_pool(&self.config)
                 .await
@@ -262,32 +340,105 @@ impl Job for PruneJob {
         );
 
         let external_api = Arc::new(
-            ExternalApiService::new(self.config.journey_session_cookie.clone())
+            ExternalApiService::new(&self.config)
                 .map_err(|e| JobError::ExternalApi(e.to_string()))?,
         );
 
-        let external_projects = self.fetch_all_external_projects(&external_api).await?;
+        let external_projects = self
+            .fetch_all_external("projects", |page| external_api.fetch_projects(Some(page)))
+            .await?;
 
-        let external_devlogs = self.fetch_all_external_devlogs(&external_api).await?;
+        let external_devlogs = self
+            .fetch_all_external("devlogs", |page| external_api.fetch_devlogs(Some(page)))
+            .await?;
 
-        self.prune_and_update_projects(
+        // A capped fetch doesn't see every upstream row, so absence proves nothing.
+        let allow_deletes = dev_mode_max_pages().is_none();
+        if !allow_deletes {
+            tracing::warn!("DEV_MODE page cap is active, skipping deletion of rows missing upstream");
+        }
+
+        let projects_changed = self.prune_and_update_projects(
             &external_projects,
             &self.embedding_service,
             &pool,
+            allow_deletes,
         ).await?;
 
-        self.prune_and_update_devlogs(
+        let devlogs_changed = self.prune_and_update_devlogs(
             &external_devlogs,
             &self.embedding_service,
             &pool,
+            allow_deletes,
         ).await?;
 
         self.cleanup_orphaned_data(&pool).await?;
 
-        Ok(())
+        self.compact_shell_history(&pool).await?;
+
+        Ok(projects_changed + devlogs_changed)
     }
 
     fn name(&self) -> &str {
         "PruneJob"
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_postgres::NoTls;
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn compaction_keeps_endpoints_and_diff_totals() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (mut client, connection) = tokio_postgres::connect(&database_url, NoTls).await.unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("compact_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 SET TIME ZONE 'UTC';
+                 CREATE TABLE shell_history (
+                     id BIGSERIAL PRIMARY KEY, slack_id TEXT NOT NULL, shells_then INTEGER, shell_diff INTEGER,
+                     shells INTEGER NOT NULL, recorded_at TIMESTAMPTZ DEFAULT NOW(), UNIQUE (slack_id, recorded_at)
+                 );
+                 INSERT INTO shell_history (slack_id, shells_then, shell_diff, shells, recorded_at)
+                 SELECT 'U1', shells_then, shell_diff, shells, date_trunc('day', NOW()) - make_interval(days => days_ago, hours => -hour)
+                 FROM (VALUES
+                     (0, 5, 5, 200, 10), (5, 3, 8, 200, 12), (8, 2, 10, 200, 18),
+                     (10, 4, 14, 150, 9),
+                     (14, 1, 15, 1, 8), (15, 6, 21, 1, 9)
+                 ) AS points(shells_then, shell_diff, shells, days_ago, hour);"
+            ))
+            .await
+            .unwrap();
+
+        let removed = compact_shell_history_older_than(&mut client, 90).await.unwrap();
+        let series: Vec<(i32, i32, i32)> = client
+            .query("SELECT shells_then, shell_diff, shells FROM shell_history ORDER BY recorded_at", &[])
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| (row.get(0), row.get(1), row.get(2)))
+            .collect();
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(removed, 2);
+        assert_eq!(series, [(0, 10, 10), (10, 4, 14), (14, 1, 15), (15, 6, 21)]);
+        assert_eq!(series.first().map(|point| point.0), Some(0), "series start moved");
+        assert_eq!(series.last().map(|point| point.2), Some(21), "series end moved");
+        assert_eq!(series.iter().map(|point| point.1).sum::<i32>(), 21, "shell_diff total changed");
+    }
+}
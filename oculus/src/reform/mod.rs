@@ -1,8 +1,14 @@
 use crate::core::progress::ProgressReporter;
-use crate::core::{Job, JobError};
+use crate::core::{
+    comment_context_enabled, comment_embedding_text, limits::ResourceLimits,
+    project_embedding_text_from_parts, storable_embedding, Job, JobError,
+};
 use async_trait::async_trait;
 use common::{
-    database::connection::{create_pool, run_migrations},
+    database::{
+        connection::{create_pool, run_migrations},
+        get_client_with_retry, RetryPolicy,
+    },
     services::EmbeddingService,
     utils::config::Config,
     DbPool,
@@ -30,6 +36,22 @@ fn get_target_from_env() -> Target {
     }
 }
 
+// Reform runs while the explorer serves searches, so each row's vector is
+// replaced by one UPDATE and never cleared first. The UPDATE only applies while
+// the row still holds the text that was embedded, so a concurrent forge or
+// prune edit is not overwritten with a stale vector.
+const UPDATE_PROJECT_EMBEDDING: &str = "UPDATE projects SET title_description_embedding = $2 
+     WHERE id = $1 AND title = $3 AND description IS NOT DISTINCT FROM $4 AND category IS NOT DISTINCT FROM $5";
+const UPDATE_COMMENT_EMBEDDING: &str =
+    "UPDATE comments SET text_embedding = $3 WHERE devlog_id = $1 AND slack_id = $2 AND text = $4";
+const UPDATE_DEVLOG_EMBEDDING: &str = "UPDATE logs SET text_embedding = $2 WHERE id = $1 AND text = $3";
+
+fn report_changed_rows(kind: &str, skipped: usize) {
+    if skipped > 0 {
+        tracing::info!("Left {} {} alone that changed while being re-embedded", skipped, kind);
+    }
+}
+
 pub struct ReformJob {
     config: Config,
     embedding_service: Arc<EmbeddingService>,
@@ -47,114 +69,125 @@ impl ReformJob {
         &self,
         embedding: &EmbeddingService,
         pool: &common::database::connection::DbPool,
-    ) -> Result<(), JobError> {
-        let client = pool
-            .get()
+    ) -> Result<usize, JobError> {
+        let client = get_client_with_retry(pool, RetryPolicy::default())
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
         let rows = client
-            .query("SELECT id, title, description FROM projects", &[])
+            .query("SELECT id, title, description, category FROM projects", &[])
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
+        let limits = ResourceLimits::global();
         let total = rows.len();
+        let mut skipped = 0;
         let progress_reporter = ProgressReporter::new_with_job("reform", "Re-embedding projects");
         for (i, row) in rows.iter().enumerate() {
             progress_reporter.report(i + 1, total);
             let id: i64 = row.get("id");
             let title: String = row.get("title");
             let description: Option<String> = row.get("description");
-            let text = format!("{} {}", title, description.unwrap_or_default());
-            let vec = embedding
-                .embed_text(&text)
+            let category: Option<String> = row.get("category");
+            let text = project_embedding_text_from_parts(&title, description.as_deref(), category.as_deref());
+            let vec = limits
+                .limit_embed(1, embedding.embed_text(&text))
                 .await
                 .map_err(|e| JobError::Embedding(e.to_string()))?;
-            let vector = pgvector::Vector::from(vec);
-            client
-                .execute(
-                    "UPDATE projects SET title_description_embedding = $2 WHERE id = $1",
-                    &[&id, &vector],
-                )
-                .await
-                .map_err(|e| JobError::Database(e.to_string()))?;
+            let vector = storable_embedding(vec);
+            let updated = limits
+                .limit_db(client.execute(
+                    UPDATE_PROJECT_EMBEDDING,
+                    &[&id, &vector, &title, &description, &category],
+                ))
+                .await?;
+            skipped += usize::from(updated == 0);
         }
         progress_reporter.finish();
-        Ok(())
+        report_changed_rows("projects", skipped);
+        Ok(total - skipped)
     }
 
     async fn embed_comments_from_db(
         &self,
         embedding: &EmbeddingService,
         pool: &common::database::connection::DbPool,
-    ) -> Result<(), JobError> {
-        let client = pool
-            .get()
+    ) -> Result<usize, JobError> {
+        let client = get_client_with_retry(pool, RetryPolicy::default())
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
         let rows = client
-            .query("SELECT devlog_id, slack_id, text FROM comments", &[])
+            .query(
+                "SELECT c.devlog_id, c.slack_id, c.text, l.text AS devlog_text 
+                 FROM comments c 
+                 LEFT JOIN logs l ON l.id = c.devlog_id",
+                &[],
+            )
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
+        let with_context = comment_context_enabled();
+        let limits = ResourceLimits::global();
         let total = rows.len();
+        let mut skipped = 0;
         let progress_reporter = ProgressReporter::new_with_job("reform", "Re-embedding comments");
         for (i, row) in rows.iter().enumerate() {
             progress_reporter.report(i + 1, total);
             let devlog_id: i64 = row.get("devlog_id");
             let slack_id: String = row.get("slack_id");
             let text: String = row.get("text");
-            let vec = embedding
-                .embed_text(&text)
+            let devlog_text: Option<String> = with_context.then(|| row.get("devlog_text")).flatten();
+            let vec = limits
+                .limit_embed(1, embedding.embed_text(&comment_embedding_text(&text, devlog_text.as_deref())))
                 .await
                 .map_err(|e| JobError::Embedding(e.to_string()))?;
-            let vector = pgvector::Vector::from(vec);
-            client
-                .execute("UPDATE comments SET text_embedding = $3 WHERE devlog_id = $1 AND slack_id = $2", &[&devlog_id, &slack_id, &vector])
-                .await
-                .map_err(|e| JobError::Database(e.to_string()))?;
+            let vector = storable_embedding(vec);
+            let updated = limits
+                .limit_db(client.execute(UPDATE_COMMENT_EMBEDDING, &[&devlog_id, &slack_id, &vector, &text]))
+                .await?;
+            skipped += usize::from(updated == 0);
         }
         progress_reporter.finish();
-        Ok(())
+        report_changed_rows("comments", skipped);
+        Ok(total - skipped)
     }
 
     async fn embed_devlogs_from_db(
         &self,
         embedding: &EmbeddingService,
         pool: &common::database::connection::DbPool,
-    ) -> Result<(), JobError> {
-        let client = pool
-            .get()
+    ) -> Result<usize, JobError> {
+        let client = get_client_with_retry(pool, RetryPolicy::default())
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
         let rows = client
             .query("SELECT id, text FROM logs", &[])
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
+        let limits = ResourceLimits::global();
         let total = rows.len();
+        let mut skipped = 0;
         let progress_reporter = ProgressReporter::new_with_job("reform", "Re-embedding devlogs");
         for (i, row) in rows.iter().enumerate() {
             progress_reporter.report(i + 1, total);
             let id: i64 = row.get("id");
             let text: String = row.get("text");
-            let vec = embedding
-                .embed_text(&text)
+            let vec = limits
+                .limit_embed(1, embedding.embed_text(&text))
                 .await
                 .map_err(|e| JobError::Embedding(e.to_string()))?;
-            let vector = pgvector::Vector::from(vec);
-            client
-                .execute(
-                    "UPDATE logs SET text_embedding = $2 WHERE id = $1",
-                    &[&id, &vector],
-                )
-                .await
-                .map_err(|e| JobError::Database(e.to_string()))?;
+            let vector = storable_embedding(vec);
+            let updated = limits
+                .limit_db(client.execute(UPDATE_DEVLOG_EMBEDDING, &[&id, &vector, &text]))
+                .await?;
+            skipped += usize::from(updated == 0);
         }
         progress_reporter.finish();
-        Ok(())
+        report_changed_rows("devlogs", skipped);
+        Ok(total - skipped)
     }
 }
 
 #[async_trait]
 impl Job for ReformJob {
-    async fn execute(&self, _pool: &DbPool) -> Result<(), JobError> {
+    async fn execute(&self, _pool: &DbPool) -> Result<usize, JobError> {
         tracing::info!("Starting reform embedding job");
         let pool = Arc::new(
             create_pool(&self.config)
@@ -168,7 +201,7 @@ impl Job for ReformJob {
         let embedding_service = &self.embedding_service;
         let target = get_target_from_env();
 
-        match target {
+        let processed = match target {
             Target::Projects => {
                 self.embed_projects_from_db(embedding_service, &pool)
                     .await?
@@ -180,18 +213,175 @@ impl Job for ReformJob {
             Target::Devlogs => self.embed_devlogs_from_db(embedding_service, &pool).await?,
             Target::All => {
                 self.embed_projects_from_db(embedding_service, &pool)
-                    .await?;
-                self.embed_comments_from_db(embedding_service, &pool)
-                    .await?;
-                self.embed_devlogs_from_db(embedding_service, &pool).await?;
+                    .await?
+                    + self.embed_comments_from_db(embedding_service, &pool)
+                        .await?
+                    + self.embed_devlogs_from_db(embedding_service, &pool).await?
             }
-        }
+        };
 
         tracing::info!("Reform embedding job completed successfully");
-        Ok(())
+        Ok(processed)
     }
 
     fn name(&self) -> &str {
         "ReformJob"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::services::embedding::EMBEDDING_DIM;
+    use pgvector::Vector;
+    use tokio_postgres::{Client, NoTls};
+
+    async fn connect(database_url: &str, schema: &str) -> Client {
+        let (client, connection) = tokio_postgres::connect(database_url, NoTls).await.unwrap();
+        tokio::spawn(connection);
+        client
+            .batch_execute(&format!("SET search_path TO {schema}, public"))
+            .await
+            .unwrap();
+        client
+    }
+
+    /// Needs a scratch database with pgvector and the embedding model: set
+    /// `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn searches_see_every_row_while_reembedding() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let schema = format!("reform_test_{}", std::process::id());
+        let writer = connect(&database_url, "public").await;
+        if writer.batch_execute("CREATE EXTENSION IF NOT EXISTS vector").await.is_err() {
+            eprintln!("pgvector not available, skipping");
+            return;
+        }
+        let Ok(embedding_service) = EmbeddingService::new(true) else {
+            eprintln!("embedding model not available, skipping");
+            return;
+        };
+        writer
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema}, public;
+                 CREATE TABLE projects (
+                     id BIGINT PRIMARY KEY, title TEXT NOT NULL, description TEXT, category TEXT,
+                     title_description_embedding vector({EMBEDDING_DIM})
+                 );
+                 INSERT INTO projects
+                     SELECT id, 'project ' || id, 'a small weather station that logs sensor readings to a web dashboard',
+                            NULL, array_fill(1, ARRAY[{EMBEDDING_DIM}])::vector
+                     FROM generate_series(1, 50) AS id;"
+            ))
+            .await
+            .unwrap();
+
+        let reader = connect(&database_url, &schema).await;
+        let search = tokio::spawn(async move {
+            let query = Vector::from(vec![1.0; EMBEDDING_DIM]);
+            let mut searches = 0;
+            loop {
+                let row = reader
+                    .query_one(
+                        "SELECT COUNT(*) AS hits, BOOL_AND(title_description_embedding IS NOT NULL) AS all_embedded FROM (
+                             SELECT title_description_embedding FROM projects
+                             WHERE title_description_embedding IS NOT NULL
+                             ORDER BY title_description_embedding <=> $1, id
+                             LIMIT 100
+                         ) hits",
+                        &[&query],
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(row.get::<_, i64>("hits"), 50, "a row dropped out of search mid-reindex");
+                assert!(row.get::<_, bool>("all_embedded"));
+                searches += 1;
+                if reader.query_one("SELECT to_regclass('reindex_done') IS NOT NULL", &[]).await.unwrap().get(0) {
+                    return searches;
+                }
+            }
+        });
+
+        let separator = if database_url.contains('?') { '&' } else { '?' };
+        let config = Config {
+            database_url: format!("{database_url}{separator}options=-csearch_path%3D{schema}%2Cpublic"),
+            max_db_connections: 2,
+            ..Config::default()
+        };
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let pool = create_pool(&config).await.unwrap();
+        let job = ReformJob::new(config, Arc::new(embedding_service));
+        let reembedded = job.embed_projects_from_db(&job.embedding_service, &pool).await.unwrap();
+
+        writer.batch_execute("CREATE TABLE reindex_done ()").await.unwrap();
+        assert!(search.await.unwrap() > 0);
+        let untouched: i64 = writer
+            .query_one(
+                &format!(
+                    "SELECT COUNT(*) FROM {schema}.projects
+                     WHERE title_description_embedding = array_fill(1, ARRAY[{EMBEDDING_DIM}])::vector"
+                ),
+                &[],
+            )
+            .await
+            .unwrap()
+            .get(0);
+
+        writer
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(reembedded, 50);
+        assert_eq!(untouched, 0);
+    }
+
+    /// Needs a scratch database with pgvector: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn stale_reembed_does_not_overwrite_a_concurrent_edit() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let schema = format!("reform_stale_test_{}", std::process::id());
+        let client = connect(&database_url, "public").await;
+        if client.batch_execute("CREATE EXTENSION IF NOT EXISTS vector").await.is_err() {
+            eprintln!("pgvector not available, skipping");
+            return;
+        }
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema}, public;
+                 CREATE TABLE logs (id BIGINT PRIMARY KEY, text TEXT NOT NULL, text_embedding vector(3));
+                 INSERT INTO logs VALUES (1, 'edited upstream', '[0,1,0]');"
+            ))
+            .await
+            .unwrap();
+
+        let stale = Some(Vector::from(vec![1.0, 0.0, 0.0]));
+        let updated = client
+            .execute(UPDATE_DEVLOG_EMBEDDING, &[&1_i64, &stale, &"original text"])
+            .await
+            .unwrap();
+        let kept: Vector = client
+            .query_one("SELECT text_embedding FROM logs WHERE id = 1", &[])
+            .await
+            .unwrap()
+            .get(0);
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(updated, 0);
+        assert_eq!(kept.to_vec(), [0.0, 1.0, 0.0]);
+    }
+}
@@ -9,7 +9,7 @@ mod trust;
 mod update;
 
 use slack::SlackManager;
-use trust::TrustManager;
+use trust::{TrustBackoff, TrustLookup, TrustManager};
 use update::UserUpdater;
 
 pub struct TraceJob {
@@ -24,17 +24,18 @@ impl TraceJob {
 
 #[async_trait]
 impl Job for TraceJob {
-    async fn execute(&self, pool: &DbPool) -> Result<(), JobError> {
+    async fn execute(&self, pool: &DbPool) -> Result<usize, JobError> {
         let pool = Arc::new(pool.clone());
 
         let external_api = Arc::new(
-            ExternalApiService::new(self.config.journey_session_cookie.clone())
+            ExternalApiService::new(&self.config)
                 .map_err(|e| JobError::ExternalApi(e.to_string()))?,
         );
 
-        let slack_manager = Arc::new(SlackManager::new(self.config.clone()));
+        let slack_manager = SlackManager::new(self.config.clone());
+        let trust_backoff = Arc::new(TrustBackoff::new());
 
-        let users_needing_info = UserUpdater::find_users_needing_info(&pool).await?;
+        let users_needing_info = UserUpdater::find_users_needing_info(&pool, self.config.trace_batch_size).await?;
 
         if users_needing_info.is_empty() {
             return Err(JobError::Other("no_work".to_string()));
@@ -43,18 +44,22 @@ impl Job for TraceJob {
         let total_users = users_needing_info.len();
 
         let progress = get_job_progress("trace");
+        progress.update_progress(0, total_users, "Fetching Slack profiles");
+
+        let slack_infos = slack_manager.fetch_user_infos(&users_needing_info).await;
+
         progress.update_progress(0, total_users, "Processing users concurrently");
 
         let mut futures = FuturesUnordered::new();
 
-        for (idx, slack_id) in users_needing_info.into_iter().enumerate() {
+        for (idx, (slack_id, slack_info)) in slack_infos.into_iter().enumerate() {
             let pool = Arc::clone(&pool);
             let external_api = Arc::clone(&external_api);
-            let slack_manager = Arc::clone(&slack_manager);
+            let trust_backoff = Arc::clone(&trust_backoff);
 
             let future = async move {
-                let slack_result = match slack_manager.fetch_user_info_from_slack(&slack_id).await {
-                    Ok(Some((username, profile))) => {
+                let slack_result = match slack_info {
+                    Some((username, profile)) => {
                         UserUpdater::update_user_with_slack_info(
                             &pool, &slack_id, &username, &profile,
                         )
@@ -62,16 +67,12 @@ impl Job for TraceJob {
                         .ok();
                         Some(())
                     }
-                    Ok(None) => None,
-                    Err(JobError::Other(ref err)) if err == "rate_limited" => {
-                        None
-                    }
-                    Err(_) => None,
+                    None => None,
                 };
 
                 let trust_result =
-                    match TrustManager::fetch_trust_info(&external_api, &slack_id).await {
-                        Ok(Some((trust_level, trust_value))) => {
+                    match TrustManager::fetch_with_backoff(&external_api, &trust_backoff, slack_id.as_str()).await {
+                        Ok(TrustLookup::Found { trust_level, trust_value }) => {
                             UserUpdater::update_user_with_trust_info(
                                 &pool,
                                 &slack_id,
@@ -82,8 +83,7 @@ impl Job for TraceJob {
                             .ok();
                             Some(())
                         }
-                        Ok(None) => None,
-                        Err(_) => None,
+                        Ok(TrustLookup::RateLimited { .. } | TrustLookup::NotFound) | Err(_) => None,
                     };
 
                 Result::<(usize, bool, bool), JobError>::Ok((
@@ -138,7 +138,7 @@ impl Job for TraceJob {
             total_users, slack_updated, trust_updated
         ));
 
-        Ok(())
+        Ok(completed)
     }
 
     fn name(&self) -> &str {
@@ -1,8 +1,14 @@
-use crate::core::JobError;
-use common::utils::config::Config;
+use crate::core::{limits::ResourceLimits, JobError};
+use common::utils::{config::Config, types::SlackId};
+use futures::stream::{FuturesUnordered, StreamExt};
 use parking_lot::RwLock;
 use serde::Deserialize;
-use std::time::Instant;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+const DEFAULT_RETRY_AFTER_SECS: u64 = 30;
+const MAX_RATE_LIMITED_ATTEMPTS: usize = 3;
 
 #[derive(Debug, Deserialize)]
 pub struct SlackProfile {
@@ -22,75 +28,159 @@ struct SlackProfileResponse {
     profile: Option<SlackProfile>,
 }
 
+struct SlackToken {
+    token: String,
+    cooling_until: Option<Instant>,
+}
+
 pub struct SlackManager {
-    config: Config,
-    slack_token: RwLock<Option<(String, Instant)>>,
+    client: reqwest::Client,
+    base_url: String,
+    concurrency: usize,
+    tokens: RwLock<Vec<SlackToken>>,
+    next_token: AtomicUsize,
 }
 
 impl SlackManager {
     pub fn new(config: Config) -> Self {
+        let mut tokens = config.slack_tokens;
+        if tokens.is_empty() && !config.slack_token.is_empty() {
+            tokens.push(config.slack_token);
+        }
+
         Self {
-            config,
-            slack_token: RwLock::new(None),
+            client: reqwest::Client::new(),
+            base_url: config.slack_api_base_url.trim_end_matches('/').to_owned(),
+            concurrency: match config.slack_concurrency {
+                0 => ResourceLimits::global().fetch_concurrency(),
+                n => n,
+            },
+            tokens: RwLock::new(
+                tokens
+                    .into_iter()
+                    .map(|token| SlackToken {
+                        token,
+                        cooling_until: None,
+                    })
+                    .collect(),
+            ),
+            next_token: AtomicUsize::new(0),
         }
     }
 
-    pub fn get_slack_token(&self) -> Result<String, JobError> {
-        if !self.config.slack_token.is_empty() {
-            return Ok(self.config.slack_token.clone());
+    /// Hands out tokens round-robin, skipping any still cooling down after a
+    /// 429. When every token is cooling, returns how long until the first frees up.
+    pub fn get_slack_token(&self) -> Result<Result<String, Duration>, JobError> {
+        let tokens = self.tokens.read();
+        if tokens.is_empty() {
+            return Err(JobError::ExternalApi(
+                "Slack API requires a bot token. Please set SLACK_TOKEN or SLACK_TOKENS (comma-separated) with your bot token(s) (xoxb-...)".to_string()
+            ));
         }
 
-        {
-            let read = self.slack_token.read();
-            if let Some((tok, t)) = &*read {
-                if t.elapsed().as_secs() < 36000 {
-                    return Ok(tok.clone());
+        let now = Instant::now();
+        let start = self.next_token.fetch_add(1, Ordering::Relaxed);
+        let available = (0..tokens.len())
+            .map(|offset| &tokens[(start + offset) % tokens.len()])
+            .find(|slot| slot.cooling_until.is_none_or(|until| until <= now));
+
+        Ok(match available {
+            Some(slot) => Ok(slot.token.clone()),
+            None => Err(tokens
+                .iter()
+                .filter_map(|slot| slot.cooling_until)
+                .min()
+                .map_or(Duration::ZERO, |until| until.saturating_duration_since(now))),
+        })
+    }
+
+    async fn next_token(&self) -> Result<String, JobError> {
+        loop {
+            match self.get_slack_token()? {
+                Ok(token) => return Ok(token),
+                Err(wait) => {
+                    tracing::debug!("All Slack tokens are rate limited, waiting {:?}", wait);
+                    tokio::time::sleep(wait).await;
                 }
             }
         }
+    }
 
-        {
-            let read = self.slack_token.read();
-            if let Some((tok, t)) = &*read {
-                if t.elapsed().as_secs() < 36000 {
-                    return Ok(tok.clone());
-                }
-            }
+    fn mark_cooling(&self, token: &str, retry_after: Duration) {
+        let mut tokens = self.tokens.write();
+        if let Some(slot) = tokens.iter_mut().find(|slot| slot.token == token) {
+            slot.cooling_until = Some(Instant::now() + retry_after);
         }
+    }
 
-        Err(JobError::ExternalApi(
-            "Slack API requires a bot token. Please set SLACK_TOKEN environment variable with your bot token (xoxb-...)".to_string()
-        ))
+    /// Looks up many profiles over the shared client with at most
+    /// `SLACK_CONCURRENCY` requests in flight, retrying users that hit a 429
+    /// once a token frees up. Results are in completion order.
+    pub async fn fetch_user_infos(
+        &self,
+        slack_ids: &[SlackId],
+    ) -> Vec<(SlackId, Option<(String, SlackProfile)>)> {
+        let semaphore = Semaphore::new(self.concurrency);
+
+        let mut lookups: FuturesUnordered<_> = slack_ids
+            .iter()
+            .map(|slack_id| {
+                let semaphore = &semaphore;
+                async move {
+                    let _permit = semaphore.acquire().await.ok();
+                    for _ in 0..MAX_RATE_LIMITED_ATTEMPTS {
+                        let lookup = async {
+                            // Wait out token cooldowns before taking a process-wide
+                            // fetch permit, so other jobs aren't starved meanwhile.
+                            let token = self.next_token().await?;
+                            let _fetch_permit = ResourceLimits::global().fetch_permit().await?;
+                            self.fetch_user_info_from_slack(slack_id.as_str(), &token).await
+                        };
+                        match lookup.await {
+                            Ok(info) => return (slack_id.clone(), info),
+                            Err(JobError::Other(ref err)) if err == "rate_limited" => continue,
+                            Err(e) => {
+                                tracing::debug!("Slack lookup for {} failed: {}", slack_id, e);
+                                break;
+                            }
+                        }
+                    }
+                    (slack_id.clone(), None)
+                }
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(slack_ids.len());
+        while let Some(result) = lookups.next().await {
+            results.push(result);
+        }
+        results
     }
 
     pub async fn fetch_user_info_from_slack(
         &self,
         slack_id: &str,
+        token: &str,
     ) -> Result<Option<(String, SlackProfile)>, JobError> {
-        let client = reqwest::Client::new();
-        let profile_url = format!("https://slack.com/api/users.profile.get?user={}", slack_id);
+        let profile_url = format!("{}/users.profile.get?user={}", self.base_url, slack_id);
 
-        let response = client
+        let response = self
+            .client
             .get(&profile_url)
-            .header(
-                "Authorization",
-                format!("Bearer {}", self.get_slack_token()?),
-            )
+            .header("Authorization", format!("Bearer {}", token))
             .send()
             .await;
 
         match response {
             Ok(resp) => {
                 if resp.status() == 429 {
-                    if let Some(retry_after) = resp.headers().get("retry-after") {
-                        if let Ok(retry_str) = retry_after.to_str() {
-                            if let Ok(retry_seconds) = retry_str.parse::<u64>() {
-                                tokio::time::sleep(tokio::time::Duration::from_secs(retry_seconds))
-                                    .await;
-                                return Err(JobError::Other("rate_limited".to_string()));
-                            }
-                        }
-                    }
+                    let retry_seconds = resp
+                        .headers()
+                        .get("retry-after")
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.parse::<u64>().ok())
+                        .unwrap_or(DEFAULT_RETRY_AFTER_SECS);
+                    self.mark_cooling(token, Duration::from_secs(retry_seconds));
                     return Err(JobError::Other("rate_limited".to_string()));
                 }
 
@@ -143,3 +233,109 @@ impl SlackManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        extract::{ConnectInfo, Query, State},
+        routing::get,
+        Json, Router,
+    };
+    use std::{collections::HashMap, collections::HashSet, net::SocketAddr, sync::Arc};
+
+    fn manager(tokens: &[&str]) -> SlackManager {
+        SlackManager::new(Config {
+            slack_tokens: tokens.iter().map(|token| (*token).to_owned()).collect(),
+            slack_concurrency: 1,
+            ..Config::default()
+        })
+    }
+
+    fn token(manager: &SlackManager) -> Result<String, Duration> {
+        manager.get_slack_token().unwrap()
+    }
+
+    #[test]
+    fn cooling_token_rotates_to_the_next() {
+        let slack = manager(&["xoxb-a", "xoxb-b"]);
+        assert_eq!(token(&slack), Ok("xoxb-a".to_owned()));
+        assert_eq!(token(&slack), Ok("xoxb-b".to_owned()));
+
+        slack.mark_cooling("xoxb-a", Duration::from_secs(60));
+        assert_eq!(token(&slack), Ok("xoxb-b".to_owned()));
+        assert_eq!(token(&slack), Ok("xoxb-b".to_owned()));
+
+        slack.mark_cooling("xoxb-b", Duration::from_secs(30));
+        let wait = token(&slack).unwrap_err();
+        assert!(wait > Duration::from_secs(25) && wait <= Duration::from_secs(30), "{wait:?}");
+    }
+
+    #[test]
+    fn missing_tokens_are_an_error() {
+        assert!(manager(&[]).get_slack_token().is_err());
+        let single = SlackManager::new(Config {
+            slack_token: "xoxb-only".to_owned(),
+            slack_concurrency: 1,
+            ..Config::default()
+        });
+        assert_eq!(token(&single), Ok("xoxb-only".to_owned()));
+    }
+
+    #[derive(Default)]
+    struct MockSlack {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+        peers: parking_lot::Mutex<HashSet<SocketAddr>>,
+    }
+
+    async fn profile(
+        State(mock): State<Arc<MockSlack>>,
+        ConnectInfo(peer): ConnectInfo<SocketAddr>,
+        Query(params): Query<HashMap<String, String>>,
+    ) -> Json<serde_json::Value> {
+        mock.peers.lock().insert(peer);
+        let in_flight = mock.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        mock.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        mock.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+        Json(serde_json::json!({ "ok": true, "profile": { "display_name": format!("name-{}", params["user"]) } }))
+    }
+
+    #[tokio::test]
+    async fn batch_lookup_reuses_the_client_and_respects_concurrency() {
+        let mock = Arc::new(MockSlack::default());
+        let app = Router::new()
+            .route("/users.profile.get", get(profile))
+            .with_state(Arc::clone(&mock));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+                .await
+                .unwrap();
+        });
+
+        let slack = SlackManager::new(Config {
+            slack_tokens: vec!["xoxb-test".to_owned()],
+            slack_concurrency: 2,
+            slack_api_base_url: format!("http://{addr}/"),
+            ..Config::default()
+        });
+        let slack_ids: Vec<SlackId> = (0..10).map(|i| SlackId::from(format!("U{i}"))).collect();
+
+        let results = slack.fetch_user_infos(&slack_ids).await;
+
+        assert_eq!(results.len(), slack_ids.len());
+        for (slack_id, info) in &results {
+            let (username, _) = info.as_ref().expect("every lookup succeeds");
+            assert_eq!(username, &format!("name-{slack_id}"));
+        }
+        assert!(mock.max_in_flight.load(Ordering::SeqCst) <= 2);
+        assert!(
+            mock.peers.lock().len() <= 2,
+            "lookups opened a connection each instead of reusing the client"
+        );
+    }
+}
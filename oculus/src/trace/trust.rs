@@ -1,5 +1,41 @@
-use crate::core::JobError;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::time::{Instant, sleep_until};
+
+use crate::core::{limits::ResourceLimits, JobError};
 use common::services::external::ExternalApiService;
+use common::utils::error::ApiError;
+
+pub enum TrustLookup {
+    Found { trust_level: String, trust_value: i32 },
+    NotFound,
+    RateLimited { retry_after: Duration },
+}
+
+/// Shared pause point for trust lookups, so one 429 from Hackatime holds back
+/// every in-flight user instead of each of them hitting the limit again.
+pub struct TrustBackoff {
+    resume_at: Mutex<Instant>,
+}
+
+impl TrustBackoff {
+    pub fn new() -> Self {
+        Self {
+            resume_at: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub async fn wait(&self) {
+        let resume_at = *self.resume_at.lock().unwrap();
+        sleep_until(resume_at).await;
+    }
+
+    pub fn pause_for(&self, retry_after: Duration) {
+        let mut resume_at = self.resume_at.lock().unwrap();
+        *resume_at = (*resume_at).max(Instant::now() + retry_after);
+    }
+}
 
 pub struct TrustManager;
 
@@ -7,14 +43,110 @@ impl TrustManager {
     pub async fn fetch_trust_info(
         external_api: &ExternalApiService,
         slack_id: &str,
-    ) -> Result<Option<(String, i32)>, JobError> {
+    ) -> Result<TrustLookup, JobError> {
         match external_api.fetch_user_stats(slack_id).await {
-            Ok(Some(stats)) => Ok(Some((
-                stats.trust_factor.trust_level,
-                stats.trust_factor.trust_value,
-            ))),
-            Ok(None) => Ok(None),
-            Err(_) => Ok(None),
+            Ok(Some(stats)) => Ok(TrustLookup::Found {
+                trust_level: stats.trust_factor.trust_level,
+                trust_value: stats.trust_factor.trust_value,
+            }),
+            Ok(None) => Ok(TrustLookup::NotFound),
+            Err(ApiError::RateLimit { retry_after, .. }) => Ok(TrustLookup::RateLimited {
+                retry_after: Duration::from_secs(retry_after),
+            }),
+            Err(e) => {
+                tracing::warn!("Trust lookup for {} failed: {}", slack_id, e);
+                Ok(TrustLookup::NotFound)
+            }
+        }
+    }
+
+    /// Waits out any pause `backoff` is holding, then looks up one user. A 429
+    /// pauses every later lookup for as long as Hackatime asked.
+    pub async fn fetch_with_backoff(
+        external_api: &ExternalApiService,
+        backoff: &TrustBackoff,
+        slack_id: &str,
+    ) -> Result<TrustLookup, JobError> {
+        backoff.wait().await;
+        let lookup = {
+            let _fetch_permit = ResourceLimits::global().fetch_permit().await?;
+            Self::fetch_trust_info(external_api, slack_id).await?
+        };
+        if let TrustLookup::RateLimited { retry_after } = &lookup {
+            tracing::warn!("Hackatime rate limited trust lookups, pausing for {:?}", retry_after);
+            backoff.pause_for(*retry_after);
         }
+        Ok(lookup)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use axum::{http::StatusCode, routing::get, Json, Router};
+    use common::utils::config::Config;
+
+    /// A Hackatime stand-in that answers every stats request with a 429.
+    async fn rate_limited_hackatime(requests: Arc<AtomicUsize>) -> ExternalApiService {
+        let app = Router::new().route(
+            "/users/{slack_id}/stats",
+            get(move || {
+                requests.fetch_add(1, Ordering::SeqCst);
+                async {
+                    (
+                        StatusCode::TOO_MANY_REQUESTS,
+                        Json(serde_json::json!({ "message": "slow down", "retry_after": 60 })),
+                    )
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        ExternalApiService::new(&Config {
+            hackatime_api_base_url: format!("http://{addr}"),
+            external_max_response_bytes: 1 << 20,
+            ..Config::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn rate_limit_holds_back_later_lookups() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let external_api = rate_limited_hackatime(Arc::clone(&requests)).await;
+        let backoff = TrustBackoff::new();
+
+        let first = TrustManager::fetch_with_backoff(&external_api, &backoff, "U1").await.unwrap();
+        assert!(matches!(first, TrustLookup::RateLimited { retry_after } if retry_after == Duration::from_secs(60)));
+
+        let second = tokio::time::timeout(
+            Duration::from_millis(200),
+            TrustManager::fetch_with_backoff(&external_api, &backoff, "U2"),
+        )
+        .await;
+        assert!(second.is_err(), "the next lookup should wait out the pause");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn unreachable_hackatime_is_not_found() {
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let external_api = ExternalApiService::new(&Config {
+            hackatime_api_base_url: format!("http://{addr}"),
+            ..Config::default()
+        })
+        .unwrap();
+
+        let lookup = TrustManager::fetch_trust_info(&external_api, "U1").await.unwrap();
+        assert!(matches!(lookup, TrustLookup::NotFound));
     }
 }
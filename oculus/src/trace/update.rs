@@ -1,16 +1,26 @@
-use crate::core::JobError;
+use crate::core::{limits::ResourceLimits, JobError};
 use crate::trace::slack::SlackProfile;
-use common::database::connection::DbPool;
+use common::{
+    database::{connection::DbPool, get_client_with_retry, RetryPolicy},
+    utils::types::SlackId,
+};
+use tokio_postgres::Client;
 
 pub struct UserUpdater;
 
 impl UserUpdater {
-    pub async fn find_users_needing_info(pool: &DbPool) -> Result<Vec<String>, JobError> {
-        let client = pool
-            .get()
+    pub async fn find_users_needing_info(
+        pool: &DbPool,
+        batch_size: i64,
+    ) -> Result<Vec<SlackId>, JobError> {
+        let client = get_client_with_retry(pool, RetryPolicy::default())
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
 
+        Self::users_needing_info(&client, batch_size).await
+    }
+
+    async fn users_needing_info(client: &Client, batch_size: i64) -> Result<Vec<SlackId>, JobError> {
         let rows = client
             .query(
                 "SELECT DISTINCT ON (slack_id) slack_id 
@@ -19,23 +29,22 @@ impl UserUpdater {
             OR pfp_url = 'notfound' 
             OR trust_level = 'unavailable'
          ORDER BY slack_id, last_synced ASC 
-         LIMIT 100",
-                &[],
+         LIMIT $1",
+                &[&batch_size],
             )
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
 
-        Ok(rows.iter().map(|row| row.get::<_, String>(0)).collect())
+        Ok(rows.iter().map(|row| row.get::<_, SlackId>(0)).collect())
     }
 
     pub async fn update_user_with_slack_info(
         pool: &DbPool,
-        slack_id: &str,
+        slack_id: &SlackId,
         username: &str,
         profile: &SlackProfile,
     ) -> Result<(), JobError> {
-        let client = pool
-            .get()
+        let client = get_client_with_retry(pool, RetryPolicy::default())
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
 
@@ -48,8 +57,8 @@ impl UserUpdater {
             .map(|s| s.as_str())
             .unwrap_or("notfound");
 
-        client
-            .execute(
+        ResourceLimits::global()
+            .limit_db(client.execute(
                 r#"UPDATE users SET 
                 username = $1, 
                 pfp_url = $2, 
@@ -72,30 +81,78 @@ impl UserUpdater {
                     &profile.image_512,
                     &slack_id,
                 ],
-            )
-            .await
-            .map_err(|e| JobError::Database(e.to_string()))?;
+            ))
+            .await?;
 
         Ok(())
     }
 
     pub async fn update_user_with_trust_info(
         pool: &DbPool,
-        slack_id: &str,
+        slack_id: &SlackId,
         trust_level: &str,
         trust_value: i32,
     ) -> Result<(), JobError> {
-        let client = pool
-            .get()
+        let client = get_client_with_retry(pool, RetryPolicy::default())
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
 
-        client.execute(
-            "UPDATE users SET trust_level = $1, trust_value = $2, last_synced = NOW() WHERE slack_id = $3",
-            &[&trust_level, &trust_value, &slack_id]
-        ).await
-        .map_err(|e| JobError::Database(e.to_string()))?;
+        ResourceLimits::global()
+            .limit_db(client.execute(
+                "UPDATE users SET trust_level = $1, trust_value = $2, last_synced = NOW() WHERE slack_id = $3",
+                &[&trust_level, &trust_value, &slack_id],
+            ))
+            .await?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_postgres::NoTls;
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn batch_size_limits_users_picked_up() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let (client, connection) = tokio_postgres::connect(&database_url, NoTls).await.unwrap();
+        tokio::spawn(connection);
+
+        let schema = format!("trace_batch_test_{}", std::process::id());
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE users (
+                     slack_id TEXT PRIMARY KEY, username TEXT, pfp_url TEXT, trust_level TEXT,
+                     last_synced TIMESTAMPTZ
+                 );
+                 INSERT INTO users VALUES
+                     ('U1', NULL, NULL, NULL, NOW()),
+                     ('U2', 'two', 'notfound', NULL, NOW()),
+                     ('U3', 'three', 'pfp', 'unavailable', NOW()),
+                     ('U4', 'four', 'pfp', 'blue', NOW());"
+            ))
+            .await
+            .unwrap();
+
+        let two = UserUpdater::users_needing_info(&client, 2).await.unwrap();
+        let all = UserUpdater::users_needing_info(&client, 100).await.unwrap();
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(two.len(), 2);
+        let mut all: Vec<_> = all.iter().map(ToString::to_string).collect();
+        all.sort();
+        assert_eq!(all, ["U1", "U2", "U3"]);
+    }
+}
@@ -1,12 +1,14 @@
-use crate::core::{Job, JobError};
+use crate::core::{ensure_leaderboard_not_empty, load_cache_validators, store_cache_validators, Job, JobError};
 use async_trait::async_trait;
 use common::{
     database::manager::ConnectionManager, services::external::ExternalApiService,
-    utils::config::Config,
+    utils::{config::Config, types::SlackId},
 };
 use std::collections::HashMap;
 use tokio_postgres::Client;
 
+const LEADERBOARD_SYNC_KEY: &str = "leaderboard_zenith";
+
 pub struct ZenithJob {
     config: Config,
 }
@@ -16,18 +18,24 @@ impl ZenithJob {
         Self { config }
     }
 
-    async fn sync_leaderboard_data(&self, pool: &common::database::DbPool) -> Result<(), JobError> {
+    async fn sync_leaderboard_data(&self, pool: &common::database::DbPool) -> Result<usize, JobError> {
         tracing::info!("Starting leaderboard sync");
 
-        let external_api = ExternalApiService::new(self.config.journey_session_cookie.clone())
+        let external_api = ExternalApiService::new(&self.config)
             .map_err(|e| {
                 JobError::ExternalApi(format!("Failed to create external API service: {}", e))
             })?;
 
-        let leaderboard_response = external_api
-            .fetch_leaderboard()
+        let cached_validators = load_cache_validators(pool, LEADERBOARD_SYNC_KEY).await?;
+        let Some((leaderboard_response, validators)) = external_api
+            .fetch_leaderboard(cached_validators.as_ref())
             .await
-            .map_err(|e| JobError::ExternalApi(format!("Failed to fetch leaderboard: {}", e)))?;
+            .map_err(|e| JobError::ExternalApi(format!("Failed to fetch leaderboard: {}", e)))?
+        else {
+            tracing::info!("Leaderboard not modified since last sync, skipping");
+            return Ok(0);
+        };
+        ensure_leaderboard_not_empty(&leaderboard_response)?;
 
         let client = pool
             .get()
@@ -81,18 +89,20 @@ impl ZenithJob {
             }
         }
 
+        store_cache_validators(pool, LEADERBOARD_SYNC_KEY, &validators).await?;
+
         tracing::info!(
             "Leaderboard sync complete: {} updated, {} new users",
             updated_count,
             new_count
         );
-        Ok(())
+        Ok(updated_count + new_count)
     }
 
     async fn get_current_users(
         &self,
         client: &Client,
-    ) -> Result<HashMap<String, Option<i32>>, JobError> {
+    ) -> Result<HashMap<SlackId, Option<i32>>, JobError> {
         let rows = client
             .query("SELECT slack_id, current_shells FROM users", &[])
             .await
@@ -100,7 +110,7 @@ impl ZenithJob {
 
         let mut users = HashMap::new();
         for row in rows {
-            let slack_id: String = row.get(0);
+            let slack_id: SlackId = row.get(0);
             let shells: Option<i32> = row.get(1);
             users.insert(slack_id, shells);
         }
@@ -111,7 +121,7 @@ impl ZenithJob {
     async fn update_user_shells(
         &self,
         client: &Client,
-        slack_id: &str,
+        slack_id: &SlackId,
         new_shells: i32,
     ) -> Result<(), JobError> {
         client
@@ -144,7 +154,7 @@ impl ZenithJob {
     async fn process_user_payouts(
         &self,
         client: &Client,
-        slack_id: &str,
+        slack_id: &SlackId,
         payouts: &[common::utils::modal::RawPayout],
         final_shells: i32,
     ) -> Result<(), JobError> {
@@ -198,14 +208,147 @@ impl Job for ZenithJob {
         "ZenithJob"
     }
 
-    async fn execute(&self, _: &common::database::DbPool) -> Result<(), JobError> {
+    async fn execute(&self, _: &common::database::DbPool) -> Result<usize, JobError> {
         let pool = ConnectionManager::get_dedicated_pool(&self.config)
             .await
             .map_err(|e| JobError::Database(e.to_string()))?;
 
-        self.sync_leaderboard_data(&pool).await?;
+        let processed = self.sync_leaderboard_data(&pool).await?;
 
         tracing::info!("Zenith job completed, releasing dedicated connection");
-        Ok(())
+        Ok(processed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        http::{header, HeaderMap, StatusCode},
+        response::IntoResponse,
+        routing::get,
+        Router,
+    };
+    use common::database::connection::create_pool;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use tokio_postgres::NoTls;
+
+    /// A scratch schema holding the tables zenith writes to, with a cached
+    /// leaderboard ETag of `"v1"`.
+    async fn scratch_schema(database_url: &str, schema: &str) -> Client {
+        let (client, connection) = tokio_postgres::connect(database_url, NoTls).await.unwrap();
+        tokio::spawn(connection);
+        client
+            .batch_execute(&format!(
+                "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                 CREATE SCHEMA {schema};
+                 SET search_path TO {schema};
+                 CREATE TABLE users (slack_id TEXT PRIMARY KEY, current_shells INTEGER);
+                 CREATE TABLE shell_history (id BIGSERIAL PRIMARY KEY, slack_id TEXT, shells INTEGER);
+                 CREATE TABLE sync_metadata (
+                     key TEXT PRIMARY KEY, last_sync TIMESTAMPTZ, status TEXT, etag TEXT, last_modified TEXT
+                 );
+                 INSERT INTO sync_metadata (key, etag) VALUES ('{LEADERBOARD_SYNC_KEY}', '\"v1\"');"
+            ))
+            .await
+            .unwrap();
+        client
+    }
+
+    /// Runs one leaderboard sync against `leaderboard` served from a local mock.
+    async fn sync_against(database_url: &str, schema: &str, leaderboard: Router) -> Result<usize, JobError> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, leaderboard).await.unwrap() });
+
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let separator = if database_url.contains('?') { '&' } else { '?' };
+        let config = Config {
+            database_url: format!("{database_url}{separator}options=-csearch_path%3D{schema}"),
+            max_db_connections: 2,
+            explorpheus_base_url: format!("http://{addr}"),
+            external_max_response_bytes: 1 << 20,
+            ..Config::default()
+        };
+        let pool = create_pool(&config).await.unwrap();
+        ZenithJob::new(config).sync_leaderboard_data(&pool).await
+    }
+
+    /// Rows zenith wrote, and whether the sync metadata is still unstamped.
+    async fn writes(client: &Client) -> (i64, bool) {
+        let row = client
+            .query_one(
+                "SELECT (SELECT COUNT(*) FROM users) + (SELECT COUNT(*) FROM shell_history),
+                        (SELECT last_sync IS NULL FROM sync_metadata)",
+                &[],
+            )
+            .await
+            .unwrap();
+        (row.get(0), row.get(1))
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn unchanged_leaderboard_writes_nothing() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let schema = format!("zenith_test_{}", std::process::id());
+        let client = scratch_schema(&database_url, &schema).await;
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&hits);
+        let app = Router::new().route(
+            "/leaderboard",
+            get(move |headers: HeaderMap| async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                if headers.get(header::IF_NONE_MATCH).is_some_and(|etag| etag == "\"v1\"") {
+                    StatusCode::NOT_MODIFIED.into_response()
+                } else {
+                    r#"[{"slack_id": "U1", "username": "one", "shells": 10, "payouts": []}]"#.into_response()
+                }
+            }),
+        );
+
+        let processed = sync_against(&database_url, &schema, app).await.unwrap();
+        let (written, unstamped) = writes(&client).await;
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+        assert_eq!(processed, 0);
+        assert_eq!(written, 0, "a 304 must not touch users or shell history");
+        assert!(unstamped, "a 304 must not stamp the sync metadata");
+    }
+
+    /// Needs a scratch database: set `TEST_DATABASE_URL` to run it.
+    #[tokio::test]
+    async fn empty_leaderboard_is_a_retryable_failure() {
+        let Ok(database_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL not set, skipping");
+            return;
+        };
+        let schema = format!("zenith_empty_test_{}", std::process::id());
+        let client = scratch_schema(&database_url, &schema).await;
+        let app = Router::new().route("/leaderboard", get(|| async { "[]" }));
+
+        let result = sync_against(&database_url, &schema, app).await;
+        let (written, unstamped) = writes(&client).await;
+
+        client
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .await
+            .unwrap();
+
+        assert!(matches!(result, Err(JobError::ExternalApi(_))), "{result:?}");
+        assert_eq!(written, 0);
+        assert!(unstamped, "an empty leaderboard must not be recorded as synced");
     }
 }